@@ -0,0 +1,125 @@
+//! Traits for Authenticated Encryption with Associated Data (AEAD)
+//!
+//! An AEAD encrypts a plaintext under a key and a nonce, producing a ciphertext and an
+//! authentication tag that protects the integrity of both the ciphertext and some additional
+//! data, which is not encrypted.
+//!
+//! The [Aead] trait describes the API offered by such a scheme. Implementations are provided
+//! in `rosenpass-ciphers`; this makes it possible to write code that is generic over the
+//! AEAD and to swap the implementation used, e.g. in tests.
+//!
+//! Below, we provide an example for how the trait can be implemented using a **HORRIBLY
+//! INSECURE** DummyAead that XORs the plaintext with the key and uses the sum of all inputs
+//! as the tag.
+//!```rust
+//! use rosenpass_cipher_traits::Aead;
+//!
+//! struct DummyAead {}
+//! impl DummyAead {
+//!     fn tag(key: &[u8], nonce: &[u8], ad: &[u8], ct: &[u8]) -> u8 {
+//!         [key, nonce, ad, ct]
+//!             .iter()
+//!             .flat_map(|v| v.iter())
+//!             .fold(0u8, |acc, v| acc.wrapping_add(*v))
+//!     }
+//! }
+//!
+//! impl Aead for DummyAead {
+//!     // For this DummyAead, using String for errors is sufficient.
+//!     type Error = String;
+//!
+//!     const KEY_LEN: usize = 1;
+//!     const NONCE_LEN: usize = 1;
+//!     const TAG_LEN: usize = 1;
+//!
+//!     fn encrypt(
+//!         ciphertext: &mut [u8],
+//!         key: &[u8],
+//!         nonce: &[u8],
+//!         ad: &[u8],
+//!         plaintext: &[u8],
+//!     ) -> Result<(), Self::Error> {
+//!         if ciphertext.len() != plaintext.len() + Self::TAG_LEN {
+//!             return Err("ciphertext does not have the correct length!".to_string());
+//!         }
+//!         let (ct, tag) = ciphertext.split_at_mut(plaintext.len());
+//!         for (c, p) in ct.iter_mut().zip(plaintext) {
+//!             *c = p ^ key[0];
+//!         }
+//!         tag[0] = Self::tag(key, nonce, ad, ct);
+//!         Ok(())
+//!     }
+//!
+//!     fn decrypt(
+//!         plaintext: &mut [u8],
+//!         key: &[u8],
+//!         nonce: &[u8],
+//!         ad: &[u8],
+//!         ciphertext: &[u8],
+//!     ) -> Result<(), Self::Error> {
+//!         if ciphertext.len() != plaintext.len() + Self::TAG_LEN {
+//!             return Err("plaintext does not have the correct length!".to_string());
+//!         }
+//!         let (ct, tag) = ciphertext.split_at(plaintext.len());
+//!         if tag[0] != Self::tag(key, nonce, ad, ct) {
+//!             return Err("Invalid tag!".to_string());
+//!         }
+//!         for (p, c) in plaintext.iter_mut().zip(ct) {
+//!             *p = c ^ key[0];
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut ct = [0u8; 3 + DummyAead::TAG_LEN];
+//! DummyAead::encrypt(&mut ct, &[42], &[0], b"ad", b"abc")?;
+//!
+//! let mut pt = [0u8; 3];
+//! DummyAead::decrypt(&mut pt, &[42], &[0], b"ad", &ct)?;
+//! assert_eq!(&pt, b"abc");
+//! assert!(DummyAead::decrypt(&mut pt, &[42], &[0], b"other ad", &ct).is_err());
+//!
+//! # Ok::<(), String>(())
+//!```
+
+/// Authenticated Encryption with Associated Data
+///
+/// The ciphertext produced by [Self::encrypt] consists of the encrypted plaintext followed
+/// by the [Self::TAG_LEN] byte authentication tag; the nonce is not included.
+pub trait Aead {
+    type Error;
+
+    /// Key length
+    const KEY_LEN: usize;
+    /// Nonce length
+    const NONCE_LEN: usize;
+    /// Authentication tag length
+    const TAG_LEN: usize;
+
+    /// Encrypt `plaintext` and authenticate it along with `ad`
+    ///
+    /// `ciphertext` must be exactly [Self::TAG_LEN] bytes longer than `plaintext`.
+    /// A `nonce` must never be reused with the same `key`.
+    ///
+    /// `encrypt(key, nonce, ad, plaintext) -> ciphertext`
+    fn encrypt(
+        ciphertext: &mut [u8],
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<(), Self::Error>;
+
+    /// Verify the integrity of `ciphertext` and `ad` and decrypt `ciphertext`
+    ///
+    /// `plaintext` must be exactly [Self::TAG_LEN] bytes shorter than `ciphertext`.
+    ///
+    /// `decrypt(key, nonce, ad, ciphertext) -> plaintext`
+    fn decrypt(
+        plaintext: &mut [u8],
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<(), Self::Error>;
+}
@@ -0,0 +1,62 @@
+//! Traits for keyed hash functions
+//!
+//! A keyed hash function maps a key and some data of arbitrary length to a fixed length
+//! output. Rosenpass uses keyed hashes as pseudo random functions; all hash domains are built
+//! on top of them (see `rosenpass_ciphers::hash_domain`).
+//!
+//! The [KeyedHash] trait describes the API offered by such a function. Implementations are
+//! provided in `rosenpass-ciphers`.
+//!
+//! Below, we provide an example for how the trait can be implemented using a **HORRIBLY
+//! INSECURE** DummyHash that just XORs the key and the data.
+//!```rust
+//! use rosenpass_cipher_traits::KeyedHash;
+//!
+//! struct DummyHash {}
+//! impl KeyedHash for DummyHash {
+//!     // For this DummyHash, using String for errors is sufficient.
+//!     type Error = String;
+//!
+//!     const KEY_LEN: usize = 4;
+//!     const OUT_LEN: usize = 4;
+//!
+//!     fn keyed_hash(key: &[u8], data: &[u8], out: &mut [u8]) -> Result<(), Self::Error> {
+//!         if key.len() != Self::KEY_LEN {
+//!             return Err("key does not have the correct length!".to_string());
+//!         }
+//!         if out.len() != Self::OUT_LEN {
+//!             return Err("out does not have the correct length!".to_string());
+//!         }
+//!         out.copy_from_slice(key);
+//!         for (i, d) in data.iter().enumerate() {
+//!             out[i % Self::OUT_LEN] ^= d;
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut out = [0u8; DummyHash::OUT_LEN];
+//! DummyHash::keyed_hash(&[1, 2, 3, 4], &[1, 2, 3, 4, 5], &mut out)?;
+//! assert_eq!(out, [4, 0, 0, 0]);
+//! assert!(DummyHash::keyed_hash(&[1, 2, 3], &[], &mut out).is_err());
+//!
+//! # Ok::<(), String>(())
+//!```
+
+/// Keyed hash function
+///
+/// Takes a key of [Self::KEY_LEN] bytes and data of arbitrary length and produces an output of
+/// [Self::OUT_LEN] bytes.
+pub trait KeyedHash {
+    type Error;
+
+    /// Key length
+    const KEY_LEN: usize;
+    /// Output length
+    const OUT_LEN: usize;
+
+    /// Hash `data` under `key` and write the result to `out`
+    ///
+    /// `keyed_hash(key, data) -> out`
+    fn keyed_hash(key: &[u8], data: &[u8], out: &mut [u8]) -> Result<(), Self::Error>;
+}
@@ -1,2 +1,6 @@
+mod aead;
 mod kem;
+mod keyed_hash;
+pub use aead::Aead;
 pub use kem::Kem;
+pub use keyed_hash::KeyedHash;
@@ -2,9 +2,9 @@ use anyhow::Result;
 use rosenpass_secret_memory::Secret;
 use rosenpass_to::To;
 
-use crate::keyed_hash as hash;
+use crate::keyed_hash::KeyedHashChoice;
 
-pub use hash::KEY_LEN;
+pub use crate::keyed_hash::KEY_LEN;
 
 ///
 ///```rust
@@ -33,58 +33,134 @@ pub use hash::KEY_LEN;
 /// # Ok::<(), anyhow::Error>(())
 ///```
 ///
+/// By default, all hash domains use the keyed hash from version one of the Rosenpass protocol
+/// ([crate::keyed_hash::hash]). A different [KeyedHashChoice] can be selected when creating
+/// the root of a hash domain chain; it is inherited by all hash domains derived from it.
+///
+///```rust
+/// # use rosenpass_ciphers::hash_domain::HashDomain;
+/// use rosenpass_ciphers::keyed_hash::KeyedHashChoice;
+///
+/// let v1 = HashDomain::zero().mix(b"MY_PROTOCOL:IDENTIFIER")?;
+/// let v2 = HashDomain::zero_with(KeyedHashChoice::HmacBlake2b).mix(b"MY_PROTOCOL:IDENTIFIER")?;
+/// assert_eq!(v2.keyed_hash(), KeyedHashChoice::HmacBlake2b);
+/// assert_eq!(v2.clone().dup().mix(b"key")?.keyed_hash(), KeyedHashChoice::HmacBlake2b);
+/// assert_ne!(v1.into_value(), v2.into_value());
+///
+/// # Ok::<(), anyhow::Error>(())
+///```
+///
+/// All backends offer the same API; the following test vectors cross-check the hash domain
+/// chains produced by each of them. The expected values were computed independently using
+/// Python's `hashlib` and `hmac` modules.
+///
+///```rust
+/// # use rosenpass_ciphers::hash_domain::HashDomain;
+/// use rosenpass_ciphers::keyed_hash::KeyedHashChoice;
+/// # rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+///
+/// fn hex(v: &[u8]) -> String {
+///     v.iter().map(|b| format!("{b:02x}")).collect()
+/// }
+///
+/// let vectors = [
+///     (
+///         KeyedHashChoice::IncorrectHmacBlake2b,
+///         "6f38b9b838831c46d29aa88c66f6555ae2096e647b28974fb6e34df2239cedf7",
+///     ),
+///     (
+///         KeyedHashChoice::HmacBlake2b,
+///         "44b6dc793952fb68671a3bb7a8e0cb43077825439b90fdac427ed73c0e985986",
+///     ),
+///     (
+///         KeyedHashChoice::Shake256,
+///         "9e78461b35bcbdca2806a37a5863092437ca8ba00b853aa9df5e231dcc1c2dfe",
+///     ),
+/// ];
+/// for (hash, expected) in vectors {
+///     let ns = HashDomain::zero_with(hash).mix(b"MY_PROTOCOL:IDENTIFIER")?.dup();
+///     let public = ns.mix(b"my_key_identifier")?.into_value();
+///     assert_eq!(hex(&public), expected, "{hash:?}");
+///
+///     // The secret variants produce the same values
+///     let secret = HashDomain::zero_with(hash)
+///         .turn_secret()
+///         .mix(b"MY_PROTOCOL:IDENTIFIER")?
+///         .dup()
+///         .mix(b"my_key_identifier")?
+///         .into_secret();
+///     assert_eq!(secret.secret(), &public);
+/// }
+///
+/// # Ok::<(), anyhow::Error>(())
+///```
+///
 
 // TODO Use a proper Dec interface
 /// A use-once hash domain for a specified key that can be used directly.
 /// The key must consist of [KEY_LEN] many bytes. If the key must remain secret,
 /// use [SecretHashDomain] instead.
 #[derive(Clone, Debug)]
-pub struct HashDomain([u8; KEY_LEN]);
+pub struct HashDomain([u8; KEY_LEN], KeyedHashChoice);
 /// A reusable hash domain for a namespace identified by the key.
 /// The key must consist of [KEY_LEN] many bytes. If the key must remain secret,
 /// use [SecretHashDomainNamespace] instead.
 #[derive(Clone, Debug)]
-pub struct HashDomainNamespace([u8; KEY_LEN]);
+pub struct HashDomainNamespace([u8; KEY_LEN], KeyedHashChoice);
 /// A use-once hash domain for a specified key that can be used directly
 /// by wrapping it in [Secret]. The key must consist of [KEY_LEN] many bytes.
 #[derive(Clone, Debug)]
-pub struct SecretHashDomain(Secret<KEY_LEN>);
+pub struct SecretHashDomain(Secret<KEY_LEN>, KeyedHashChoice);
 /// A reusable secure hash domain for a namespace identified by the key and that keeps the key secure
 /// by wrapping it in [Secret]. The key must consist of [KEY_LEN] many bytes.
 #[derive(Clone, Debug)]
-pub struct SecretHashDomainNamespace(Secret<KEY_LEN>);
+pub struct SecretHashDomainNamespace(Secret<KEY_LEN>, KeyedHashChoice);
 
 impl HashDomain {
     /// Creates a nw [HashDomain] initialized with a all-zeros key.
     pub fn zero() -> Self {
-        Self([0u8; KEY_LEN])
+        Self::zero_with(KeyedHashChoice::default())
+    }
+
+    /// Creates a new [HashDomain] initialized with an all-zeros key, using the keyed hash
+    /// `hash` for this and all derived hash domains.
+    pub fn zero_with(hash: KeyedHashChoice) -> Self {
+        Self([0u8; KEY_LEN], hash)
+    }
+
+    /// The keyed hash used by this [HashDomain]
+    pub fn keyed_hash(&self) -> KeyedHashChoice {
+        self.1
     }
 
     /// Turns this [HashDomain] into a [HashDomainNamespace], keeping the key.
     pub fn dup(self) -> HashDomainNamespace {
-        HashDomainNamespace(self.0)
+        HashDomainNamespace(self.0, self.1)
     }
 
     /// Turns this [HashDomain] into a [SecretHashDomain] by wrapping the key into a [Secret]
     /// and creating a new [SecretHashDomain] from it.
     pub fn turn_secret(self) -> SecretHashDomain {
-        SecretHashDomain(Secret::from_slice(&self.0))
+        SecretHashDomain(Secret::from_slice(&self.0), self.1)
     }
 
     // TODO: Protocol! Use domain separation to ensure that
     /// Creates a new [HashDomain] by mixing in a new key `v`. Specifically,
-    /// it evaluates [hash::hash] with this HashDomain's key as the key and `v`
+    /// it evaluates [KeyedHashChoice::hash] with this HashDomain's key as the key and `v`
     /// as the `data` and uses the result as the key for the new [HashDomain].
     ///
     pub fn mix(self, v: &[u8]) -> Result<Self> {
-        Ok(Self(hash::hash(&self.0, v).collect::<[u8; KEY_LEN]>()?))
+        Ok(Self(
+            self.1.hash(&self.0, v).collect::<[u8; KEY_LEN]>()?,
+            self.1,
+        ))
     }
 
     /// Creates a new [SecretHashDomain] by mixing in a new key `v`
-    /// by calling [SecretHashDomain::invoke_primitive] with this
+    /// by calling [SecretHashDomain::invoke_primitive_with] with this
     /// [HashDomain]'s key as `k` and `v` as `d`.
     pub fn mix_secret<const N: usize>(self, v: Secret<N>) -> Result<SecretHashDomain> {
-        SecretHashDomain::invoke_primitive(&self.0, v.secret())
+        SecretHashDomain::invoke_primitive_with(self.1, &self.0, v.secret())
     }
 
     /// Gets the key of this [HashDomain].
@@ -95,68 +171,96 @@ impl HashDomain {
 
 impl HashDomainNamespace {
     /// Creates a new [HashDomain] by mixing in a new key `v`. Specifically,
-    /// it evaluates [hash::hash] with the key of this HashDomainNamespace key as the key and `v`
+    /// it evaluates [KeyedHashChoice::hash] with the key of this HashDomainNamespace key as the key and `v`
     /// as the `data` and uses the result as the key for the new [HashDomain].
     pub fn mix(&self, v: &[u8]) -> Result<HashDomain> {
         Ok(HashDomain(
-            hash::hash(&self.0, v).collect::<[u8; KEY_LEN]>()?,
+            self.1.hash(&self.0, v).collect::<[u8; KEY_LEN]>()?,
+            self.1,
         ))
     }
 
     /// Creates a new [SecretHashDomain] by mixing in a new key `v`
-    /// by calling [SecretHashDomain::invoke_primitive] with the key of this
+    /// by calling [SecretHashDomain::invoke_primitive_with] with the key of this
     /// [HashDomainNamespace] as `k` and `v` as `d`.
     ///
     /// It requires that `v` consists of exactly [KEY_LEN] many bytes.
     pub fn mix_secret<const N: usize>(&self, v: Secret<N>) -> Result<SecretHashDomain> {
-        SecretHashDomain::invoke_primitive(&self.0, v.secret())
+        SecretHashDomain::invoke_primitive_with(self.1, &self.0, v.secret())
+    }
+
+    /// The keyed hash used by this [HashDomainNamespace]
+    pub fn keyed_hash(&self) -> KeyedHashChoice {
+        self.1
     }
 }
 
 impl SecretHashDomain {
     /// Create a new [SecretHashDomain] with the given key `k` and data `d` by calling
-    /// [hash::hash] with `k` as the `key` and `d` s the `data`, and using the result
+    /// [crate::keyed_hash::hash] with `k` as the `key` and `d` s the `data`, and using the result
     /// as the content for the new [SecretHashDomain].
     /// Both `k` and `d` have to be exactly [KEY_LEN] bytes in length.
     pub fn invoke_primitive(k: &[u8], d: &[u8]) -> Result<SecretHashDomain> {
-        let mut r = SecretHashDomain(Secret::zero());
-        hash::hash(k, d).to(r.0.secret_mut())?;
+        Self::invoke_primitive_with(KeyedHashChoice::default(), k, d)
+    }
+
+    /// Like [Self::invoke_primitive], but uses the keyed hash `hash` instead of the default one;
+    /// the new [SecretHashDomain] uses `hash` too.
+    pub fn invoke_primitive_with(
+        hash: KeyedHashChoice,
+        k: &[u8],
+        d: &[u8],
+    ) -> Result<SecretHashDomain> {
+        let mut r = SecretHashDomain(Secret::zero(), hash);
+        hash.hash(k, d).to(r.0.secret_mut())?;
         Ok(r)
     }
 
     /// Creates a new [SecretHashDomain] that is initialized with an all zeros key.
     pub fn zero() -> Self {
-        Self(Secret::zero())
+        Self(Secret::zero(), KeyedHashChoice::default())
+    }
+
+    /// The keyed hash used by this [SecretHashDomain]
+    pub fn keyed_hash(&self) -> KeyedHashChoice {
+        self.1
     }
 
     /// Turns this [SecretHashDomain] into a [SecretHashDomainNamespace].
     pub fn dup(self) -> SecretHashDomainNamespace {
-        SecretHashDomainNamespace(self.0)
+        SecretHashDomainNamespace(self.0, self.1)
     }
 
     /// Creates a new [SecretHashDomain] from a [Secret] `k`.
     ///
     /// It requires that `k` consist of exactly [KEY_LEN] bytes.
     pub fn danger_from_secret(k: Secret<KEY_LEN>) -> Self {
-        Self(k)
+        Self::danger_from_secret_with(k, KeyedHashChoice::default())
+    }
+
+    /// Creates a new [SecretHashDomain] from a [Secret] `k` that uses the keyed hash `hash`.
+    ///
+    /// It requires that `k` consist of exactly [KEY_LEN] bytes.
+    pub fn danger_from_secret_with(k: Secret<KEY_LEN>, hash: KeyedHashChoice) -> Self {
+        Self(k, hash)
     }
 
     /// Creates a new [SecretHashDomain] by mixing in a new key `v`. Specifically,
-    /// it evaluates [hash::hash] with this [SecretHashDomain]'s key as the key and `v`
+    /// it evaluates [KeyedHashChoice::hash] with this [SecretHashDomain]'s key as the key and `v`
     /// as the `data` and uses the result as the key for the new [SecretHashDomain].
     ///
     /// It requires that `v` consists of exactly [KEY_LEN] many bytes.
     pub fn mix(self, v: &[u8]) -> Result<SecretHashDomain> {
-        Self::invoke_primitive(self.0.secret(), v)
+        Self::invoke_primitive_with(self.1, self.0.secret(), v)
     }
 
     /// Creates a new [SecretHashDomain] by mixing in a new key `v`
-    /// by calling [SecretHashDomain::invoke_primitive] with the key of this
+    /// by calling [SecretHashDomain::invoke_primitive_with] with the key of this
     /// [HashDomainNamespace] as `k` and `v` as `d`.
     ///
     /// It requires that `v` consists of exactly [KEY_LEN] many bytes.
     pub fn mix_secret<const N: usize>(self, v: Secret<N>) -> Result<SecretHashDomain> {
-        Self::invoke_primitive(self.0.secret(), v.secret())
+        Self::invoke_primitive_with(self.1, self.0.secret(), v.secret())
     }
 
     /// Get the secret key data from this [SecretHashDomain].
@@ -164,32 +268,37 @@ impl SecretHashDomain {
         self.0
     }
 
-    /// Evaluate [hash::hash] with this [SecretHashDomain]'s data as the `key` and
+    /// Evaluate [KeyedHashChoice::hash] with this [SecretHashDomain]'s data as the `key` and
     /// `dst` as the `data` and stores the result as the new data for this [SecretHashDomain].
     ///
     /// It requires that both `v` and `d` consist of exactly [KEY_LEN] many bytes.
     pub fn into_secret_slice(mut self, v: &[u8], dst: &[u8]) -> Result<()> {
-        hash::hash(v, dst).to(self.0.secret_mut())
+        self.1.hash(v, dst).to(self.0.secret_mut())
     }
 }
 
 impl SecretHashDomainNamespace {
     /// Creates a new [SecretHashDomain] by mixing in a new key `v`. Specifically,
-    /// it evaluates [hash::hash] with the key of this HashDomainNamespace key as the key and `v`
+    /// it evaluates [KeyedHashChoice::hash] with the key of this HashDomainNamespace key as the key and `v`
     /// as the `data` and uses the result as the key for the new [HashDomain].
     ///
     /// It requires that `v` consists of exactly [KEY_LEN] many bytes.
     pub fn mix(&self, v: &[u8]) -> Result<SecretHashDomain> {
-        SecretHashDomain::invoke_primitive(self.0.secret(), v)
+        SecretHashDomain::invoke_primitive_with(self.1, self.0.secret(), v)
     }
 
     /// Creates a new [SecretHashDomain] by mixing in a new key `v`
-    /// by calling [SecretHashDomain::invoke_primitive] with the key of this
+    /// by calling [SecretHashDomain::invoke_primitive_with] with the key of this
     /// [HashDomainNamespace] as `k` and `v` as `d`.
     ///
     /// It requires that `v` consists of exactly [KEY_LEN] many bytes.
     pub fn mix_secret<const N: usize>(&self, v: Secret<N>) -> Result<SecretHashDomain> {
-        SecretHashDomain::invoke_primitive(self.0.secret(), v.secret())
+        SecretHashDomain::invoke_primitive_with(self.1, self.0.secret(), v.secret())
+    }
+
+    /// The keyed hash used by this [SecretHashDomainNamespace]
+    pub fn keyed_hash(&self) -> KeyedHashChoice {
+        self.1
     }
 
     // TODO: This entire API is not very nice; we need this for biscuits, but
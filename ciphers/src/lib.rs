@@ -13,28 +13,164 @@ const_assert!(KEY_LEN == hash_domain::KEY_LEN);
 /// This should only be used for implementation details; anything with relevance
 /// to the cryptographic protocol should use the facilities in [hash_domain], (though
 /// hash domain uses this module internally)
+///
+/// [hash] is the keyed hash used by version one of the Rosenpass protocol; use
+/// [KeyedHashChoice] to select one of the other keyed hashes at runtime.
 pub mod keyed_hash {
+    use rosenpass_cipher_traits::KeyedHash;
+    use rosenpass_to::{with_destination, To};
+    use static_assertions::const_assert;
+
+    pub use crate::subtle::hmac_blake2b::HmacBlake2b;
+    pub use crate::subtle::incorrect_hmac_blake2b::IncorrectHmacBlake2b;
+    pub use crate::subtle::keyed_shake256::KeyedShake256;
+
     pub use crate::subtle::incorrect_hmac_blake2b::{
         hash, KEY_LEN, KEY_MAX, KEY_MIN, OUT_MAX, OUT_MIN,
     };
+
+    /// Make sure all keyed hashes can be used interchangeably
+    macro_rules! assert_compatible {
+        ($hash:ty) => {
+            const_assert!(<$hash as KeyedHash>::KEY_LEN == KEY_LEN);
+            const_assert!(<$hash as KeyedHash>::OUT_LEN == OUT_MAX);
+        };
+    }
+
+    const_assert!(OUT_MIN == OUT_MAX);
+    assert_compatible!(IncorrectHmacBlake2b);
+    assert_compatible!(HmacBlake2b);
+    assert_compatible!(KeyedShake256);
+
+    /// The keyed hash functions supported by [hash_domain](crate::hash_domain)
+    ///
+    /// All of them implement [KeyedHash], use [KEY_LEN] byte keys and produce outputs of the
+    /// same length.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rosenpass_ciphers::keyed_hash::{self, KeyedHashChoice};
+    /// use rosenpass_to::To;
+    ///
+    /// let key = [0u8; keyed_hash::KEY_LEN];
+    /// let v1 = keyed_hash::hash(&key, b"data").collect::<[u8; 32]>()?;
+    /// assert_eq!(KeyedHashChoice::default().hash(&key, b"data").collect::<[u8; 32]>()?, v1);
+    /// assert_ne!(KeyedHashChoice::HmacBlake2b.hash(&key, b"data").collect::<[u8; 32]>()?, v1);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    ///
+    /// Test vectors for all backends; the expected values were computed independently using
+    /// Python's `hashlib` and `hmac` modules.
+    ///
+    /// ```rust
+    /// use rosenpass_ciphers::keyed_hash::KeyedHashChoice;
+    /// use rosenpass_to::To;
+    ///
+    /// fn hex(v: &[u8]) -> String {
+    ///     v.iter().map(|b| format!("{b:02x}")).collect()
+    /// }
+    ///
+    /// let key: Vec<u8> = (0u8..32).collect();
+    /// let vectors = [
+    ///     (
+    ///         KeyedHashChoice::IncorrectHmacBlake2b,
+    ///         "3563daf266d18459da2800a6d8e68a9ff3e313ee6a3a1caedbd04a64e980eb1d",
+    ///     ),
+    ///     (
+    ///         KeyedHashChoice::HmacBlake2b,
+    ///         "125f9eb36473696258c22a010ee0f1ba6dcefaf3ae36a64ec3f8887f1ac425e9",
+    ///     ),
+    ///     (
+    ///         KeyedHashChoice::Shake256,
+    ///         "1a6de2dc680edf6e9066852c0754f4a0635c391aec7bd4c94db2363f5bb30ed0",
+    ///     ),
+    /// ];
+    /// for (choice, expected) in vectors {
+    ///     let out = choice.hash(&key, b"Rosenpass").collect::<[u8; 32]>()?;
+    ///     assert_eq!(hex(&out), expected, "{choice:?}");
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum KeyedHashChoice {
+        /// See [IncorrectHmacBlake2b]; used by version one of the Rosenpass protocol
+        #[default]
+        IncorrectHmacBlake2b,
+        /// See [HmacBlake2b]
+        HmacBlake2b,
+        /// See [KeyedShake256]
+        Shake256,
+    }
+
+    impl KeyedHashChoice {
+        /// Evaluate the selected keyed hash function
+        #[inline]
+        pub fn hash<'a>(
+            self,
+            key: &'a [u8],
+            data: &'a [u8],
+        ) -> impl To<[u8], anyhow::Result<()>> + 'a {
+            with_destination(move |out: &mut [u8]| match self {
+                Self::IncorrectHmacBlake2b => IncorrectHmacBlake2b::keyed_hash(key, data, out),
+                Self::HmacBlake2b => HmacBlake2b::keyed_hash(key, data, out),
+                Self::Shake256 => KeyedShake256::keyed_hash(key, data, out),
+            })
+        }
+    }
 }
 
 /// Authenticated encryption with associated data
 /// Chacha20poly1305 is used.
+///
+/// [ChaCha20Poly1305](aead::ChaCha20Poly1305) implements [rosenpass_cipher_traits::Aead] for the
+/// implementation selected at build time, so code can be written generically over the AEAD.
+///
+/// ```rust
+/// use rosenpass_cipher_traits::Aead;
+/// use rosenpass_ciphers::subtle::chacha20poly1305_ietf;
+/// use rosenpass_ciphers::{aead, xaead};
+///
+/// fn roundtrip<A: Aead<Error = anyhow::Error>>() -> anyhow::Result<Vec<u8>> {
+///     let (key, nonce) = (vec![0u8; A::KEY_LEN], vec![0u8; A::NONCE_LEN]);
+///     let mut ct = vec![0u8; 5 + A::TAG_LEN];
+///     A::encrypt(&mut ct, &key, &nonce, b"ad", b"hello")?;
+///
+///     let mut pt = [0u8; 5];
+///     A::decrypt(&mut pt, &key, &nonce, b"ad", &ct)?;
+///     assert_eq!(&pt, b"hello");
+///     assert!(A::decrypt(&mut pt, &key, &nonce, b"other ad", &ct).is_err());
+///     Ok(ct)
+/// }
+///
+/// // All ChaCha20Poly1305 implementations produce the same ciphertexts
+/// assert_eq!(
+///     roundtrip::<aead::ChaCha20Poly1305>()?,
+///     roundtrip::<chacha20poly1305_ietf::ChaCha20Poly1305>()?,
+/// );
+/// roundtrip::<xaead::XChaCha20Poly1305>()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
 pub mod aead {
     #[cfg(not(feature = "experiment_libcrux"))]
-    pub use crate::subtle::chacha20poly1305_ietf::{decrypt, encrypt, KEY_LEN, NONCE_LEN, TAG_LEN};
+    pub use crate::subtle::chacha20poly1305_ietf::{
+        decrypt, encrypt, ChaCha20Poly1305, KEY_LEN, NONCE_LEN, TAG_LEN,
+    };
     #[cfg(feature = "experiment_libcrux")]
     pub use crate::subtle::chacha20poly1305_ietf_libcrux::{
-        decrypt, encrypt, KEY_LEN, NONCE_LEN, TAG_LEN,
+        decrypt, encrypt, ChaCha20Poly1305Libcrux as ChaCha20Poly1305, KEY_LEN, NONCE_LEN, TAG_LEN,
     };
 }
 
 /// Authenticated encryption with associated data with a constant nonce
 /// XChacha20poly1305 is used.
+///
+/// Note that [encrypt](xaead::encrypt) and [decrypt](xaead::decrypt) include the nonce in the
+/// ciphertext, while the [rosenpass_cipher_traits::Aead] implementation
+/// [XChaCha20Poly1305](xaead::XChaCha20Poly1305) does not.
 pub mod xaead {
     pub use crate::subtle::xchacha20poly1305_ietf::{
-        decrypt, encrypt, KEY_LEN, NONCE_LEN, TAG_LEN,
+        decrypt, encrypt, XChaCha20Poly1305, KEY_LEN, NONCE_LEN, TAG_LEN,
     };
 }
 
@@ -44,10 +180,37 @@ pub mod hash_domain;
 /// Namely ClassicMceliece460896 (also referred to as `StaticKem` sometimes) and
 /// Kyber512 (also referred to as  `EphemeralKem` sometimes).
 ///
-/// See [rosenpass_oqs::ClassicMceliece460896]
-/// and [rosenpass_oqs::Kyber512] for more details on the specific KEMS.
+/// The ephemeral KEM can be switched to FIPS 203 ML-KEM at build time using the
+/// `experiment_ml_kem_512` or `experiment_ml_kem_768` features. [EPHEMERAL_KEM_ID](kem::EPHEMERAL_KEM_ID)
+/// names the KEM in use; Rosenpass mixes it into the protocol identifier so peers using
+/// different ephemeral KEMs never interoperate.
+///
+/// See [rosenpass_oqs::ClassicMceliece460896], [rosenpass_oqs::Kyber512],
+/// [rosenpass_oqs::MlKem512], and [rosenpass_oqs::MlKem768] for more details on the specific KEMS.
 ///
 pub mod kem {
+    #[cfg(all(feature = "experiment_ml_kem_512", feature = "experiment_ml_kem_768"))]
+    compile_error!(
+        "The features experiment_ml_kem_512 and experiment_ml_kem_768 are mutually exclusive"
+    );
+
     pub use rosenpass_oqs::ClassicMceliece460896 as StaticKem;
+    pub use rosenpass_oqs::{Kyber512, MlKem512, MlKem768};
+
+    #[cfg(not(any(feature = "experiment_ml_kem_512", feature = "experiment_ml_kem_768")))]
     pub use rosenpass_oqs::Kyber512 as EphemeralKem;
+    #[cfg(feature = "experiment_ml_kem_512")]
+    pub use rosenpass_oqs::MlKem512 as EphemeralKem;
+    #[cfg(feature = "experiment_ml_kem_768")]
+    pub use rosenpass_oqs::MlKem768 as EphemeralKem;
+
+    /// Name of the KEM used as [EphemeralKem]
+    #[cfg(not(any(feature = "experiment_ml_kem_512", feature = "experiment_ml_kem_768")))]
+    pub const EPHEMERAL_KEM_ID: &str = "Kyber512";
+    /// Name of the KEM used as [EphemeralKem]
+    #[cfg(feature = "experiment_ml_kem_512")]
+    pub const EPHEMERAL_KEM_ID: &str = "ML-KEM-512";
+    /// Name of the KEM used as [EphemeralKem]
+    #[cfg(feature = "experiment_ml_kem_768")]
+    pub const EPHEMERAL_KEM_ID: &str = "ML-KEM-768";
 }
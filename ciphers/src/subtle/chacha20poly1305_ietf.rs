@@ -1,3 +1,4 @@
+use rosenpass_cipher_traits::Aead;
 use rosenpass_to::ops::copy_slice;
 use rosenpass_to::To;
 use rosenpass_util::typenum2const;
@@ -97,3 +98,34 @@ pub fn decrypt(
     AeadImpl::new_from_slice(key)?.decrypt_in_place_detached(nonce, ad, plaintext, tag)?;
     Ok(())
 }
+
+/// [Aead] implementation for ChaCha20Poly1305 as implemented in RustCrypto; see [encrypt] and [decrypt]
+pub enum ChaCha20Poly1305 {}
+
+impl Aead for ChaCha20Poly1305 {
+    type Error = anyhow::Error;
+
+    const KEY_LEN: usize = KEY_LEN;
+    const NONCE_LEN: usize = NONCE_LEN;
+    const TAG_LEN: usize = TAG_LEN;
+
+    fn encrypt(
+        ciphertext: &mut [u8],
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        plaintext: &[u8],
+    ) -> anyhow::Result<()> {
+        encrypt(ciphertext, key, nonce, ad, plaintext)
+    }
+
+    fn decrypt(
+        plaintext: &mut [u8],
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> anyhow::Result<()> {
+        decrypt(plaintext, key, nonce, ad, ciphertext)
+    }
+}
@@ -1,3 +1,4 @@
+use rosenpass_cipher_traits::Aead;
 use rosenpass_to::ops::copy_slice;
 use rosenpass_to::To;
 
@@ -106,12 +107,44 @@ pub fn decrypt(
     let crux_tag = C::Tag::from_slice(mac).unwrap();
 
     copy_slice(ciphertext).to(plaintext);
-    libcrux::aead::decrypt(&crux_key, plaintext, crux_iv, ad, &crux_tag).unwrap();
+    let res = libcrux::aead::decrypt(&crux_key, plaintext, crux_iv, ad, &crux_tag);
 
     match crux_key {
         C::Key::Chacha20Poly1305(mut k) => k.0.zeroize(),
         _ => panic!(),
     }
 
-    Ok(())
+    // Report authentication failures as errors like the RustCrypto implementation does
+    res.map_err(|_| anyhow::anyhow!("ChaCha20Poly1305 decryption failed"))
+}
+
+/// [Aead] implementation for ChaCha20Poly1305 as implemented in libcrux; see [encrypt] and [decrypt]
+pub enum ChaCha20Poly1305Libcrux {}
+
+impl Aead for ChaCha20Poly1305Libcrux {
+    type Error = anyhow::Error;
+
+    const KEY_LEN: usize = KEY_LEN;
+    const NONCE_LEN: usize = NONCE_LEN;
+    const TAG_LEN: usize = TAG_LEN;
+
+    fn encrypt(
+        ciphertext: &mut [u8],
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        plaintext: &[u8],
+    ) -> anyhow::Result<()> {
+        encrypt(ciphertext, key, nonce, ad, plaintext)
+    }
+
+    fn decrypt(
+        plaintext: &mut [u8],
+        key: &[u8],
+        nonce: &[u8],
+        ad: &[u8],
+        ciphertext: &[u8],
+    ) -> anyhow::Result<()> {
+        decrypt(plaintext, key, nonce, ad, ciphertext)
+    }
 }
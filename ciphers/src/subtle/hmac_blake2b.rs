@@ -0,0 +1,126 @@
+use anyhow::ensure;
+use rosenpass_cipher_traits::KeyedHash;
+use zeroize::Zeroizing;
+
+use blake2::digest::crypto_common::generic_array::GenericArray;
+use blake2::digest::crypto_common::typenum::U32;
+use blake2::digest::crypto_common::BlockSizeUser;
+use blake2::digest::{Digest, OutputSizeUser};
+use blake2::Blake2b;
+
+use rosenpass_constant_time::xor;
+use rosenpass_to::{ops::copy_slice, with_destination, To};
+use rosenpass_util::typenum2const;
+
+/// The underlying hash function: unkeyed BLAKE2b with an output length of 32 bytes
+type Impl = Blake2b<U32>;
+
+type BlockLen = <Impl as BlockSizeUser>::BlockSize;
+type OutLen = <Impl as OutputSizeUser>::OutputSize;
+
+/// The block length of BLAKE2b; 128 bytes. The key is padded to this length.
+const BLOCK_LEN: usize = typenum2const! { BlockLen };
+
+/// The key length, 32 bytes or 256 bits.
+pub const KEY_LEN: usize = 32;
+/// The minimal key length, identical to [KEY_LEN]
+pub const KEY_MIN: usize = KEY_LEN;
+/// The maximal key length, identical to [KEY_LEN]
+pub const KEY_MAX: usize = KEY_LEN;
+/// The output length, 32 bytes or 256 bits.
+pub const OUT_LEN: usize = typenum2const! { OutLen };
+/// The minimal output length, identical to [OUT_LEN]
+pub const OUT_MIN: usize = OUT_LEN;
+/// The maximal output length, identical to [OUT_LEN]
+pub const OUT_MAX: usize = OUT_LEN;
+
+/// HMAC as specified in [RFC 2104](https://www.rfc-editor.org/rfc/rfc2104), instantiated with
+/// BLAKE2b-256 (i.e. BLAKE2b with a 32 byte output length) as the hash function.
+///
+/// This is the replacement for [super::incorrect_hmac_blake2b], which XORs the padding into
+/// the key without extending it to the block length and then uses the keyed mode of BLAKE2b.
+///
+/// It accepts 32 byte keys, exclusively. Keys are padded with zeros to the 128 byte block length
+/// of BLAKE2b.
+///
+/// # Examples
+///```rust
+/// # use rosenpass_ciphers::subtle::hmac_blake2b::hash;
+/// use rosenpass_to::To;
+/// let key: [u8; 32] = [0; 32];
+/// let data: [u8; 32] = [255; 32];
+/// // buffer for the hash output
+/// let mut hash_data: [u8; 32] = [0u8; 32];
+///
+/// assert!(hash(&key, &data).to(&mut hash_data).is_ok(), "Hashing has to return OK result");
+/// # let expected_hash: &[u8] = &[22, 121, 232, 112, 241, 207, 160, 220, 90, 126, 135, 53, 164,
+/// # 183, 211, 224, 175, 135, 233, 6, 26, 97, 89, 117, 107, 191, 228, 87, 115, 39, 79, 151];
+/// # assert_eq!(hash_data, expected_hash);
+///
+/// // Only 32 byte keys are supported
+/// assert!(hash(&[0u8; 16], &data).to(&mut hash_data).is_err());
+///```
+///
+/// The output matches other implementations of RFC 2104, e.g. Python's `hmac` module used with
+/// `hashlib.blake2b(digest_size=32)`:
+///
+///```rust
+/// # use rosenpass_ciphers::subtle::hmac_blake2b::hash;
+/// use rosenpass_to::To;
+/// let key: Vec<u8> = (0u8..32).collect();
+/// let hash_data = hash(&key, b"Rosenpass").collect::<[u8; 32]>()?;
+/// assert_eq!(hash_data, [
+///     0x12, 0x5f, 0x9e, 0xb3, 0x64, 0x73, 0x69, 0x62, 0x58, 0xc2, 0x2a, 0x01, 0x0e, 0xe0, 0xf1,
+///     0xba, 0x6d, 0xce, 0xfa, 0xf3, 0xae, 0x36, 0xa6, 0x4e, 0xc3, 0xf8, 0x88, 0x7f, 0x1a, 0xc4,
+///     0x25, 0xe9,
+/// ]);
+/// # Ok::<(), anyhow::Error>(())
+///```
+///
+#[inline]
+pub fn hash<'a>(key: &'a [u8], data: &'a [u8]) -> impl To<[u8], anyhow::Result<()>> + 'a {
+    const IPAD: [u8; BLOCK_LEN] = [0x36u8; BLOCK_LEN];
+    // Applied on top of the inner padding to get the outer padding
+    const IPAD_TO_OPAD: [u8; BLOCK_LEN] = [0x36u8 ^ 0x5Cu8; BLOCK_LEN];
+
+    with_destination(|out: &mut [u8]| {
+        ensure!(key.len() == KEY_LEN);
+        ensure!(out.len() == OUT_LEN);
+
+        let mut padded_key = Zeroizing::new([0u8; BLOCK_LEN]);
+        copy_slice(key).to(&mut padded_key[..KEY_LEN]);
+
+        // H((K ⊕ ipad) || data)
+        xor(&IPAD).to(padded_key.as_mut());
+        let mut inner_hash = Zeroizing::new([0u8; OUT_LEN]);
+        let mut h = Impl::new();
+        h.update(padded_key.as_ref());
+        h.update(data);
+        h.finalize_into(GenericArray::from_mut_slice(inner_hash.as_mut()));
+
+        // H((K ⊕ opad) || H((K ⊕ ipad) || data))
+        xor(&IPAD_TO_OPAD).to(padded_key.as_mut());
+        let mut outer_hash = Zeroizing::new([0u8; OUT_LEN]);
+        let mut h = Impl::new();
+        h.update(padded_key.as_ref());
+        h.update(inner_hash.as_ref());
+        h.finalize_into(GenericArray::from_mut_slice(outer_hash.as_mut()));
+
+        copy_slice(outer_hash.as_ref()).to(out);
+        Ok(())
+    })
+}
+
+/// [KeyedHash] implementation for [hash]
+pub enum HmacBlake2b {}
+
+impl KeyedHash for HmacBlake2b {
+    type Error = anyhow::Error;
+
+    const KEY_LEN: usize = KEY_LEN;
+    const OUT_LEN: usize = OUT_MAX;
+
+    fn keyed_hash(key: &[u8], data: &[u8], out: &mut [u8]) -> anyhow::Result<()> {
+        hash(key, data).to(out)
+    }
+}
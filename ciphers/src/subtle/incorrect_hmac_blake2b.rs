@@ -1,4 +1,5 @@
 use anyhow::ensure;
+use rosenpass_cipher_traits::KeyedHash;
 use zeroize::Zeroizing;
 
 use rosenpass_constant_time::xor;
@@ -65,3 +66,17 @@ pub fn hash<'a>(key: &'a [u8], data: &'a [u8]) -> impl To<[u8], anyhow::Result<(
         Ok(())
     })
 }
+
+/// [KeyedHash] implementation for [hash]
+pub enum IncorrectHmacBlake2b {}
+
+impl KeyedHash for IncorrectHmacBlake2b {
+    type Error = anyhow::Error;
+
+    const KEY_LEN: usize = KEY_LEN;
+    const OUT_LEN: usize = OUT_MAX;
+
+    fn keyed_hash(key: &[u8], data: &[u8], out: &mut [u8]) -> anyhow::Result<()> {
+        hash(key, data).to(out)
+    }
+}
@@ -0,0 +1,72 @@
+use anyhow::ensure;
+use rosenpass_cipher_traits::KeyedHash;
+use rosenpass_to::{with_destination, To};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+/// The key length, 32 bytes or 256 bits.
+pub const KEY_LEN: usize = 32;
+/// The minimal key length, identical to [KEY_LEN]
+pub const KEY_MIN: usize = KEY_LEN;
+/// The maximal key length, identical to [KEY_LEN]
+pub const KEY_MAX: usize = KEY_LEN;
+/// The output length, 32 bytes or 256 bits.
+pub const OUT_LEN: usize = 32;
+/// The minimal output length, identical to [OUT_LEN]
+pub const OUT_MIN: usize = OUT_LEN;
+/// The maximal output length, identical to [OUT_LEN]
+pub const OUT_MAX: usize = OUT_LEN;
+
+/// A keyed hash based on the SHAKE256 extendable output function (FIPS 202)
+///
+/// The output is the first 32 bytes of `SHAKE256(key || data)`. Since SHAKE256 is a sponge
+/// construction and the key has a fixed length, prefixing the key is sufficient to turn it into
+/// a pseudo random function; no HMAC construction is needed.
+///
+/// It accepts 32 byte keys, exclusively.
+///
+/// # Examples
+///```rust
+/// # use rosenpass_ciphers::subtle::keyed_shake256::hash;
+/// use rosenpass_to::To;
+/// let key: [u8; 32] = [0; 32];
+/// let data: [u8; 32] = [255; 32];
+/// // buffer for the hash output
+/// let mut hash_data: [u8; 32] = [0u8; 32];
+///
+/// assert!(hash(&key, &data).to(&mut hash_data).is_ok(), "Hashing has to return OK result");
+/// # let expected_hash: &[u8] = &[174, 4, 47, 188, 1, 228, 179, 246, 67, 43, 255, 94, 155, 11,
+/// # 187, 161, 38, 110, 217, 23, 4, 62, 172, 30, 218, 187, 249, 80, 171, 21, 145, 238];
+/// # assert_eq!(hash_data, expected_hash);
+///
+/// // Only 32 byte keys are supported
+/// assert!(hash(&[0u8; 16], &data).to(&mut hash_data).is_err());
+///```
+///
+#[inline]
+pub fn hash<'a>(key: &'a [u8], data: &'a [u8]) -> impl To<[u8], anyhow::Result<()>> + 'a {
+    with_destination(|out: &mut [u8]| {
+        ensure!(key.len() == KEY_LEN);
+        ensure!(out.len() == OUT_LEN);
+
+        let mut h = Shake256::default();
+        h.update(key);
+        h.update(data);
+        h.finalize_xof().read(out);
+        Ok(())
+    })
+}
+
+/// [KeyedHash] implementation for [hash]
+pub enum KeyedShake256 {}
+
+impl KeyedHash for KeyedShake256 {
+    type Error = anyhow::Error;
+
+    const KEY_LEN: usize = KEY_LEN;
+    const OUT_LEN: usize = OUT_LEN;
+
+    fn keyed_hash(key: &[u8], data: &[u8], out: &mut [u8]) -> anyhow::Result<()> {
+        hash(key, data).to(out)
+    }
+}
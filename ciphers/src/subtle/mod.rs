@@ -1,13 +1,16 @@
 /// This module provides the following cryptographic schemes:
 /// - [blake2b]: The blake2b hash function
-/// - [chacha20poly1305_ietf]: The Chacha20Poly1305 AEAD as implemented in [RustCrypto](https://crates.io/crates/chacha20poly1305) (only used when the feature `experiment_libcrux` is disabled).
+/// - [chacha20poly1305_ietf]: The Chacha20Poly1305 AEAD as implemented in [RustCrypto](https://crates.io/crates/chacha20poly1305) (only used by [crate::aead] when the feature `experiment_libcrux` is disabled).
 /// - [chacha20poly1305_ietf_libcrux]: The Chacha20Poly1305 AEAD as implemented in [libcrux](https://github.com/cryspen/libcrux) (only used when the feature `experiment_libcrux` is enabled).
+/// - [hmac_blake2b]: HMAC (RFC 2104) based on BLAKE2b.
 /// - [incorrect_hmac_blake2b]: An (incorrect) hmac based on [blake2b].
+/// - [keyed_shake256]: A keyed hash based on SHAKE256.
 /// - [xchacha20poly1305_ietf] The Chacha20Poly1305 AEAD as implemented in [RustCrypto](https://crates.io/crates/chacha20poly1305)
 pub mod blake2b;
-#[cfg(not(feature = "experiment_libcrux"))]
 pub mod chacha20poly1305_ietf;
 #[cfg(feature = "experiment_libcrux")]
 pub mod chacha20poly1305_ietf_libcrux;
+pub mod hmac_blake2b;
 pub mod incorrect_hmac_blake2b;
+pub mod keyed_shake256;
 pub mod xchacha20poly1305_ietf;
@@ -23,4 +23,6 @@ macro_rules! oqs_call {
 #[macro_use]
 mod kem_macro;
 oqs_kem!(kyber_512);
+oqs_kem!(ml_kem_512);
+oqs_kem!(ml_kem_768);
 oqs_kem!(classic_mceliece_460896);
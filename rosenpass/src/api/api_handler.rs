@@ -1,9 +1,10 @@
 // Note: This is business logic; tested through the integration tests in
 // rosenpass/tests/
 
-use std::{borrow::BorrowMut, collections::VecDeque, os::fd::OwnedFd};
+use std::{borrow::BorrowMut, collections::VecDeque, net::SocketAddr, os::fd::OwnedFd};
 
 use anyhow::Context;
+use rosenpass_secret_memory::Public;
 use rosenpass_to::{ops::copy_slice, To};
 use rosenpass_util::{
     fd::FdIo,
@@ -13,12 +14,25 @@ use rosenpass_util::{
     mio::UnixStreamExt,
     result::OkExt,
 };
-use rosenpass_wireguard_broker::brokers::mio_client::MioBrokerClient;
+use rosenpass_wireguard_broker::brokers::{
+    mio_client::MioBrokerClient,
+    native_unix::{NativeUnixBrokerConfigBaseBuilder, NativeUnixBrokerConfigBaseBuilderError},
+};
 
 use crate::{
-    api::{add_listen_socket_response_status, add_psk_broker_response_status},
-    app_server::AppServer,
-    protocol::BuildCryptoServer,
+    api::{
+        add_listen_socket_response_status, add_peer_response_status,
+        add_psk_broker_response_status, fetch_events_response_status, force_rekey_response_status,
+        get_peer_status_response_status, list_peers_response_status, remove_peer_response_status,
+        rotate_keypair_response_status, set_log_level_response_status,
+        set_peer_timing_response_status, shutdown_response_status, subscribe_response_status,
+        AddPeerResponse, ApiEvent, ApiPeerStatus, FetchEventsResponse, GetPeerStatusResponse,
+        ListPeersResponse, SetLogLevelResponse, SubscribeResponse,
+    },
+    app_server::{AppPeerPtr, AppServer, BrokerPeer, BrokerStorePtr, Endpoint},
+    endpoint_refresh::validate_hostname,
+    logging,
+    protocol::{BuildCryptoServer, PeerId, PeerParams, SPk, SSk, SymKey},
 };
 
 use super::{supply_keypair_response_status, Server as ApiServer};
@@ -31,14 +45,31 @@ use super::{supply_keypair_response_status, Server as ApiServer};
 /// [ApiHandlerContext] is what actually contains the API handler functions.
 #[derive(Debug)]
 pub struct ApiHandler {
-    _dummy: (),
+    /// Set once the connection was turned into an event stream through
+    /// [ApiServer::subscribe]; the sequence number of the last event sent
+    subscribed_after: Option<u64>,
 }
 
 impl ApiHandler {
     /// Construct an [Self]
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self { _dummy: () }
+        Self {
+            subscribed_after: None,
+        }
+    }
+
+    /// If the connection was turned into an event stream through [ApiServer::subscribe],
+    /// the sequence number of the last event sent
+    pub fn subscribed_after(&self) -> Option<u64> {
+        self.subscribed_after
+    }
+
+    /// Record that the event with the given sequence number was sent to the subscriber;
+    /// see [Self::subscribed_after]
+    pub fn event_sent(&mut self, seq: u64) {
+        debug_assert!(self.subscribed_after.is_some());
+        self.subscribed_after = Some(seq);
     }
 }
 
@@ -121,6 +152,24 @@ impl<T, E: Into<anyhow::Error>> SupplyKeypairErrorExt<T> for Result<T, E> {
     }
 }
 
+/// The PSK broker registered most recently, if any; see [ApiServer::add_psk_broker]
+fn latest_broker(srv: &AppServer) -> Option<BrokerStorePtr> {
+    use zerocopy::AsBytes;
+    let idx = srv.brokers.store.len().checked_sub(1)? as u64;
+    Some(BrokerStorePtr(Public::from_slice(idx.as_bytes())))
+}
+
+/// Encode the status of a peer; see [ApiServer::list_peers] and [ApiServer::get_peer_status]
+fn api_peer_status(srv: &AppServer, peer: AppPeerPtr) -> anyhow::Result<ApiPeerStatus> {
+    // During peer discovery, there is no single address in use
+    let endpoint = match &peer.get_app(srv).current_endpoint {
+        Some(ep @ Endpoint::SocketBoundAddress(_)) => ep.addresses().first().copied(),
+        _ => None,
+    };
+    let status = srv.peer_status(peer)?;
+    Ok(ApiPeerStatus::new(srv.peer_id(peer)?, &status, endpoint))
+}
+
 impl<T> ApiServer for T
 where
     T: ?Sized + ApiHandlerContext,
@@ -338,4 +387,454 @@ where
         res.payload.status = add_psk_broker_response_status::OK;
         Ok(())
     }
+
+    fn set_peer_timing(
+        &mut self,
+        req: &super::boilerplate::SetPeerTimingRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::SetPeerTimingResponse,
+    ) -> anyhow::Result<()> {
+        use set_peer_timing_response_status as status;
+
+        let timing = req.payload.timing_profile();
+        if let Err(e) = timing.validate() {
+            log::debug!(
+                "Request found to be invalid while processing SetPeerTiming API request: {e:?}"
+            );
+            res.payload.status = status::INVALID_REQUEST;
+            return Ok(());
+        }
+
+        let peer_id = PeerId::from_slice(&req.payload.peer_id);
+        let peer = match self.app_server().find_peer(peer_id) {
+            Ok(Some(peer)) => peer,
+            Ok(None) => {
+                res.payload.status = status::NO_SUCH_PEER;
+                return Ok(());
+            }
+            Err(e) => {
+                log::warn!("Internal error while processing SetPeerTiming API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+                return Ok(());
+            }
+        };
+
+        if let Err(e) = self.app_server_mut().set_peer_timing(peer, timing) {
+            log::warn!("Internal error while processing SetPeerTiming API request: {e:?}");
+            res.payload.status = status::INTERNAL_ERROR;
+            return Ok(());
+        }
+
+        res.payload.status = status::OK;
+        Ok(())
+    }
+
+    fn fetch_events(
+        &mut self,
+        req: &super::boilerplate::FetchEventsRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::FetchEventsResponse,
+    ) -> anyhow::Result<()> {
+        let (missed, events) = self.app_server().event_log.since(req.payload.after);
+        *res = FetchEventsResponse::new(fetch_events_response_status::OK, missed, events);
+        Ok(())
+    }
+
+    fn set_log_level(
+        &mut self,
+        req: &super::boilerplate::SetLogLevelRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::SetLogLevelResponse,
+    ) -> anyhow::Result<()> {
+        use set_log_level_response_status as status;
+
+        let filter = match req.payload.filter() {
+            Ok(Some(filter)) => {
+                logging::override_log_filter(filter.clone());
+                filter
+            }
+            Ok(None) => logging::reset_log_filter(),
+            Err(e) => {
+                log::debug!(
+                    "Request found to be invalid while processing SetLogLevel API request: {e:?}"
+                );
+                *res = SetLogLevelResponse::new(status::INVALID_REQUEST, &logging::log_filter());
+                return Ok(());
+            }
+        };
+
+        log::info!(
+            "Log filter changed through the API to {:?}",
+            filter.to_string()
+        );
+        *res = SetLogLevelResponse::new(status::OK, &filter);
+        Ok(())
+    }
+
+    fn add_peer(
+        &mut self,
+        req: &super::boilerplate::AddPeerRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::AddPeerResponse,
+    ) -> anyhow::Result<()> {
+        use add_peer_response_status as status;
+
+        // Read the keys and check the endpoint
+        let parsed = run(|| -> anyhow::Result<_> {
+            let pk_fd = req_fds
+                .pop_front()
+                .context("First file descriptor, public key, missing.")?;
+            let mut pk = SPk::zero();
+            FdIo(&pk_fd).read_exact_til_end(pk.borrow_mut())?;
+
+            let psk = match req_fds.pop_front() {
+                Some(psk_fd) => {
+                    let mut psk = SymKey::zero();
+                    FdIo(&psk_fd).read_exact_til_end(psk.secret_mut())?;
+                    Some(psk)
+                }
+                None => None,
+            };
+
+            let hostname = req.payload.endpoint()?;
+            if let Some(hostname) = hostname.as_deref() {
+                validate_hostname(hostname)?;
+            }
+            // Literal addresses are used right away; hostnames are resolved in the background,
+            // so DNS lookups never block the event loop
+            let endpoint = hostname
+                .as_deref()
+                .and_then(|hostname| hostname.parse::<SocketAddr>().ok())
+                .map(|addr| Endpoint::discovery_from_addresses(vec![addr]));
+
+            let broker_peer = match req.payload.wireguard()? {
+                Some((device, peer)) => {
+                    let broker = latest_broker(self.app_server())
+                        .context("Keys can not be supplied to WireGuard without a PSK broker")?;
+                    let peer_cfg = NativeUnixBrokerConfigBaseBuilder::default()
+                        .peer_id(Public::from_slice(&peer))
+                        .interface(device)
+                        .extra_params_ser(&Vec::new())?
+                        .build()
+                        .map_err(|e: NativeUnixBrokerConfigBaseBuilderError| {
+                            anyhow::Error::msg(format!(
+                                "NativeUnixBrokerConfigBaseBuilderError: {:?}",
+                                e
+                            ))
+                        })?;
+                    Some(BrokerPeer::new(broker, Box::new(peer_cfg)))
+                }
+                None => None,
+            };
+
+            Ok((pk, psk, hostname, endpoint, broker_peer))
+        });
+
+        let (pk, psk, hostname, endpoint, broker_peer) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                log::debug!(
+                    "Request found to be invalid while processing AddPeer API request: {e:?}"
+                );
+                res.payload.status = status::INVALID_REQUEST;
+                return Ok(());
+            }
+        };
+
+        // Peers are identified by their public key
+        let existing = run(|| -> anyhow::Result<_> {
+            let peer_id = PeerParams::new(None, pk.clone()).pidt()?;
+            let existing = self.app_server().find_peer(peer_id)?;
+            Ok((peer_id, existing))
+        });
+        let peer_id = match existing {
+            Ok((peer_id, None)) => peer_id,
+            Ok((peer_id, Some(_))) => {
+                *res = AddPeerResponse::new(status::PEER_ALREADY_EXISTS, peer_id.value);
+                return Ok(());
+            }
+            Err(e) => {
+                log::warn!("Internal error while processing AddPeer API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+                return Ok(());
+            }
+        };
+
+        let key_out = req.payload.key_out();
+        let srv = self.app_server_mut();
+        let peer = match srv.add_peer(psk, pk, key_out, broker_peer, None) {
+            Ok(peer) => peer,
+            Err(e) => {
+                log::warn!("Internal error while processing AddPeer API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+                return Ok(());
+            }
+        };
+        let resolve = endpoint.is_none() && hostname.is_some();
+        let ap = peer.get_app_mut(srv);
+        ap.initial_endpoint = endpoint;
+        ap.hostname = hostname;
+        if resolve {
+            if let Err(e) = srv.resolve_endpoint_in_background(peer) {
+                log::warn!("Internal error while processing AddPeer API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+                return Ok(());
+            }
+        }
+
+        *res = AddPeerResponse::new(status::OK, peer_id.value);
+        Ok(())
+    }
+
+    fn remove_peer(
+        &mut self,
+        req: &super::boilerplate::RemovePeerRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::RemovePeerResponse,
+    ) -> anyhow::Result<()> {
+        use remove_peer_response_status as status;
+
+        if !self.app_server().crypto_site.is_available() {
+            log::debug!(
+                "Request found to be invalid while processing RemovePeer API request: \
+                Peers can not be removed before the keypair is supplied"
+            );
+            res.payload.status = status::INVALID_REQUEST;
+            return Ok(());
+        }
+
+        let peer_id = PeerId::from_slice(&req.payload.peer_id);
+        let peer = match self.app_server().find_peer(peer_id) {
+            Ok(Some(peer)) => peer,
+            Ok(None) => {
+                res.payload.status = status::NO_SUCH_PEER;
+                return Ok(());
+            }
+            Err(e) => {
+                log::warn!("Internal error while processing RemovePeer API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+                return Ok(());
+            }
+        };
+
+        if let Err(e) = self.app_server_mut().remove_peer(peer) {
+            log::warn!("Internal error while processing RemovePeer API request: {e:?}");
+            res.payload.status = status::INTERNAL_ERROR;
+            return Ok(());
+        }
+
+        res.payload.status = status::OK;
+        Ok(())
+    }
+
+    fn list_peers(
+        &mut self,
+        req: &super::boilerplate::ListPeersRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::ListPeersResponse,
+    ) -> anyhow::Result<()> {
+        use list_peers_response_status as status;
+
+        let srv = self.app_server();
+        let offset = usize::try_from(req.payload.offset).unwrap_or(usize::MAX);
+        let peers = run(|| -> anyhow::Result<_> {
+            let mut peers = Vec::new();
+            for peer in srv
+                .peer_ptrs()
+                .skip(offset)
+                .take(super::MAX_PEERS_PER_RESPONSE)
+            {
+                peers.push(api_peer_status(srv, peer)?);
+            }
+            Ok(peers)
+        });
+
+        match peers {
+            Ok(peers) => {
+                let total = srv.peer_ptrs().count() as u64;
+                *res = ListPeersResponse::new(status::OK, total, peers);
+            }
+            Err(e) => {
+                log::warn!("Internal error while processing ListPeers API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+            }
+        }
+        Ok(())
+    }
+
+    fn get_peer_status(
+        &mut self,
+        req: &super::boilerplate::GetPeerStatusRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::GetPeerStatusResponse,
+    ) -> anyhow::Result<()> {
+        use get_peer_status_response_status as status;
+
+        let srv = self.app_server();
+        let peer_id = PeerId::from_slice(&req.payload.peer_id);
+        let peer = run(|| -> anyhow::Result<_> {
+            srv.find_peer(peer_id)?
+                .map(|peer| api_peer_status(srv, peer))
+                .transpose()
+        });
+
+        match peer {
+            Ok(Some(peer)) => *res = GetPeerStatusResponse::new(status::OK, peer),
+            Ok(None) => res.payload.status = status::NO_SUCH_PEER,
+            Err(e) => {
+                log::warn!("Internal error while processing GetPeerStatus API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+            }
+        }
+        Ok(())
+    }
+
+    fn force_rekey(
+        &mut self,
+        req: &super::boilerplate::ForceRekeyRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::ForceRekeyResponse,
+    ) -> anyhow::Result<()> {
+        use force_rekey_response_status as status;
+
+        let erase_key = match req.payload.erase_key {
+            0 => false,
+            1 => true,
+            v => {
+                log::debug!(
+                    "Request found to be invalid while processing ForceRekey API request: \
+                    Invalid value for erase_key: {v}"
+                );
+                res.payload.status = status::INVALID_REQUEST;
+                return Ok(());
+            }
+        };
+
+        if !self.app_server().crypto_site.is_available() {
+            log::debug!(
+                "Request found to be invalid while processing ForceRekey API request: \
+                Handshakes can not be initiated before the keypair is supplied"
+            );
+            res.payload.status = status::INVALID_REQUEST;
+            return Ok(());
+        }
+
+        let peer_id = PeerId::from_slice(&req.payload.peer_id);
+        let peer = match self.app_server().find_peer(peer_id) {
+            Ok(Some(peer)) => peer,
+            Ok(None) => {
+                res.payload.status = status::NO_SUCH_PEER;
+                return Ok(());
+            }
+            Err(e) => {
+                log::warn!("Internal error while processing ForceRekey API request: {e:?}");
+                res.payload.status = status::INTERNAL_ERROR;
+                return Ok(());
+            }
+        };
+
+        if peer.get_app(self.app_server()).endpoint().is_none() {
+            res.payload.status = status::NO_ENDPOINT;
+            return Ok(());
+        }
+
+        if let Err(e) = self.app_server_mut().force_rekey(peer, erase_key) {
+            log::warn!("Internal error while processing ForceRekey API request: {e:?}");
+            res.payload.status = status::INTERNAL_ERROR;
+            return Ok(());
+        }
+
+        res.payload.status = status::OK;
+        Ok(())
+    }
+
+    fn subscribe(
+        &mut self,
+        req: &super::boilerplate::SubscribeRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::SubscribeResponse,
+    ) -> anyhow::Result<()> {
+        use subscribe_response_status as status;
+        use zerocopy::FromZeroes;
+
+        // The events are sent by the connection itself; see [crate::api::mio::MioConnectionContext]
+        let after = req
+            .payload
+            .after
+            .min(self.app_server().event_log.last_seq());
+        self.api_handler_mut().subscribed_after = Some(after);
+
+        *res = SubscribeResponse::new(status::OK, 0, ApiEvent::new_zeroed());
+        Ok(())
+    }
+
+    fn shutdown(
+        &mut self,
+        _req: &super::boilerplate::ShutdownRequest,
+        _req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::ShutdownResponse,
+    ) -> anyhow::Result<()> {
+        use shutdown_response_status as status;
+
+        // The response is flushed before the event loop gets to process the request
+        self.app_server_mut().request_shutdown();
+        res.payload.status = status::OK;
+        Ok(())
+    }
+
+    fn rotate_keypair(
+        &mut self,
+        req: &super::boilerplate::RotateKeypairRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::boilerplate::RotateKeypairResponse,
+    ) -> anyhow::Result<()> {
+        use rotate_keypair_response_status as status;
+
+        if !self.app_server().crypto_site.is_available() {
+            log::debug!(
+                "Request found to be invalid while processing RotateKeypair API request: \
+                No keypair to rotate; the keypair must be supplied first"
+            );
+            res.payload.status = status::NO_KEYPAIR;
+            return Ok(());
+        }
+
+        // Read the keys
+        let keypair = run(|| -> anyhow::Result<_> {
+            let sk_fd = req_fds
+                .pop_front()
+                .context("First file descriptor, secret key, missing.")?;
+            let mut sk = SSk::zero();
+            FdIo(&sk_fd).read_exact_til_end(sk.secret_mut())?;
+
+            let pk_fd = req_fds
+                .pop_front()
+                .context("Second file descriptor, public key, missing.")?;
+            let mut pk = SPk::zero();
+            FdIo(&pk_fd).read_exact_til_end(pk.borrow_mut())?;
+
+            Ok((sk, pk))
+        });
+
+        let (sk, pk) = match keypair {
+            Ok(keypair) => keypair,
+            Err(e) => {
+                log::debug!(
+                    "Request found to be invalid while processing RotateKeypair API request: {e:?}"
+                );
+                res.payload.status = status::INVALID_REQUEST;
+                return Ok(());
+            }
+        };
+
+        let grace = req.payload.grace_period_ms as f64 / 1000.0;
+        if let Err(e) = self.app_server_mut().rotate_keypair(sk, pk, grace) {
+            log::warn!("Internal error while processing RotateKeypair API request: {e:?}");
+            res.payload.status = status::INTERNAL_ERROR;
+            return Ok(());
+        }
+
+        res.payload.status = status::OK;
+        Ok(())
+    }
 }
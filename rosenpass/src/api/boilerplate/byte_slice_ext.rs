@@ -257,6 +257,451 @@ pub trait ByteSliceRefExt: ByteSlice {
     ) -> anyhow::Result<Ref<Self, super::AddPskBrokerResponse>> {
         self.zk_parse_suffix()
     }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn set_peer_timing_request(self) -> anyhow::Result<Ref<Self, super::SetPeerTimingRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn set_peer_timing_request_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetPeerTimingRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn set_peer_timing_request_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetPeerTimingRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn set_peer_timing_response_maker(self) -> RefMaker<Self, super::SetPeerTimingResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn set_peer_timing_response(self) -> anyhow::Result<Ref<Self, super::SetPeerTimingResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn set_peer_timing_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetPeerTimingResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn set_peer_timing_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetPeerTimingResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn fetch_events_request(self) -> anyhow::Result<Ref<Self, super::FetchEventsRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn fetch_events_request_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::FetchEventsRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn fetch_events_request_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::FetchEventsRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn fetch_events_response_maker(self) -> RefMaker<Self, super::FetchEventsResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn fetch_events_response(self) -> anyhow::Result<Ref<Self, super::FetchEventsResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn fetch_events_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::FetchEventsResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn fetch_events_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::FetchEventsResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn set_log_level_request(self) -> anyhow::Result<Ref<Self, super::SetLogLevelRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn set_log_level_request_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetLogLevelRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn set_log_level_request_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetLogLevelRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn set_log_level_response_maker(self) -> RefMaker<Self, super::SetLogLevelResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn set_log_level_response(self) -> anyhow::Result<Ref<Self, super::SetLogLevelResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn set_log_level_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetLogLevelResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn set_log_level_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::SetLogLevelResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn add_peer_request(self) -> anyhow::Result<Ref<Self, super::AddPeerRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn add_peer_request_from_prefix(self) -> anyhow::Result<Ref<Self, super::AddPeerRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn add_peer_request_from_suffix(self) -> anyhow::Result<Ref<Self, super::AddPeerRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn add_peer_response_maker(self) -> RefMaker<Self, super::AddPeerResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn add_peer_response(self) -> anyhow::Result<Ref<Self, super::AddPeerResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn add_peer_response_from_prefix(self) -> anyhow::Result<Ref<Self, super::AddPeerResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn add_peer_response_from_suffix(self) -> anyhow::Result<Ref<Self, super::AddPeerResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn remove_peer_request(self) -> anyhow::Result<Ref<Self, super::RemovePeerRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn remove_peer_request_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RemovePeerRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn remove_peer_request_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RemovePeerRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn remove_peer_response_maker(self) -> RefMaker<Self, super::RemovePeerResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn remove_peer_response(self) -> anyhow::Result<Ref<Self, super::RemovePeerResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn remove_peer_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RemovePeerResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn remove_peer_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RemovePeerResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn list_peers_request(self) -> anyhow::Result<Ref<Self, super::ListPeersRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn list_peers_request_from_prefix(self) -> anyhow::Result<Ref<Self, super::ListPeersRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn list_peers_request_from_suffix(self) -> anyhow::Result<Ref<Self, super::ListPeersRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn list_peers_response_maker(self) -> RefMaker<Self, super::ListPeersResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn list_peers_response(self) -> anyhow::Result<Ref<Self, super::ListPeersResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn list_peers_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::ListPeersResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn list_peers_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::ListPeersResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn get_peer_status_request(self) -> anyhow::Result<Ref<Self, super::GetPeerStatusRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn get_peer_status_request_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::GetPeerStatusRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn get_peer_status_request_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::GetPeerStatusRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn get_peer_status_response_maker(self) -> RefMaker<Self, super::GetPeerStatusResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn get_peer_status_response(self) -> anyhow::Result<Ref<Self, super::GetPeerStatusResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn get_peer_status_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::GetPeerStatusResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn get_peer_status_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::GetPeerStatusResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn force_rekey_request(self) -> anyhow::Result<Ref<Self, super::ForceRekeyRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn force_rekey_request_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::ForceRekeyRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn force_rekey_request_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::ForceRekeyRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn force_rekey_response_maker(self) -> RefMaker<Self, super::ForceRekeyResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn force_rekey_response(self) -> anyhow::Result<Ref<Self, super::ForceRekeyResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn force_rekey_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::ForceRekeyResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn force_rekey_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::ForceRekeyResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn subscribe_request(self) -> anyhow::Result<Ref<Self, super::SubscribeRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn subscribe_request_from_prefix(self) -> anyhow::Result<Ref<Self, super::SubscribeRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn subscribe_request_from_suffix(self) -> anyhow::Result<Ref<Self, super::SubscribeRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn subscribe_response_maker(self) -> RefMaker<Self, super::SubscribeResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn subscribe_response(self) -> anyhow::Result<Ref<Self, super::SubscribeResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn subscribe_response_from_prefix(self) -> anyhow::Result<Ref<Self, super::SubscribeResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn subscribe_response_from_suffix(self) -> anyhow::Result<Ref<Self, super::SubscribeResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn shutdown_request(self) -> anyhow::Result<Ref<Self, super::ShutdownRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn shutdown_request_from_prefix(self) -> anyhow::Result<Ref<Self, super::ShutdownRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn shutdown_request_from_suffix(self) -> anyhow::Result<Ref<Self, super::ShutdownRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn shutdown_response_maker(self) -> RefMaker<Self, super::ShutdownResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn shutdown_response(self) -> anyhow::Result<Ref<Self, super::ShutdownResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn shutdown_response_from_prefix(self) -> anyhow::Result<Ref<Self, super::ShutdownResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn shutdown_response_from_suffix(self) -> anyhow::Result<Ref<Self, super::ShutdownResponse>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn rotate_keypair_request(self) -> anyhow::Result<Ref<Self, super::RotateKeypairRequest>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn rotate_keypair_request_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RotateKeypairRequest>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn rotate_keypair_request_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RotateKeypairRequest>> {
+        self.zk_parse_suffix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_ref_maker].
+    fn rotate_keypair_response_maker(self) -> RefMaker<Self, super::RotateKeypairResponse> {
+        self.zk_ref_maker()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse].
+    fn rotate_keypair_response(self) -> anyhow::Result<Ref<Self, super::RotateKeypairResponse>> {
+        self.zk_parse()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_prefix].
+    fn rotate_keypair_response_from_prefix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RotateKeypairResponse>> {
+        self.zk_parse_prefix()
+    }
+
+    /// Shorthand for the typed use of [ZerocopySliceExt::zk_parse_suffix].
+    fn rotate_keypair_response_from_suffix(
+        self,
+    ) -> anyhow::Result<Ref<Self, super::RotateKeypairResponse>> {
+        self.zk_parse_suffix()
+    }
 }
 
 impl<B: ByteSlice> ByteSliceRefExt for B {}
@@ -35,6 +35,83 @@ const ADD_PSK_BROKER_REQUEST: RawMsgType =
 const ADD_PSK_BROKER_RESPONSE: RawMsgType =
     RawMsgType::from_le_bytes(hex!("bd25 e418 ffb0 6930    248b 217e 2fae e353"));
 
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Set Peer Timing Request
+const SET_PEER_TIMING_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("920d 4809 0376 efdd    b7be a85c 0f10 d2b5"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Set Peer Timing Response
+const SET_PEER_TIMING_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("e52f 3c6f b3de 9b1d    3daf 21d4 6dbb 3964"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Fetch Events Request
+const FETCH_EVENTS_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("404f 413a ef1f 4549    bc43 f34c 4d48 b3de"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Fetch Events Response
+const FETCH_EVENTS_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("231b 9045 0b6c 41b2    11f9 8c3a 73c2 2612"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Set Log Level Request
+const SET_LOG_LEVEL_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("f61e ab14 438f 7ac9    c285 4c97 a0ba 6d17"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Set Log Level Response
+const SET_LOG_LEVEL_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("6b78 be1e c583 389c    5afc 3fe9 36c3 6fb8"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Add Peer Request
+const ADD_PEER_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("69ac 7483 ce72 b30b    cf7f 37ad 40da a4a0"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Add Peer Response
+const ADD_PEER_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("ff13 d033 8575 b2eb    3615 d0ad 81c7 4b34"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Remove Peer Request
+const REMOVE_PEER_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("d33c 778c 7434 40ab    d3cb 115c 5fd5 a18c"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Remove Peer Response
+const REMOVE_PEER_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("c45e 842e 9c1f 377c    3bb3 3f0a d037 c867"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> List Peers Request
+const LIST_PEERS_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("97b8 a3ca 8d8c 924f    02c8 b967 1bfc 3775"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> List Peers Response
+const LIST_PEERS_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("16d6 30ea 2182 edcd    3158 461c 928b 886c"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Get Peer Status Request
+const GET_PEER_STATUS_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("7048 4da2 8178 2207    46ea 0db7 ee93 1272"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Get Peer Status Response
+const GET_PEER_STATUS_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("de5e da3b 7a58 ef8f    6a5e 604b b859 6e7f"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Force Rekey Request
+const FORCE_REKEY_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("8fca b26e 8d8c 10a9    655d de9f 4343 2f0f"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Force Rekey Response
+const FORCE_REKEY_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("0286 fced 168c d442    64b0 d00a 95cd 9bb3"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Subscribe Request
+const SUBSCRIBE_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("c652 4f7f 44be 8fc7    9707 c62b 042e d750"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Subscribe Response
+const SUBSCRIBE_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("b83a 1f24 9a6d 8948    f849 7302 481f be38"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Shutdown Request
+const SHUTDOWN_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("b742 7432 361a 353c    b422 e3cc 3ff7 ed31"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Shutdown Response
+const SHUTDOWN_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("4ae7 4525 035d 7c4c    4691 f45c 44a9 4591"));
+
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Rotate Keypair Request
+const ROTATE_KEYPAIR_REQUEST: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("3484 de06 2c70 e714    9525 4972 a805 2668"));
+// hash domain hash of: Rosenpass IPC API -> Rosenpass Protocol Server -> Rotate Keypair Response
+const ROTATE_KEYPAIR_RESPONSE: RawMsgType =
+    RawMsgType::from_le_bytes(hex!("0125 54c8 3adc 597d    df97 ff46 4118 64ff"));
+
 /// Message properties global to the message type
 pub trait MessageAttributes {
     /// Get the size of the message
@@ -50,6 +127,17 @@ pub enum RequestMsgType {
     SupplyKeypair,
     AddListenSocket,
     AddPskBroker,
+    SetPeerTiming,
+    FetchEvents,
+    SetLogLevel,
+    AddPeer,
+    RemovePeer,
+    ListPeers,
+    GetPeerStatus,
+    ForceRekey,
+    Subscribe,
+    Shutdown,
+    RotateKeypair,
 }
 
 /// API response messages types as an enum
@@ -59,6 +147,17 @@ pub enum ResponseMsgType {
     SupplyKeypair,
     AddListenSocket,
     AddPskBroker,
+    SetPeerTiming,
+    FetchEvents,
+    SetLogLevel,
+    AddPeer,
+    RemovePeer,
+    ListPeers,
+    GetPeerStatus,
+    ForceRekey,
+    Subscribe,
+    Shutdown,
+    RotateKeypair,
 }
 
 impl MessageAttributes for RequestMsgType {
@@ -68,6 +167,17 @@ impl MessageAttributes for RequestMsgType {
             Self::SupplyKeypair => std::mem::size_of::<super::SupplyKeypairRequest>(),
             Self::AddListenSocket => std::mem::size_of::<super::AddListenSocketRequest>(),
             Self::AddPskBroker => std::mem::size_of::<super::AddPskBrokerRequest>(),
+            Self::SetPeerTiming => std::mem::size_of::<super::SetPeerTimingRequest>(),
+            Self::FetchEvents => std::mem::size_of::<super::FetchEventsRequest>(),
+            Self::SetLogLevel => std::mem::size_of::<super::SetLogLevelRequest>(),
+            Self::AddPeer => std::mem::size_of::<super::AddPeerRequest>(),
+            Self::RemovePeer => std::mem::size_of::<super::RemovePeerRequest>(),
+            Self::ListPeers => std::mem::size_of::<super::ListPeersRequest>(),
+            Self::GetPeerStatus => std::mem::size_of::<super::GetPeerStatusRequest>(),
+            Self::ForceRekey => std::mem::size_of::<super::ForceRekeyRequest>(),
+            Self::Subscribe => std::mem::size_of::<super::SubscribeRequest>(),
+            Self::Shutdown => std::mem::size_of::<super::ShutdownRequest>(),
+            Self::RotateKeypair => std::mem::size_of::<super::RotateKeypairRequest>(),
         }
     }
 }
@@ -79,6 +189,17 @@ impl MessageAttributes for ResponseMsgType {
             Self::SupplyKeypair => std::mem::size_of::<super::SupplyKeypairResponse>(),
             Self::AddListenSocket => std::mem::size_of::<super::AddListenSocketResponse>(),
             Self::AddPskBroker => std::mem::size_of::<super::AddPskBrokerResponse>(),
+            Self::SetPeerTiming => std::mem::size_of::<super::SetPeerTimingResponse>(),
+            Self::FetchEvents => std::mem::size_of::<super::FetchEventsResponse>(),
+            Self::SetLogLevel => std::mem::size_of::<super::SetLogLevelResponse>(),
+            Self::AddPeer => std::mem::size_of::<super::AddPeerResponse>(),
+            Self::RemovePeer => std::mem::size_of::<super::RemovePeerResponse>(),
+            Self::ListPeers => std::mem::size_of::<super::ListPeersResponse>(),
+            Self::GetPeerStatus => std::mem::size_of::<super::GetPeerStatusResponse>(),
+            Self::ForceRekey => std::mem::size_of::<super::ForceRekeyResponse>(),
+            Self::Subscribe => std::mem::size_of::<super::SubscribeResponse>(),
+            Self::Shutdown => std::mem::size_of::<super::ShutdownResponse>(),
+            Self::RotateKeypair => std::mem::size_of::<super::RotateKeypairResponse>(),
         }
     }
 }
@@ -93,6 +214,17 @@ impl TryFrom<RawMsgType> for RequestMsgType {
             self::SUPPLY_KEYPAIR_REQUEST => E::SupplyKeypair,
             self::ADD_LISTEN_SOCKET_REQUEST => E::AddListenSocket,
             self::ADD_PSK_BROKER_REQUEST => E::AddPskBroker,
+            self::SET_PEER_TIMING_REQUEST => E::SetPeerTiming,
+            self::FETCH_EVENTS_REQUEST => E::FetchEvents,
+            self::SET_LOG_LEVEL_REQUEST => E::SetLogLevel,
+            self::ADD_PEER_REQUEST => E::AddPeer,
+            self::REMOVE_PEER_REQUEST => E::RemovePeer,
+            self::LIST_PEERS_REQUEST => E::ListPeers,
+            self::GET_PEER_STATUS_REQUEST => E::GetPeerStatus,
+            self::FORCE_REKEY_REQUEST => E::ForceRekey,
+            self::SUBSCRIBE_REQUEST => E::Subscribe,
+            self::SHUTDOWN_REQUEST => E::Shutdown,
+            self::ROTATE_KEYPAIR_REQUEST => E::RotateKeypair,
             _ => return Err(InvalidApiMessageType(value)),
         })
     }
@@ -106,6 +238,17 @@ impl From<RequestMsgType> for RawMsgType {
             E::SupplyKeypair => self::SUPPLY_KEYPAIR_REQUEST,
             E::AddListenSocket => self::ADD_LISTEN_SOCKET_REQUEST,
             E::AddPskBroker => self::ADD_PSK_BROKER_REQUEST,
+            E::SetPeerTiming => self::SET_PEER_TIMING_REQUEST,
+            E::FetchEvents => self::FETCH_EVENTS_REQUEST,
+            E::SetLogLevel => self::SET_LOG_LEVEL_REQUEST,
+            E::AddPeer => self::ADD_PEER_REQUEST,
+            E::RemovePeer => self::REMOVE_PEER_REQUEST,
+            E::ListPeers => self::LIST_PEERS_REQUEST,
+            E::GetPeerStatus => self::GET_PEER_STATUS_REQUEST,
+            E::ForceRekey => self::FORCE_REKEY_REQUEST,
+            E::Subscribe => self::SUBSCRIBE_REQUEST,
+            E::Shutdown => self::SHUTDOWN_REQUEST,
+            E::RotateKeypair => self::ROTATE_KEYPAIR_REQUEST,
         }
     }
 }
@@ -120,6 +263,17 @@ impl TryFrom<RawMsgType> for ResponseMsgType {
             self::SUPPLY_KEYPAIR_RESPONSE => E::SupplyKeypair,
             self::ADD_LISTEN_SOCKET_RESPONSE => E::AddListenSocket,
             self::ADD_PSK_BROKER_RESPONSE => E::AddPskBroker,
+            self::SET_PEER_TIMING_RESPONSE => E::SetPeerTiming,
+            self::FETCH_EVENTS_RESPONSE => E::FetchEvents,
+            self::SET_LOG_LEVEL_RESPONSE => E::SetLogLevel,
+            self::ADD_PEER_RESPONSE => E::AddPeer,
+            self::REMOVE_PEER_RESPONSE => E::RemovePeer,
+            self::LIST_PEERS_RESPONSE => E::ListPeers,
+            self::GET_PEER_STATUS_RESPONSE => E::GetPeerStatus,
+            self::FORCE_REKEY_RESPONSE => E::ForceRekey,
+            self::SUBSCRIBE_RESPONSE => E::Subscribe,
+            self::SHUTDOWN_RESPONSE => E::Shutdown,
+            self::ROTATE_KEYPAIR_RESPONSE => E::RotateKeypair,
             _ => return Err(InvalidApiMessageType(value)),
         })
     }
@@ -133,6 +287,17 @@ impl From<ResponseMsgType> for RawMsgType {
             E::SupplyKeypair => self::SUPPLY_KEYPAIR_RESPONSE,
             E::AddListenSocket => self::ADD_LISTEN_SOCKET_RESPONSE,
             E::AddPskBroker => self::ADD_PSK_BROKER_RESPONSE,
+            E::SetPeerTiming => self::SET_PEER_TIMING_RESPONSE,
+            E::FetchEvents => self::FETCH_EVENTS_RESPONSE,
+            E::SetLogLevel => self::SET_LOG_LEVEL_RESPONSE,
+            E::AddPeer => self::ADD_PEER_RESPONSE,
+            E::RemovePeer => self::REMOVE_PEER_RESPONSE,
+            E::ListPeers => self::LIST_PEERS_RESPONSE,
+            E::GetPeerStatus => self::GET_PEER_STATUS_RESPONSE,
+            E::ForceRekey => self::FORCE_REKEY_RESPONSE,
+            E::Subscribe => self::SUBSCRIBE_RESPONSE,
+            E::Shutdown => self::SHUTDOWN_RESPONSE,
+            E::RotateKeypair => self::ROTATE_KEYPAIR_RESPONSE,
         }
     }
 }
@@ -1,6 +1,18 @@
 use rosenpass_util::zerocopy::ZerocopyMutSliceExt;
 use zerocopy::{AsBytes, ByteSliceMut, FromBytes, FromZeroes, Ref};
 
+use std::ffi::OsStr;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+use crate::app_server::DoSOperation;
+use crate::events::AppEvent;
+use crate::logging::LogFilter;
+use crate::protocol::{
+    HandshakeRole, HandshakeStateMachine, Lifecycle, PeerId, PeerStatus, TimingProfile,
+};
+
 use super::{Message, RawMsgType, RequestMsgType, ResponseMsgType};
 
 /// Size required to fit any request message in binary form
@@ -396,3 +408,1592 @@ impl Message for AddPskBrokerResponse {
         self.msg_type = Self::MESSAGE_TYPE.into();
     }
 }
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct SetPeerTimingRequestPayload {
+    /// The peer id ([crate::protocol::Peer::pidt]) of the peer to modify
+    pub peer_id: [u8; 32],
+    /// [crate::protocol::TimingProfile::rekey_after_time_responder] in milliseconds; zero for the default
+    pub rekey_after_time_responder_ms: u64,
+    /// [crate::protocol::TimingProfile::rekey_after_time_initiator] in milliseconds; zero for the default
+    pub rekey_after_time_initiator_ms: u64,
+    /// [crate::protocol::TimingProfile::reject_after_time] in milliseconds; zero for the default
+    pub reject_after_time_ms: u64,
+    /// [crate::protocol::TimingProfile::retransmit_delay_begin] in milliseconds; zero for the default
+    pub retransmit_delay_begin_ms: u64,
+    /// [crate::protocol::TimingProfile::retransmit_delay_end] in milliseconds; zero for the default
+    pub retransmit_delay_end_ms: u64,
+    /// [crate::protocol::TimingProfile::retransmit_delay_growth] in thousandths; zero for the default
+    pub retransmit_delay_growth_milli: u64,
+    /// [crate::protocol::TimingProfile::retransmit_delay_jitter] in thousandths; zero for the default
+    pub retransmit_delay_jitter_milli: u64,
+}
+
+impl SetPeerTimingRequestPayload {
+    /// The [TimingProfile] requested; zero values are replaced by the defaults
+    ///
+    /// The profile is not validated.
+    pub fn timing_profile(&self) -> TimingProfile {
+        let d = TimingProfile::default();
+        let val = |v: u64, default: f64| match v {
+            0 => default,
+            v => v as f64 / 1000.0,
+        };
+        TimingProfile {
+            rekey_after_time_responder: val(
+                self.rekey_after_time_responder_ms,
+                d.rekey_after_time_responder,
+            ),
+            rekey_after_time_initiator: val(
+                self.rekey_after_time_initiator_ms,
+                d.rekey_after_time_initiator,
+            ),
+            reject_after_time: val(self.reject_after_time_ms, d.reject_after_time),
+            retransmit_delay_begin: val(self.retransmit_delay_begin_ms, d.retransmit_delay_begin),
+            retransmit_delay_end: val(self.retransmit_delay_end_ms, d.retransmit_delay_end),
+            retransmit_delay_growth: val(
+                self.retransmit_delay_growth_milli,
+                d.retransmit_delay_growth,
+            ),
+            retransmit_delay_jitter: val(
+                self.retransmit_delay_jitter_milli,
+                d.retransmit_delay_jitter,
+            ),
+        }
+    }
+}
+
+#[allow(missing_docs)]
+pub type SetPeerTimingRequest = RequestEnvelope<SetPeerTimingRequestPayload>;
+
+impl SetPeerTimingRequest {
+    /// Construct a request setting the given [TimingProfile] for the peer with the given id
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::api::SetPeerTimingRequest;
+    /// use rosenpass::protocol::TimingProfile;
+    ///
+    /// let timing = TimingProfile {
+    ///     rekey_after_time_responder: 600.0,
+    ///     rekey_after_time_initiator: 610.0,
+    ///     reject_after_time: 660.0,
+    ///     ..TimingProfile::default()
+    /// };
+    /// let req = SetPeerTimingRequest::new([0u8; 32], &timing);
+    /// assert_eq!(req.payload.timing_profile(), timing);
+    /// ```
+    pub fn new(peer_id: [u8; 32], timing: &TimingProfile) -> Self {
+        let ms = |v: f64| (v * 1000.0).round() as u64;
+        Self::from_payload(SetPeerTimingRequestPayload {
+            peer_id,
+            rekey_after_time_responder_ms: ms(timing.rekey_after_time_responder),
+            rekey_after_time_initiator_ms: ms(timing.rekey_after_time_initiator),
+            reject_after_time_ms: ms(timing.reject_after_time),
+            retransmit_delay_begin_ms: ms(timing.retransmit_delay_begin),
+            retransmit_delay_end_ms: ms(timing.retransmit_delay_end),
+            retransmit_delay_growth_milli: ms(timing.retransmit_delay_growth),
+            retransmit_delay_jitter_milli: ms(timing.retransmit_delay_jitter),
+        })
+    }
+}
+
+impl Message for SetPeerTimingRequest {
+    type Payload = SetPeerTimingRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::SetPeerTiming;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod set_peer_timing_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INVALID_REQUEST: u128 = 1;
+    #[allow(missing_docs)]
+    pub const INTERNAL_ERROR: u128 = 2;
+    #[allow(missing_docs)]
+    pub const NO_SUCH_PEER: u128 = 3;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct SetPeerTimingResponsePayload {
+    pub status: u128,
+}
+
+#[allow(missing_docs)]
+pub type SetPeerTimingResponse = ResponseEnvelope<SetPeerTimingResponsePayload>;
+
+impl SetPeerTimingResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128) -> Self {
+        Self::from_payload(SetPeerTimingResponsePayload { status })
+    }
+}
+
+impl Message for SetPeerTimingResponse {
+    type Payload = SetPeerTimingResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::SetPeerTiming;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct FetchEventsRequestPayload {
+    /// Sequence number of the last event already seen; zero to fetch all events still kept
+    /// in the [crate::events::EventLog]
+    pub after: u64,
+}
+
+#[allow(missing_docs)]
+pub type FetchEventsRequest = RequestEnvelope<FetchEventsRequestPayload>;
+
+impl FetchEventsRequest {
+    #[allow(missing_docs)]
+    pub fn new(after: u64) -> Self {
+        Self::from_payload(FetchEventsRequestPayload { after })
+    }
+}
+
+impl Message for FetchEventsRequest {
+    type Payload = FetchEventsRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::FetchEvents;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod fetch_events_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+}
+
+/// Values for [ApiEvent::kind]
+pub mod api_event_kind {
+    /// Unused entry in [super::FetchEventsResponsePayload::events]
+    pub const NONE: u32 = 0;
+    /// [crate::events::AppEvent::EndpointChanged]
+    pub const ENDPOINT_CHANGED: u32 = 1;
+    /// [crate::events::AppEvent::EndpointRejected]
+    pub const ENDPOINT_REJECTED: u32 = 2;
+    /// [crate::events::AppEvent::KeyExchanged]
+    pub const KEY_EXCHANGED: u32 = 3;
+    /// [crate::events::AppEvent::KeyStale]
+    pub const KEY_STALE: u32 = 4;
+    /// [crate::events::AppEvent::HandshakeFailed]
+    pub const HANDSHAKE_FAILED: u32 = 5;
+    /// [crate::events::AppEvent::DoSModeChanged] to
+    /// [crate::app_server::DoSOperation::UnderLoad]
+    pub const DOS_MITIGATION_STARTED: u32 = 6;
+    /// [crate::events::AppEvent::DoSModeChanged] to [crate::app_server::DoSOperation::Normal]
+    pub const DOS_MITIGATION_STOPPED: u32 = 7;
+    /// [crate::events::AppEvent::BrokerError]
+    pub const BROKER_ERROR: u32 = 8;
+}
+
+/// Network address in binary form, as used in [ApiEvent]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ApiSocketAddr {
+    /// 4 for IPv4, 6 for IPv6 and 0 if there is no address
+    pub family: u8,
+    /// The IPv6 address, or the IPv4 address mapped to IPv6
+    pub addr: [u8; 16],
+    /// The port number
+    pub port: u16,
+}
+
+impl ApiSocketAddr {
+    /// The address represented; `None` if [Self::family] is not set
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::api::ApiSocketAddr;
+    /// use std::net::SocketAddr;
+    ///
+    /// let v4: SocketAddr = "192.0.2.1:9999".parse()?;
+    /// let v6: SocketAddr = "[2001:db8::1]:9999".parse()?;
+    /// assert_eq!(ApiSocketAddr::from(Some(v4)).socket_addr(), Some(v4));
+    /// assert_eq!(ApiSocketAddr::from(Some(v6)).socket_addr(), Some(v6));
+    /// assert_eq!(ApiSocketAddr::from(None).socket_addr(), None);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        let ip = Ipv6Addr::from(self.addr);
+        let ip = match self.family {
+            4 => IpAddr::V4(ip.to_ipv4_mapped()?),
+            6 => IpAddr::V6(ip),
+            _ => return None,
+        };
+        Some(SocketAddr::new(ip, self.port))
+    }
+}
+
+impl From<Option<SocketAddr>> for ApiSocketAddr {
+    fn from(addr: Option<SocketAddr>) -> Self {
+        let Some(addr) = addr else {
+            return Self::new_zeroed();
+        };
+        let (family, ip) = match addr.ip() {
+            IpAddr::V4(ip) => (4, ip.to_ipv6_mapped()),
+            IpAddr::V6(ip) => (6, ip),
+        };
+        Self {
+            family,
+            addr: ip.octets(),
+            port: addr.port(),
+        }
+    }
+}
+
+/// An [AppEvent] in binary form, as used in [FetchEventsResponse] and [SubscribeResponse]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ApiEvent {
+    /// Sequence number of the event in the [crate::events::EventLog]
+    pub seq: u64,
+    /// Which event this is; see [api_event_kind]
+    pub kind: u32,
+    /// The peer id ([crate::protocol::Peer::pidt]) of the peer the event concerns; all zeros
+    /// for events not concerning a particular peer
+    pub peer_id: [u8; 32],
+    /// For endpoint events, the address used before the event
+    pub from: ApiSocketAddr,
+    /// For endpoint events, the address the peer changed to or the address that was rejected
+    pub to: ApiSocketAddr,
+}
+
+impl ApiEvent {
+    /// Encode the event with the given sequence number
+    pub fn new(seq: u64, event: &AppEvent) -> Self {
+        use api_event_kind as kind;
+        let (kind, from, to) = match *event {
+            AppEvent::EndpointChanged { from, to, .. } => (kind::ENDPOINT_CHANGED, from, Some(to)),
+            AppEvent::EndpointRejected {
+                current, rejected, ..
+            } => (kind::ENDPOINT_REJECTED, current, Some(rejected)),
+            AppEvent::KeyExchanged { .. } => (kind::KEY_EXCHANGED, None, None),
+            AppEvent::KeyStale { .. } => (kind::KEY_STALE, None, None),
+            AppEvent::HandshakeFailed { .. } => (kind::HANDSHAKE_FAILED, None, None),
+            AppEvent::DoSModeChanged {
+                to: DoSOperation::UnderLoad,
+            } => (kind::DOS_MITIGATION_STARTED, None, None),
+            AppEvent::DoSModeChanged {
+                to: DoSOperation::Normal,
+            } => (kind::DOS_MITIGATION_STOPPED, None, None),
+            AppEvent::BrokerError { .. } => (kind::BROKER_ERROR, None, None),
+        };
+        Self {
+            seq,
+            kind,
+            peer_id: event.peer().map_or([0u8; 32], |peer| peer.value),
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+}
+
+/// Maximum number of events in a single [FetchEventsResponse]
+pub const MAX_EVENTS_PER_RESPONSE: usize = 16;
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct FetchEventsResponsePayload {
+    pub status: u128,
+    /// Number of events after [FetchEventsRequestPayload::after] that were already dropped
+    /// from the [crate::events::EventLog]
+    pub missed: u64,
+    /// Number of entries used in [Self::events]
+    pub count: u64,
+    /// The events, oldest first; pass the [ApiEvent::seq] of the last one as
+    /// [FetchEventsRequestPayload::after] to fetch the following ones
+    pub events: [ApiEvent; MAX_EVENTS_PER_RESPONSE],
+}
+
+impl FetchEventsResponsePayload {
+    /// The events used in [Self::events]
+    pub fn events(&self) -> &[ApiEvent] {
+        let count = (self.count as usize).min(MAX_EVENTS_PER_RESPONSE);
+        &self.events[..count]
+    }
+}
+
+#[allow(missing_docs)]
+pub type FetchEventsResponse = ResponseEnvelope<FetchEventsResponsePayload>;
+
+impl FetchEventsResponse {
+    /// Construct a response; at most [MAX_EVENTS_PER_RESPONSE] events are included
+    pub fn new<'a, I>(status: u128, missed: u64, events: I) -> Self
+    where
+        I: IntoIterator<Item = &'a (u64, AppEvent)>,
+    {
+        let mut payload = FetchEventsResponsePayload {
+            status,
+            missed,
+            count: 0,
+            events: [ApiEvent::new_zeroed(); MAX_EVENTS_PER_RESPONSE],
+        };
+        for (slot, (seq, event)) in payload.events.iter_mut().zip(events) {
+            *slot = ApiEvent::new(*seq, event);
+            payload.count += 1;
+        }
+        Self::from_payload(payload)
+    }
+}
+
+impl Message for FetchEventsResponse {
+    type Payload = FetchEventsResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::FetchEvents;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+/// Maximum length of the [LogFilter] in [SetLogLevelRequestPayload::filter] and
+/// [SetLogLevelResponsePayload::filter]
+pub const MAX_LOG_FILTER_LEN: usize = 256;
+
+/// Encode a string as a fixed size, zero padded field; `None` is encoded as all zeros
+fn encode_padded<const N: usize>(value: Option<&[u8]>, what: &str) -> anyhow::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    let Some(value) = value else {
+        return Ok(buf);
+    };
+    anyhow::ensure!(
+        value.len() <= N,
+        "{what} is longer than {N} bytes: {:?}",
+        String::from_utf8_lossy(value)
+    );
+    anyhow::ensure!(
+        !value.is_empty() && !value.contains(&0),
+        "{what} must neither be empty nor contain null bytes"
+    );
+    buf[..value.len()].copy_from_slice(value);
+    Ok(buf)
+}
+
+/// Decode a field encoded using [encode_padded]; `None` if it is all zeros
+fn decode_padded(buf: &[u8]) -> Option<&[u8]> {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    (len > 0).then(|| &buf[..len])
+}
+
+/// Decode a field encoded using [encode_padded] as UTF-8
+fn decode_padded_str<'a>(buf: &'a [u8], what: &str) -> anyhow::Result<Option<&'a str>> {
+    decode_padded(buf)
+        .map(|s| std::str::from_utf8(s).map_err(|e| anyhow::anyhow!("Invalid {what}: {e}")))
+        .transpose()
+}
+
+/// Encode a [LogFilter] for [SetLogLevelRequestPayload::filter] or
+/// [SetLogLevelResponsePayload::filter]
+fn encode_log_filter(filter: Option<&LogFilter>) -> anyhow::Result<[u8; MAX_LOG_FILTER_LEN]> {
+    let filter = filter.map(|f| f.to_string());
+    encode_padded(filter.as_ref().map(|f| f.as_bytes()), "Log filter")
+}
+
+/// Decode a [LogFilter] encoded using [encode_log_filter]; `None` if the buffer is all zeros
+fn decode_log_filter(buf: &[u8; MAX_LOG_FILTER_LEN]) -> anyhow::Result<Option<LogFilter>> {
+    decode_padded_str(buf, "log filter")?
+        .map(str::parse)
+        .transpose()
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct SetLogLevelRequestPayload {
+    /// The new [LogFilter] in text form, e.g. `warn,protocol=debug`, padded with zeros; all
+    /// zeros to restore the configured filter
+    pub filter: [u8; MAX_LOG_FILTER_LEN],
+}
+
+impl SetLogLevelRequestPayload {
+    /// The [LogFilter] requested; `None` to restore the configured filter
+    pub fn filter(&self) -> anyhow::Result<Option<LogFilter>> {
+        decode_log_filter(&self.filter)
+    }
+}
+
+#[allow(missing_docs)]
+pub type SetLogLevelRequest = RequestEnvelope<SetLogLevelRequestPayload>;
+
+impl SetLogLevelRequest {
+    /// Construct a request setting the given [LogFilter], or restoring the configured filter
+    /// if `filter` is `None`
+    ///
+    /// Fails if the filter is longer than [MAX_LOG_FILTER_LEN] bytes in text form.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::api::SetLogLevelRequest;
+    /// use rosenpass::logging::LogFilter;
+    ///
+    /// let filter: LogFilter = "warn,protocol=debug".parse()?;
+    /// let req = SetLogLevelRequest::new(Some(&filter))?;
+    /// assert_eq!(req.payload.filter()?, Some(filter));
+    ///
+    /// let req = SetLogLevelRequest::new(None)?;
+    /// assert_eq!(req.payload.filter()?, None);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn new(filter: Option<&LogFilter>) -> anyhow::Result<Self> {
+        Ok(Self::from_payload(SetLogLevelRequestPayload {
+            filter: encode_log_filter(filter)?,
+        }))
+    }
+}
+
+impl Message for SetLogLevelRequest {
+    type Payload = SetLogLevelRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::SetLogLevel;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod set_log_level_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INVALID_REQUEST: u128 = 1;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct SetLogLevelResponsePayload {
+    pub status: u128,
+    /// The [LogFilter] in effect after processing the request, in the same form as
+    /// [SetLogLevelRequestPayload::filter]
+    pub filter: [u8; MAX_LOG_FILTER_LEN],
+}
+
+impl SetLogLevelResponsePayload {
+    /// The [LogFilter] in effect after processing the request
+    pub fn filter(&self) -> anyhow::Result<LogFilter> {
+        Ok(decode_log_filter(&self.filter)?.unwrap_or(LogFilter::off()))
+    }
+}
+
+#[allow(missing_docs)]
+pub type SetLogLevelResponse = ResponseEnvelope<SetLogLevelResponsePayload>;
+
+impl SetLogLevelResponse {
+    /// Construct a response; the filter is left out if it is longer than [MAX_LOG_FILTER_LEN]
+    /// bytes in text form
+    pub fn new(status: u128, filter: &LogFilter) -> Self {
+        Self::from_payload(SetLogLevelResponsePayload {
+            status,
+            filter: encode_log_filter(Some(filter)).unwrap_or([0u8; MAX_LOG_FILTER_LEN]),
+        })
+    }
+}
+
+impl Message for SetLogLevelResponse {
+    type Payload = SetLogLevelResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::SetLogLevel;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+/// Maximum length of [AddPeerRequestPayload::endpoint]
+pub const MAX_ENDPOINT_LEN: usize = 256;
+/// Maximum length of [AddPeerRequestPayload::key_out]
+pub const MAX_KEY_OUT_LEN: usize = 512;
+/// Maximum length of [AddPeerRequestPayload::wg_device]; the size of network device names
+/// on Linux (`IFNAMSIZ`), including the terminating null byte
+pub const MAX_WG_DEVICE_LEN: usize = 16;
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct AddPeerRequestPayload {
+    /// Host name or address and port of the peer, e.g. `192.0.2.1:9999`, padded with zeros; all
+    /// zeros if the peer has no known endpoint
+    pub endpoint: [u8; MAX_ENDPOINT_LEN],
+    /// Path of the file to write exchanged keys to (see
+    /// [crate::config::RosenpassPeer::key_out]), padded with zeros; all zeros to not write keys
+    /// to a file
+    pub key_out: [u8; MAX_KEY_OUT_LEN],
+    /// Name of the WireGuard interface to supply exchanged keys to through the PSK broker,
+    /// padded with zeros; all zeros to not supply keys to WireGuard
+    pub wg_device: [u8; MAX_WG_DEVICE_LEN],
+    /// WireGuard public key of the peer on [Self::wg_device]
+    pub wg_peer: [u8; 32],
+}
+
+impl AddPeerRequestPayload {
+    /// The endpoint of the peer, if any
+    pub fn endpoint(&self) -> anyhow::Result<Option<String>> {
+        Ok(decode_padded_str(&self.endpoint, "endpoint")?.map(str::to_owned))
+    }
+
+    /// The file to write exchanged keys to, if any
+    pub fn key_out(&self) -> Option<PathBuf> {
+        decode_padded(&self.key_out).map(|p| PathBuf::from(OsStr::from_bytes(p)))
+    }
+
+    /// The WireGuard interface and the public key of the peer on that interface, if keys are
+    /// to be supplied to WireGuard
+    pub fn wireguard(&self) -> anyhow::Result<Option<(String, [u8; 32])>> {
+        let device = decode_padded_str(&self.wg_device, "WireGuard interface name")?;
+        Ok(device.map(|device| (device.to_owned(), self.wg_peer)))
+    }
+}
+
+#[allow(missing_docs)]
+pub type AddPeerRequest = RequestEnvelope<AddPeerRequestPayload>;
+
+impl AddPeerRequest {
+    /// Construct a request adding a peer
+    ///
+    /// The public key of the peer and, optionally, the pre-shared key are passed as file
+    /// descriptors along with the request; see [super::Server::add_peer]. `wireguard` consists
+    /// of the WireGuard interface and the public key of the peer on that interface.
+    ///
+    /// Fails if any of the values does not fit into the request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::api::AddPeerRequest;
+    /// use std::path::Path;
+    ///
+    /// let req = AddPeerRequest::new(
+    ///     Some("192.0.2.1:9999"),
+    ///     Some(Path::new("/run/rosenpass/peer-a.key")),
+    ///     Some(("wg0", &[7u8; 32])),
+    /// )?;
+    /// assert_eq!(req.payload.endpoint()?.as_deref(), Some("192.0.2.1:9999"));
+    /// assert_eq!(req.payload.key_out().as_deref(), Some(Path::new("/run/rosenpass/peer-a.key")));
+    /// assert_eq!(req.payload.wireguard()?, Some(("wg0".to_owned(), [7u8; 32])));
+    ///
+    /// let req = AddPeerRequest::new(None, None, None)?;
+    /// assert_eq!(req.payload.endpoint()?, None);
+    /// assert_eq!(req.payload.key_out(), None);
+    /// assert_eq!(req.payload.wireguard()?, None);
+    ///
+    /// assert!(AddPeerRequest::new(None, None, Some(("wireguard-interface", &[7u8; 32]))).is_err());
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn new(
+        endpoint: Option<&str>,
+        key_out: Option<&Path>,
+        wireguard: Option<(&str, &[u8; 32])>,
+    ) -> anyhow::Result<Self> {
+        let (wg_device, wg_peer) = match wireguard {
+            Some((device, peer)) => {
+                // Leave room for the terminating null byte
+                anyhow::ensure!(
+                    device.len() < MAX_WG_DEVICE_LEN,
+                    "WireGuard interface name is longer than {} bytes: {device:?}",
+                    MAX_WG_DEVICE_LEN - 1
+                );
+                (Some(device), *peer)
+            }
+            None => (None, [0u8; 32]),
+        };
+        Ok(Self::from_payload(AddPeerRequestPayload {
+            endpoint: encode_padded(endpoint.map(str::as_bytes), "Endpoint")?,
+            key_out: encode_padded(key_out.map(|p| p.as_os_str().as_bytes()), "Key output path")?,
+            wg_device: encode_padded(wg_device.map(str::as_bytes), "WireGuard interface name")?,
+            wg_peer,
+        }))
+    }
+}
+
+impl Message for AddPeerRequest {
+    type Payload = AddPeerRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::AddPeer;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod add_peer_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INVALID_REQUEST: u128 = 1;
+    #[allow(missing_docs)]
+    pub const INTERNAL_ERROR: u128 = 2;
+    #[allow(missing_docs)]
+    pub const PEER_ALREADY_EXISTS: u128 = 3;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct AddPeerResponsePayload {
+    pub status: u128,
+    /// The peer id ([crate::protocol::Peer::pidt]) of the peer added, or of the peer already
+    /// registered with the same public key; all zeros otherwise
+    pub peer_id: [u8; 32],
+}
+
+#[allow(missing_docs)]
+pub type AddPeerResponse = ResponseEnvelope<AddPeerResponsePayload>;
+
+impl AddPeerResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128, peer_id: [u8; 32]) -> Self {
+        Self::from_payload(AddPeerResponsePayload { status, peer_id })
+    }
+}
+
+impl Message for AddPeerResponse {
+    type Payload = AddPeerResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::AddPeer;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct RemovePeerRequestPayload {
+    /// The peer id ([crate::protocol::Peer::pidt]) of the peer to remove
+    pub peer_id: [u8; 32],
+}
+
+#[allow(missing_docs)]
+pub type RemovePeerRequest = RequestEnvelope<RemovePeerRequestPayload>;
+
+impl RemovePeerRequest {
+    #[allow(missing_docs)]
+    pub fn new(peer_id: [u8; 32]) -> Self {
+        Self::from_payload(RemovePeerRequestPayload { peer_id })
+    }
+}
+
+impl Message for RemovePeerRequest {
+    type Payload = RemovePeerRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::RemovePeer;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod remove_peer_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INVALID_REQUEST: u128 = 1;
+    #[allow(missing_docs)]
+    pub const INTERNAL_ERROR: u128 = 2;
+    #[allow(missing_docs)]
+    pub const NO_SUCH_PEER: u128 = 3;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct RemovePeerResponsePayload {
+    pub status: u128,
+}
+
+#[allow(missing_docs)]
+pub type RemovePeerResponse = ResponseEnvelope<RemovePeerResponsePayload>;
+
+impl RemovePeerResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128) -> Self {
+        Self::from_payload(RemovePeerResponsePayload { status })
+    }
+}
+
+impl Message for RemovePeerResponse {
+    type Payload = RemovePeerResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::RemovePeer;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+/// Values for [ApiPeerStatus::session]
+pub mod api_session_state {
+    /// No session is established
+    pub const NONE: u8 = 0;
+    /// The session key is fresh; [crate::protocol::Lifecycle::Young]
+    pub const LIVE: u8 = 1;
+    /// The session key is still in use, but a new handshake is due;
+    /// [crate::protocol::Lifecycle::Retired]
+    pub const RETIRED: u8 = 2;
+    /// The session key expired and is about to be erased; [crate::protocol::Lifecycle::Dead]
+    pub const EXPIRED: u8 = 3;
+}
+
+/// Values for [ApiPeerStatus::session_role]
+pub mod api_handshake_role {
+    /// No session is established
+    pub const NONE: u8 = 0;
+    /// We were the initiator; [crate::protocol::HandshakeRole::Initiator]
+    pub const INITIATOR: u8 = 1;
+    /// We were the responder; [crate::protocol::HandshakeRole::Responder]
+    pub const RESPONDER: u8 = 2;
+}
+
+/// Values for [ApiPeerStatus::handshake]
+pub mod api_handshake_state {
+    /// No handshake in initiator role is ongoing
+    pub const NONE: u8 = 0;
+    /// Waiting for the responder's RespHello; [crate::protocol::HandshakeStateMachine::RespHello]
+    pub const AWAIT_RESP_HELLO: u8 = 1;
+    /// Waiting for the responder to confirm InitConf;
+    /// [crate::protocol::HandshakeStateMachine::RespConf]
+    pub const AWAIT_RESP_CONF: u8 = 2;
+}
+
+/// A [PeerStatus] in binary form, as used in [ListPeersResponse] and [GetPeerStatusResponse]
+///
+/// Durations are given in milliseconds. This contains no key material.
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ApiPeerStatus {
+    /// The peer id ([crate::protocol::Peer::pidt]) of the peer
+    pub peer_id: [u8; 32],
+    /// State of the session; see [api_session_state]
+    pub session: u8,
+    /// Our role in the handshake that established the session; see [api_handshake_role]
+    pub session_role: u8,
+    /// State of the handshake in initiator role; see [api_handshake_state]
+    pub handshake: u8,
+    /// Time since the last handshake finished; i.e. the age of the session key
+    pub session_age_ms: u64,
+    /// Time since the ongoing handshake was started
+    pub handshake_age_ms: u64,
+    /// Number of times the last message of the ongoing handshake was retransmitted
+    pub handshake_retransmissions: u64,
+    /// Time until the next retransmission of the ongoing handshake
+    pub handshake_retransmit_in_ms: u64,
+    /// The address the last handshake with the peer used, if any; see
+    /// [crate::app_server::AppPeer::current_endpoint]
+    pub endpoint: ApiSocketAddr,
+}
+
+impl ApiPeerStatus {
+    /// Encode the status of the given peer
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::api::{api_handshake_role, api_handshake_state, api_session_state, ApiPeerStatus};
+    /// use rosenpass::protocol::{HandshakeRole, Lifecycle, PeerId, PeerStatus, SessionStatus};
+    ///
+    /// let status = PeerStatus {
+    ///     session: Some(SessionStatus {
+    ///         age: 1.5,
+    ///         role: HandshakeRole::Responder,
+    ///         lifecycle: Lifecycle::Young,
+    ///     }),
+    ///     handshake: None,
+    /// };
+    /// let endpoint = "[::1]:9999".parse()?;
+    /// let api = ApiPeerStatus::new(PeerId::from_slice(&[1u8; 32]), &status, Some(endpoint));
+    /// assert_eq!(api.peer_id, [1u8; 32]);
+    /// assert_eq!(api.session, api_session_state::LIVE);
+    /// assert_eq!(api.session_role, api_handshake_role::RESPONDER);
+    /// assert_eq!({ api.session_age_ms }, 1500);
+    /// assert_eq!(api.handshake, api_handshake_state::NONE);
+    /// assert_eq!(api.endpoint.socket_addr(), Some(endpoint));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn new(peer_id: PeerId, status: &PeerStatus, endpoint: Option<SocketAddr>) -> Self {
+        // Negative durations (e.g. an overdue retransmission) saturate to zero
+        let ms = |t: f64| (t * 1000.0) as u64;
+
+        let mut r = Self::new_zeroed();
+        r.peer_id = peer_id.value;
+        r.endpoint = endpoint.into();
+
+        if let Some(s) = status.session {
+            r.session = match s.lifecycle {
+                Lifecycle::Young => api_session_state::LIVE,
+                Lifecycle::Retired => api_session_state::RETIRED,
+                Lifecycle::Dead | Lifecycle::Void => api_session_state::EXPIRED,
+            };
+            r.session_role = match s.role {
+                HandshakeRole::Initiator => api_handshake_role::INITIATOR,
+                HandshakeRole::Responder => api_handshake_role::RESPONDER,
+            };
+            r.session_age_ms = ms(s.age);
+        }
+
+        if let Some(hs) = status.handshake {
+            r.handshake = match hs.next {
+                HandshakeStateMachine::RespHello => api_handshake_state::AWAIT_RESP_HELLO,
+                HandshakeStateMachine::RespConf => api_handshake_state::AWAIT_RESP_CONF,
+            };
+            r.handshake_age_ms = ms(hs.age);
+            r.handshake_retransmissions = hs.retransmissions as u64;
+            r.handshake_retransmit_in_ms = ms(hs.retransmit_in);
+        }
+
+        r
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ListPeersRequestPayload {
+    /// Number of peers to skip; pass the number of peers received so far to fetch
+    /// the following ones
+    pub offset: u64,
+}
+
+#[allow(missing_docs)]
+pub type ListPeersRequest = RequestEnvelope<ListPeersRequestPayload>;
+
+impl ListPeersRequest {
+    #[allow(missing_docs)]
+    pub fn new(offset: u64) -> Self {
+        Self::from_payload(ListPeersRequestPayload { offset })
+    }
+}
+
+impl Message for ListPeersRequest {
+    type Payload = ListPeersRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::ListPeers;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod list_peers_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INTERNAL_ERROR: u128 = 2;
+}
+
+/// Maximum number of peers in a single [ListPeersResponse]
+pub const MAX_PEERS_PER_RESPONSE: usize = 16;
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ListPeersResponsePayload {
+    pub status: u128,
+    /// Total number of peers
+    pub total: u64,
+    /// Number of entries used in [Self::peers]
+    pub count: u64,
+    /// The peers following [ListPeersRequestPayload::offset]
+    pub peers: [ApiPeerStatus; MAX_PEERS_PER_RESPONSE],
+}
+
+impl ListPeersResponsePayload {
+    /// The peers used in [Self::peers]
+    pub fn peers(&self) -> &[ApiPeerStatus] {
+        let count = (self.count as usize).min(MAX_PEERS_PER_RESPONSE);
+        &self.peers[..count]
+    }
+}
+
+#[allow(missing_docs)]
+pub type ListPeersResponse = ResponseEnvelope<ListPeersResponsePayload>;
+
+impl ListPeersResponse {
+    /// Construct a response; at most [MAX_PEERS_PER_RESPONSE] peers are included
+    pub fn new<I>(status: u128, total: u64, peers: I) -> Self
+    where
+        I: IntoIterator<Item = ApiPeerStatus>,
+    {
+        let mut payload = ListPeersResponsePayload {
+            status,
+            total,
+            count: 0,
+            peers: [ApiPeerStatus::new_zeroed(); MAX_PEERS_PER_RESPONSE],
+        };
+        for (slot, peer) in payload.peers.iter_mut().zip(peers) {
+            *slot = peer;
+            payload.count += 1;
+        }
+        Self::from_payload(payload)
+    }
+}
+
+impl Message for ListPeersResponse {
+    type Payload = ListPeersResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::ListPeers;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct GetPeerStatusRequestPayload {
+    /// The peer id ([crate::protocol::Peer::pidt]) of the peer
+    pub peer_id: [u8; 32],
+}
+
+#[allow(missing_docs)]
+pub type GetPeerStatusRequest = RequestEnvelope<GetPeerStatusRequestPayload>;
+
+impl GetPeerStatusRequest {
+    #[allow(missing_docs)]
+    pub fn new(peer_id: [u8; 32]) -> Self {
+        Self::from_payload(GetPeerStatusRequestPayload { peer_id })
+    }
+}
+
+impl Message for GetPeerStatusRequest {
+    type Payload = GetPeerStatusRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::GetPeerStatus;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod get_peer_status_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INTERNAL_ERROR: u128 = 2;
+    #[allow(missing_docs)]
+    pub const NO_SUCH_PEER: u128 = 3;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct GetPeerStatusResponsePayload {
+    pub status: u128,
+    /// The status of the peer; all zeros unless [Self::status] is
+    /// [get_peer_status_response_status::OK]
+    pub peer: ApiPeerStatus,
+}
+
+#[allow(missing_docs)]
+pub type GetPeerStatusResponse = ResponseEnvelope<GetPeerStatusResponsePayload>;
+
+impl GetPeerStatusResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128, peer: ApiPeerStatus) -> Self {
+        Self::from_payload(GetPeerStatusResponsePayload { status, peer })
+    }
+}
+
+impl Message for GetPeerStatusResponse {
+    type Payload = GetPeerStatusResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::GetPeerStatus;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ForceRekeyRequestPayload {
+    /// The peer id ([crate::protocol::Peer::pidt]) of the peer
+    pub peer_id: [u8; 32],
+    /// One to erase the current key right away, zero to keep using it until the
+    /// new handshake finishes
+    pub erase_key: u8,
+}
+
+#[allow(missing_docs)]
+pub type ForceRekeyRequest = RequestEnvelope<ForceRekeyRequestPayload>;
+
+impl ForceRekeyRequest {
+    #[allow(missing_docs)]
+    pub fn new(peer_id: [u8; 32], erase_key: bool) -> Self {
+        Self::from_payload(ForceRekeyRequestPayload {
+            peer_id,
+            erase_key: erase_key.into(),
+        })
+    }
+}
+
+impl Message for ForceRekeyRequest {
+    type Payload = ForceRekeyRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::ForceRekey;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod force_rekey_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INVALID_REQUEST: u128 = 1;
+    #[allow(missing_docs)]
+    pub const INTERNAL_ERROR: u128 = 2;
+    #[allow(missing_docs)]
+    pub const NO_SUCH_PEER: u128 = 3;
+    #[allow(missing_docs)]
+    pub const NO_ENDPOINT: u128 = 4;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ForceRekeyResponsePayload {
+    pub status: u128,
+}
+
+#[allow(missing_docs)]
+pub type ForceRekeyResponse = ResponseEnvelope<ForceRekeyResponsePayload>;
+
+impl ForceRekeyResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128) -> Self {
+        Self::from_payload(ForceRekeyResponsePayload { status })
+    }
+}
+
+impl Message for ForceRekeyResponse {
+    type Payload = ForceRekeyResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::ForceRekey;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct SubscribeRequestPayload {
+    /// Only stream events with a greater sequence number ([ApiEvent::seq]); zero to start with
+    /// the oldest event still kept and [u64::MAX] to receive new events only
+    pub after: u64,
+}
+
+#[allow(missing_docs)]
+pub type SubscribeRequest = RequestEnvelope<SubscribeRequestPayload>;
+
+impl SubscribeRequest {
+    #[allow(missing_docs)]
+    pub fn new(after: u64) -> Self {
+        Self::from_payload(SubscribeRequestPayload { after })
+    }
+}
+
+impl Message for SubscribeRequest {
+    type Payload = SubscribeRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::Subscribe;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod subscribe_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct SubscribeResponsePayload {
+    pub status: u128,
+    /// Number of events before [Self::event] that were already dropped from the
+    /// [crate::events::EventLog] and will never be sent
+    pub missed: u64,
+    /// The event; [api_event_kind::NONE] in the first response confirming the subscription
+    pub event: ApiEvent,
+}
+
+#[allow(missing_docs)]
+pub type SubscribeResponse = ResponseEnvelope<SubscribeResponsePayload>;
+
+impl SubscribeResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128, missed: u64, event: ApiEvent) -> Self {
+        Self::from_payload(SubscribeResponsePayload {
+            status,
+            missed,
+            event,
+        })
+    }
+}
+
+impl Message for SubscribeResponse {
+    type Payload = SubscribeResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::Subscribe;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ShutdownRequestPayload {}
+
+#[allow(missing_docs)]
+pub type ShutdownRequest = RequestEnvelope<ShutdownRequestPayload>;
+
+impl Default for ShutdownRequest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownRequest {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self::from_payload(ShutdownRequestPayload {})
+    }
+}
+
+impl Message for ShutdownRequest {
+    type Payload = ShutdownRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::Shutdown;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod shutdown_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct ShutdownResponsePayload {
+    pub status: u128,
+}
+
+#[allow(missing_docs)]
+pub type ShutdownResponse = ResponseEnvelope<ShutdownResponsePayload>;
+
+impl ShutdownResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128) -> Self {
+        Self::from_payload(ShutdownResponsePayload { status })
+    }
+}
+
+impl Message for ShutdownResponse {
+    type Payload = ShutdownResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::Shutdown;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct RotateKeypairRequestPayload {
+    /// For how long peers may keep using our previous public key, in milliseconds
+    pub grace_period_ms: u64,
+}
+
+#[allow(missing_docs)]
+pub type RotateKeypairRequest = RequestEnvelope<RotateKeypairRequestPayload>;
+
+impl RotateKeypairRequest {
+    #[allow(missing_docs)]
+    pub fn new(grace_period_ms: u64) -> Self {
+        Self::from_payload(RotateKeypairRequestPayload { grace_period_ms })
+    }
+}
+
+impl Message for RotateKeypairRequest {
+    type Payload = RotateKeypairRequestPayload;
+    type MessageClass = RequestMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = RequestMsgType::RotateKeypair;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
+
+#[allow(missing_docs)]
+pub mod rotate_keypair_response_status {
+    #[allow(missing_docs)]
+    pub const OK: u128 = 0;
+    #[allow(missing_docs)]
+    pub const INVALID_REQUEST: u128 = 1;
+    #[allow(missing_docs)]
+    pub const INTERNAL_ERROR: u128 = 2;
+    /// No keypair was supplied yet; use [super::SupplyKeypairRequest] instead
+    pub const NO_KEYPAIR: u128 = 3;
+}
+
+#[allow(missing_docs)]
+#[repr(packed)]
+#[derive(Debug, Copy, Clone, Hash, AsBytes, FromBytes, FromZeroes, PartialEq, Eq)]
+pub struct RotateKeypairResponsePayload {
+    pub status: u128,
+}
+
+#[allow(missing_docs)]
+pub type RotateKeypairResponse = ResponseEnvelope<RotateKeypairResponsePayload>;
+
+impl RotateKeypairResponse {
+    #[allow(missing_docs)]
+    pub fn new(status: u128) -> Self {
+        Self::from_payload(RotateKeypairResponsePayload { status })
+    }
+}
+
+impl Message for RotateKeypairResponse {
+    type Payload = RotateKeypairResponsePayload;
+    type MessageClass = ResponseMsgType;
+    const MESSAGE_TYPE: Self::MessageClass = ResponseMsgType::RotateKeypair;
+
+    fn from_payload(payload: Self::Payload) -> Self {
+        Self {
+            msg_type: Self::MESSAGE_TYPE.into(),
+            payload,
+        }
+    }
+
+    fn setup<B: ByteSliceMut>(buf: B) -> anyhow::Result<Ref<B, Self>> {
+        let mut r: Ref<B, Self> = buf.zk_zeroized()?;
+        r.init();
+        Ok(r)
+    }
+
+    fn init(&mut self) {
+        self.msg_type = Self::MESSAGE_TYPE.into();
+    }
+}
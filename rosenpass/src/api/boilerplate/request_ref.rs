@@ -67,6 +67,17 @@ impl<B: ByteSlice> RequestRef<B> {
             Self::SupplyKeypair(_) => RequestMsgType::SupplyKeypair,
             Self::AddListenSocket(_) => RequestMsgType::AddListenSocket,
             Self::AddPskBroker(_) => RequestMsgType::AddPskBroker,
+            Self::SetPeerTiming(_) => RequestMsgType::SetPeerTiming,
+            Self::FetchEvents(_) => RequestMsgType::FetchEvents,
+            Self::SetLogLevel(_) => RequestMsgType::SetLogLevel,
+            Self::AddPeer(_) => RequestMsgType::AddPeer,
+            Self::RemovePeer(_) => RequestMsgType::RemovePeer,
+            Self::ListPeers(_) => RequestMsgType::ListPeers,
+            Self::GetPeerStatus(_) => RequestMsgType::GetPeerStatus,
+            Self::ForceRekey(_) => RequestMsgType::ForceRekey,
+            Self::Subscribe(_) => RequestMsgType::Subscribe,
+            Self::Shutdown(_) => RequestMsgType::Shutdown,
+            Self::RotateKeypair(_) => RequestMsgType::RotateKeypair,
         }
     }
 }
@@ -95,6 +106,72 @@ impl<B> From<Ref<B, super::AddPskBrokerRequest>> for RequestRef<B> {
     }
 }
 
+impl<B> From<Ref<B, super::SetPeerTimingRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::SetPeerTimingRequest>) -> Self {
+        Self::SetPeerTiming(v)
+    }
+}
+
+impl<B> From<Ref<B, super::FetchEventsRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::FetchEventsRequest>) -> Self {
+        Self::FetchEvents(v)
+    }
+}
+
+impl<B> From<Ref<B, super::SetLogLevelRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::SetLogLevelRequest>) -> Self {
+        Self::SetLogLevel(v)
+    }
+}
+
+impl<B> From<Ref<B, super::AddPeerRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::AddPeerRequest>) -> Self {
+        Self::AddPeer(v)
+    }
+}
+
+impl<B> From<Ref<B, super::RemovePeerRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::RemovePeerRequest>) -> Self {
+        Self::RemovePeer(v)
+    }
+}
+
+impl<B> From<Ref<B, super::ListPeersRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::ListPeersRequest>) -> Self {
+        Self::ListPeers(v)
+    }
+}
+
+impl<B> From<Ref<B, super::GetPeerStatusRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::GetPeerStatusRequest>) -> Self {
+        Self::GetPeerStatus(v)
+    }
+}
+
+impl<B> From<Ref<B, super::ForceRekeyRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::ForceRekeyRequest>) -> Self {
+        Self::ForceRekey(v)
+    }
+}
+
+impl<B> From<Ref<B, super::SubscribeRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::SubscribeRequest>) -> Self {
+        Self::Subscribe(v)
+    }
+}
+
+impl<B> From<Ref<B, super::ShutdownRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::ShutdownRequest>) -> Self {
+        Self::Shutdown(v)
+    }
+}
+
+impl<B> From<Ref<B, super::RotateKeypairRequest>> for RequestRef<B> {
+    fn from(v: Ref<B, super::RotateKeypairRequest>) -> Self {
+        Self::RotateKeypair(v)
+    }
+}
+
 impl<B: ByteSlice> RequestRefMaker<B> {
     fn new(buf: B) -> anyhow::Result<Self> {
         let msg_type = buf.deref().request_msg_type_from_prefix()?;
@@ -117,6 +194,27 @@ impl<B: ByteSlice> RequestRefMaker<B> {
             RequestMsgType::AddPskBroker => {
                 RequestRef::AddPskBroker(self.buf.add_psk_broker_request()?)
             }
+            RequestMsgType::SetPeerTiming => {
+                RequestRef::SetPeerTiming(self.buf.set_peer_timing_request()?)
+            }
+            RequestMsgType::FetchEvents => {
+                RequestRef::FetchEvents(self.buf.fetch_events_request()?)
+            }
+            RequestMsgType::SetLogLevel => {
+                RequestRef::SetLogLevel(self.buf.set_log_level_request()?)
+            }
+            RequestMsgType::AddPeer => RequestRef::AddPeer(self.buf.add_peer_request()?),
+            RequestMsgType::RemovePeer => RequestRef::RemovePeer(self.buf.remove_peer_request()?),
+            RequestMsgType::ListPeers => RequestRef::ListPeers(self.buf.list_peers_request()?),
+            RequestMsgType::GetPeerStatus => {
+                RequestRef::GetPeerStatus(self.buf.get_peer_status_request()?)
+            }
+            RequestMsgType::ForceRekey => RequestRef::ForceRekey(self.buf.force_rekey_request()?),
+            RequestMsgType::Subscribe => RequestRef::Subscribe(self.buf.subscribe_request()?),
+            RequestMsgType::Shutdown => RequestRef::Shutdown(self.buf.shutdown_request()?),
+            RequestMsgType::RotateKeypair => {
+                RequestRef::RotateKeypair(self.buf.rotate_keypair_request()?)
+            }
         })
     }
 
@@ -155,6 +253,17 @@ pub enum RequestRef<B> {
     SupplyKeypair(Ref<B, super::SupplyKeypairRequest>),
     AddListenSocket(Ref<B, super::AddListenSocketRequest>),
     AddPskBroker(Ref<B, super::AddPskBrokerRequest>),
+    SetPeerTiming(Ref<B, super::SetPeerTimingRequest>),
+    FetchEvents(Ref<B, super::FetchEventsRequest>),
+    SetLogLevel(Ref<B, super::SetLogLevelRequest>),
+    AddPeer(Ref<B, super::AddPeerRequest>),
+    RemovePeer(Ref<B, super::RemovePeerRequest>),
+    ListPeers(Ref<B, super::ListPeersRequest>),
+    GetPeerStatus(Ref<B, super::GetPeerStatusRequest>),
+    ForceRekey(Ref<B, super::ForceRekeyRequest>),
+    Subscribe(Ref<B, super::SubscribeRequest>),
+    Shutdown(Ref<B, super::ShutdownRequest>),
+    RotateKeypair(Ref<B, super::RotateKeypairRequest>),
 }
 
 impl<B> RequestRef<B>
@@ -172,6 +281,17 @@ where
             Self::SupplyKeypair(r) => r.bytes(),
             Self::AddListenSocket(r) => r.bytes(),
             Self::AddPskBroker(r) => r.bytes(),
+            Self::SetPeerTiming(r) => r.bytes(),
+            Self::FetchEvents(r) => r.bytes(),
+            Self::SetLogLevel(r) => r.bytes(),
+            Self::AddPeer(r) => r.bytes(),
+            Self::RemovePeer(r) => r.bytes(),
+            Self::ListPeers(r) => r.bytes(),
+            Self::GetPeerStatus(r) => r.bytes(),
+            Self::ForceRekey(r) => r.bytes(),
+            Self::Subscribe(r) => r.bytes(),
+            Self::Shutdown(r) => r.bytes(),
+            Self::RotateKeypair(r) => r.bytes(),
         }
     }
 }
@@ -187,6 +307,17 @@ where
             Self::SupplyKeypair(r) => r.bytes_mut(),
             Self::AddListenSocket(r) => r.bytes_mut(),
             Self::AddPskBroker(r) => r.bytes_mut(),
+            Self::SetPeerTiming(r) => r.bytes_mut(),
+            Self::FetchEvents(r) => r.bytes_mut(),
+            Self::SetLogLevel(r) => r.bytes_mut(),
+            Self::AddPeer(r) => r.bytes_mut(),
+            Self::RemovePeer(r) => r.bytes_mut(),
+            Self::ListPeers(r) => r.bytes_mut(),
+            Self::GetPeerStatus(r) => r.bytes_mut(),
+            Self::ForceRekey(r) => r.bytes_mut(),
+            Self::Subscribe(r) => r.bytes_mut(),
+            Self::Shutdown(r) => r.bytes_mut(),
+            Self::RotateKeypair(r) => r.bytes_mut(),
         }
     }
 }
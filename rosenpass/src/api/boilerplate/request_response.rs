@@ -73,6 +73,94 @@ impl ResponseMsg for super::AddPskBrokerResponse {
     type RequestMsg = super::AddPskBrokerRequest;
 }
 
+impl RequestMsg for super::SetPeerTimingRequest {
+    type ResponseMsg = super::SetPeerTimingResponse;
+}
+
+impl ResponseMsg for super::SetPeerTimingResponse {
+    type RequestMsg = super::SetPeerTimingRequest;
+}
+
+impl RequestMsg for super::FetchEventsRequest {
+    type ResponseMsg = super::FetchEventsResponse;
+}
+
+impl ResponseMsg for super::FetchEventsResponse {
+    type RequestMsg = super::FetchEventsRequest;
+}
+
+impl RequestMsg for super::SetLogLevelRequest {
+    type ResponseMsg = super::SetLogLevelResponse;
+}
+
+impl ResponseMsg for super::SetLogLevelResponse {
+    type RequestMsg = super::SetLogLevelRequest;
+}
+
+impl RequestMsg for super::AddPeerRequest {
+    type ResponseMsg = super::AddPeerResponse;
+}
+
+impl ResponseMsg for super::AddPeerResponse {
+    type RequestMsg = super::AddPeerRequest;
+}
+
+impl RequestMsg for super::RemovePeerRequest {
+    type ResponseMsg = super::RemovePeerResponse;
+}
+
+impl ResponseMsg for super::RemovePeerResponse {
+    type RequestMsg = super::RemovePeerRequest;
+}
+
+impl RequestMsg for super::ListPeersRequest {
+    type ResponseMsg = super::ListPeersResponse;
+}
+
+impl ResponseMsg for super::ListPeersResponse {
+    type RequestMsg = super::ListPeersRequest;
+}
+
+impl RequestMsg for super::GetPeerStatusRequest {
+    type ResponseMsg = super::GetPeerStatusResponse;
+}
+
+impl ResponseMsg for super::GetPeerStatusResponse {
+    type RequestMsg = super::GetPeerStatusRequest;
+}
+
+impl RequestMsg for super::ForceRekeyRequest {
+    type ResponseMsg = super::ForceRekeyResponse;
+}
+
+impl ResponseMsg for super::ForceRekeyResponse {
+    type RequestMsg = super::ForceRekeyRequest;
+}
+
+impl RequestMsg for super::SubscribeRequest {
+    type ResponseMsg = super::SubscribeResponse;
+}
+
+impl ResponseMsg for super::SubscribeResponse {
+    type RequestMsg = super::SubscribeRequest;
+}
+
+impl RequestMsg for super::ShutdownRequest {
+    type ResponseMsg = super::ShutdownResponse;
+}
+
+impl ResponseMsg for super::ShutdownResponse {
+    type RequestMsg = super::ShutdownRequest;
+}
+
+impl RequestMsg for super::RotateKeypairRequest {
+    type ResponseMsg = super::RotateKeypairResponse;
+}
+
+impl ResponseMsg for super::RotateKeypairResponse {
+    type RequestMsg = super::RotateKeypairRequest;
+}
+
 /// Request and response for the [crate::api::RequestMsgType::Ping] message type
 pub type PingPair<B1, B2> = (Ref<B1, PingRequest>, Ref<B2, PingResponse>);
 /// Request and response for the [crate::api::RequestMsgType::SupplyKeypair] message type
@@ -91,12 +179,78 @@ pub type AddPskBrokerPair<B1, B2> = (
     Ref<B2, super::AddPskBrokerResponse>,
 );
 
+/// Request and response for the [crate::api::RequestMsgType::SetPeerTiming] message type
+pub type SetPeerTimingPair<B1, B2> = (
+    Ref<B1, super::SetPeerTimingRequest>,
+    Ref<B2, super::SetPeerTimingResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::FetchEvents] message type
+pub type FetchEventsPair<B1, B2> = (
+    Ref<B1, super::FetchEventsRequest>,
+    Ref<B2, super::FetchEventsResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::SetLogLevel] message type
+pub type SetLogLevelPair<B1, B2> = (
+    Ref<B1, super::SetLogLevelRequest>,
+    Ref<B2, super::SetLogLevelResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::AddPeer] message type
+pub type AddPeerPair<B1, B2> = (
+    Ref<B1, super::AddPeerRequest>,
+    Ref<B2, super::AddPeerResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::RemovePeer] message type
+pub type RemovePeerPair<B1, B2> = (
+    Ref<B1, super::RemovePeerRequest>,
+    Ref<B2, super::RemovePeerResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::ListPeers] message type
+pub type ListPeersPair<B1, B2> = (
+    Ref<B1, super::ListPeersRequest>,
+    Ref<B2, super::ListPeersResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::GetPeerStatus] message type
+pub type GetPeerStatusPair<B1, B2> = (
+    Ref<B1, super::GetPeerStatusRequest>,
+    Ref<B2, super::GetPeerStatusResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::ForceRekey] message type
+pub type ForceRekeyPair<B1, B2> = (
+    Ref<B1, super::ForceRekeyRequest>,
+    Ref<B2, super::ForceRekeyResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::Subscribe] message type
+pub type SubscribePair<B1, B2> = (
+    Ref<B1, super::SubscribeRequest>,
+    Ref<B2, super::SubscribeResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::Shutdown] message type
+pub type ShutdownPair<B1, B2> = (
+    Ref<B1, super::ShutdownRequest>,
+    Ref<B2, super::ShutdownResponse>,
+);
+/// Request and response for the [crate::api::RequestMsgType::RotateKeypair] message type
+pub type RotateKeypairPair<B1, B2> = (
+    Ref<B1, super::RotateKeypairRequest>,
+    Ref<B2, super::RotateKeypairResponse>,
+);
 /// A pair of references to messages; request and response each.
 pub enum RequestResponsePair<B1, B2> {
     Ping(PingPair<B1, B2>),
     SupplyKeypair(SupplyKeypairPair<B1, B2>),
     AddListenSocket(AddListenSocketPair<B1, B2>),
     AddPskBroker(AddPskBrokerPair<B1, B2>),
+    SetPeerTiming(SetPeerTimingPair<B1, B2>),
+    FetchEvents(FetchEventsPair<B1, B2>),
+    SetLogLevel(SetLogLevelPair<B1, B2>),
+    AddPeer(AddPeerPair<B1, B2>),
+    RemovePeer(RemovePeerPair<B1, B2>),
+    ListPeers(ListPeersPair<B1, B2>),
+    GetPeerStatus(GetPeerStatusPair<B1, B2>),
+    ForceRekey(ForceRekeyPair<B1, B2>),
+    Subscribe(SubscribePair<B1, B2>),
+    Shutdown(ShutdownPair<B1, B2>),
+    RotateKeypair(RotateKeypairPair<B1, B2>),
 }
 
 impl<B1, B2> From<PingPair<B1, B2>> for RequestResponsePair<B1, B2> {
@@ -123,6 +277,72 @@ impl<B1, B2> From<AddPskBrokerPair<B1, B2>> for RequestResponsePair<B1, B2> {
     }
 }
 
+impl<B1, B2> From<SetPeerTimingPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: SetPeerTimingPair<B1, B2>) -> Self {
+        RequestResponsePair::SetPeerTiming(v)
+    }
+}
+
+impl<B1, B2> From<FetchEventsPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: FetchEventsPair<B1, B2>) -> Self {
+        RequestResponsePair::FetchEvents(v)
+    }
+}
+
+impl<B1, B2> From<SetLogLevelPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: SetLogLevelPair<B1, B2>) -> Self {
+        RequestResponsePair::SetLogLevel(v)
+    }
+}
+
+impl<B1, B2> From<AddPeerPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: AddPeerPair<B1, B2>) -> Self {
+        RequestResponsePair::AddPeer(v)
+    }
+}
+
+impl<B1, B2> From<RemovePeerPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: RemovePeerPair<B1, B2>) -> Self {
+        RequestResponsePair::RemovePeer(v)
+    }
+}
+
+impl<B1, B2> From<ListPeersPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: ListPeersPair<B1, B2>) -> Self {
+        RequestResponsePair::ListPeers(v)
+    }
+}
+
+impl<B1, B2> From<GetPeerStatusPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: GetPeerStatusPair<B1, B2>) -> Self {
+        RequestResponsePair::GetPeerStatus(v)
+    }
+}
+
+impl<B1, B2> From<ForceRekeyPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: ForceRekeyPair<B1, B2>) -> Self {
+        RequestResponsePair::ForceRekey(v)
+    }
+}
+
+impl<B1, B2> From<SubscribePair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: SubscribePair<B1, B2>) -> Self {
+        RequestResponsePair::Subscribe(v)
+    }
+}
+
+impl<B1, B2> From<ShutdownPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: ShutdownPair<B1, B2>) -> Self {
+        RequestResponsePair::Shutdown(v)
+    }
+}
+
+impl<B1, B2> From<RotateKeypairPair<B1, B2>> for RequestResponsePair<B1, B2> {
+    fn from(v: RotateKeypairPair<B1, B2>) -> Self {
+        RequestResponsePair::RotateKeypair(v)
+    }
+}
+
 impl<B1, B2> RequestResponsePair<B1, B2>
 where
     B1: ByteSlice,
@@ -151,6 +371,61 @@ where
                 let res = ResponseRef::AddPskBroker(res.emancipate());
                 (req, res)
             }
+            Self::SetPeerTiming((req, res)) => {
+                let req = RequestRef::SetPeerTiming(req.emancipate());
+                let res = ResponseRef::SetPeerTiming(res.emancipate());
+                (req, res)
+            }
+            Self::FetchEvents((req, res)) => {
+                let req = RequestRef::FetchEvents(req.emancipate());
+                let res = ResponseRef::FetchEvents(res.emancipate());
+                (req, res)
+            }
+            Self::SetLogLevel((req, res)) => {
+                let req = RequestRef::SetLogLevel(req.emancipate());
+                let res = ResponseRef::SetLogLevel(res.emancipate());
+                (req, res)
+            }
+            Self::AddPeer((req, res)) => {
+                let req = RequestRef::AddPeer(req.emancipate());
+                let res = ResponseRef::AddPeer(res.emancipate());
+                (req, res)
+            }
+            Self::RemovePeer((req, res)) => {
+                let req = RequestRef::RemovePeer(req.emancipate());
+                let res = ResponseRef::RemovePeer(res.emancipate());
+                (req, res)
+            }
+            Self::ListPeers((req, res)) => {
+                let req = RequestRef::ListPeers(req.emancipate());
+                let res = ResponseRef::ListPeers(res.emancipate());
+                (req, res)
+            }
+            Self::GetPeerStatus((req, res)) => {
+                let req = RequestRef::GetPeerStatus(req.emancipate());
+                let res = ResponseRef::GetPeerStatus(res.emancipate());
+                (req, res)
+            }
+            Self::ForceRekey((req, res)) => {
+                let req = RequestRef::ForceRekey(req.emancipate());
+                let res = ResponseRef::ForceRekey(res.emancipate());
+                (req, res)
+            }
+            Self::Subscribe((req, res)) => {
+                let req = RequestRef::Subscribe(req.emancipate());
+                let res = ResponseRef::Subscribe(res.emancipate());
+                (req, res)
+            }
+            Self::Shutdown((req, res)) => {
+                let req = RequestRef::Shutdown(req.emancipate());
+                let res = ResponseRef::Shutdown(res.emancipate());
+                (req, res)
+            }
+            Self::RotateKeypair((req, res)) => {
+                let req = RequestRef::RotateKeypair(req.emancipate());
+                let res = ResponseRef::RotateKeypair(res.emancipate());
+                (req, res)
+            }
         }
     }
 
@@ -193,6 +468,61 @@ where
                 let res = ResponseRef::AddPskBroker(res.emancipate_mut());
                 (req, res)
             }
+            Self::SetPeerTiming((req, res)) => {
+                let req = RequestRef::SetPeerTiming(req.emancipate_mut());
+                let res = ResponseRef::SetPeerTiming(res.emancipate_mut());
+                (req, res)
+            }
+            Self::FetchEvents((req, res)) => {
+                let req = RequestRef::FetchEvents(req.emancipate_mut());
+                let res = ResponseRef::FetchEvents(res.emancipate_mut());
+                (req, res)
+            }
+            Self::SetLogLevel((req, res)) => {
+                let req = RequestRef::SetLogLevel(req.emancipate_mut());
+                let res = ResponseRef::SetLogLevel(res.emancipate_mut());
+                (req, res)
+            }
+            Self::AddPeer((req, res)) => {
+                let req = RequestRef::AddPeer(req.emancipate_mut());
+                let res = ResponseRef::AddPeer(res.emancipate_mut());
+                (req, res)
+            }
+            Self::RemovePeer((req, res)) => {
+                let req = RequestRef::RemovePeer(req.emancipate_mut());
+                let res = ResponseRef::RemovePeer(res.emancipate_mut());
+                (req, res)
+            }
+            Self::ListPeers((req, res)) => {
+                let req = RequestRef::ListPeers(req.emancipate_mut());
+                let res = ResponseRef::ListPeers(res.emancipate_mut());
+                (req, res)
+            }
+            Self::GetPeerStatus((req, res)) => {
+                let req = RequestRef::GetPeerStatus(req.emancipate_mut());
+                let res = ResponseRef::GetPeerStatus(res.emancipate_mut());
+                (req, res)
+            }
+            Self::ForceRekey((req, res)) => {
+                let req = RequestRef::ForceRekey(req.emancipate_mut());
+                let res = ResponseRef::ForceRekey(res.emancipate_mut());
+                (req, res)
+            }
+            Self::Subscribe((req, res)) => {
+                let req = RequestRef::Subscribe(req.emancipate_mut());
+                let res = ResponseRef::Subscribe(res.emancipate_mut());
+                (req, res)
+            }
+            Self::Shutdown((req, res)) => {
+                let req = RequestRef::Shutdown(req.emancipate_mut());
+                let res = ResponseRef::Shutdown(res.emancipate_mut());
+                (req, res)
+            }
+            Self::RotateKeypair((req, res)) => {
+                let req = RequestRef::RotateKeypair(req.emancipate_mut());
+                let res = ResponseRef::RotateKeypair(res.emancipate_mut());
+                (req, res)
+            }
         }
     }
 
@@ -71,6 +71,17 @@ impl<B: ByteSlice> ResponseRef<B> {
             Self::SupplyKeypair(_) => ResponseMsgType::SupplyKeypair,
             Self::AddListenSocket(_) => ResponseMsgType::AddListenSocket,
             Self::AddPskBroker(_) => ResponseMsgType::AddPskBroker,
+            Self::SetPeerTiming(_) => ResponseMsgType::SetPeerTiming,
+            Self::FetchEvents(_) => ResponseMsgType::FetchEvents,
+            Self::SetLogLevel(_) => ResponseMsgType::SetLogLevel,
+            Self::AddPeer(_) => ResponseMsgType::AddPeer,
+            Self::RemovePeer(_) => ResponseMsgType::RemovePeer,
+            Self::ListPeers(_) => ResponseMsgType::ListPeers,
+            Self::GetPeerStatus(_) => ResponseMsgType::GetPeerStatus,
+            Self::ForceRekey(_) => ResponseMsgType::ForceRekey,
+            Self::Subscribe(_) => ResponseMsgType::Subscribe,
+            Self::Shutdown(_) => ResponseMsgType::Shutdown,
+            Self::RotateKeypair(_) => ResponseMsgType::RotateKeypair,
         }
     }
 }
@@ -99,6 +110,72 @@ impl<B> From<Ref<B, super::AddPskBrokerResponse>> for ResponseRef<B> {
     }
 }
 
+impl<B> From<Ref<B, super::SetPeerTimingResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::SetPeerTimingResponse>) -> Self {
+        Self::SetPeerTiming(v)
+    }
+}
+
+impl<B> From<Ref<B, super::FetchEventsResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::FetchEventsResponse>) -> Self {
+        Self::FetchEvents(v)
+    }
+}
+
+impl<B> From<Ref<B, super::SetLogLevelResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::SetLogLevelResponse>) -> Self {
+        Self::SetLogLevel(v)
+    }
+}
+
+impl<B> From<Ref<B, super::AddPeerResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::AddPeerResponse>) -> Self {
+        Self::AddPeer(v)
+    }
+}
+
+impl<B> From<Ref<B, super::RemovePeerResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::RemovePeerResponse>) -> Self {
+        Self::RemovePeer(v)
+    }
+}
+
+impl<B> From<Ref<B, super::ListPeersResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::ListPeersResponse>) -> Self {
+        Self::ListPeers(v)
+    }
+}
+
+impl<B> From<Ref<B, super::GetPeerStatusResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::GetPeerStatusResponse>) -> Self {
+        Self::GetPeerStatus(v)
+    }
+}
+
+impl<B> From<Ref<B, super::ForceRekeyResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::ForceRekeyResponse>) -> Self {
+        Self::ForceRekey(v)
+    }
+}
+
+impl<B> From<Ref<B, super::SubscribeResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::SubscribeResponse>) -> Self {
+        Self::Subscribe(v)
+    }
+}
+
+impl<B> From<Ref<B, super::ShutdownResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::ShutdownResponse>) -> Self {
+        Self::Shutdown(v)
+    }
+}
+
+impl<B> From<Ref<B, super::RotateKeypairResponse>> for ResponseRef<B> {
+    fn from(v: Ref<B, super::RotateKeypairResponse>) -> Self {
+        Self::RotateKeypair(v)
+    }
+}
+
 impl<B: ByteSlice> ResponseRefMaker<B> {
     fn new(buf: B) -> anyhow::Result<Self> {
         let msg_type = buf.deref().response_msg_type_from_prefix()?;
@@ -121,6 +198,31 @@ impl<B: ByteSlice> ResponseRefMaker<B> {
             ResponseMsgType::AddPskBroker => {
                 ResponseRef::AddPskBroker(self.buf.add_psk_broker_response()?)
             }
+            ResponseMsgType::SetPeerTiming => {
+                ResponseRef::SetPeerTiming(self.buf.set_peer_timing_response()?)
+            }
+            ResponseMsgType::FetchEvents => {
+                ResponseRef::FetchEvents(self.buf.fetch_events_response()?)
+            }
+            ResponseMsgType::SetLogLevel => {
+                ResponseRef::SetLogLevel(self.buf.set_log_level_response()?)
+            }
+            ResponseMsgType::AddPeer => ResponseRef::AddPeer(self.buf.add_peer_response()?),
+            ResponseMsgType::RemovePeer => {
+                ResponseRef::RemovePeer(self.buf.remove_peer_response()?)
+            }
+            ResponseMsgType::ListPeers => ResponseRef::ListPeers(self.buf.list_peers_response()?),
+            ResponseMsgType::GetPeerStatus => {
+                ResponseRef::GetPeerStatus(self.buf.get_peer_status_response()?)
+            }
+            ResponseMsgType::ForceRekey => {
+                ResponseRef::ForceRekey(self.buf.force_rekey_response()?)
+            }
+            ResponseMsgType::Subscribe => ResponseRef::Subscribe(self.buf.subscribe_response()?),
+            ResponseMsgType::Shutdown => ResponseRef::Shutdown(self.buf.shutdown_response()?),
+            ResponseMsgType::RotateKeypair => {
+                ResponseRef::RotateKeypair(self.buf.rotate_keypair_response()?)
+            }
         })
     }
 
@@ -159,6 +261,17 @@ pub enum ResponseRef<B> {
     SupplyKeypair(Ref<B, super::SupplyKeypairResponse>),
     AddListenSocket(Ref<B, super::AddListenSocketResponse>),
     AddPskBroker(Ref<B, super::AddPskBrokerResponse>),
+    SetPeerTiming(Ref<B, super::SetPeerTimingResponse>),
+    FetchEvents(Ref<B, super::FetchEventsResponse>),
+    SetLogLevel(Ref<B, super::SetLogLevelResponse>),
+    AddPeer(Ref<B, super::AddPeerResponse>),
+    RemovePeer(Ref<B, super::RemovePeerResponse>),
+    ListPeers(Ref<B, super::ListPeersResponse>),
+    GetPeerStatus(Ref<B, super::GetPeerStatusResponse>),
+    ForceRekey(Ref<B, super::ForceRekeyResponse>),
+    Subscribe(Ref<B, super::SubscribeResponse>),
+    Shutdown(Ref<B, super::ShutdownResponse>),
+    RotateKeypair(Ref<B, super::RotateKeypairResponse>),
 }
 
 impl<B> ResponseRef<B>
@@ -176,6 +289,17 @@ where
             Self::SupplyKeypair(r) => r.bytes(),
             Self::AddListenSocket(r) => r.bytes(),
             Self::AddPskBroker(r) => r.bytes(),
+            Self::SetPeerTiming(r) => r.bytes(),
+            Self::FetchEvents(r) => r.bytes(),
+            Self::SetLogLevel(r) => r.bytes(),
+            Self::AddPeer(r) => r.bytes(),
+            Self::RemovePeer(r) => r.bytes(),
+            Self::ListPeers(r) => r.bytes(),
+            Self::GetPeerStatus(r) => r.bytes(),
+            Self::ForceRekey(r) => r.bytes(),
+            Self::Subscribe(r) => r.bytes(),
+            Self::Shutdown(r) => r.bytes(),
+            Self::RotateKeypair(r) => r.bytes(),
         }
     }
 }
@@ -191,6 +315,17 @@ where
             Self::SupplyKeypair(r) => r.bytes_mut(),
             Self::AddListenSocket(r) => r.bytes_mut(),
             Self::AddPskBroker(r) => r.bytes_mut(),
+            Self::SetPeerTiming(r) => r.bytes_mut(),
+            Self::FetchEvents(r) => r.bytes_mut(),
+            Self::SetLogLevel(r) => r.bytes_mut(),
+            Self::AddPeer(r) => r.bytes_mut(),
+            Self::RemovePeer(r) => r.bytes_mut(),
+            Self::ListPeers(r) => r.bytes_mut(),
+            Self::GetPeerStatus(r) => r.bytes_mut(),
+            Self::ForceRekey(r) => r.bytes_mut(),
+            Self::Subscribe(r) => r.bytes_mut(),
+            Self::Shutdown(r) => r.bytes_mut(),
+            Self::RotateKeypair(r) => r.bytes_mut(),
         }
     }
 }
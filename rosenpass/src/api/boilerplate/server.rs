@@ -141,6 +141,390 @@ pub trait Server {
         res: &mut super::AddPskBrokerResponse,
     ) -> anyhow::Result<()>;
 
+    /// Change the timers (rekey and reject intervals, retransmission delays) used for a peer
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::SetPeerTiming] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::set_peer_timing_response_status::OK] - Indicates success
+    /// 2. [crate::api::set_peer_timing_response_status::NO_SUCH_PEER] – No peer with the given
+    ///    peer id is known
+    /// 3. [crate::api::set_peer_timing_response_status::INVALID_REQUEST] – The resulting timing
+    ///    profile is outside the safety bounds (see [crate::protocol::TimingProfile::validate])
+    /// 4. [crate::api::set_peer_timing_response_status::INTERNAL_ERROR] – Some other, non-fatal error
+    ///    occured. Check the logs on log
+    ///
+    /// # Description
+    ///
+    /// The peer is identified through its peer id ([crate::protocol::Peer::pidt]). Timers are
+    /// given in milliseconds, the retransmission growth factor and jitter in thousandths; a value
+    /// of zero selects the protocol default. The new timers take effect immediately.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn set_peer_timing(
+        &mut self,
+        req: &super::SetPeerTimingRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::SetPeerTimingResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Fetch the most recent events raised by the server, such as endpoint changes
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::FetchEvents] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::fetch_events_response_status::OK] - Indicates success
+    ///
+    /// # Description
+    ///
+    /// Returns up to [crate::api::MAX_EVENTS_PER_RESPONSE] events from the
+    /// [crate::events::EventLog] whose sequence numbers are greater than the one given in the
+    /// request, oldest first. To keep up with the events, clients pass the sequence number of
+    /// the last event received in the next request. The server only keeps a limited number of
+    /// events; the response indicates how many events the client missed.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn fetch_events(
+        &mut self,
+        req: &super::FetchEventsRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::FetchEventsResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Change which records are logged
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::SetLogLevel] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::set_log_level_response_status::OK] - Indicates success
+    /// 2. [crate::api::set_log_level_response_status::INVALID_REQUEST] – The filter given is not
+    ///    a valid [crate::logging::LogFilter]
+    ///
+    /// # Description
+    ///
+    /// Sets the [crate::logging::LogFilter] until it is changed again, through another request,
+    /// SIGUSR1 or SIGUSR2 (see [crate::logging]), or the configuration file is reloaded. A
+    /// request without a filter restores the configured filter. Either way, the response contains
+    /// the filter in effect afterwards.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn set_log_level(
+        &mut self,
+        req: &super::SetLogLevelRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::SetLogLevelResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Add a protocol peer at runtime
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::AddPeer] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// 1. The public key of the peer
+    /// 2. The pre-shared key for the peer; optional
+    ///
+    /// Both are read in raw binary form, just like the keys in [Self::supply_keypair].
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::add_peer_response_status::OK] - Indicates success
+    /// 2. [crate::api::add_peer_response_status::INVALID_REQUEST] – Malformed request; the
+    ///    public key is missing or has the wrong size, the endpoint is not of the form
+    ///    `host:port`, or keys are to be supplied to WireGuard but there is no PSK broker
+    /// 3. [crate::api::add_peer_response_status::PEER_ALREADY_EXISTS] – A peer with the same
+    ///    public key is registered already
+    /// 4. [crate::api::add_peer_response_status::INTERNAL_ERROR] – Some other, non-fatal error
+    ///    occured. Check the logs on log
+    ///
+    /// # Description
+    ///
+    /// The peer is added just like a peer from the configuration file (see
+    /// [crate::config::RosenpassPeer]), using the default timers and protocol suite. The
+    /// request may contain the endpoint of the peer, a file to write exchanged keys to, and a
+    /// WireGuard interface and peer to supply the keys to; the latter uses the PSK broker
+    /// registered most recently (see [Self::add_psk_broker]). The response contains the peer id
+    /// used to refer to the peer in other requests.
+    ///
+    /// An endpoint given by hostname is resolved in the background (see
+    /// [crate::endpoint_refresh]), so the response does not wait for DNS; if it can not be
+    /// resolved, this is only logged.
+    ///
+    /// Peers can be added both before and after the keypair is supplied.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn add_peer(
+        &mut self,
+        req: &super::AddPeerRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::AddPeerResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Remove a protocol peer at runtime
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::RemovePeer] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::remove_peer_response_status::OK] - Indicates success
+    /// 2. [crate::api::remove_peer_response_status::NO_SUCH_PEER] – No peer with the given
+    ///    peer id is known
+    /// 3. [crate::api::remove_peer_response_status::INVALID_REQUEST] – The keypair was not
+    ///    supplied yet; peers can only be removed afterwards
+    /// 4. [crate::api::remove_peer_response_status::INTERNAL_ERROR] – Some other, non-fatal error
+    ///    occured. Check the logs on log
+    ///
+    /// # Description
+    ///
+    /// The peer is identified through its peer id ([crate::protocol::Peer::pidt]). If a key was
+    /// exchanged with the peer, it is replaced by a stale key first; see
+    /// [crate::app_server::AppServer::remove_peer]. This applies to peers from the
+    /// configuration file as well.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn remove_peer(
+        &mut self,
+        req: &super::RemovePeerRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::RemovePeerResponse,
+    ) -> anyhow::Result<()>;
+
+    /// List the protocol peers along with the state of their sessions and handshakes
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::ListPeers] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::list_peers_response_status::OK] - Indicates success
+    /// 2. [crate::api::list_peers_response_status::INTERNAL_ERROR] – Some non-fatal error
+    ///    occured. Check the logs on log
+    ///
+    /// # Description
+    ///
+    /// Returns at most [crate::api::MAX_PEERS_PER_RESPONSE] peers, skipping the first
+    /// [crate::api::ListPeersRequestPayload::offset] ones; see [crate::api::ApiPeerStatus] for
+    /// the information returned. No key material is included.
+    ///
+    /// Peers are listed in the order they were added in. If peers are added or removed while
+    /// paging through the list, some peers may be skipped or returned twice;
+    /// [crate::api::ListPeersResponsePayload::total] can be used to detect this.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn list_peers(
+        &mut self,
+        req: &super::ListPeersRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::ListPeersResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Retrieve the state of the session and handshake of a single protocol peer
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::GetPeerStatus] API
+    /// message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::get_peer_status_response_status::OK] - Indicates success
+    /// 2. [crate::api::get_peer_status_response_status::NO_SUCH_PEER] – No peer with the given
+    ///    peer id is known
+    /// 3. [crate::api::get_peer_status_response_status::INTERNAL_ERROR] – Some other, non-fatal
+    ///    error occured. Check the logs on log
+    ///
+    /// # Description
+    ///
+    /// The peer is identified through its peer id ([crate::protocol::Peer::pidt]); see
+    /// [crate::api::ApiPeerStatus] for the information returned. No key material is included.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn get_peer_status(
+        &mut self,
+        req: &super::GetPeerStatusRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::GetPeerStatusResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Start a new key exchange with a protocol peer right away
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::ForceRekey] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::force_rekey_response_status::OK] - Indicates success
+    /// 2. [crate::api::force_rekey_response_status::NO_SUCH_PEER] – No peer with the given
+    ///    peer id is known
+    /// 3. [crate::api::force_rekey_response_status::NO_ENDPOINT] – The peer's endpoint is not
+    ///    known, so no handshake can be initiated; nothing was changed
+    /// 4. [crate::api::force_rekey_response_status::INVALID_REQUEST] – The keypair was not
+    ///    supplied yet or [crate::api::ForceRekeyRequestPayload::erase_key] is neither zero
+    ///    nor one
+    /// 5. [crate::api::force_rekey_response_status::INTERNAL_ERROR] – Some other, non-fatal error
+    ///    occured. Check the logs on log
+    ///
+    /// # Description
+    ///
+    /// The peer is identified through its peer id ([crate::protocol::Peer::pidt]). A new
+    /// handshake is initiated, discarding any handshake in progress; see
+    /// [crate::app_server::AppServer::force_rekey].
+    ///
+    /// If [crate::api::ForceRekeyRequestPayload::erase_key] is set, the current key is replaced
+    /// by a stale key immediately; otherwise it remains in use until the new key arrives.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn force_rekey(
+        &mut self,
+        req: &super::ForceRekeyRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::ForceRekeyResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Turn the API connection into a stream of events
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::Subscribe] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::subscribe_response_status::OK] - Indicates success
+    ///
+    /// # Description
+    ///
+    /// The first response confirms the subscription; its
+    /// [crate::api::SubscribeResponsePayload::event] is empty. After that, no more requests are
+    /// processed on the connection. Instead, every [crate::events::AppEvent] raised by the server
+    /// is sent as another, length prefixed [crate::api::SubscribeResponse], starting with the
+    /// events after [crate::api::SubscribeRequestPayload::after] that are still kept in the
+    /// [crate::events::EventLog].
+    ///
+    /// Events are sent without ever blocking the server. A subscriber that reads too slowly to
+    /// keep up with the [crate::events::EventLog] skips the events dropped from the log in the
+    /// meantime; their number is reported in [crate::api::SubscribeResponsePayload::missed].
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn subscribe(
+        &mut self,
+        req: &super::SubscribeRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::SubscribeResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Stop the server, erasing all secrets
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::Shutdown] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// None
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::shutdown_response_status::OK] - Indicates success
+    ///
+    /// # Description
+    ///
+    /// The response is sent before the server shuts down. Every key exchanged with a peer is then
+    /// replaced by a random, stale key, just as if the session had expired, and all secrets
+    /// including the server keys are erased; see [crate::app_server::AppServer::shutdown]. No
+    /// state snapshot is written, even if one is configured.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn shutdown(
+        &mut self,
+        req: &super::ShutdownRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::ShutdownResponse,
+    ) -> anyhow::Result<()>;
+
+    /// Replace the cryptographic server keypair without interrupting key exchanges
+    ///
+    /// This implements the handler for the [crate::api::RequestMsgType::RotateKeypair] API message.
+    ///
+    /// # File descriptors
+    ///
+    /// 1. The new secret key (size must match exactly); see [Self::supply_keypair]
+    /// 2. The new public key (size must match exactly); see [Self::supply_keypair]
+    ///
+    /// # API Return Status
+    ///
+    /// 1. [crate::api::rotate_keypair_response_status::OK] - Indicates success
+    /// 2. [crate::api::rotate_keypair_response_status::INVALID_REQUEST] – Malformed request; could be:
+    ///     - Missing file descriptors for the keys
+    ///     - File descriptors contain data of invalid length
+    /// 3. [crate::api::rotate_keypair_response_status::INTERNAL_ERROR] – Some other error
+    /// 4. [crate::api::rotate_keypair_response_status::NO_KEYPAIR] – The server has no keypair
+    ///    yet; use [Self::supply_keypair] instead
+    ///
+    /// # Description
+    ///
+    /// The new keypair is used right away. For
+    /// [crate::api::RotateKeypairRequestPayload::grace_period_ms], messages from peers still
+    /// using the previous public key are processed using the previous keypair, so the peers can
+    /// switch over to the new public key; see [crate::protocol::CryptoServer::rotate_keypair].
+    /// The previous keypair is erased afterwards.
+    ///
+    /// # Examples
+    ///
+    /// See the example of how to use the API in [crate::api].
+    fn rotate_keypair(
+        &mut self,
+        req: &super::RotateKeypairRequest,
+        req_fds: &mut VecDeque<OwnedFd>,
+        res: &mut super::RotateKeypairResponse,
+    ) -> anyhow::Result<()>;
+
     /// Similar to [Self::handle_message], but takes a [RequestResponsePair]
     /// instead of taking to separate byte buffers.
     ///
@@ -164,6 +548,23 @@ pub trait Server {
                 self.add_listen_socket(req, req_fds, res)
             }
             RequestResponsePair::AddPskBroker((req, res)) => self.add_psk_broker(req, req_fds, res),
+            RequestResponsePair::SetPeerTiming((req, res)) => {
+                self.set_peer_timing(req, req_fds, res)
+            }
+            RequestResponsePair::FetchEvents((req, res)) => self.fetch_events(req, req_fds, res),
+            RequestResponsePair::SetLogLevel((req, res)) => self.set_log_level(req, req_fds, res),
+            RequestResponsePair::AddPeer((req, res)) => self.add_peer(req, req_fds, res),
+            RequestResponsePair::RemovePeer((req, res)) => self.remove_peer(req, req_fds, res),
+            RequestResponsePair::ListPeers((req, res)) => self.list_peers(req, req_fds, res),
+            RequestResponsePair::GetPeerStatus((req, res)) => {
+                self.get_peer_status(req, req_fds, res)
+            }
+            RequestResponsePair::ForceRekey((req, res)) => self.force_rekey(req, req_fds, res),
+            RequestResponsePair::Subscribe((req, res)) => self.subscribe(req, req_fds, res),
+            RequestResponsePair::Shutdown((req, res)) => self.shutdown(req, req_fds, res),
+            RequestResponsePair::RotateKeypair((req, res)) => {
+                self.rotate_keypair(req, req_fds, res)
+            }
         }
     }
 
@@ -208,6 +609,61 @@ pub trait Server {
                 res.init();
                 RequestResponsePair::AddPskBroker((req, res))
             }
+            RequestRef::SetPeerTiming(req) => {
+                let mut res = res.set_peer_timing_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::SetPeerTiming((req, res))
+            }
+            RequestRef::FetchEvents(req) => {
+                let mut res = res.fetch_events_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::FetchEvents((req, res))
+            }
+            RequestRef::SetLogLevel(req) => {
+                let mut res = res.set_log_level_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::SetLogLevel((req, res))
+            }
+            RequestRef::AddPeer(req) => {
+                let mut res = res.add_peer_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::AddPeer((req, res))
+            }
+            RequestRef::RemovePeer(req) => {
+                let mut res = res.remove_peer_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::RemovePeer((req, res))
+            }
+            RequestRef::ListPeers(req) => {
+                let mut res = res.list_peers_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::ListPeers((req, res))
+            }
+            RequestRef::GetPeerStatus(req) => {
+                let mut res = res.get_peer_status_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::GetPeerStatus((req, res))
+            }
+            RequestRef::ForceRekey(req) => {
+                let mut res = res.force_rekey_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::ForceRekey((req, res))
+            }
+            RequestRef::Subscribe(req) => {
+                let mut res = res.subscribe_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::Subscribe((req, res))
+            }
+            RequestRef::Shutdown(req) => {
+                let mut res = res.shutdown_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::Shutdown((req, res))
+            }
+            RequestRef::RotateKeypair(req) => {
+                let mut res = res.rotate_keypair_response_from_prefix()?;
+                res.init();
+                RequestResponsePair::RotateKeypair((req, res))
+            }
         };
         self.dispatch(&mut pair, req_fds)?;
 
@@ -13,9 +13,10 @@ use rosenpass_util::{
     },
     mio::interest::RW as MIO_RW,
 };
+use zerocopy::AsBytes;
 use zeroize::Zeroize;
 
-use crate::api::MAX_REQUEST_FDS;
+use crate::api::{subscribe_response_status, ApiEvent, SubscribeResponse, MAX_REQUEST_FDS};
 use crate::{api::Server, app_server::AppServer};
 
 use super::super::{ApiHandler, ApiHandlerContext};
@@ -138,6 +139,11 @@ pub trait MioConnectionContext {
             };
         }
 
+        // Requests are not processed any more once the client subscribed to events
+        if self.api_handler().subscribed_after().is_some() {
+            return self.poll_event_stream();
+        }
+
         // All of these functions return an error, None ("operation incomplete")
         // or some ("operation complete, keep processing")
         short!(self.flush_write_buffer()?); // Flush last message
@@ -172,6 +178,91 @@ pub trait MioConnectionContext {
         })
     }
 
+    /// Called by [Self::poll] instead of processing requests once the connection was turned
+    /// into an event stream through [Server::subscribe]
+    ///
+    /// Sends events until there are none left or the unix stream would block; events not sent
+    /// yet remain in the [crate::events::EventLog]. Errors while writing, e.g. because the
+    /// subscriber went away, close the connection.
+    ///
+    /// The read side is still polled, so a subscriber hanging up is noticed even when there are
+    /// no events to send.
+    fn poll_event_stream(&mut self) -> anyhow::Result<()> {
+        if self.subscriber_hung_up() {
+            log::debug!("Closing API event stream, the subscriber hung up");
+            self.write_buf_mut().zeroize();
+            self.mio_connection_mut().invalid_read = true; // Closed by mio_manager
+            return Ok(());
+        }
+
+        loop {
+            match self.flush_write_buffer() {
+                Ok(Some(())) => {}
+                Ok(None) => return Ok(()), // Would block
+                Err(e) => {
+                    log::debug!("Closing API event stream after error while writing: {e:?}");
+                    self.write_buf_mut().zeroize();
+                    self.mio_connection_mut().invalid_read = true; // Closed by mio_manager
+                    return Ok(());
+                }
+            }
+
+            if self.queue_event()?.is_none() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Called by [Self::poll_event_stream] to check whether the subscriber closed its end of the
+    /// unix stream
+    ///
+    /// Subscribers are not supposed to send anything; any data received is discarded.
+    fn subscriber_hung_up(&mut self) -> bool {
+        use std::io::{ErrorKind as K, Read};
+
+        let mut sock = &self.mio_connection().io;
+        let mut discard = [0u8; 64];
+        loop {
+            match sock.read(&mut discard) {
+                Ok(0) => break true, // End of file
+                Ok(_) => continue,
+                Err(e) if e.kind() == K::WouldBlock => break false,
+                Err(e) if e.kind() == K::Interrupted => continue,
+                Err(e) => {
+                    log::debug!("IO error while polling API event stream for hangup: {e:?}");
+                    break true;
+                }
+            }
+        }
+    }
+
+    /// Called by [Self::poll_event_stream] to put the next event, if any, into the send buffer
+    fn queue_event(&mut self) -> anyhow::Result<Option<()>> {
+        let Some(after) = self.api_handler().subscribed_after() else {
+            return Ok(None);
+        };
+
+        let log = &MioConnectionContext::app_server(self).event_log;
+        let (missed, mut events) = log.since(after);
+        let Some((seq, event)) = events.next() else {
+            return Ok(None);
+        };
+        let seq = *seq;
+        let msg = SubscribeResponse::new(
+            subscribe_response_status::OK,
+            missed,
+            ApiEvent::new(seq, event),
+        );
+
+        let write_buf = self.write_buf_mut();
+        let msg = msg.as_bytes();
+        write_buf.buffer_bytes_mut()[..msg.len()].copy_from_slice(msg);
+        write_buf.restart_write_with_new_message(msg.len())?;
+        self.api_handler_mut().event_sent(seq);
+
+        Ok(Some(()))
+    }
+
     /// Called by [Self::poll] to write data in the send buffer to the unix stream
     fn flush_write_buffer(&mut self) -> anyhow::Result<Option<()>> {
         if self.write_buf_mut().exhausted() {
@@ -1,6 +1,7 @@
 /// This contains the bulk of the rosenpass server IO handling code whereas
 /// the actual cryptographic code lives in the [crate::protocol] module
 use anyhow::bail;
+use anyhow::ensure;
 
 use anyhow::Context;
 use anyhow::Result;
@@ -12,6 +13,7 @@ use rosenpass_secret_memory::Public;
 use rosenpass_secret_memory::Secret;
 use rosenpass_util::build::ConstructionSite;
 use rosenpass_util::file::StoreValueB64;
+use rosenpass_util::file::{fopen_w, Visibility};
 use rosenpass_util::functional::run;
 use rosenpass_util::functional::ApplyExt;
 use rosenpass_util::io::IoResultKindHintExt;
@@ -39,22 +41,39 @@ use std::net::SocketAddrV6;
 use std::net::ToSocketAddrs;
 use std::path::PathBuf;
 use std::slice;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
 
+use crate::endpoint_refresh::{EndpointRefresh, ResolvedChannel};
+use crate::events::{AppEvent, EventLog};
+use crate::exchange_command::{ExchangeCommand, ExchangeCommandRunner};
+use crate::logging::{ErrorChain, LogLevelSignals};
+use crate::metrics::{DropReason, Metrics, SocketPathGuard};
+use crate::msgs::MsgType;
 use crate::protocol::BuildCryptoServer;
 use crate::protocol::HostIdentification;
+use crate::reload::ConfigReload;
+use crate::roaming::Roaming;
+use crate::sd_notify::SdNotify;
+use crate::socket_options::SocketOptions;
 use crate::{
     config::Verbosity,
-    protocol::{CryptoServer, MsgBuf, PeerPtr, SPk, SSk, SymKey, Timing},
+    protocol::{
+        validate_cookie_secret_epoch, CryptoServer, HandshakeTracer, MsgBuf, PeerId, PeerPtr,
+        PeerStatus, SPk, SSk, SuiteId, SymKey, Timing, TimingProfile,
+    },
 };
 use rosenpass_util::attempt;
 use rosenpass_util::b64::B64Display;
+use rosenpass_util::time::Timebase;
 
 /// The maximum size of a base64 encoded symmetric key (estimate)
 pub const MAX_B64_KEY_SIZE: usize = 32 * 5 / 3;
 /// The maximum size of a base64 peer ID (estimate)
 pub const MAX_B64_PEER_ID_SIZE: usize = 32 * 5 / 3;
+/// The maximum size of a base64 session ID (estimate)
+pub const MAX_B64_SESSION_ID_SIZE: usize = 16;
 
 /// The zero IPv4 address; this is generally used to tell network servers to choose any interface
 /// when listening
@@ -68,6 +87,10 @@ const IPV6_ANY_ADDR: Ipv6Addr = Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0);
 const UNDER_LOAD_RATIO: f64 = 0.5;
 /// Period at which the DOS detection code updates whether there is an "under load" status
 const DURATION_UPDATE_UNDER_LOAD_STATUS: Duration = Duration::from_millis(500);
+/// Time [AppServer::shutdown] waits for the PSK brokers to accept the stale keys
+const BROKER_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often [AppServer::shutdown] checks whether the PSK brokers accepted the stale keys
+const BROKER_FLUSH_INTERVAL: Duration = Duration::from_millis(10);
 
 pub const BROKER_ID_BYTES: usize = 8;
 
@@ -91,6 +114,89 @@ pub fn ipv6_any_binding() -> SocketAddr {
     SocketAddr::V6(SocketAddrV6::new(IPV6_ANY_ADDR, 0, 0, 0))
 }
 
+/// Bind the UDP sockets to listen on; used by [AppServer::new] and
+/// [AppServer::rebind_listen_sockets]
+///
+/// If `addrs` is empty, this binds to any interface (see [ipv4_any_binding] and
+/// [ipv6_any_binding]).
+fn bind_listen_sockets(addrs: Vec<SocketAddr>) -> anyhow::Result<Vec<mio::net::UdpSocket>> {
+    // bind each SocketAddr to a socket
+    let maybe_sockets: Result<Vec<_>, _> =
+        addrs.into_iter().map(mio::net::UdpSocket::bind).collect();
+    let mut sockets = maybe_sockets?;
+
+    // When no socket is specified, rosenpass should open one port on all
+    // available interfaces best-effort. Here are the cases how this can possibly go:
+    //
+    // Some operating systems (such as Linux [^linux] and FreeBSD [^freebsd])
+    // using IPv6 sockets to handle IPv4 connections; on these systems
+    // binding to the `[::]:0` address will typically open a dual-stack
+    // socket. Some other systems such as OpenBSD [^openbsd] do not support this feature.
+    //
+    // Dual-stack systems provide a flag to enable or disable this
+    // behavior – the IPV6_V6ONLY flag. OpenBSD supports this flag
+    // read-only. MIO[^mio] provides a way to read this flag but not
+    // to write it.
+    //
+    // - One dual-stack IPv6 socket, if the operating supports dual-stack sockets and
+    //   correctly reports this
+    // - One IPv6 socket and one IPv4 socket if the operating does not support dual stack
+    //   sockets or disables them by default assuming this is also correctly reported
+    // - One IPv6 socket and no IPv4 socket if IPv6 socket is not dual-stack and opening
+    //   the IPv6 socket fails
+    // - One IPv4 socket and no IPv6 socket if opening the IPv6 socket fails
+    // - One dual-stack IPv6 socket and a redundant IPv4 socket if dual-stack sockets are
+    //   supported but the operating system does not correctly report this (specifically,
+    //   if the only_v6() call raises an error)
+    // - Rosenpass exits if no socket could be opened
+    //
+    // [^freebsd]: https://man.freebsd.org/cgi/man.cgi?query=ip6&sektion=4&manpath=FreeBSD+6.0-RELEASE
+    // [^openbsd]: https://man.openbsd.org/ip6.4
+    // [^linux]: https://man7.org/linux/man-pages/man7/ipv6.7.html
+    // [^mio]: https://docs.rs/mio/0.8.6/mio/net/struct.UdpSocket.html#method.only_v6
+    if sockets.is_empty() {
+        macro_rules! try_register_socket {
+            ($title:expr, $binding:expr) => {{
+                let r = mio::net::UdpSocket::bind($binding);
+                match r {
+                    Ok(sock) => {
+                        sockets.push(sock);
+                        Some(sockets.len() - 1)
+                    }
+                    Err(e) => {
+                        warn!("Could not bind to {} socket: {}", $title, e);
+                        None
+                    }
+                }
+            }};
+        }
+
+        let v6 = try_register_socket!("IPv6", ipv6_any_binding());
+
+        let need_v4 = match v6.map(|no| sockets[no].only_v6()) {
+            Some(Ok(v)) => v,
+            None => true,
+            Some(Err(e)) => {
+                warn!(
+                    "Unable to detect whether the IPv6 socket supports dual-stack operation: {}",
+                    e
+                );
+                true
+            }
+        };
+
+        if need_v4 {
+            try_register_socket!("IPv4", ipv4_any_binding());
+        }
+    }
+
+    if sockets.is_empty() {
+        bail!("No sockets to listen on!")
+    }
+
+    Ok(sockets)
+}
+
 /// This is used to assign indices to MIO (epoll) event sources
 #[derive(Debug, Default)]
 pub struct MioTokenDispenser {
@@ -196,6 +302,9 @@ pub struct AppPeer {
     /// If this option is set, then [AppServer::output_key] will send generated output
     /// keys to the broker configured here
     pub broker_peer: Option<BrokerPeer>,
+    /// If this option is set, then [AppServer::output_key] will run this command with the
+    /// generated output key; see [crate::exchange_command]
+    pub exchange_command: Option<ExchangeCommand>,
     /// This is the network address configured for a particular peer at program start.
     ///
     /// I.e. this is the address the rosenpass program will send [crate::msgs::InitHello]
@@ -203,14 +312,20 @@ pub struct AppPeer {
     ///
     /// Note that the remote peer may connect with another address. See [Self::current_endpoint].
     pub initial_endpoint: Option<Endpoint>,
+    /// The hostname [Self::initial_endpoint] was resolved from, if any
+    pub hostname: Option<String>,
+    /// When to resolve [Self::hostname] again; see [crate::endpoint_refresh]
+    pub endpoint_refresh: EndpointRefresh,
     /// The network address currently used for a particular peer.
     ///
     /// This is not necessarily the address that was configured at program start (see [Self::initial_endpoint]),
     /// because the remote peer can initiate handshakes from an arbitrary network address.
     ///
     /// If another peer successfully connects to this one from any address, then this field will
-    /// be updated to reflect which address this was.
+    /// be updated to reflect which address this was, unless [Self::roaming] forbids it.
     pub current_endpoint: Option<Endpoint>,
+    /// Which addresses [Self::current_endpoint] may change to; see [crate::roaming]
+    pub roaming: Roaming,
 }
 
 impl AppPeer {
@@ -227,7 +342,11 @@ impl AppPeer {
     /// let mut peer = AppPeer {
     ///   outfile: None,
     ///   broker_peer: None,
+    ///   exchange_command: None,
     ///   initial_endpoint: Some(Endpoint::discovery_from_hostname("0.0.0.0:0".to_string())?),
+    ///   hostname: Some("0.0.0.0:0".to_string()),
+    ///   endpoint_refresh: Default::default(),
+    ///   roaming: Default::default(),
     ///   current_endpoint: Some(Endpoint::discovery_from_hostname("0.0.0.0:1".to_string())?),
     /// };
     ///
@@ -275,7 +394,7 @@ pub struct WireguardOut {
 
 /// Used to indicate whether the rosenpass server is in normal operating
 /// conditions or under load (i.e. a DOS attack is happening)
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DoSOperation {
     UnderLoad,
     Normal,
@@ -296,6 +415,10 @@ pub struct AppServerTest {
     /// Terminate application signal
     #[builder(default = "None")]
     pub termination_handler: Option<std::sync::mpsc::Receiver<()>>,
+    /// Custom time source for the server, e.g. a [rosenpass_util::time::ManualClock]
+    /// to drive protocol timeouts deterministically; see [AppServer::timebase]
+    #[builder(default = "None")]
+    pub timebase: Option<Timebase>,
 }
 
 /// This represents a some source of IO operations in the context of the Rosenpass server
@@ -358,9 +481,12 @@ pub struct AppServer {
     pub brokers: BrokerStore,
     /// This is our view of the peers; generally every peer in here is associated with one peer in
     /// CryptoServer
-    pub peers: Vec<AppPeer>,
-    /// If set to [Verbosity::Verbose], then some extra information will be printed
-    /// at the info log level
+    ///
+    /// Just like [CryptoServer::peers], peers removed through [Self::remove_peer] leave
+    /// a vacant slot behind so the [AppPeerPtr]s of the remaining peers stay valid.
+    pub peers: Vec<Option<AppPeer>>,
+    /// The [Verbosity] configured; which records are logged is decided by the
+    /// [crate::logging::LogFilter] alone
     pub verbosity: Verbosity,
     /// Used by [AppServer::try_recv] to ensure that all packages have been read
     /// from the UDP sockets
@@ -374,7 +500,12 @@ pub struct AppServer {
     /// State kept by the [AppServer::try_recv] for polling
     pub unpolled_count: usize,
     /// State kept by the [AppServer::try_recv] for polling
-    pub last_update_time: Instant,
+    pub last_update_time: Timing,
+    /// The time source shared by [Self] and the [CryptoServer] in [Self::crypto_site]
+    ///
+    /// This uses the real monotonic clock, unless a custom time source is
+    /// supplied through [AppServerTest::timebase].
+    pub timebase: Timebase,
     /// Used by integration tests to force [Self] into DoS condition
     /// and to terminate the AppServer after the test is complete
     pub test_helpers: Option<AppServerTest>,
@@ -386,8 +517,46 @@ pub struct AppServer {
     /// signal handler.
     ///
     /// See <https://github.com/rosenpass/rosenpass/issues/385>
-    #[cfg(feature = "internal_signal_handling_for_coverage_reports")]
-    pub term_signal: terminate::TerminateRequested,
+    ///
+    /// Outside of coverage testing, this is also used to shut down gracefully
+    /// when [Self::state_file] is set; see [Self::enable_graceful_shutdown].
+    pub term_signal: Option<terminate::TerminateRequested>,
+    /// Location of the encrypted [CryptoServer] state snapshot.
+    ///
+    /// If set, the state is restored from this file by [Self::restore_state] during
+    /// startup and written back by [Self::store_state] upon graceful shutdown.
+    pub state_file: Option<PathBuf>,
+    /// Metrics about the operation of the server; collected only if enabled through
+    /// [Self::enable_metrics]
+    pub metrics: Option<Arc<Metrics>>,
+    /// Removes the unix socket the metrics are served on once the server is dropped; see
+    /// [crate::metrics::MetricsConfig::listen_path]
+    pub metrics_socket: Option<SocketPathGuard>,
+    /// Re-reads the configuration file upon SIGHUP; see [Self::enable_config_reload]
+    pub config_reload: Option<ConfigReload>,
+    /// Changes the log filter upon SIGUSR1 and SIGUSR2; see [Self::enable_log_level_signals]
+    pub log_level_signals: Option<LogLevelSignals>,
+    /// Readiness and watchdog notifications for systemd; see [Self::enable_sd_notify]
+    pub sd_notify: Option<SdNotify>,
+    /// Runs the [AppPeer::exchange_command]s in the background
+    pub exchange_commands: ExchangeCommandRunner,
+    /// Results of resolving [AppPeer::hostname] in the background; see
+    /// [crate::endpoint_refresh]
+    pub resolved_endpoints: ResolvedChannel,
+    /// The earliest time an interval based [AppPeer::endpoint_refresh] is due
+    pub next_endpoint_refresh: Option<Timing>,
+    /// The most recent events raised through [Self::emit_event]; see [crate::events]
+    pub event_log: EventLog,
+    /// Applied to every socket registered through [Self::register_listen_socket]; see
+    /// [Self::set_socket_options]
+    pub socket_options: SocketOptions,
+    /// Whether [Self::sockets] were passed in by the service manager (see
+    /// [crate::socket_activation]); these are never rebound, so
+    /// [crate::config::Rosenpass::listen] is ignored
+    pub socket_activated: bool,
+    /// Set through [Self::request_shutdown]; [Self::event_loop] then returns after calling
+    /// [Self::shutdown]
+    pub shutdown_requested: bool,
     #[cfg(feature = "experiment_api")]
     /// The Rosenpass unix socket API handler; this is an experimental
     /// feature that can be used to embed Rosenpass in external applications
@@ -446,26 +615,49 @@ impl AppPeerPtr {
     }
 
     /// Retrieve the [AppPeer] pointed to by [Self]
+    ///
+    /// Panics if the peer does not exist or has been removed.
     pub fn get_app<'a>(&self, srv: &'a AppServer) -> &'a AppPeer {
-        &srv.peers[self.0]
+        srv.peers[self.0]
+            .as_ref()
+            .expect("AppPeerPtr refers to a removed peer")
     }
 
     /// Retrieve the [AppPeer] pointed to by [Self], mutably
+    ///
+    /// Panics if the peer does not exist or has been removed.
     pub fn get_app_mut<'a>(&self, srv: &'a mut AppServer) -> &'a mut AppPeer {
-        &mut srv.peers[self.0]
+        srv.peers[self.0]
+            .as_mut()
+            .expect("AppPeerPtr refers to a removed peer")
+    }
+
+    /// Check whether the peer exists and has not been removed through [AppServer::remove_peer]
+    pub fn exists(&self, srv: &AppServer) -> bool {
+        matches!(srv.peers.get(self.0), Some(Some(_)))
     }
 
     /// Use the associated WireGuard PSK broker via [BrokerStorePtr]
     /// to upload a new PSK.
     ///
-    /// If no PSK broker is set and [AppPeer::outfile] is none, then
-    /// this prints a warning
+    /// If no PSK broker is set and neither [AppPeer::outfile] nor
+    /// [AppPeer::exchange_command] is set, then this prints a warning
     pub fn set_psk(&self, server: &mut AppServer, psk: &Secret<WG_KEY_LEN>) -> anyhow::Result<()> {
-        if let Some(broker) = server.peers[self.0].broker_peer.as_ref() {
+        let ap = server.peers[self.0]
+            .as_ref()
+            .expect("AppPeerPtr refers to a removed peer");
+        if let Some(broker) = ap.broker_peer.as_ref() {
             let config = broker.peer_cfg.create_config(psk);
             let broker = server.brokers.store.get_mut(&broker.ptr().0).unwrap();
-            broker.set_psk(config)?;
-        } else if server.peers[self.0].outfile.is_none() {
+            let start = Instant::now();
+            let res = broker.set_psk(config);
+            server.record_metrics(|m| m.set_psk_done(start.elapsed(), res.is_ok()));
+            if res.is_err() {
+                let peer = self.lower().get(server.crypto_server()?).pidt()?;
+                server.emit_event(AppEvent::BrokerError { peer });
+            }
+            res?;
+        } else if ap.outfile.is_none() && ap.exchange_command.is_none() {
             log::warn!("No broker peer found for peer {}", self.0);
         }
         Ok(())
@@ -488,10 +680,12 @@ pub enum AppPollResult {
     ///
     /// This is the only case without a correspondence in [crate::protocol::PollResult]
     ReceivedMessage(usize, Endpoint),
+    /// A shutdown was requested through [AppServer::request_shutdown]
+    Shutdown,
 }
 
 /// The reason why we are outputting a key
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeyOutputReason {
     /// The reason is that a new key for the given peer was successfully exchanged
     Exchanged,
@@ -503,6 +697,17 @@ pub enum KeyOutputReason {
     Stale,
 }
 
+impl KeyOutputReason {
+    /// The name used for this reason on standard output and in the
+    /// [crate::exchange_command] environment
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyOutputReason::Exchanged => "exchanged",
+            KeyOutputReason::Stale => "stale",
+        }
+    }
+}
+
 /// Represents a communication partner rosenpass may be sending packets to
 ///
 /// Generally at the start of Rosenpass either no address or a Hostname is known;
@@ -678,7 +883,7 @@ impl Endpoint {
     /// List of addresses this endpoint may be associated with.
     ///
     /// During peer discovery, this can be multiple addresses.
-    fn addresses(&self) -> &[SocketAddr] {
+    pub fn addresses(&self) -> &[SocketAddr] {
         use Endpoint::*;
         match self {
             SocketBoundAddress(host) => slice::from_ref(&host.addr),
@@ -823,82 +1028,27 @@ impl AppServer {
         verbosity: Verbosity,
         test_helpers: Option<AppServerTest>,
     ) -> anyhow::Result<Self> {
+        let sockets = bind_listen_sockets(addrs)?;
+        Self::with_sockets(keypair, sockets, verbosity, test_helpers)
+    }
+
+    /// Construct a new AppServer listening on already bound UDP `sockets`
+    ///
+    /// This is used with sockets passed by the service manager; see
+    /// [crate::socket_activation] and [Self::socket_activated].
+    pub fn with_sockets(
+        keypair: Option<(SSk, SPk)>,
+        mut sockets: Vec<mio::net::UdpSocket>,
+        verbosity: Verbosity,
+        test_helpers: Option<AppServerTest>,
+    ) -> anyhow::Result<Self> {
+        ensure!(!sockets.is_empty(), "No sockets to listen on!");
+
         // setup mio
         let mio_poll = mio::Poll::new()?;
         let events = mio::Events::with_capacity(EVENT_CAPACITY);
         let mut mio_token_dispenser = MioTokenDispenser::default();
 
-        // bind each SocketAddr to a socket
-        let maybe_sockets: Result<Vec<_>, _> =
-            addrs.into_iter().map(mio::net::UdpSocket::bind).collect();
-        let mut sockets = maybe_sockets?;
-
-        // When no socket is specified, rosenpass should open one port on all
-        // available interfaces best-effort. Here are the cases how this can possibly go:
-        //
-        // Some operating systems (such as Linux [^linux] and FreeBSD [^freebsd])
-        // using IPv6 sockets to handle IPv4 connections; on these systems
-        // binding to the `[::]:0` address will typically open a dual-stack
-        // socket. Some other systems such as OpenBSD [^openbsd] do not support this feature.
-        //
-        // Dual-stack systems provide a flag to enable or disable this
-        // behavior – the IPV6_V6ONLY flag. OpenBSD supports this flag
-        // read-only. MIO[^mio] provides a way to read this flag but not
-        // to write it.
-        //
-        // - One dual-stack IPv6 socket, if the operating supports dual-stack sockets and
-        //   correctly reports this
-        // - One IPv6 socket and one IPv4 socket if the operating does not support dual stack
-        //   sockets or disables them by default assuming this is also correctly reported
-        // - One IPv6 socket and no IPv4 socket if IPv6 socket is not dual-stack and opening
-        //   the IPv6 socket fails
-        // - One IPv4 socket and no IPv6 socket if opening the IPv6 socket fails
-        // - One dual-stack IPv6 socket and a redundant IPv4 socket if dual-stack sockets are
-        //   supported but the operating system does not correctly report this (specifically,
-        //   if the only_v6() call raises an error)
-        // - Rosenpass exits if no socket could be opened
-        //
-        // [^freebsd]: https://man.freebsd.org/cgi/man.cgi?query=ip6&sektion=4&manpath=FreeBSD+6.0-RELEASE
-        // [^openbsd]: https://man.openbsd.org/ip6.4
-        // [^linux]: https://man7.org/linux/man-pages/man7/ipv6.7.html
-        // [^mio]: https://docs.rs/mio/0.8.6/mio/net/struct.UdpSocket.html#method.only_v6
-        if sockets.is_empty() {
-            macro_rules! try_register_socket {
-                ($title:expr, $binding:expr) => {{
-                    let r = mio::net::UdpSocket::bind($binding);
-                    match r {
-                        Ok(sock) => {
-                            sockets.push(sock);
-                            Some(sockets.len() - 1)
-                        }
-                        Err(e) => {
-                            warn!("Could not bind to {} socket: {}", $title, e);
-                            None
-                        }
-                    }
-                }};
-            }
-
-            let v6 = try_register_socket!("IPv6", ipv6_any_binding());
-
-            let need_v4 = match v6.map(|no| sockets[no].only_v6()) {
-                Some(Ok(v)) => v,
-                None => true,
-                Some(Err(e)) => {
-                    warn!("Unable to detect whether the IPv6 socket supports dual-stack operation: {}", e);
-                    true
-                }
-            };
-
-            if need_v4 {
-                try_register_socket!("IPv4", ipv4_any_binding());
-            }
-        }
-
-        if sockets.is_empty() {
-            bail!("No sockets to listen on!")
-        }
-
         // register all sockets to mio
         let mut io_source_index = HashMap::new();
         for (idx, socket) in sockets.iter_mut().enumerate() {
@@ -910,14 +1060,42 @@ impl AppServer {
             assert!(prev.is_none());
         }
 
+        let timebase = test_helpers
+            .as_ref()
+            .and_then(|t| t.timebase.clone())
+            .unwrap_or_default();
+
         let crypto_site = match keypair {
-            Some((sk, pk)) => ConstructionSite::from_product(CryptoServer::new(sk, pk)),
-            None => ConstructionSite::new(BuildCryptoServer::empty()),
+            Some((sk, pk)) => ConstructionSite::from_product(CryptoServer::with_timebase(
+                sk,
+                pk,
+                timebase.clone(),
+            )),
+            None => {
+                let mut builder = BuildCryptoServer::empty();
+                builder.timebase = timebase.clone();
+                ConstructionSite::new(builder)
+            }
         };
 
         Ok(Self {
             #[cfg(feature = "internal_signal_handling_for_coverage_reports")]
-            term_signal: terminate::TerminateRequested::new()?,
+            term_signal: Some(terminate::TerminateRequested::new()?),
+            #[cfg(not(feature = "internal_signal_handling_for_coverage_reports"))]
+            term_signal: None,
+            state_file: None,
+            metrics: None,
+            metrics_socket: None,
+            config_reload: None,
+            log_level_signals: None,
+            sd_notify: None,
+            socket_activated: false,
+            shutdown_requested: false,
+            exchange_commands: ExchangeCommandRunner::default(),
+            resolved_endpoints: ResolvedChannel::default(),
+            next_endpoint_refresh: None,
+            event_log: EventLog::default(),
+            socket_options: SocketOptions::default(),
             crypto_site,
             peers: Vec::new(),
             verbosity,
@@ -934,7 +1112,8 @@ impl AppServer {
             blocking_polls_count: 0,
             non_blocking_polls_count: 0,
             unpolled_count: 0,
-            last_update_time: Instant::now(),
+            last_update_time: timebase.now(),
+            timebase,
             test_helpers,
             #[cfg(feature = "experiment_api")]
             api_manager: crate::api::mio::MioManager::default(),
@@ -965,14 +1144,16 @@ impl AppServer {
             .context("Cryptography handler not initialized")
     }
 
-    /// If set to [Verbosity::Verbose], then some extra information will be printed
-    /// at the info log level
+    /// Whether [Self::verbosity] is [Verbosity::Verbose]
     pub fn verbose(&self) -> bool {
         matches!(self.verbosity, Verbosity::Verbose)
     }
 
     /// Used by [Self::new] to register a new udp listen source
+    ///
+    /// Applies [Self::socket_options] to the socket.
     pub fn register_listen_socket(&mut self, mut sock: mio::net::UdpSocket) -> anyhow::Result<()> {
+        self.socket_options.apply(&sock)?;
         let mio_token = self.mio_token_dispenser.dispense();
         self.mio_poll
             .registry()
@@ -983,6 +1164,90 @@ impl AppServer {
         Ok(())
     }
 
+    /// Change the addresses the UDP [Self::sockets] are bound to
+    ///
+    /// Sockets already bound to one of `addrs` are kept open; sockets for the other
+    /// addresses are bound and all remaining sockets are closed. Just like [Self::new], this
+    /// binds to any interface if `addrs` is empty.
+    ///
+    /// If binding any of the new sockets fails, nothing is changed. Peers whose
+    /// [AppPeer::current_endpoint] refers to a closed socket go back to peer discovery.
+    pub fn rebind_listen_sockets(&mut self, addrs: Vec<SocketAddr>) -> anyhow::Result<()> {
+        let bound = self
+            .sockets
+            .iter()
+            .map(|sock| sock.local_addr().ok())
+            .collect::<Vec<_>>();
+        let keep = bound
+            .iter()
+            .map(|addr| addr.is_some_and(|addr| addrs.contains(&addr)))
+            .collect::<Vec<_>>();
+
+        // Bind the new sockets first, so we can bail out without changing anything
+        let missing = addrs
+            .iter()
+            .filter(|addr| !bound.contains(&Some(**addr)))
+            .copied()
+            .collect::<Vec<_>>();
+        let new_sockets = match (addrs.is_empty(), missing.is_empty()) {
+            (true, _) => bind_listen_sockets(addrs)?,
+            (false, true) => Vec::new(),
+            (false, false) => bind_listen_sockets(missing)?,
+        };
+
+        // Close the sockets no longer needed; `moved_to` maps old to new socket indices
+        let mut moved_to = Vec::with_capacity(keep.len());
+        let mut sockets = Vec::with_capacity(keep.len());
+        for (mut sock, keep) in self.sockets.drain(..).zip(keep) {
+            if keep {
+                moved_to.push(Some(sockets.len()));
+                sockets.push(sock);
+            } else {
+                if let Err(e) = self.mio_poll.registry().deregister(&mut sock) {
+                    warn!("Could not deregister listen socket: {e:?}");
+                }
+                moved_to.push(None);
+            }
+        }
+        self.sockets = sockets;
+        self.io_source_index.retain(|_, src| match src {
+            AppServerIoSource::Socket(idx) => match moved_to[*idx] {
+                Some(new_idx) => {
+                    *idx = new_idx;
+                    true
+                }
+                None => false,
+            },
+            _ => true,
+        });
+        // Pending events may refer to the closed sockets
+        self.short_poll_queue.clear();
+        self.all_sockets_drained = false;
+
+        for sock in new_sockets {
+            self.register_listen_socket(sock)?;
+        }
+
+        for peer in self.peers.iter_mut().flatten() {
+            let endpoint = match &peer.current_endpoint {
+                Some(Endpoint::SocketBoundAddress(ep)) => match moved_to[ep.socket.0] {
+                    Some(idx) => Some(Endpoint::SocketBoundAddress(SocketBoundEndpoint::new(
+                        SocketPtr(idx),
+                        ep.addr,
+                    ))),
+                    None => Endpoint::discovery_from_multiple_sources(
+                        peer.current_endpoint.as_ref(),
+                        peer.initial_endpoint.as_ref(),
+                    ),
+                },
+                _ => continue,
+            };
+            peer.current_endpoint = endpoint;
+        }
+
+        Ok(())
+    }
+
     /// Used to register a source of IO such as a listen socket with [Self::io_source_index]
     pub fn register_io_source(&mut self, token: mio::Token, io_source: AppServerIoSource) {
         let prev = self.io_source_index.insert(token, io_source);
@@ -1051,18 +1316,498 @@ impl AppServer {
         assert!(pn == self.peers.len());
 
         let initial_endpoint = hostname
+            .clone()
             .map(Endpoint::discovery_from_hostname)
             .transpose()?;
         let current_endpoint = None;
-        self.peers.push(AppPeer {
+        self.peers.push(Some(AppPeer {
             outfile,
             broker_peer,
+            exchange_command: None,
             initial_endpoint,
+            hostname,
+            endpoint_refresh: EndpointRefresh::default(),
+            roaming: Roaming::default(),
             current_endpoint,
-        });
+        }));
         Ok(AppPeerPtr(pn))
     }
 
+    /// Remove a protocol peer registered with [Self::add_peer]
+    ///
+    /// If a key was exchanged with the peer, it is replaced by a random, stale key first,
+    /// just as if the session had expired. The peer is then removed from the
+    /// [CryptoServer] (see [CryptoServer::remove_peer]) and from [Self::peers].
+    ///
+    /// The [AppPeerPtr]s of all other peers remain valid.
+    pub fn remove_peer(&mut self, peer: AppPeerPtr) -> anyhow::Result<()> {
+        if !peer.exists(self) {
+            bail!("Cannot remove peer {}; no such peer", peer.0);
+        }
+
+        let had_session = match &self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(_) => {
+                bail!("Cannot remove peers before the crypto server is initialized")
+            }
+            ConstructionSite::Product(srv) => peer.lower().session().get(srv).is_some(),
+        };
+
+        if had_session {
+            self.output_key(peer, KeyOutputReason::Stale, &SymKey::random())?;
+        }
+
+        if let Some(id) = self.metrics_peer_id(peer) {
+            self.record_metrics(|m| m.remove_peer(id));
+        }
+        self.crypto_server_mut()?.remove_peer(peer.lower())?;
+        self.peers[peer.0] = None;
+        if let Some(reload) = self.config_reload.as_mut() {
+            reload.forget_peer(peer);
+        }
+
+        Ok(())
+    }
+
+    /// Start a new handshake with a protocol peer registered with [Self::add_peer] right away
+    ///
+    /// Any ongoing handshake in initiator role is discarded; see
+    /// [CryptoServer::initiate_handshake]. If `erase_key` is set, the current session is erased
+    /// first and the key exchanged is replaced by a random, stale key, just as if the session had
+    /// expired. Otherwise, the current key remains in use until the new handshake finishes.
+    ///
+    /// Fails without changing anything if no endpoint is known for the peer; see
+    /// [AppPeer::endpoint].
+    pub fn force_rekey(&mut self, peer: AppPeerPtr, erase_key: bool) -> anyhow::Result<()> {
+        ensure!(
+            peer.exists(self),
+            "Cannot rekey peer {}; no such peer",
+            peer.0
+        );
+        ensure!(
+            peer.get_app(self).endpoint().is_some(),
+            "Cannot rekey peer {}; its endpoint is unknown",
+            peer.0
+        );
+
+        let srv = self.crypto_server_mut()?;
+        if erase_key && peer.lower().session().take(srv).is_some() {
+            self.output_key(peer, KeyOutputReason::Stale, &SymKey::random())?;
+        }
+
+        let mut tx = MsgBuf::zero();
+        let len = self
+            .crypto_server_mut()?
+            .initiate_handshake(peer.lower(), &mut *tx)?;
+        if let Some(id) = self.metrics_peer_id(peer) {
+            self.record_metrics(|m| m.handshake_started(id));
+        }
+
+        let ep = peer.get_app(self).endpoint().unwrap();
+        ep.send(self, &tx[..len])
+    }
+
+    /// Look up a peer given its [PeerId]; see [CryptoServer::find_peer]
+    ///
+    /// This works both before and after the [CryptoServer] is initialized.
+    pub fn find_peer(&self, id: PeerId) -> anyhow::Result<Option<AppPeerPtr>> {
+        let ptr = match &self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(builder) => {
+                let mut ptr = None;
+                for (idx, peer) in builder.peers.iter().enumerate() {
+                    if peer.pidt()? == id {
+                        ptr = Some(PeerPtr(idx));
+                        break;
+                    }
+                }
+                ptr
+            }
+            ConstructionSite::Product(srv) => srv.find_peer(id),
+        };
+
+        Ok(ptr.map(AppPeerPtr::lift).filter(|peer| peer.exists(self)))
+    }
+
+    /// Iterate over all peers registered with [Self::add_peer] that were not removed since
+    pub fn peer_ptrs(&self) -> impl Iterator<Item = AppPeerPtr> + '_ {
+        (0..self.peers.len())
+            .map(AppPeerPtr)
+            .filter(|peer| peer.exists(self))
+    }
+
+    /// The [PeerId] of a peer registered with [Self::add_peer]
+    ///
+    /// This works both before and after the [CryptoServer] is initialized.
+    pub fn peer_id(&self, peer: AppPeerPtr) -> anyhow::Result<PeerId> {
+        ensure!(peer.exists(self), "No peer {}", peer.0);
+        match &self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(builder) => builder
+                .peers
+                .get(peer.0)
+                .with_context(|| format!("No peer {}", peer.0))?
+                .pidt(),
+            ConstructionSite::Product(srv) => peer.lower().get(srv).pidt(),
+        }
+    }
+
+    /// State of the session and handshake of a peer registered with [Self::add_peer];
+    /// see [PeerPtr::status]
+    ///
+    /// Before the [CryptoServer] is initialized, there is neither a session nor a handshake.
+    pub fn peer_status(&self, peer: AppPeerPtr) -> anyhow::Result<PeerStatus> {
+        ensure!(peer.exists(self), "No peer {}", peer.0);
+        match &self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(_) => Ok(PeerStatus {
+                session: None,
+                handshake: None,
+            }),
+            ConstructionSite::Product(srv) => Ok(peer.lower().status(srv)),
+        }
+    }
+
+    /// Change the timers used for a protocol peer registered with [Self::add_peer]
+    ///
+    /// See [CryptoServer::set_peer_timing]; this works both before and after the
+    /// [CryptoServer] is initialized.
+    pub fn set_peer_timing(
+        &mut self,
+        peer: AppPeerPtr,
+        timing: TimingProfile,
+    ) -> anyhow::Result<()> {
+        if !peer.exists(self) {
+            bail!("Cannot set timing for peer {}; no such peer", peer.0);
+        }
+
+        match &mut self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(builder) => builder.set_peer_timing(peer.lower(), timing),
+            ConstructionSite::Product(srv) => srv.set_peer_timing(peer.lower(), timing),
+        }
+    }
+
+    /// Change the protocol suite used for a protocol peer registered with [Self::add_peer]
+    ///
+    /// See [CryptoServer::set_peer_suite]; this works both before and after the
+    /// [CryptoServer] is initialized.
+    pub fn set_peer_suite(&mut self, peer: AppPeerPtr, suite: SuiteId) -> anyhow::Result<()> {
+        if !peer.exists(self) {
+            bail!(
+                "Cannot set protocol suite for peer {}; no such peer",
+                peer.0
+            );
+        }
+
+        match &mut self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(builder) => builder.set_peer_suite(peer.lower(), suite),
+            ConstructionSite::Product(srv) => srv.set_peer_suite(peer.lower(), suite),
+        }
+    }
+
+    /// Set or clear the [AppPeer::exchange_command] of a peer registered with [Self::add_peer]
+    ///
+    /// Setting the command it already has does nothing, so invocations that are still running
+    /// or queued are not affected.
+    pub fn set_peer_exchange_command(
+        &mut self,
+        peer: AppPeerPtr,
+        argv: Option<Vec<String>>,
+    ) -> anyhow::Result<()> {
+        if !peer.exists(self) {
+            bail!(
+                "Cannot set exchange command for peer {}; no such peer",
+                peer.0
+            );
+        }
+
+        let ap = peer.get_app_mut(self);
+        let current = ap.exchange_command.as_ref().map(|cmd| cmd.argv());
+        if current != argv.as_deref() {
+            ap.exchange_command = argv.map(ExchangeCommand::new).transpose()?;
+        }
+        Ok(())
+    }
+
+    /// Change the life time of the cookie secret
+    ///
+    /// See [CryptoServer::set_cookie_secret_epoch]; this works both before and after the
+    /// [CryptoServer] is initialized.
+    pub fn set_cookie_secret_epoch(&mut self, epoch: Timing) -> anyhow::Result<()> {
+        match &mut self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(builder) => {
+                validate_cookie_secret_epoch(epoch)?;
+                builder.cookie_secret_epoch = epoch;
+                Ok(())
+            }
+            ConstructionSite::Product(srv) => srv.set_cookie_secret_epoch(epoch),
+        }
+    }
+
+    /// Install a [HandshakeTracer] that receives the [crate::protocol::HandshakeEvent]s of the
+    /// [CryptoServer]
+    ///
+    /// See [CryptoServer::set_tracer]; this works both before and after the [CryptoServer] is
+    /// initialized.
+    pub fn set_tracer(&mut self, tracer: Option<Arc<dyn HandshakeTracer>>) -> anyhow::Result<()> {
+        match &mut self.crypto_site {
+            ConstructionSite::Void => bail!("Crypto server construction site is void"),
+            ConstructionSite::Builder(builder) => {
+                builder.tracer = tracer;
+                Ok(())
+            }
+            ConstructionSite::Product(srv) => {
+                srv.set_tracer(tracer);
+                Ok(())
+            }
+        }
+    }
+
+    /// Start collecting [Metrics]; see [Self::metrics]
+    ///
+    /// Returns the metrics, so they can be served (see [Metrics::serve]). Calling this
+    /// repeatedly returns the same metrics.
+    pub fn enable_metrics(&mut self) -> Arc<Metrics> {
+        let timebase = self.timebase.clone();
+        self.metrics
+            .get_or_insert_with(|| Arc::new(Metrics::new(timebase)))
+            .clone()
+    }
+
+    /// Update the [Metrics], if enabled
+    fn record_metrics<F: FnOnce(&Metrics)>(&self, f: F) {
+        if let Some(metrics) = self.metrics.as_ref() {
+            f(metrics);
+        }
+    }
+
+    /// The [PeerId] used to label the [Metrics] of a peer
+    ///
+    /// Returns `None` if metrics are disabled or the peer id can not be determined.
+    fn metrics_peer_id(&self, peer: AppPeerPtr) -> Option<PeerId> {
+        self.metrics.as_ref()?;
+        peer.lower().get(self.crypto_server().ok()?).pidt().ok()
+    }
+
+    /// Notify systemd about readiness and status changes and feed its watchdog, if the
+    /// process was started with a notify socket; see [crate::sd_notify]
+    ///
+    /// This reads (and clears) the `NOTIFY_SOCKET` and `WATCHDOG_USEC` environment variables,
+    /// so it should be called early, before any brokers or threads are started.
+    pub fn enable_sd_notify(&mut self) -> anyhow::Result<()> {
+        self.sd_notify = SdNotify::from_env()?;
+        Ok(())
+    }
+
+    /// Human readable status line reported to systemd
+    fn sd_notify_status(&self) -> String {
+        let (live, total) = match self.crypto_server() {
+            Ok(srv) => {
+                let peers: Vec<PeerPtr> = srv.peer_ptrs().collect();
+                let live = peers
+                    .iter()
+                    .filter(|p| p.session().get(srv).is_some())
+                    .count();
+                (live, peers.len())
+            }
+            Err(_) => (0, 0),
+        };
+        let mode = match self.under_load {
+            DoSOperation::Normal => "normal operation",
+            DoSOperation::UnderLoad => "under load",
+        };
+        format!("{live}/{total} peers with a live key; {mode}")
+    }
+
+    /// Signal readiness to systemd, if enabled; see [Self::enable_sd_notify]
+    fn sd_notify_ready(&mut self) {
+        match self.sd_notify.as_ref() {
+            Some(n) if !n.is_ready() => {}
+            _ => return,
+        }
+        let status = self.sd_notify_status();
+        if let Some(Err(e)) = self.sd_notify.as_mut().map(|n| n.ready(&status)) {
+            warn!("Could not notify systemd about readiness: {e:?}");
+        }
+    }
+
+    /// Send status updates and watchdog pings to systemd, if enabled
+    fn sd_notify_tick(&mut self) {
+        let Some(mut notify) = self.sd_notify.take() else {
+            return;
+        };
+        if let Err(e) = notify.tick(|| self.sd_notify_status()) {
+            warn!("Could not notify systemd: {e:?}");
+        }
+        self.sd_notify = Some(notify);
+    }
+
+    /// Install signal handlers for the common termination signals, so [Self::event_loop]
+    /// returns normally instead of the process being killed.
+    ///
+    /// This is needed to write the state snapshot (see [Self::state_file]) on shutdown.
+    pub fn enable_graceful_shutdown(&mut self) -> anyhow::Result<()> {
+        if self.term_signal.is_none() {
+            self.term_signal = Some(terminate::TerminateRequested::new()?);
+        }
+        Ok(())
+    }
+
+    /// Check whether a termination signal was received; see [Self::enable_graceful_shutdown]
+    pub fn termination_requested(&self) -> bool {
+        self.term_signal.as_ref().is_some_and(|t| t.value())
+    }
+
+    /// Make [Self::event_loop] stop after calling [Self::shutdown]
+    ///
+    /// Unlike a termination signal, this erases all secrets before returning.
+    pub fn request_shutdown(&mut self) {
+        self.shutdown_requested = true;
+    }
+
+    /// Stop using all secrets; used by [Self::event_loop] after [Self::request_shutdown]
+    ///
+    /// Every key exchanged with a peer is replaced by a random, stale key, just as if the session
+    /// had expired. Since the process usually exits right after, this waits for the exchange
+    /// commands to finish and for the PSK brokers to receive the stale keys. The [CryptoServer] is
+    /// then dropped, erasing all secrets it holds including our secret key; consequently,
+    /// [Self::store_state] does not write a state snapshot afterwards.
+    pub fn shutdown(&mut self) -> anyhow::Result<()> {
+        let with_session: Vec<AppPeerPtr> = match &self.crypto_site {
+            ConstructionSite::Product(srv) => self
+                .peer_ptrs()
+                .filter(|peer| peer.lower().session().get(srv).is_some())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Keep going on errors; erasing the remaining keys is more important
+        for peer in with_session {
+            if let Err(e) = self.output_key(peer, KeyOutputReason::Stale, &SymKey::random()) {
+                warn!(
+                    "Could not replace the key of peer {} by a stale key: {e:?}",
+                    peer.0
+                );
+            }
+        }
+
+        if !self.exchange_commands.wait_idle() {
+            warn!(
+                "Exchange commands still running on shutdown; they may not receive the stale keys"
+            );
+        }
+        self.flush_brokers();
+
+        self.crypto_site = ConstructionSite::Void;
+        Ok(())
+    }
+
+    /// Wait for the PSK brokers to accept all keys passed to them; used by [Self::shutdown]
+    ///
+    /// Gives up after [BROKER_FLUSH_TIMEOUT].
+    fn flush_brokers(&mut self) {
+        let deadline = Instant::now() + BROKER_FLUSH_TIMEOUT;
+        loop {
+            let mut pending = false;
+            for (_, broker) in self.brokers.store.iter_mut() {
+                if let Err(e) = broker.process_poll() {
+                    warn!("Error while flushing PSK broker on shutdown: {e:?}");
+                    continue;
+                }
+                pending |= broker.has_pending_writes();
+            }
+
+            if !pending {
+                return;
+            }
+            if Instant::now() >= deadline {
+                warn!("PSK brokers did not accept all keys on shutdown; they may not receive the stale keys");
+                return;
+            }
+            std::thread::sleep(BROKER_FLUSH_INTERVAL);
+        }
+    }
+
+    /// Replace our static keypair; see [CryptoServer::rotate_keypair]
+    ///
+    /// Peers may keep using our previous public key for `grace` seconds. Fails if the
+    /// [CryptoServer] is not initialized yet; the keypair is simply supplied to the
+    /// [BuildCryptoServer] in that case.
+    pub fn rotate_keypair(&mut self, sk: SSk, pk: SPk, grace: Timing) -> anyhow::Result<()> {
+        self.crypto_server_mut()?.rotate_keypair(sk, pk, grace);
+        info!(
+            event = "keypair_rotated";
+            "Rotated our static keypair; the previous keypair is accepted for another {grace}s"
+        );
+        Ok(())
+    }
+
+    /// Restore the [CryptoServer] state from [Self::state_file], if configured and present.
+    ///
+    /// This must be called after all peers are added and before [Self::event_loop].
+    /// The snapshot file is deleted after reading it, so the same state is never used twice;
+    /// in particular, this makes sure the biscuit replay protection can not be rolled back
+    /// by a crash after restoring.
+    ///
+    /// Failing to restore the state is not fatal; the error is logged and Rosenpass
+    /// proceeds with a fresh state.
+    pub fn restore_state(&mut self) -> anyhow::Result<()> {
+        let Some(path) = self.state_file.clone() else {
+            return Ok(());
+        };
+        if !self.crypto_site.is_available() {
+            warn!("Not restoring state from {path:?}: No keypair configured yet");
+            return Ok(());
+        }
+
+        let snapshot = match std::fs::read(&path) {
+            Ok(snapshot) => snapshot,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e).with_context(|| format!("Could not read state file {path:?}")),
+        };
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Could not remove state file {path:?} after reading it"))?;
+
+        match self.crypto_server_mut()?.restore_snapshot(&snapshot) {
+            Ok(stats) => info!(
+                "Restored state from {path:?}: {} sessions, {} biscuit keys ({} expired or unknown sessions discarded)",
+                stats.sessions, stats.biscuit_keys, stats.sessions_discarded
+            ),
+            Err(e) => warn!("Ignoring state file {path:?}: {e:?}"),
+        }
+
+        Ok(())
+    }
+
+    /// Write the [CryptoServer] state to [Self::state_file], if configured.
+    ///
+    /// The snapshot is written to a temporary file first and then moved into place,
+    /// so an interrupted write never leaves a partial snapshot behind.
+    pub fn store_state(&self) -> anyhow::Result<()> {
+        let Some(path) = self.state_file.as_ref() else {
+            return Ok(());
+        };
+        let Ok(srv) = self.crypto_server() else {
+            return Ok(());
+        };
+
+        let snapshot = srv.store_snapshot()?;
+        let mut tmp = path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut f = fopen_w(&tmp, Visibility::Secret)
+            .with_context(|| format!("Could not open state file {tmp:?}"))?;
+        f.write_all(&snapshot)?;
+        f.sync_all()?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Could not move state file into place at {path:?}"))?;
+
+        info!("Stored state in {path:?}");
+        Ok(())
+    }
+
     /// Main IO handler; this generally does not terminate
     ///
     /// # Examples
@@ -1080,21 +1825,32 @@ impl AppServer {
                 Err(e) => e,
             };
 
-            #[cfg(feature = "internal_signal_handling_for_coverage_reports")]
-            {
-                let terminated_by_signal = err
-                    .downcast_ref::<std::io::Error>()
-                    .filter(|e| e.kind() == std::io::ErrorKind::Interrupted)
-                    .filter(|_| self.term_signal.value())
-                    .is_some();
-                if terminated_by_signal {
-                    log::warn!(
-                        "\
-                        Terminated by signal; this signal handler is correct during coverage testing \
-                        but should be otherwise disabled"
-                    );
-                    return Ok(());
-                }
+            let terminated_by_signal = err
+                .downcast_ref::<std::io::Error>()
+                .filter(|e| e.kind() == std::io::ErrorKind::Interrupted)
+                .filter(|_| self.termination_requested())
+                .is_some();
+            if terminated_by_signal {
+                #[cfg(feature = "internal_signal_handling_for_coverage_reports")]
+                log::warn!(
+                    "\
+                    Terminated by signal; this signal handler is correct during coverage testing \
+                    but should be otherwise disabled"
+                );
+                #[cfg(not(feature = "internal_signal_handling_for_coverage_reports"))]
+                info!("Terminated by signal; shutting down gracefully");
+                return Ok(());
+            }
+
+            // Interrupted by SIGHUP, SIGUSR1 or SIGUSR2; the signal is handled in the next
+            // iteration
+            let interrupted_by_request = err
+                .downcast_ref::<std::io::Error>()
+                .filter(|e| e.kind() == std::io::ErrorKind::Interrupted)
+                .filter(|_| self.reload_requested() || self.log_level_change_requested())
+                .is_some();
+            if interrupted_by_request {
+                continue;
             }
 
             // This should not happen…
@@ -1106,6 +1862,8 @@ impl AppServer {
             let sleep = INIT_SLEEP * 2.0f64.powf(f64::from(failure_cnt - 1));
             let tries_left = MAX_FAILURES - (failure_cnt - 1);
             error!(
+                event = "error",
+                error:% = ErrorChain(&err);
                 "unexpected error after processing {} messages: {:?} {}",
                 msgs_processed,
                 err,
@@ -1127,6 +1885,9 @@ impl AppServer {
     pub fn event_loop_without_error_handling(&mut self) -> anyhow::Result<()> {
         let (mut rx, mut tx) = (MsgBuf::zero(), MsgBuf::zero());
 
+        // Sockets are bound and brokers are connected by now
+        self.sd_notify_ready();
+
         /// if socket address for peer is known, call closure
         /// assumes that closure leaves a message in `tx`
         /// assumes that closure returns the length of message in bytes
@@ -1159,6 +1920,23 @@ impl AppServer {
                 }
             }
 
+            if self.termination_requested() {
+                info!("Terminated by signal; shutting down gracefully");
+                return Ok(());
+            }
+
+            self.handle_log_level_signals();
+
+            if self.take_reload_request() {
+                if let Err(e) = self.reload_config() {
+                    error!(
+                        event = "reload_failed",
+                        error:% = ErrorChain(&e);
+                        "Could not reload the configuration, keeping the previous one: {e:?}"
+                    );
+                }
+            }
+
             enum CryptoSrv {
                 Avail,
                 Missing,
@@ -1172,18 +1950,37 @@ impl AppServer {
 
             #[allow(clippy::redundant_closure_call)]
             match (have_crypto, poll_result) {
+                (_, Shutdown) => {
+                    info!("Shutdown requested; erasing all secrets");
+                    return self.shutdown();
+                }
+
                 (CryptoSrv::Missing, SendInitiation(_)) => {}
-                (CryptoSrv::Avail, SendInitiation(peer)) => tx_maybe_with!(peer, || self
-                    .crypto_server_mut()?
-                    .initiate_handshake(peer.lower(), &mut *tx))?,
+                (CryptoSrv::Avail, SendInitiation(peer)) => tx_maybe_with!(peer, || {
+                    let len = self
+                        .crypto_server_mut()?
+                        .initiate_handshake(peer.lower(), &mut *tx)?;
+                    if let Some(id) = self.metrics_peer_id(peer) {
+                        self.record_metrics(|m| m.handshake_started(id));
+                    }
+                    anyhow::Ok(len)
+                })?,
 
                 (CryptoSrv::Missing, SendRetransmission(_)) => {}
-                (CryptoSrv::Avail, SendRetransmission(peer)) => tx_maybe_with!(peer, || self
-                    .crypto_server_mut()?
-                    .retransmit_handshake(peer.lower(), &mut *tx))?,
+                (CryptoSrv::Avail, SendRetransmission(peer)) => {
+                    self.endpoint_refresh_retransmitted(peer);
+                    tx_maybe_with!(peer, || self
+                        .crypto_server_mut()?
+                        .retransmit_handshake(peer.lower(), &mut *tx))?
+                }
 
                 (CryptoSrv::Missing, DeleteKey(_)) => {}
                 (CryptoSrv::Avail, DeleteKey(peer)) => {
+                    if let Some(id) = self.metrics_peer_id(peer) {
+                        self.record_metrics(|m| m.handshake_failed(id));
+                    }
+                    let id = peer.lower().get(self.crypto_server()?).pidt()?;
+                    self.emit_event(AppEvent::HandshakeFailed { peer: id });
                     self.output_key(peer, Stale, &SymKey::random())?;
 
                     // There was a loss of connection apparently; restart host discovery
@@ -1197,7 +1994,9 @@ impl AppServer {
                     );
                 }
 
-                (CryptoSrv::Missing, ReceivedMessage(_, _)) => {}
+                (CryptoSrv::Missing, ReceivedMessage(_, _)) => {
+                    self.record_metrics(|m| m.message_dropped(DropReason::CryptoServerMissing));
+                }
                 (CryptoSrv::Avail, ReceivedMessage(len, endpoint)) => {
                     let msg_result = match self.under_load {
                         DoSOperation::UnderLoad => {
@@ -1209,14 +2008,17 @@ impl AppServer {
                     };
                     match msg_result {
                         Err(ref e) => {
-                            self.verbose().then(|| {
-                                info!(
-                                    "error processing incoming message from {}: {:?} {}",
-                                    endpoint,
-                                    e,
-                                    e.backtrace()
-                                );
-                            });
+                            let reason = DropReason::classify(e, self.under_load);
+                            self.record_metrics(|m| m.message_dropped(reason));
+                            info!(
+                                event = "message_rejected",
+                                endpoint:% = endpoint,
+                                error:% = ErrorChain(e);
+                                "error processing incoming message from {}: {:?} {}",
+                                endpoint,
+                                e,
+                                e.backtrace()
+                            );
                         }
 
                         Ok(HandleMsgResult {
@@ -1226,11 +2028,18 @@ impl AppServer {
                         }) => {
                             if let Some(len) = resp {
                                 endpoint.send(self, &tx[0..len])?;
+                                if tx[0] == u8::from(MsgType::CookieReply) {
+                                    self.record_metrics(|m| m.cookie_reply_sent());
+                                }
                             }
 
                             if let Some(p) = exchanged_with {
                                 let ap = AppPeerPtr::lift(p);
-                                ap.get_app_mut(self).current_endpoint = Some(endpoint);
+                                if let Some(id) = self.metrics_peer_id(ap) {
+                                    self.record_metrics(|m| m.handshake_completed(id));
+                                }
+                                self.endpoint_refresh_replied(ap);
+                                self.peer_endpoint_confirmed(ap, endpoint)?;
 
                                 // TODO: Maybe we should rather call the key "rosenpass output"?
                                 let osk = &self.crypto_server_mut()?.osk(p)?;
@@ -1271,13 +2080,32 @@ impl AppServer {
     ) -> anyhow::Result<()> {
         let peerid = peer.lower().get(self.crypto_server()?).pidt()?;
 
-        if self.verbose() {
-            let msg = match why {
-                KeyOutputReason::Exchanged => "Exchanged key with peer",
-                KeyOutputReason::Stale => "Erasing outdated key from peer",
-            };
-            info!("{} {}", msg, peerid.fmt_b64::<MAX_B64_PEER_ID_SIZE>());
-        }
+        let (event, msg) = match why {
+            KeyOutputReason::Exchanged => ("key_exchanged", "Exchanged key with peer"),
+            KeyOutputReason::Stale => ("key_stale", "Erasing outdated key from peer"),
+        };
+        let peer_id = peerid.fmt_b64::<MAX_B64_PEER_ID_SIZE>();
+        let session_id = peer
+            .lower()
+            .session()
+            .get(self.crypto_server()?)
+            .as_ref()
+            .map(|s| {
+                s.sidm
+                    .value
+                    .fmt_b64::<MAX_B64_SESSION_ID_SIZE>()
+                    .to_string()
+            });
+        info!(
+            event = event,
+            peer_id:% = peer_id,
+            session_id = session_id;
+            "{msg} {peer_id}"
+        );
+        self.emit_event(match why {
+            KeyOutputReason::Exchanged => AppEvent::KeyExchanged { peer: peerid },
+            KeyOutputReason::Stale => AppEvent::KeyStale { peer: peerid },
+        });
 
         let ap = peer.get_app(self);
 
@@ -1290,10 +2118,6 @@ impl AppServer {
             // implementation, going to great length to erase the secret here is
             // not worth it right now.
             key.store_b64::<MAX_B64_KEY_SIZE, _>(of)?;
-            let why = match why {
-                KeyOutputReason::Exchanged => "exchanged",
-                KeyOutputReason::Stale => "stale",
-            };
 
             // this is intentionally writing to stdout instead of stderr, because
             // it is meant to allow external detection of a successful key-exchange
@@ -1301,12 +2125,24 @@ impl AppServer {
             let mut stdout = stdout.lock();
             writeln!(
                 stdout,
-                "output-key peer {} key-file {of:?} {why}",
-                peerid.fmt_b64::<MAX_B64_PEER_ID_SIZE>()
+                "output-key peer {} key-file {of:?} {}",
+                peerid.fmt_b64::<MAX_B64_PEER_ID_SIZE>(),
+                why.as_str()
             )?;
             stdout.flush()?;
         }
 
+        if let Some(cmd) = ap.exchange_command.as_ref() {
+            if let Err(e) = self.exchange_commands.run(cmd, peerid, why, key) {
+                error!(
+                    event = "exchange_command_failed",
+                    peer_id:% = peerid.fmt_b64::<MAX_B64_PEER_ID_SIZE>(),
+                    error:% = ErrorChain(&e);
+                    "Could not run the exchange command: {e:?}"
+                );
+            }
+        }
+
         peer.set_psk(self, key)?;
 
         Ok(())
@@ -1320,6 +2156,13 @@ impl AppServer {
         use crate::protocol::PollResult as C;
         use AppPollResult as A;
         let res = loop {
+            if self.shutdown_requested {
+                break A::Shutdown;
+            }
+
+            self.sd_notify_tick();
+            self.refresh_endpoints();
+
             // Call CryptoServer's poll (if available)
             let crypto_poll = self
                 .crypto_site
@@ -1338,6 +2181,18 @@ impl AppServer {
                 None => crate::protocol::UNENDING,  // Crypto server is uninitialized, do IO
             };
 
+            // Wake up in time to feed the systemd watchdog
+            let watchdog = self.sd_notify.as_ref().and_then(|n| n.watchdog_timeout());
+            let io_poll_timeout = match watchdog {
+                Some(watchdog) => io_poll_timeout.min(watchdog.as_secs_f64()),
+                None => io_poll_timeout,
+            };
+            // …and to resolve peer endpoints again
+            let io_poll_timeout = match self.endpoint_refresh_timeout() {
+                Some(refresh) => io_poll_timeout.min(refresh),
+                None => io_poll_timeout,
+            };
+
             // Perform IO (look for a message)
             if let Some((len, addr)) = self.try_recv(rx_buf, io_poll_timeout)? {
                 break A::ReceivedMessage(len, addr);
@@ -1360,6 +2215,7 @@ impl AppServer {
         timeout: Timing,
     ) -> anyhow::Result<Option<(usize, Endpoint)>> {
         let timeout = Duration::from_secs_f64(timeout);
+        let prev_under_load = self.under_load;
 
         // if there is no time to wait on IO, well, then, lets not waste any time!
         if timeout.is_zero() {
@@ -1420,8 +2276,9 @@ impl AppServer {
             self.under_load = DoSOperation::UnderLoad;
         } else {
             //Reset blocking poll count if waiting for more than BLOCKING_POLL_COUNT_DURATION
-            if self.last_update_time.elapsed() > DURATION_UPDATE_UNDER_LOAD_STATUS {
-                self.last_update_time = Instant::now();
+            let now = self.timebase.now();
+            if now - self.last_update_time > DURATION_UPDATE_UNDER_LOAD_STATUS.as_secs_f64() {
+                self.last_update_time = now;
                 let total_polls = self.blocking_polls_count + self.non_blocking_polls_count;
 
                 let load_ratio = if total_polls > 0 {
@@ -1445,6 +2302,12 @@ impl AppServer {
             }
         }
 
+        if self.under_load != prev_under_load {
+            let under_load = self.under_load;
+            self.record_metrics(|m| m.dos_transition(under_load));
+            self.emit_event(AppEvent::DoSModeChanged { to: under_load });
+        }
+
         // Focused polling – i.e. actually using mio::Token – is experimental for now.
         // The reason for this is that we need to figure out how to integrate load detection
         // and focused polling for one. Mio event-based polling also does not play nice with
@@ -1651,19 +2514,18 @@ impl crate::api::mio::MioManagerContext for MioManagerFocus<'_> {
     }
 }
 
-/// These signal handlers are used exclusively used during coverage testing
+/// These signal handlers are used during coverage testing
 /// to ensure that the llvm-cov can produce reports during integration tests
 /// with multiple processes where subprocesses are terminated via kill(2).
 ///
 /// llvm-cov does not support producing coverage reports when the process exits
 /// through a signal, so this is necessary.
 ///
-/// The functionality of exiting gracefully upon reception of a terminating signal
-/// is desired for the production variant of Rosenpass, but we should make sure
-/// to use a higher quality implementation; in particular, we should use signalfd(2).
+/// They are also used to exit gracefully when a state file is configured,
+/// so the state snapshot can be written (see [AppServer::enable_graceful_shutdown]).
+/// We should eventually use a higher quality implementation; in particular, we should use signalfd(2).
 ///
-#[cfg(feature = "internal_signal_handling_for_coverage_reports")]
-mod terminate {
+pub mod terminate {
     use signal_hook::flag::register as sig_register;
     use std::sync::{
         atomic::{AtomicBool, Ordering},
@@ -87,6 +87,28 @@ fn main() -> Result<()> {
                 Tree::Leaf("Add Listen Socket Response".to_owned()),
                 Tree::Leaf("Add Psk Broker Request".to_owned()),
                 Tree::Leaf("Add Psk Broker Response".to_owned()),
+                Tree::Leaf("Set Peer Timing Request".to_owned()),
+                Tree::Leaf("Set Peer Timing Response".to_owned()),
+                Tree::Leaf("Fetch Events Request".to_owned()),
+                Tree::Leaf("Fetch Events Response".to_owned()),
+                Tree::Leaf("Set Log Level Request".to_owned()),
+                Tree::Leaf("Set Log Level Response".to_owned()),
+                Tree::Leaf("Add Peer Request".to_owned()),
+                Tree::Leaf("Add Peer Response".to_owned()),
+                Tree::Leaf("Remove Peer Request".to_owned()),
+                Tree::Leaf("Remove Peer Response".to_owned()),
+                Tree::Leaf("List Peers Request".to_owned()),
+                Tree::Leaf("List Peers Response".to_owned()),
+                Tree::Leaf("Get Peer Status Request".to_owned()),
+                Tree::Leaf("Get Peer Status Response".to_owned()),
+                Tree::Leaf("Force Rekey Request".to_owned()),
+                Tree::Leaf("Force Rekey Response".to_owned()),
+                Tree::Leaf("Subscribe Request".to_owned()),
+                Tree::Leaf("Subscribe Response".to_owned()),
+                Tree::Leaf("Shutdown Request".to_owned()),
+                Tree::Leaf("Shutdown Response".to_owned()),
+                Tree::Leaf("Rotate Keypair Request".to_owned()),
+                Tree::Leaf("Rotate Keypair Response".to_owned()),
             ],
         )],
     );
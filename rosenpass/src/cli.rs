@@ -8,16 +8,16 @@ use clap::{Parser, Subcommand};
 use rosenpass_cipher_traits::Kem;
 use rosenpass_ciphers::kem::StaticKem;
 use rosenpass_secret_memory::file::StoreSecret;
-use rosenpass_util::file::{LoadValue, LoadValueB64, StoreValue};
-use rosenpass_wireguard_broker::brokers::native_unix::{
-    NativeUnixBroker, NativeUnixBrokerConfigBaseBuilder, NativeUnixBrokerConfigBaseBuilderError,
-};
+use rosenpass_util::file::{LoadValue, StoreValue};
+use rosenpass_wireguard_broker::brokers::native_unix::NativeUnixBroker;
 use std::ops::DerefMut;
 use std::path::PathBuf;
 
+use crate::app_server::AppServer;
 use crate::app_server::AppServerTest;
-use crate::app_server::{AppServer, BrokerPeer};
-use crate::protocol::{SPk, SSk, SymKey};
+use crate::logging::{self, LogFilter, LogFormat};
+use crate::protocol::{SPk, SSk};
+use crate::socket_activation;
 
 use super::config;
 
@@ -58,9 +58,12 @@ pub enum BrokerInterface {
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about, arg_required_else_help = true)]
 pub struct CliArgs {
-    /// Lowest log level to show
+    /// Lowest log level to show, optionally followed by levels for specific modules, e.g.
+    /// "warn,protocol=debug"
+    ///
+    /// Overrides the `log_level` of the configuration file.
     #[arg(long = "log-level", value_name = "LOG_LEVEL", group = "log-level")]
-    log_level: Option<log::LevelFilter>,
+    log_level: Option<LogFilter>,
 
     /// Show verbose log output – sets log level to "debug"
     #[arg(short, long, group = "log-level")]
@@ -70,6 +73,12 @@ pub struct CliArgs {
     #[arg(short, long, group = "log-level")]
     quiet: bool,
 
+    /// How to write log records; `json` writes one JSON object per line
+    ///
+    /// Overrides the `log_format` of the configuration file.
+    #[arg(long = "log-format", value_name = "LOG_FORMAT")]
+    log_format: Option<LogFormat>,
+
     #[command(flatten)]
     #[cfg(feature = "experiment_api")]
     api: crate::api::cli::ApiCli,
@@ -118,9 +127,15 @@ impl CliArgs {
     ///
     /// Generally the flow of control here is that all the command line parameters
     /// are merged into the configuration file to avoid much code duplication.
-    pub fn apply_to_config(&self, _cfg: &mut config::Rosenpass) -> anyhow::Result<()> {
+    pub fn apply_to_config(&self, cfg: &mut config::Rosenpass) -> anyhow::Result<()> {
         #[cfg(feature = "experiment_api")]
-        self.api.apply_to_config(_cfg)?;
+        self.api.apply_to_config(cfg)?;
+        if let Some(filter) = self.get_log_level() {
+            cfg.log_level = Some(filter);
+        }
+        if let Some(format) = self.log_format {
+            cfg.log_format = Some(format);
+        }
         Ok(())
     }
 
@@ -130,17 +145,20 @@ impl CliArgs {
     /// NOTE: the clap feature of ["argument groups"](https://docs.rs/clap/latest/clap/_derive/_tutorial/chapter_3/index.html#argument-relations)
     /// ensures that the user can not specify more than one of the possible log level arguments.
     /// Note the `#[arg("group")]` in the [`CliArgs`] struct.
-    pub fn get_log_level(&self) -> Option<log::LevelFilter> {
+    pub fn get_log_level(&self) -> Option<LogFilter> {
         if self.verbose {
-            return Some(log::LevelFilter::Info);
+            return Some(log::LevelFilter::Info.into());
         }
         if self.quiet {
-            return Some(log::LevelFilter::Warn);
-        }
-        if let Some(level_filter) = self.log_level {
-            return Some(level_filter);
+            return Some(log::LevelFilter::Warn.into());
         }
-        None
+        self.log_level.clone()
+    }
+
+    /// returns the log format set by CLI args
+    /// returns `None` if the user did not specify `--log-format`
+    pub fn get_log_format(&self) -> Option<LogFormat> {
+        self.log_format
     }
 
     /// Return the WireGuard PSK broker interface configured.
@@ -178,6 +196,9 @@ pub enum CliCommand {
     /// the specified peers. If a peer's endpoint is specified, this Rosenpass
     /// instance will try to initiate a key exchange with the peer; otherwise,
     /// only initiation attempts from other peers will be responded to.
+    ///
+    /// Sending SIGHUP makes Rosenpass re-read the configuration file and apply
+    /// changes to the peers and listen addresses without restarting.
     ExchangeConfig { config_file: PathBuf },
 
     /// Start Rosenpass key exchanges based on command line arguments
@@ -193,7 +214,8 @@ pub enum CliCommand {
     #[allow(rustdoc::broken_intra_doc_links)]
     #[allow(rustdoc::invalid_html_tags)]
     Exchange {
-        /// public-key <PATH> secret-key <PATH> [listen <ADDR>:<PORT>]... [verbose]
+        /// public-key <PATH> secret-key <PATH> [listen <ADDR>:<PORT>]... [bind-device <DEV>]
+        /// [fwmark <MARK>] [verbose]
         #[clap(value_name = "OWN_CONFIG")]
         first_arg: String,
 
@@ -434,7 +456,12 @@ impl CliArgs {
         broker_interface: Option<BrokerInterface>,
         test_helpers: Option<AppServerTest>,
     ) -> anyhow::Result<()> {
-        const MAX_PSK_SIZE: usize = 1000;
+        if let Some(filter) = config.log_filter() {
+            logging::set_log_filter(filter);
+        }
+        if let Some(format) = config.log_format {
+            logging::set_log_format(format);
+        }
 
         // load own keys
         let keypair = config
@@ -447,53 +474,51 @@ impl CliArgs {
             })
             .transpose()?;
 
-        // start an application server
-        let mut srv = std::boxed::Box::<AppServer>::new(AppServer::new(
-            keypair,
-            config.listen.clone(),
-            config.verbosity,
-            test_helpers,
-        )?);
+        // start an application server, using the listen sockets passed by systemd if any
+        let mut srv = match socket_activation::udp_listen_sockets()? {
+            Some(sockets) => {
+                if !config.listen.is_empty() {
+                    log::warn!("Ignoring the configured listen addresses; using the sockets passed by the service manager");
+                }
+                let mut srv =
+                    AppServer::with_sockets(keypair, sockets, config.verbosity, test_helpers)?;
+                srv.socket_activated = true;
+                srv
+            }
+            None => AppServer::new(
+                keypair,
+                config.listen.clone(),
+                config.verbosity,
+                test_helpers,
+            )?,
+        };
+        let mut srv = std::boxed::Box::new(srv);
+
+        // Before any threads or the broker process are started
+        srv.enable_sd_notify()?;
 
         config.apply_to_app_server(&mut srv)?;
 
         let broker = Self::create_broker(broker_interface)?;
         let broker_store_ptr = srv.register_broker(broker)?;
 
-        fn cfg_err_map(e: NativeUnixBrokerConfigBaseBuilderError) -> anyhow::Error {
-            anyhow::Error::msg(format!("NativeUnixBrokerConfigBaseBuilderError: {:?}", e))
-        }
+        let peers = config
+            .peers
+            .iter()
+            .map(|cfg_peer| cfg_peer.apply_to_app_server(&mut srv, &broker_store_ptr))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // Change the log filter upon SIGUSR1 and SIGUSR2
+        srv.enable_log_level_signals()?;
 
-        for cfg_peer in config.peers {
-            let broker_peer = if let Some(wg) = &cfg_peer.wg {
-                let peer_cfg = NativeUnixBrokerConfigBaseBuilder::default()
-                    .peer_id_b64(&wg.peer)?
-                    .interface(wg.device.clone())
-                    .extra_params_ser(&wg.extra_params)?
-                    .build()
-                    .map_err(cfg_err_map)?;
-
-                let broker_peer = BrokerPeer::new(broker_store_ptr.clone(), Box::new(peer_cfg));
-
-                Some(broker_peer)
-            } else {
-                None
-            };
-
-            srv.add_peer(
-                // psk, pk, outfile, outwg, tx_addr
-                cfg_peer
-                    .pre_shared_key
-                    .map(SymKey::load_b64::<MAX_PSK_SIZE, _>)
-                    .transpose()?,
-                SPk::load(&cfg_peer.public_key)?,
-                cfg_peer.key_out,
-                broker_peer,
-                cfg_peer.endpoint.clone(),
-            )?;
+        // Re-read the configuration file upon SIGHUP
+        if !config.config_file_path.as_os_str().is_empty() {
+            srv.enable_config_reload(config, peers, broker_store_ptr)?;
         }
 
-        srv.event_loop()
+        srv.restore_state()?;
+        srv.event_loop()?;
+        srv.store_state()
     }
 
     /// Create the WireGuard PSK broker to be used by
@@ -7,7 +7,9 @@
 //! - TODO: support `~` in <https://github.com/rosenpass/rosenpass/issues/237>
 //! - TODO: provide tooling to create config file from shell <https://github.com/rosenpass/rosenpass/issues/247>
 
-use crate::protocol::{SPk, SSk};
+use crate::protocol::{
+    validate_cookie_secret_epoch, JsonTracer, SPk, SSk, SuiteId, SymKey, Timing, TimingProfile,
+};
 use rosenpass_util::file::LoadValue;
 use std::{
     collections::HashSet,
@@ -15,13 +17,23 @@ use std::{
     io::Write,
     net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, ToSocketAddrs},
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use anyhow::{bail, ensure};
+use anyhow::{bail, ensure, Context};
+use rosenpass_util::file::LoadValueB64;
 use rosenpass_util::file::{fopen_w, Visibility};
+use rosenpass_wireguard_broker::brokers::native_unix::{
+    NativeUnixBrokerConfigBaseBuilder, NativeUnixBrokerConfigBaseBuilderError,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::app_server::AppServer;
+use crate::app_server::{AppPeerPtr, AppServer, BrokerPeer, BrokerStorePtr};
+use crate::endpoint_refresh::{validate_hostname, EndpointRefreshConfig};
+use crate::logging::{self, LogFilter, LogFormat};
+use crate::metrics::MetricsConfig;
+use crate::roaming::RoamingPolicy;
+use crate::socket_options::{parse_fwmark, SocketOptions};
 
 #[cfg(feature = "experiment_api")]
 fn empty_api_config() -> crate::api::config::ApiConfig {
@@ -35,7 +47,7 @@ fn empty_api_config() -> crate::api::config::ApiConfig {
 /// Configuration for the Rosenpass key exchange
 ///
 /// i.e. configuration for the `rosenpass exchange` and `rosenpass exchange-config` commands
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct Rosenpass {
     // TODO: Raise error if secret key or public key alone is set during deserialization
     // SEE: https://github.com/serde-rs/serde/issues/2793
@@ -56,17 +68,72 @@ pub struct Rosenpass {
     /// - `[::]:4476` – Listen on any IPv4 or IPv6 interface, port 4476
     pub listen: Vec<SocketAddr>,
 
+    /// name of the network device to bind the listen sockets to, e.g. `eth0`
+    ///
+    /// Only supported on Linux; see [crate::socket_options] for details.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_device: Option<String>,
+
+    /// firewall mark to set on all packets sent by Rosenpass, e.g. for policy routing
+    ///
+    /// Works just like WireGuard's `FwMark`; requires `CAP_NET_ADMIN` and is only supported on
+    /// Linux. See [crate::socket_options] for details.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fwmark: Option<u32>,
+
     /// log verbosity
     ///
-    /// This is subject to change. See [`Verbosity`] for details.
+    /// Superseded by [Self::log_level]; see [`Verbosity`] for details.
     #[serde(default)]
     pub verbosity: Verbosity,
 
+    /// which records to log, e.g. `info` or `warn,protocol=debug,app_server=info`
+    ///
+    /// See [LogFilter] for details. The `--log-level`, `--verbose` and `--quiet` command line
+    /// options take precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<LogFilter>,
+
+    /// how to write log records, e.g. `json` for one JSON object per line
+    ///
+    /// See [LogFormat] for details. The `--log-format` command line option takes precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_format: Option<LogFormat>,
+
     /// list of peers
     ///
     /// See the [`RosenpassPeer`] type for more information and examples.
     pub peers: Vec<RosenpassPeer>,
 
+    /// path of the file used to persist the protocol state across restarts
+    ///
+    /// If set, established sessions and biscuit keys are stored in this file (encrypted using
+    /// a key derived from our secret key) when Rosenpass is shut down gracefully, and restored
+    /// on startup, so peers do not need to perform a new handshake after a restart.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_file: Option<PathBuf>,
+
+    /// life time of the cookie secret in seconds
+    ///
+    /// The cookie secret is used to protect against denial of service attacks; if unset, the
+    /// protocol default is used (see [crate::protocol::COOKIE_SECRET_EPOCH]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookie_secret_epoch: Option<Timing>,
+
+    /// path of a file to write handshake traces to
+    ///
+    /// If set, every step of processing a handshake message is logged to this file as one line
+    /// of JSON; this helps debugging failed key exchanges. The traces identify peers by their
+    /// peer id and contain no secrets. See [crate::protocol::HandshakeEvent].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_file: Option<PathBuf>,
+
+    /// where to serve metrics in the Prometheus text format
+    ///
+    /// See [crate::metrics::MetricsConfig] for details.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<MetricsConfig>,
+
     /// path to the file which provided this configuration
     ///
     /// This item is of course not read from the TOML but is added by the algorithm that parses
@@ -99,10 +166,10 @@ impl Keypair {
 
 /// Level of verbosity for [crate::app_server::AppServer]
 ///
-/// The value of the field [crate::app_server::AppServer::verbosity]. See the field documentation
-/// for details.
-///
-/// - TODO: replace this type with [`log::LevelFilter`], also see <https://github.com/rosenpass/rosenpass/pull/246>
+/// The value of the field [crate::app_server::AppServer::verbosity]. This is kept for
+/// compatibility with existing configuration files: unless a log filter is given through
+/// [Rosenpass::log_level], the command line or `RUST_LOG`, [Self::Verbose] logs records of level
+/// `info` and above (see [Rosenpass::log_filter]).
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Copy, Clone)]
 pub enum Verbosity {
     Quiet,
@@ -110,7 +177,7 @@ pub enum Verbosity {
 }
 
 /// Configuration data for a single Rosenpass peer
-#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct RosenpassPeer {
     /// path to the public key of the peer
     pub public_key: PathBuf,
@@ -138,6 +205,213 @@ pub struct RosenpassPeer {
     /// Information for supplying exchanged keys directly to WireGuard
     #[serde(flatten)]
     pub wg: Option<WireGuard>,
+
+    /// Timers used for this peer, e.g. how often to rekey
+    ///
+    /// NOTE: this item can be skipped in the config if the protocol defaults should be used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing: Option<PeerTiming>,
+
+    /// The protocol suite used with this peer, e.g. `rosenpass-v1-ml-kem-512`
+    ///
+    /// Both peers must use the same suite. See [SuiteId] for the supported suites.
+    ///
+    /// NOTE: this item can be skipped in the config to use the default suite
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suite: Option<SuiteId>,
+
+    /// Command to run whenever a key is output for this peer, e.g.
+    /// `["wg", "set", "wg0", "peer", "<WIREGUARD_PUBLIC_KEY>", "preshared-key", "/dev/stdin"]`
+    ///
+    /// The key is passed on standard input; see [crate::exchange_command] for details.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange_command: Option<Vec<String>>,
+
+    /// When to resolve the hostname in [Self::endpoint] again, e.g. for peers behind dynamic DNS
+    ///
+    /// See [EndpointRefreshConfig] for details.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint_refresh: Option<EndpointRefreshConfig>,
+
+    /// Whether the endpoint may change to the address the peer completes a handshake from
+    ///
+    /// See [RoamingPolicy] for details.
+    ///
+    /// NOTE: this item can be skipped in the config to allow roaming to any address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roaming: Option<RoamingPolicy>,
+}
+
+impl RosenpassPeer {
+    /// Maximum size of the base64 encoded pre-shared key file
+    const MAX_PSK_SIZE: usize = 1000;
+
+    /// Load the public key from [Self::public_key]
+    pub fn load_public_key(&self) -> anyhow::Result<SPk> {
+        SPk::load(&self.public_key)
+            .with_context(|| format!("Could not load public key {:?}", self.public_key))
+    }
+
+    /// Load the pre-shared key from [Self::pre_shared_key], if set
+    pub fn load_pre_shared_key(&self) -> anyhow::Result<Option<SymKey>> {
+        self.pre_shared_key
+            .as_ref()
+            .map(|path| {
+                SymKey::load_b64::<{ Self::MAX_PSK_SIZE }, _>(path)
+                    .with_context(|| format!("Could not load pre-shared key {path:?}"))
+            })
+            .transpose()
+    }
+
+    /// Create a [BrokerPeer] supplying keys to the WireGuard peer configured in [Self::wg]
+    /// through the given broker
+    pub fn broker_peer(&self, broker: &BrokerStorePtr) -> anyhow::Result<Option<BrokerPeer>> {
+        let Some(wg) = &self.wg else {
+            return Ok(None);
+        };
+
+        let peer_cfg = NativeUnixBrokerConfigBaseBuilder::default()
+            .peer_id_b64(&wg.peer)?
+            .interface(wg.device.clone())
+            .extra_params_ser(&wg.extra_params)?
+            .build()
+            .map_err(|e: NativeUnixBrokerConfigBaseBuilderError| {
+                anyhow::Error::msg(format!("NativeUnixBrokerConfigBaseBuilderError: {:?}", e))
+            })?;
+
+        Ok(Some(BrokerPeer::new(broker.clone(), Box::new(peer_cfg))))
+    }
+
+    /// Add this peer to the [AppServer], using the given broker for the WireGuard settings
+    pub fn apply_to_app_server(
+        &self,
+        srv: &mut AppServer,
+        broker: &BrokerStorePtr,
+    ) -> anyhow::Result<AppPeerPtr> {
+        let peer = srv.add_peer(
+            self.load_pre_shared_key()?,
+            self.load_public_key()?,
+            self.key_out.clone(),
+            self.broker_peer(broker)?,
+            self.endpoint.clone(),
+        )?;
+        self.apply_settings_to_app_server(srv, peer)?;
+        Ok(peer)
+    }
+
+    /// Apply the [Self::timing], [Self::suite], [Self::exchange_command],
+    /// [Self::endpoint_refresh] and [Self::roaming] to a peer in the [AppServer]
+    pub fn apply_settings_to_app_server(
+        &self,
+        srv: &mut AppServer,
+        peer: AppPeerPtr,
+    ) -> anyhow::Result<()> {
+        let timing = match &self.timing {
+            Some(timing) => timing.to_profile()?,
+            None => TimingProfile::default(),
+        };
+        srv.set_peer_timing(peer, timing)?;
+        srv.set_peer_suite(peer, self.suite.unwrap_or_default())?;
+        srv.set_peer_exchange_command(peer, self.exchange_command.clone())?;
+        srv.set_peer_endpoint_refresh(peer, self.endpoint_refresh)?;
+        srv.set_peer_roaming(peer, self.roaming.clone().unwrap_or_default())?;
+        Ok(())
+    }
+}
+
+/// Timers used for a single peer
+///
+/// All values are given in seconds. Values that are not set use the protocol defaults; see
+/// [TimingProfile] for details and [TimingProfile::validate] for the permissible bounds.
+///
+/// ```toml
+/// [[peers]]
+/// public_key = "/path/to/rp-peer-public-key"
+/// key_out = "/path/to/rp-key-out.txt"
+///
+/// [peers.timing]
+/// rekey_after_time_responder = 600
+/// rekey_after_time_initiator = 610
+/// reject_after_time = 660
+/// ```
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct PeerTiming {
+    /// See [TimingProfile::rekey_after_time_responder]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rekey_after_time_responder: Option<Timing>,
+    /// See [TimingProfile::rekey_after_time_initiator]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rekey_after_time_initiator: Option<Timing>,
+    /// See [TimingProfile::reject_after_time]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reject_after_time: Option<Timing>,
+    /// See [TimingProfile::retransmit_delay_begin]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retransmit_delay_begin: Option<Timing>,
+    /// See [TimingProfile::retransmit_delay_end]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retransmit_delay_end: Option<Timing>,
+    /// See [TimingProfile::retransmit_delay_growth]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retransmit_delay_growth: Option<Timing>,
+    /// See [TimingProfile::retransmit_delay_jitter]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retransmit_delay_jitter: Option<Timing>,
+}
+
+impl PeerTiming {
+    /// Apply the values set here to the default [TimingProfile] and validate the result
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::config::PeerTiming;
+    /// use rosenpass::protocol::{TimingProfile, REJECT_AFTER_TIME};
+    ///
+    /// assert_eq!(PeerTiming::default().to_profile()?, TimingProfile::default());
+    ///
+    /// let fast = PeerTiming {
+    ///     rekey_after_time_responder: Some(30.0),
+    ///     rekey_after_time_initiator: Some(40.0),
+    ///     reject_after_time: Some(60.0),
+    ///     ..PeerTiming::default()
+    /// };
+    /// assert_eq!(fast.to_profile()?.reject_after_time, 60.0);
+    ///
+    /// // Rekeying after the key was rejected is not allowed
+    /// let invalid = PeerTiming {
+    ///     rekey_after_time_responder: Some(REJECT_AFTER_TIME * 2.0),
+    ///     ..PeerTiming::default()
+    /// };
+    /// assert!(invalid.to_profile().is_err());
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn to_profile(&self) -> anyhow::Result<TimingProfile> {
+        let d = TimingProfile::default();
+        let r = TimingProfile {
+            rekey_after_time_responder: self
+                .rekey_after_time_responder
+                .unwrap_or(d.rekey_after_time_responder),
+            rekey_after_time_initiator: self
+                .rekey_after_time_initiator
+                .unwrap_or(d.rekey_after_time_initiator),
+            reject_after_time: self.reject_after_time.unwrap_or(d.reject_after_time),
+            retransmit_delay_begin: self
+                .retransmit_delay_begin
+                .unwrap_or(d.retransmit_delay_begin),
+            retransmit_delay_end: self.retransmit_delay_end.unwrap_or(d.retransmit_delay_end),
+            retransmit_delay_growth: self
+                .retransmit_delay_growth
+                .unwrap_or(d.retransmit_delay_growth),
+            retransmit_delay_jitter: self
+                .retransmit_delay_jitter
+                .unwrap_or(d.retransmit_delay_jitter),
+        };
+        r.validate()?;
+        Ok(r)
+    }
 }
 
 /// Information for supplying exchanged keys directly to WireGuard
@@ -239,10 +513,52 @@ impl Rosenpass {
         self.store(&self.config_file_path)
     }
 
+    /// The [LogFilter] configured through [Self::log_level] or, failing that, [Self::verbosity]
+    ///
+    /// The verbosity is only used if no filter was given on the command line or through
+    /// `RUST_LOG` (see [logging::filter_given_on_startup]). `None` if neither applies, in which
+    /// case the filter set on startup is kept.
+    pub fn log_filter(&self) -> Option<LogFilter> {
+        self.log_filter_over(logging::filter_given_on_startup())
+    }
+
+    /// [Self::log_filter], given whether a filter was set on startup
+    fn log_filter_over(&self, filter_given_on_startup: bool) -> Option<LogFilter> {
+        match (&self.log_level, self.verbosity) {
+            (Some(filter), _) => Some(filter.clone()),
+            (None, Verbosity::Verbose) if !filter_given_on_startup => {
+                Some(log::LevelFilter::Info.into())
+            }
+            (None, _) => None,
+        }
+    }
+
+    /// The [Self::bind_device] and [Self::fwmark] used for the listen sockets
+    pub fn socket_options(&self) -> SocketOptions {
+        SocketOptions {
+            bind_device: self.bind_device.clone(),
+            fwmark: self.fwmark,
+        }
+    }
+
     /// Apply the configuration in this object to the given [crate::app_server::AppServer]
-    pub fn apply_to_app_server(&self, _srv: &mut AppServer) -> anyhow::Result<()> {
+    pub fn apply_to_app_server(&self, srv: &mut AppServer) -> anyhow::Result<()> {
         #[cfg(feature = "experiment_api")]
-        self.api.apply_to_app_server(_srv)?;
+        self.api.apply_to_app_server(srv)?;
+        srv.set_socket_options(self.socket_options())?;
+        if let Some(ref state_file) = self.state_file {
+            srv.state_file = Some(state_file.clone());
+            srv.enable_graceful_shutdown()?;
+        }
+        if let Some(epoch) = self.cookie_secret_epoch {
+            srv.set_cookie_secret_epoch(epoch)?;
+        }
+        if let Some(ref trace_file) = self.trace_file {
+            srv.set_tracer(Some(Arc::new(JsonTracer::open(trace_file)?)))?;
+        }
+        if let Some(ref metrics) = self.metrics {
+            metrics.apply_to_app_server(srv)?;
+        }
         Ok(())
     }
 
@@ -285,6 +601,18 @@ impl Rosenpass {
             );
         }
 
+        if let Some(epoch) = self.cookie_secret_epoch {
+            validate_cookie_secret_epoch(epoch)?;
+        }
+
+        if let Some(ref dev) = self.bind_device {
+            ensure!(!dev.is_empty(), "bind_device must not be empty");
+        }
+
+        if let Some(ref metrics) = self.metrics {
+            metrics.validate()?;
+        }
+
         for (i, peer) in self.peers.iter().enumerate() {
             // check peer's public-key file exists
             ensure!(
@@ -300,31 +628,58 @@ impl Rosenpass {
                 peer.public_key
             );
 
-            // check endpoint is usable
+            // check endpoint is usable; it is resolved later on, so this must not block
             if let Some(addr) = peer.endpoint.as_ref() {
-                ensure!(
-                    addr.to_socket_addrs().is_ok(),
-                    "peer {i} endpoint {} can not be parsed to a socket address",
-                    addr
-                );
+                validate_hostname(addr)
+                    .with_context(|| format!("peer {i} has an invalid endpoint"))?;
             }
 
-            // check if `key_out` or `device` and `peer` are defined
-            if peer.key_out.is_none() {
+            // check if `key_out`, `exchange_command` or `device` and `peer` are defined
+            if peer.key_out.is_none() && peer.exchange_command.is_none() {
                 if let Some(wg) = &peer.wg {
                     if wg.device.is_empty() || wg.peer.is_empty() {
                         ensure!(
                             false,
-                            "peer {i} has neither `key_out` nor valid wireguard config defined"
+                            "peer {i} has neither `key_out`, `exchange_command` nor valid wireguard config defined"
                         );
                     }
                 } else {
                     ensure!(
                         false,
-                        "peer {i} has neither `key_out` nor valid wireguard config defined"
+                        "peer {i} has neither `key_out`, `exchange_command` nor valid wireguard config defined"
                     );
                 }
             }
+
+            // check the endpoint refresh settings
+            if let Some(refresh) = &peer.endpoint_refresh {
+                refresh
+                    .validate()
+                    .with_context(|| format!("peer {i} has an invalid endpoint_refresh"))?;
+                ensure!(
+                    peer.endpoint.is_some(),
+                    "peer {i} has endpoint_refresh but no endpoint to resolve"
+                );
+            }
+
+            // check the roaming policy
+            if let Some(roaming) = &peer.roaming {
+                roaming
+                    .validate()
+                    .with_context(|| format!("peer {i} has an invalid roaming policy"))?;
+            }
+
+            // check the exchange command names a program to run
+            if let Some(cmd) = &peer.exchange_command {
+                ensure!(!cmd.is_empty(), "peer {i} has an empty `exchange_command`");
+            }
+
+            // check the timing values are within bounds
+            if let Some(timing) = &peer.timing {
+                timing
+                    .to_profile()
+                    .with_context(|| format!("peer {i} has an invalid timing configuration"))?;
+            }
         }
 
         Ok(())
@@ -396,10 +751,18 @@ impl Rosenpass {
         Self {
             keypair,
             listen: vec![],
+            bind_device: None,
+            fwmark: None,
             #[cfg(feature = "experiment_api")]
             api: crate::api::config::ApiConfig::default(),
             verbosity: Verbosity::Quiet,
+            log_level: None,
+            log_format: None,
             peers: vec![],
+            state_file: None,
+            cookie_secret_epoch: None,
+            trace_file: None,
+            metrics: None,
             config_file_path: PathBuf::new(),
         }
     }
@@ -448,6 +811,8 @@ impl Rosenpass {
             OwnPublicKey,
             OwnSecretKey,
             OwnListen,
+            OwnBindDevice,
+            OwnFwmark,
             Peer,
             PeerPsk,
             PeerPublicKey,
@@ -477,6 +842,8 @@ impl Rosenpass {
                     OwnSecretKey
                 }
                 (Own, "listen", None) => OwnListen,
+                (Own, "bind-device", None) => OwnBindDevice,
+                (Own, "fwmark", None) => OwnFwmark,
                 (Own, "verbose", None) => {
                     config.verbosity = Verbosity::Verbose;
                     Own
@@ -520,6 +887,19 @@ impl Rosenpass {
 
                     Own
                 }
+                (OwnBindDevice, dev, None) => {
+                    ensure!(
+                        already_set.insert(OwnBindDevice),
+                        "bind-device was already set"
+                    );
+                    config.bind_device = Some(dev.to_owned());
+                    Own
+                }
+                (OwnFwmark, mark, None) => {
+                    ensure!(already_set.insert(OwnFwmark), "fwmark was already set");
+                    config.fwmark = Some(parse_fwmark(mark)?);
+                    Own
+                }
                 (Peer | PeerWireguardExtraArgs, "peer", maybe_peer @ Some(_)) => {
                     // TODO check current peer
                     // commit current peer, create a new one
@@ -595,7 +975,11 @@ impl Rosenpass {
                 (Own, x, None) => {
                     bail!("unrecognised argument {x}");
                 }
-                (Own | OwnPublicKey | OwnSecretKey | OwnListen, _, Some(_)) => {
+                (
+                    Own | OwnPublicKey | OwnSecretKey | OwnListen | OwnBindDevice | OwnFwmark,
+                    _,
+                    Some(_),
+                ) => {
                     panic!("current_peer is not None while in Own* state, this must never happen")
                 }
 
@@ -639,13 +1023,24 @@ impl Default for Verbosity {
 pub static EXAMPLE_CONFIG: &str = r###"public_key = "/path/to/rp-public-key"
 secret_key = "/path/to/rp-secret-key"
 listen = []
-verbosity = "Verbose"
+log_level = "info" # or e.g. "warn,protocol=debug,app_server=info"
+# log_format = "json" # one JSON object per line instead of text
+# state_file = "/path/to/rp-state" # persist sessions across restarts
+# trace_file = "/path/to/rp-trace.json" # log handshake steps for debugging
+# bind_device = "eth0" # send and receive only through this network device
+# fwmark = 0xca6c # firewall mark for policy routing; requires CAP_NET_ADMIN
+
+# Serve metrics in the Prometheus text format
+# [metrics]
+# listen_addr = "127.0.0.1:9587"
+# listen_path = "/path/to/rp-metrics.sock"
 
 [[peers]]
 # Commented out fields are optional
 public_key = "/path/to/rp-peer-public-key"
 endpoint = "127.0.0.1:9998"
 # pre_shared_key = "/path/to/preshared-key"
+# suite = "rosenpass-v1-kyber512" # protocol suite; must match the other peer
 
 # Choose to store the key in a file via `key_out` or pass it to WireGuard by
 # defining `device` and `peer`. You may choose to do both.
@@ -653,6 +1048,12 @@ key_out = "/path/to/rp-key-out.txt" # path to store the key
 # device = "wg0" # WireGuard interface
 #peer = "RULdRAtUw7SFfVfGD..." # WireGuard public key
 # extra_params = [] # passed to WireGuard `wg set`
+
+# Optionally override the protocol timers for this peer (in seconds)
+# [peers.timing]
+# rekey_after_time_responder = 600
+# rekey_after_time_initiator = 610
+# reject_after_time = 660
 "###;
 
 #[cfg(test)]
@@ -770,6 +1171,187 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn toml_metrics() -> anyhow::Result<()> {
+        let config: Rosenpass = toml::from_str(
+            r#"
+            listen = []
+            peers = []
+
+            [metrics]
+            listen_addr = "127.0.0.1:9587"
+        "#,
+        )?;
+
+        let metrics = config.metrics.as_ref().unwrap();
+        assert_eq!(metrics.listen_addr, Some("127.0.0.1:9587".parse()?));
+        assert_eq!(metrics.listen_path, None);
+        assert!(metrics.validate().is_ok());
+
+        // At least one listen option is required
+        let config: Rosenpass = toml::from_str(
+            r#"
+            listen = []
+            peers = []
+            [metrics]
+        "#,
+        )?;
+        assert!(config.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn toml_peer_timing() -> anyhow::Result<()> {
+        let config: Rosenpass = toml::from_str(
+            r#"
+            listen = []
+            cookie_secret_epoch = 60
+
+            [[peers]]
+            public_key = "/peer/pk"
+            key_out = "/peer/out"
+
+            [peers.timing]
+            rekey_after_time_responder = 600
+            rekey_after_time_initiator = 610.5
+            reject_after_time = 700
+        "#,
+        )?;
+
+        assert_eq!(config.cookie_secret_epoch, Some(60.0));
+        let timing = config.peers[0].timing.unwrap().to_profile()?;
+        assert_eq!(timing.rekey_after_time_responder, 600.0);
+        assert_eq!(timing.rekey_after_time_initiator, 610.5);
+        assert_eq!(timing.reject_after_time, 700.0);
+        assert_eq!(
+            timing.retransmit_delay_begin,
+            TimingProfile::default().retransmit_delay_begin
+        );
+
+        // Typos must not silently fall back to the defaults
+        let typo = toml::from_str::<Rosenpass>(
+            r#"
+            listen = []
+
+            [[peers]]
+            public_key = "/peer/pk"
+
+            [peers.timing]
+            rekey_after_time = 600
+        "#,
+        );
+        assert!(typo.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn toml_peer_suite() -> anyhow::Result<()> {
+        let suite = SuiteId::all().last().unwrap();
+        let config: Rosenpass = toml::from_str(&format!(
+            r#"
+            listen = []
+
+            [[peers]]
+            public_key = "/peer/pk"
+            suite = "{suite}"
+
+            [[peers]]
+            public_key = "/peer2/pk"
+        "#
+        ))?;
+
+        assert_eq!(config.peers[0].suite, Some(suite));
+        assert_eq!(config.peers[1].suite, None);
+
+        let unknown = toml::from_str::<Rosenpass>(
+            r#"
+            listen = []
+
+            [[peers]]
+            public_key = "/peer/pk"
+            suite = "rosenpass-v0-rot13"
+        "#,
+        );
+        assert!(unknown.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn log_filter() -> anyhow::Result<()> {
+        let config: Rosenpass = toml::from_str(
+            r#"
+            listen = []
+            peers = []
+            verbosity = "Verbose"
+            log_level = "warn,protocol=debug"
+        "#,
+        )?;
+        let filter = config.log_filter().unwrap();
+        assert_eq!(filter.level("rosenpass::protocol"), log::LevelFilter::Debug);
+        assert_eq!(
+            filter.level("rosenpass::app_server"),
+            log::LevelFilter::Warn
+        );
+
+        // Without `log_level`, the verbosity is used
+        let config: Rosenpass = toml::from_str(
+            r#"
+            listen = []
+            peers = []
+            verbosity = "Verbose"
+        "#,
+        )?;
+        assert_eq!(config.log_filter(), Some(log::LevelFilter::Info.into()));
+        // ...unless a filter was given on the command line or through `RUST_LOG`
+        assert_eq!(config.log_filter_over(true), None);
+        let config: Rosenpass = toml::from_str("listen = []\npeers = []")?;
+        assert_eq!(config.log_filter(), None);
+
+        let invalid = toml::from_str::<Rosenpass>(
+            r#"
+            listen = []
+            peers = []
+            log_level = "warn,protocol=loud"
+        "#,
+        );
+        assert!(invalid.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn socket_options() -> anyhow::Result<()> {
+        let config: Rosenpass = toml::from_str(
+            r#"
+            listen = []
+            peers = []
+            bind_device = "eth0"
+            fwmark = 0xca6c
+        "#,
+        )?;
+        let expected = SocketOptions {
+            bind_device: Some("eth0".to_owned()),
+            fwmark: Some(0xca6c),
+        };
+        assert_eq!(config.socket_options(), expected);
+
+        let args = split_str(
+            "public-key /my/public-key secret-key /my/secret-key \
+                bind-device eth0 fwmark 0xca6c",
+        );
+        assert_eq!(Rosenpass::parse_args(args)?.socket_options(), expected);
+
+        let args = split_str("public-key /my/public-key fwmark 1 fwmark 2");
+        assert!(Rosenpass::parse_args(args).is_err());
+        let args = split_str("public-key /my/public-key fwmark eth0");
+        assert!(Rosenpass::parse_args(args).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_cli_parse_multiple_peers() {
         let args = split_str(
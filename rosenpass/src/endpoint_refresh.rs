@@ -0,0 +1,474 @@
+//! Re-resolving the hostnames of peer endpoints
+//!
+//! The endpoint of a peer (see [crate::config::RosenpassPeer::endpoint]) is usually resolved
+//! only once, when the peer is added. A peer behind dynamic DNS would become unreachable once
+//! its address changes. With [EndpointRefreshConfig], the hostname is resolved again on a fixed
+//! interval and/or after a number of handshake retransmissions without a reply.
+//!
+//! Resolution happens on a background thread, so it never blocks the event loop. The results
+//! are picked up by [AppServer::poll]; new addresses replace the
+//! [AppPeer::initial_endpoint] and, while the peer is not connected, are used for peer
+//! discovery right away (see [HostPathDiscoveryEndpoint::send_scouting]).
+//!
+//! [AppServer::poll]: crate::app_server::AppServer::poll
+//! [HostPathDiscoveryEndpoint::send_scouting]: crate::app_server::HostPathDiscoveryEndpoint::send_scouting
+
+use std::collections::HashSet;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::ensure;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::app_server::{AppPeer, AppPeerPtr, AppServer, Endpoint};
+use crate::protocol::Timing;
+
+/// Minimum permissible [EndpointRefreshConfig::interval] in seconds
+pub const MIN_REFRESH_INTERVAL: Timing = 1.0;
+
+/// Check that `hostname` is of the form `host:port`, without resolving it
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::endpoint_refresh::validate_hostname;
+///
+/// assert!(validate_hostname("peer.dyndns.example:9999").is_ok());
+/// assert!(validate_hostname("[::1]:9999").is_ok());
+/// assert!(validate_hostname("peer.dyndns.example").is_err());
+/// assert!(validate_hostname(":9999").is_err());
+/// ```
+pub fn validate_hostname(hostname: &str) -> anyhow::Result<()> {
+    let valid = hostname
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+    ensure!(valid, "Endpoint {hostname:?} is not of the form host:port");
+    Ok(())
+}
+
+/// When to resolve the hostname of a peer's endpoint again
+///
+/// At least one of the options must be set.
+///
+/// ```toml
+/// [[peers]]
+/// public_key = "/path/to/rp-peer-public-key"
+/// endpoint = "peer.dyndns.example:9999"
+/// key_out = "/path/to/rp-key-out.txt"
+///
+/// [peers.endpoint_refresh]
+/// interval = 300
+/// after_retransmissions = 5
+/// ```
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct EndpointRefreshConfig {
+    /// Resolve the hostname every this many seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<Timing>,
+
+    /// Resolve the hostname after this many handshake retransmissions without a reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_retransmissions: Option<u32>,
+}
+
+impl EndpointRefreshConfig {
+    /// Check that the configuration is sound
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::endpoint_refresh::EndpointRefreshConfig;
+    ///
+    /// let cfg = EndpointRefreshConfig {
+    ///     interval: Some(300.0),
+    ///     after_retransmissions: None,
+    /// };
+    /// assert!(cfg.validate().is_ok());
+    ///
+    /// // Nothing would ever trigger a refresh
+    /// assert!(EndpointRefreshConfig::default().validate().is_err());
+    ///
+    /// let too_fast = EndpointRefreshConfig {
+    ///     interval: Some(0.1),
+    ///     after_retransmissions: None,
+    /// };
+    /// assert!(too_fast.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.interval.is_some() || self.after_retransmissions.is_some(),
+            "endpoint_refresh requires either interval or after_retransmissions to be set"
+        );
+        if let Some(interval) = self.interval {
+            ensure!(
+                interval >= MIN_REFRESH_INTERVAL,
+                "endpoint_refresh interval must be at least {MIN_REFRESH_INTERVAL} seconds"
+            );
+        }
+        if let Some(n) = self.after_retransmissions {
+            ensure!(
+                n > 0,
+                "endpoint_refresh after_retransmissions must not be zero"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Per-peer state for re-resolving the endpoint; see [AppPeer::endpoint_refresh]
+#[derive(Debug, Default)]
+pub struct EndpointRefresh {
+    /// The configuration; nothing is resolved again if this is `None`
+    config: Option<EndpointRefreshConfig>,
+    /// When the next interval based resolution is due
+    next_at: Option<Timing>,
+    /// Handshake retransmissions since the last reply or resolution
+    retransmissions: u32,
+    /// Whether a resolution is currently running
+    in_flight: bool,
+}
+
+/// The result of resolving a hostname in the background
+#[derive(Debug)]
+pub struct Resolved {
+    peer: AppPeerPtr,
+    hostname: String,
+    result: std::io::Result<Vec<SocketAddr>>,
+}
+
+/// Channel through which background resolutions report back to the [AppServer]
+#[derive(Debug)]
+pub struct ResolvedChannel {
+    tx: mpsc::Sender<Resolved>,
+    rx: mpsc::Receiver<Resolved>,
+}
+
+impl Default for ResolvedChannel {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self { tx, rx }
+    }
+}
+
+impl AppServer {
+    /// Set or clear the [EndpointRefreshConfig] of a peer registered with [Self::add_peer]
+    pub fn set_peer_endpoint_refresh(
+        &mut self,
+        peer: AppPeerPtr,
+        config: Option<EndpointRefreshConfig>,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            peer.exists(self),
+            "Cannot set endpoint refresh for peer {}; no such peer",
+            peer.0
+        );
+        if let Some(config) = &config {
+            config.validate()?;
+        }
+
+        let now = self.timebase.now();
+        let refresh = &mut peer.get_app_mut(self).endpoint_refresh;
+        if refresh.config == config {
+            return Ok(());
+        }
+        refresh.config = config;
+        refresh.retransmissions = 0;
+        refresh.next_at = config.and_then(|c| c.interval).map(|i| now + i);
+        self.update_next_endpoint_refresh();
+        Ok(())
+    }
+
+    /// Seconds until the next interval based endpoint refresh is due; used by [Self::poll]
+    /// to wake up in time
+    pub(crate) fn endpoint_refresh_timeout(&self) -> Option<Timing> {
+        let next = self.next_endpoint_refresh?;
+        Some((next - self.timebase.now()).max(0.0))
+    }
+
+    /// Apply the results of background resolutions and start the resolutions that are due
+    ///
+    /// Called by [Self::poll] on every iteration.
+    pub(crate) fn refresh_endpoints(&mut self) {
+        while let Ok(resolved) = self.resolved_endpoints.rx.try_recv() {
+            self.apply_resolved(resolved);
+        }
+
+        let Some(next) = self.next_endpoint_refresh else {
+            return;
+        };
+        let now = self.timebase.now();
+        if now < next {
+            return;
+        }
+
+        for idx in 0..self.peers.len() {
+            let peer = AppPeerPtr(idx);
+            let due = self.peers[idx].as_ref().is_some_and(|ap| {
+                ap.endpoint_refresh
+                    .next_at
+                    .is_some_and(|next_at| next_at <= now)
+            });
+            if due {
+                self.start_endpoint_resolution(peer);
+            }
+        }
+        self.update_next_endpoint_refresh();
+    }
+
+    /// Count a handshake retransmission to `peer`, resolving its endpoint again once
+    /// [EndpointRefreshConfig::after_retransmissions] is reached
+    pub(crate) fn endpoint_refresh_retransmitted(&mut self, peer: AppPeerPtr) {
+        let refresh = &mut peer.get_app_mut(self).endpoint_refresh;
+        let Some(limit) = refresh.config.and_then(|c| c.after_retransmissions) else {
+            return;
+        };
+        refresh.retransmissions += 1;
+        if refresh.retransmissions >= limit {
+            self.start_endpoint_resolution(peer);
+        }
+    }
+
+    /// Reset the retransmission count of `peer` after it replied
+    pub(crate) fn endpoint_refresh_replied(&mut self, peer: AppPeerPtr) {
+        peer.get_app_mut(self).endpoint_refresh.retransmissions = 0;
+    }
+
+    /// Recompute [Self::next_endpoint_refresh]
+    fn update_next_endpoint_refresh(&mut self) {
+        self.next_endpoint_refresh = self
+            .peers
+            .iter()
+            .flatten()
+            .filter_map(|ap| ap.endpoint_refresh.next_at)
+            .min_by(|a, b| a.total_cmp(b));
+    }
+
+    /// Resolve [AppPeer::hostname] of a peer added without resolving it, e.g. through the API
+    ///
+    /// Unlike [Self::add_peer], this does not block; the peer gets its endpoint once the
+    /// hostname is resolved on a background thread.
+    pub fn resolve_endpoint_in_background(&mut self, peer: AppPeerPtr) -> anyhow::Result<()> {
+        ensure!(
+            peer.exists(self),
+            "Cannot resolve the endpoint of peer {}; no such peer",
+            peer.0
+        );
+        self.start_endpoint_resolution(peer);
+        Ok(())
+    }
+
+    /// Resolve the hostname of `peer` on a background thread
+    fn start_endpoint_resolution(&mut self, peer: AppPeerPtr) {
+        let now = self.timebase.now();
+        let tx = self.resolved_endpoints.tx.clone();
+        let ap = peer.get_app_mut(self);
+        let refresh = &mut ap.endpoint_refresh;
+        refresh.retransmissions = 0;
+        refresh.next_at = refresh.config.and_then(|c| c.interval).map(|i| now + i);
+
+        let Some(hostname) = ap.hostname.clone() else {
+            return;
+        };
+        if refresh.in_flight {
+            return;
+        }
+        refresh.in_flight = true;
+
+        let spawned = thread::Builder::new()
+            .name("rp-resolve".to_owned())
+            .spawn(move || {
+                let result = hostname.to_socket_addrs().map(Iterator::collect);
+                // The server may have shut down in the mean time
+                let _ = tx.send(Resolved {
+                    peer,
+                    hostname,
+                    result,
+                });
+            });
+        if let Err(e) = spawned {
+            warn!("Could not start a thread to resolve the endpoint of peer {peer:?}: {e}");
+            peer.get_app_mut(self).endpoint_refresh.in_flight = false;
+        }
+    }
+
+    /// Update the endpoint of a peer with the result of a background resolution
+    fn apply_resolved(&mut self, resolved: Resolved) {
+        let Resolved {
+            peer,
+            hostname,
+            result,
+        } = resolved;
+        if !peer.exists(self) {
+            return;
+        }
+        let ap = peer.get_app_mut(self);
+        ap.endpoint_refresh.in_flight = false;
+        // The endpoint was reconfigured in the mean time
+        if ap.hostname.as_ref() != Some(&hostname) {
+            return;
+        }
+
+        let addrs = match result {
+            Ok(addrs) if !addrs.is_empty() => addrs,
+            Ok(_) => {
+                warn!("Endpoint {hostname} of peer {peer:?} resolved to no addresses");
+                return;
+            }
+            Err(e) => {
+                warn!("Could not resolve endpoint {hostname} of peer {peer:?}: {e}");
+                return;
+            }
+        };
+        if !replace_resolved_addresses(ap, addrs) {
+            return;
+        }
+        info!(
+            "Endpoint {hostname} of peer {peer:?} now resolves to {}",
+            ap.initial_endpoint.as_ref().unwrap()
+        );
+    }
+}
+
+/// Replace the addresses of [AppPeer::initial_endpoint]; while the peer is not connected, the
+/// new addresses are also used for peer discovery right away
+///
+/// Returns false if the addresses did not change.
+fn replace_resolved_addresses(ap: &mut AppPeer, addrs: Vec<SocketAddr>) -> bool {
+    let old: HashSet<&SocketAddr> = ap
+        .initial_endpoint
+        .as_ref()
+        .map(|ep| ep.addresses().iter().collect())
+        .unwrap_or_default();
+    if old == addrs.iter().collect::<HashSet<_>>() {
+        return false;
+    }
+
+    ap.initial_endpoint = Some(Endpoint::discovery_from_addresses(addrs));
+    if !matches!(ap.current_endpoint, Some(Endpoint::SocketBoundAddress(_))) {
+        // The new addresses come first, so they are tried first
+        ap.current_endpoint = Endpoint::discovery_from_multiple_sources(
+            ap.initial_endpoint.as_ref(),
+            ap.current_endpoint.as_ref(),
+        );
+    }
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app_server::AppServerTest;
+    use crate::config::Verbosity;
+    use crate::protocol::{SPk, SSk};
+    use rosenpass_cipher_traits::Kem;
+    use rosenpass_ciphers::kem::StaticKem;
+    use rosenpass_util::time::{ManualClock, Timebase};
+    use std::net::Ipv4Addr;
+    use std::ops::DerefMut;
+
+    fn keypair() -> anyhow::Result<(SSk, SPk)> {
+        let (mut sk, mut pk) = (SSk::zero(), SPk::zero());
+        StaticKem::keygen(sk.secret_mut(), pk.deref_mut())?;
+        Ok((sk, pk))
+    }
+
+    /// Wait for the background resolution of `peer` to be applied
+    fn await_resolution(srv: &mut AppServer, peer: AppPeerPtr) {
+        while peer.get_app(srv).endpoint_refresh.in_flight {
+            let resolved = srv.resolved_endpoints.rx.recv().unwrap();
+            srv.apply_resolved(resolved);
+        }
+    }
+
+    #[test]
+    fn endpoint_is_resolved_again() -> anyhow::Result<()> {
+        rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+        let clock = ManualClock::default();
+        let test_helpers = AppServerTest {
+            enable_dos_permanently: false,
+            termination_handler: None,
+            timebase: Some(Timebase::new(clock.clone())),
+        };
+        let localhost = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+        let mut srv = AppServer::new(
+            Some(keypair()?),
+            vec![localhost],
+            Verbosity::Quiet,
+            Some(test_helpers),
+        )?;
+        let peer = srv.add_peer(
+            None,
+            keypair()?.1,
+            None,
+            None,
+            Some("127.0.0.1:9999".into()),
+        )?;
+
+        let config = EndpointRefreshConfig {
+            interval: Some(60.0),
+            after_retransmissions: Some(2),
+        };
+        srv.set_peer_endpoint_refresh(peer, Some(config))?;
+        assert_eq!(srv.endpoint_refresh_timeout(), Some(60.0));
+
+        // Pretend the hostname resolved to another address at startup
+        let stale = SocketAddr::from((Ipv4Addr::LOCALHOST, 1));
+        let ap = peer.get_app_mut(&mut srv);
+        ap.initial_endpoint = Some(Endpoint::discovery_from_addresses(vec![stale]));
+        ap.current_endpoint = Some(Endpoint::discovery_from_addresses(vec![stale]));
+
+        // Not due yet
+        srv.refresh_endpoints();
+        assert!(!peer.get_app(&srv).endpoint_refresh.in_flight);
+
+        // Due after the interval
+        clock.advance(60.0);
+        srv.refresh_endpoints();
+        assert!(peer.get_app(&srv).endpoint_refresh.in_flight);
+        assert_eq!(srv.endpoint_refresh_timeout(), Some(60.0));
+        await_resolution(&mut srv, peer);
+
+        let fresh = SocketAddr::from((Ipv4Addr::LOCALHOST, 9999));
+        let ap = peer.get_app(&srv);
+        assert_eq!(ap.initial_endpoint.as_ref().unwrap().addresses(), &[fresh]);
+        // The new address is tried first; the old one is kept as a fallback
+        assert_eq!(
+            ap.current_endpoint.as_ref().unwrap().addresses(),
+            &[fresh, stale]
+        );
+
+        // Due after two retransmissions without a reply
+        srv.endpoint_refresh_retransmitted(peer);
+        srv.endpoint_refresh_replied(peer);
+        srv.endpoint_refresh_retransmitted(peer);
+        assert!(!peer.get_app(&srv).endpoint_refresh.in_flight);
+        srv.endpoint_refresh_retransmitted(peer);
+        assert!(peer.get_app(&srv).endpoint_refresh.in_flight);
+        await_resolution(&mut srv, peer);
+
+        Ok(())
+    }
+
+    #[test]
+    fn endpoint_is_resolved_in_background() -> anyhow::Result<()> {
+        rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+        let localhost = SocketAddr::from((Ipv4Addr::LOCALHOST, 0));
+        let mut srv = AppServer::new(Some(keypair()?), vec![localhost], Verbosity::Quiet, None)?;
+
+        // Added without an endpoint, as the API does for hostnames
+        let peer = srv.add_peer(None, keypair()?.1, None, None, None)?;
+        peer.get_app_mut(&mut srv).hostname = Some("127.0.0.1:9999".into());
+        srv.resolve_endpoint_in_background(peer)?;
+        assert!(peer.get_app(&srv).initial_endpoint.is_none());
+        await_resolution(&mut srv, peer);
+
+        let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 9999));
+        let ap = peer.get_app(&srv);
+        assert_eq!(ap.initial_endpoint.as_ref().unwrap().addresses(), &[addr]);
+        assert_eq!(ap.current_endpoint.as_ref().unwrap().addresses(), &[addr]);
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+//! Events raised by the [AppServer]
+//!
+//! Noteworthy changes in the state of the server, such as a key being exchanged or a peer
+//! changing its endpoint (see [crate::roaming]), are raised as [AppEvent]s through
+//! [AppServer::emit_event]. Each event is logged and kept in the [EventLog] of the server
+//! ([AppServer::event_log]), where API clients can fetch it through the `FetchEvents` request or
+//! receive it as it happens after sending a `Subscribe` request (with the `experiment_api`
+//! feature).
+//!
+//! The log only keeps the most recent [EVENT_LOG_CAPACITY] events, so a slow reader never holds
+//! up the server; readers are told how many events they missed instead.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::SocketAddr;
+
+use log::{info, warn};
+use rosenpass_util::b64::B64Display;
+
+use crate::app_server::{AppServer, DoSOperation, MAX_B64_PEER_ID_SIZE};
+use crate::protocol::PeerId;
+
+/// Number of events kept in the [EventLog]
+pub const EVENT_LOG_CAPACITY: usize = 1024;
+
+/// An event raised by the [AppServer]; see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppEvent {
+    /// A handshake with the peer completed from a new address, which is now used as its
+    /// endpoint
+    EndpointChanged {
+        /// The peer
+        peer: PeerId,
+        /// The address the previous handshake completed from, if any
+        from: Option<SocketAddr>,
+        /// The new address
+        to: SocketAddr,
+    },
+    /// A handshake with the peer completed from an address its
+    /// [crate::roaming::RoamingPolicy] does not permit; the endpoint was not changed
+    EndpointRejected {
+        /// The peer
+        peer: PeerId,
+        /// The address the last accepted handshake completed from, if any
+        current: Option<SocketAddr>,
+        /// The address that was not accepted
+        rejected: SocketAddr,
+    },
+    /// A new key was exchanged with the peer; see [crate::app_server::KeyOutputReason::Exchanged]
+    KeyExchanged {
+        /// The peer
+        peer: PeerId,
+    },
+    /// The key exchanged with the peer was replaced by a stale key; see
+    /// [crate::app_server::KeyOutputReason::Stale]
+    KeyStale {
+        /// The peer
+        peer: PeerId,
+    },
+    /// The session with the peer expired without a new key being exchanged in time
+    HandshakeFailed {
+        /// The peer
+        peer: PeerId,
+    },
+    /// The server switched between normal operation and DoS mitigation mode
+    DoSModeChanged {
+        /// The new mode
+        to: DoSOperation,
+    },
+    /// The WireGuard PSK broker failed to accept the key exchanged with the peer
+    BrokerError {
+        /// The peer
+        peer: PeerId,
+    },
+}
+
+impl AppEvent {
+    /// The peer the event concerns, if any
+    pub fn peer(&self) -> Option<PeerId> {
+        match self {
+            Self::EndpointChanged { peer, .. }
+            | Self::EndpointRejected { peer, .. }
+            | Self::KeyExchanged { peer }
+            | Self::KeyStale { peer }
+            | Self::HandshakeFailed { peer }
+            | Self::BrokerError { peer } => Some(*peer),
+            Self::DoSModeChanged { .. } => None,
+        }
+    }
+}
+
+/// Formats an optional address for log messages
+struct MaybeAddr(Option<SocketAddr>);
+
+impl fmt::Display for MaybeAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(addr) => write!(f, "{addr}"),
+            None => write!(f, "no address"),
+        }
+    }
+}
+
+impl fmt::Display for AppEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let peer = self.peer().unwrap_or(PeerId::zero());
+        let peer = peer.fmt_b64::<MAX_B64_PEER_ID_SIZE>();
+        match self {
+            Self::EndpointChanged { from, to, .. } => write!(
+                f,
+                "Endpoint of peer {peer} changed from {} to {to}",
+                MaybeAddr(*from)
+            ),
+            Self::EndpointRejected {
+                current, rejected, ..
+            } => write!(
+                f,
+                "Endpoint of peer {peer} kept at {}; {rejected} is not permitted by its roaming policy",
+                MaybeAddr(*current)
+            ),
+            Self::KeyExchanged { .. } => write!(f, "Exchanged key with peer {peer}"),
+            Self::KeyStale { .. } => write!(f, "Erasing outdated key from peer {peer}"),
+            Self::HandshakeFailed { .. } => write!(
+                f,
+                "Session with peer {peer} expired before a new key could be exchanged"
+            ),
+            Self::DoSModeChanged {
+                to: DoSOperation::UnderLoad,
+            } => write!(f, "Server is under load; switching to DoS mitigation mode"),
+            Self::DoSModeChanged {
+                to: DoSOperation::Normal,
+            } => write!(f, "Server load is back to normal; leaving DoS mitigation mode"),
+            Self::BrokerError { .. } => write!(
+                f,
+                "PSK broker failed to accept the key exchanged with peer {peer}"
+            ),
+        }
+    }
+}
+
+/// The most recent events raised by the [AppServer]; see the [module documentation](self)
+///
+/// Events are numbered consecutively, starting at one.
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::events::{AppEvent, EventLog};
+/// use rosenpass::protocol::PeerId;
+///
+/// let mut log = EventLog::new(2);
+/// let event = |port| AppEvent::EndpointChanged {
+///     peer: PeerId::from_slice(&[0u8; 32]),
+///     from: None,
+///     to: ([127, 0, 0, 1], port).into(),
+/// };
+/// for port in 1..=3 {
+///     log.push(event(port));
+/// }
+///
+/// // The first event was dropped to make room for the third one
+/// let (missed, events) = log.since(0);
+/// assert_eq!(missed, 1);
+/// assert_eq!(events.map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![2, 3]);
+///
+/// let (missed, mut events) = log.since(2);
+/// assert_eq!(missed, 0);
+/// assert_eq!(events.next(), Some(&(3, event(3))));
+/// assert_eq!(events.next(), None);
+/// ```
+#[derive(Debug)]
+pub struct EventLog {
+    /// Maximum number of events kept
+    capacity: usize,
+    /// Sequence number of the most recent event; zero if there were none yet
+    last_seq: u64,
+    /// The events kept, along with their sequence numbers
+    events: VecDeque<(u64, AppEvent)>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(EVENT_LOG_CAPACITY)
+    }
+}
+
+impl EventLog {
+    /// Create a log keeping at most `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            last_seq: 0,
+            events: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Add an event, dropping the oldest one if the log is full; returns the sequence number
+    /// of the new event
+    pub fn push(&mut self, event: AppEvent) -> u64 {
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.last_seq += 1;
+        self.events.push_back((self.last_seq, event));
+        self.last_seq
+    }
+
+    /// Sequence number of the most recent event; zero if there were none yet
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// The events with sequence numbers greater than `after`, oldest first
+    ///
+    /// Also returns how many of these events were already dropped from the log.
+    pub fn since(&self, after: u64) -> (u64, impl Iterator<Item = &(u64, AppEvent)>) {
+        let first = self
+            .events
+            .front()
+            .map_or(self.last_seq + 1, |(seq, _)| *seq);
+        let missed = first.saturating_sub(after.saturating_add(1));
+        let skip = after
+            .saturating_sub(first - 1)
+            .min(self.events.len() as u64);
+        (missed, self.events.iter().skip(skip as usize))
+    }
+}
+
+impl AppServer {
+    /// Log an event and add it to [Self::event_log]; see [crate::events]
+    ///
+    /// The log record carries the fields described in [crate::logging]. Events about keys and
+    /// PSK brokers are logged in more detail where they are raised and are not logged again.
+    pub fn emit_event(&mut self, event: AppEvent) {
+        let peer = event.peer().unwrap_or(PeerId::zero());
+        let peer_id = peer.fmt_b64::<MAX_B64_PEER_ID_SIZE>();
+        match &event {
+            AppEvent::EndpointChanged { to, .. } => info!(
+                event = "endpoint_changed",
+                peer_id:% = peer_id,
+                endpoint:% = to;
+                "{event}"
+            ),
+            AppEvent::EndpointRejected { rejected, .. } => warn!(
+                event = "endpoint_rejected",
+                peer_id:% = peer_id,
+                endpoint:% = rejected;
+                "{event}"
+            ),
+            AppEvent::HandshakeFailed { .. } => info!(
+                event = "handshake_failed",
+                peer_id:% = peer_id;
+                "{event}"
+            ),
+            AppEvent::DoSModeChanged { to } => warn!(
+                event = "dos_mode_changed",
+                under_load = *to == DoSOperation::UnderLoad;
+                "{event}"
+            ),
+            AppEvent::KeyExchanged { .. }
+            | AppEvent::KeyStale { .. }
+            | AppEvent::BrokerError { .. } => {}
+        }
+        self.event_log.push(event);
+
+        // Make sure event stream subscribers are served before the next blocking poll;
+        // see [AppServer::try_recv]
+        self.performed_long_poll = false;
+    }
+}
@@ -0,0 +1,413 @@
+//! Running a user supplied command whenever a key is output for a peer
+//!
+//! If [crate::config::RosenpassPeer::exchange_command] is set, the command is run every time
+//! [AppServer::output_key] outputs a key for the peer. The command receives
+//!
+//! - the base64 encoded key on standard input,
+//! - the peer id in the [PEER_ID_ENV] environment variable and
+//! - the [KeyOutputReason] (`exchanged` or `stale`) in [REASON_ENV].
+//!
+//! The commands run on background threads, so they never block the event loop. For each peer,
+//! at most one command runs at a time; if keys are output while the command is still running,
+//! only the most recent key is passed to the next invocation, since it supersedes the older
+//! ones. At most [MAX_CONCURRENT_EXCHANGE_COMMANDS] commands run at a time in total. Commands
+//! taking longer than [EXCHANGE_COMMAND_TIMEOUT] are killed. Failures are logged.
+//!
+//! [AppServer::output_key]: crate::app_server::AppServer::output_key
+
+use std::io::Write;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Context};
+use log::{debug, error, warn};
+use rosenpass_util::b64::B64Display;
+use rosenpass_util::file::StoreValueB64Writer;
+use zeroize::Zeroizing;
+
+use crate::app_server::{KeyOutputReason, MAX_B64_KEY_SIZE, MAX_B64_PEER_ID_SIZE};
+use crate::protocol::{PeerId, SymKey};
+
+/// Environment variable holding the base64 encoded peer id
+pub const PEER_ID_ENV: &str = "ROSENPASS_PEER_ID";
+/// Environment variable holding the [KeyOutputReason]
+pub const REASON_ENV: &str = "ROSENPASS_KEY_OUTPUT_REASON";
+
+/// Time after which an exchange command is killed
+pub const EXCHANGE_COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum number of exchange commands running at the same time, across all peers
+pub const MAX_CONCURRENT_EXCHANGE_COMMANDS: usize = 8;
+/// How often a running exchange command is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Lock a mutex, ignoring poisoning; the protected state stays consistent even if a thread
+/// panicked while holding the lock
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The exchange command configured for one peer; see the [module documentation](self)
+#[derive(Debug)]
+pub struct ExchangeCommand {
+    /// Program and arguments
+    argv: Vec<String>,
+    /// Serializes the invocations for this peer
+    queue: Arc<Mutex<PeerQueue>>,
+}
+
+/// Invocations for a single peer
+#[derive(Debug, Default)]
+struct PeerQueue {
+    /// Whether a thread is currently processing invocations for this peer
+    running: bool,
+    /// The invocation to perform once the running one is done
+    pending: Option<Invocation>,
+}
+
+/// One execution of an [ExchangeCommand]
+struct Invocation {
+    argv: Vec<String>,
+    peer_id: String,
+    reason: KeyOutputReason,
+    /// The base64 encoded key followed by a newline
+    key: Zeroizing<Vec<u8>>,
+}
+
+impl std::fmt::Debug for Invocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Invocation")
+            .field("argv", &self.argv)
+            .field("peer_id", &self.peer_id)
+            .field("reason", &self.reason)
+            .field("key", &"<SECRET>")
+            .finish()
+    }
+}
+
+impl ExchangeCommand {
+    /// Create an exchange command from its program and arguments
+    pub fn new(argv: Vec<String>) -> anyhow::Result<Self> {
+        ensure!(!argv.is_empty(), "The exchange command must not be empty");
+        Ok(Self {
+            argv,
+            queue: Default::default(),
+        })
+    }
+
+    /// Program and arguments
+    pub fn argv(&self) -> &[String] {
+        &self.argv
+    }
+}
+
+/// Limits the number of exchange commands running at the same time; see
+/// [MAX_CONCURRENT_EXCHANGE_COMMANDS]
+#[derive(Debug)]
+struct Slots {
+    max: usize,
+    running: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Slots {
+    fn acquire(&self) {
+        let mut running = lock(&self.running);
+        while *running >= self.max {
+            running = self
+                .released
+                .wait(running)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *running += 1;
+    }
+
+    fn release(&self) {
+        *lock(&self.running) -= 1;
+        self.released.notify_one();
+    }
+}
+
+/// Number of background threads processing invocations; see [ExchangeCommandRunner::wait_idle]
+#[derive(Debug, Default)]
+struct Threads {
+    running: Mutex<usize>,
+    finished: Condvar,
+}
+
+impl Threads {
+    fn start(&self) {
+        *lock(&self.running) += 1;
+    }
+
+    fn finish(&self) {
+        *lock(&self.running) -= 1;
+        self.finished.notify_all();
+    }
+}
+
+/// Runs [ExchangeCommand]s in the background; see the [module documentation](self)
+#[derive(Debug, Clone)]
+pub struct ExchangeCommandRunner {
+    slots: Arc<Slots>,
+    threads: Arc<Threads>,
+    timeout: Duration,
+}
+
+impl Default for ExchangeCommandRunner {
+    fn default() -> Self {
+        Self::new(MAX_CONCURRENT_EXCHANGE_COMMANDS, EXCHANGE_COMMAND_TIMEOUT)
+    }
+}
+
+impl ExchangeCommandRunner {
+    /// Create a runner executing at most `max_concurrent` commands at a time and killing
+    /// commands after `timeout`
+    pub fn new(max_concurrent: usize, timeout: Duration) -> Self {
+        let slots = Arc::new(Slots {
+            max: max_concurrent.max(1),
+            running: Mutex::new(0),
+            released: Condvar::new(),
+        });
+        Self {
+            slots,
+            threads: Default::default(),
+            timeout,
+        }
+    }
+
+    /// Wait until no commands are running or waiting to be run
+    ///
+    /// Waits at most for the timeout of a single command. Returns whether all commands finished.
+    pub fn wait_idle(&self) -> bool {
+        let deadline = Instant::now() + self.timeout;
+        let mut running = lock(&self.threads.running);
+        while *running > 0 {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            running = self
+                .threads
+                .finished
+                .wait_timeout(running, left)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        true
+    }
+
+    /// Run `cmd` for the given key in the background
+    ///
+    /// This returns immediately; failures of the command itself are only logged.
+    pub fn run(
+        &self,
+        cmd: &ExchangeCommand,
+        peer_id: PeerId,
+        reason: KeyOutputReason,
+        key: &SymKey,
+    ) -> anyhow::Result<()> {
+        let mut encoded = Zeroizing::new(Vec::with_capacity(MAX_B64_KEY_SIZE + 1));
+        key.store_b64_writer::<MAX_B64_KEY_SIZE, _>(&mut *encoded)?;
+        encoded.push(b'\n');
+        let inv = Invocation {
+            argv: cmd.argv.clone(),
+            peer_id: peer_id.fmt_b64::<MAX_B64_PEER_ID_SIZE>().to_string(),
+            reason,
+            key: encoded,
+        };
+
+        let mut queue = lock(&cmd.queue);
+        if queue.running {
+            if let Some(prev) = queue.pending.replace(inv) {
+                debug!(
+                    "Exchange command for peer {} superseded by a newer key before it ran",
+                    prev.peer_id
+                );
+            }
+            return Ok(());
+        }
+        queue.running = true;
+        drop(queue);
+
+        let (runner, queue) = (self.clone(), cmd.queue.clone());
+        self.threads.start();
+        let spawned = thread::Builder::new()
+            .name("rp-exchange-cmd".to_owned())
+            .spawn(move || {
+                runner.process(&queue, inv);
+                runner.threads.finish();
+            });
+        if let Err(e) = spawned {
+            self.threads.finish();
+            lock(&cmd.queue).running = false;
+            return Err(e).context("Could not start a thread for the exchange command");
+        }
+        Ok(())
+    }
+
+    /// Background thread performing the invocations for one peer until there are none left
+    fn process(&self, queue: &Mutex<PeerQueue>, first: Invocation) {
+        let mut next = Some(first);
+        while let Some(inv) = next {
+            self.slots.acquire();
+            self.execute(inv);
+            self.slots.release();
+
+            let mut queue = lock(queue);
+            next = queue.pending.take();
+            queue.running = next.is_some();
+        }
+    }
+
+    /// Run a single invocation to completion and log the result
+    fn execute(&self, inv: Invocation) {
+        let program = &inv.argv[0];
+        match self.execute_with_timeout(&inv) {
+            Ok(Some(status)) if status.success() => debug!(
+                "Exchange command {program:?} for peer {} succeeded",
+                inv.peer_id
+            ),
+            Ok(Some(status)) => error!(
+                "Exchange command {program:?} for peer {} failed: {status}",
+                inv.peer_id
+            ),
+            Ok(None) => error!(
+                "Exchange command {program:?} for peer {} did not finish within {:?}; killed it",
+                inv.peer_id, self.timeout
+            ),
+            Err(e) => error!(
+                "Could not run exchange command {program:?} for peer {}: {e:?}",
+                inv.peer_id
+            ),
+        }
+    }
+
+    /// Run a single invocation; returns `None` if it timed out
+    fn execute_with_timeout(&self, inv: &Invocation) -> anyhow::Result<Option<ExitStatus>> {
+        let deadline = Instant::now() + self.timeout;
+        let mut child = Command::new(&inv.argv[0])
+            .args(&inv.argv[1..])
+            .env(PEER_ID_ENV, &inv.peer_id)
+            .env(REASON_ENV, inv.reason.as_str())
+            .stdin(Stdio::piped())
+            // Standard output is reserved for the key exchange notifications
+            .stdout(Stdio::null())
+            .spawn()?;
+
+        // The key is much smaller than the pipe buffer, so this does not block
+        let mut stdin = child
+            .stdin
+            .take()
+            .context("No stdin for the exchange command")?;
+        if let Err(e) = stdin.write_all(&inv.key) {
+            warn!(
+                "Could not pass the key to exchange command {:?} for peer {}: {e}",
+                inv.argv[0], inv.peer_id
+            );
+        }
+        drop(stdin);
+
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(Some(status));
+            }
+            if Instant::now() >= deadline {
+                child.kill()?;
+                child.wait()?;
+                return Ok(None);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    /// Wait for a file written by an exchange command to show up
+    fn await_file(path: &std::path::Path) -> anyhow::Result<String> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Ok(content) = fs::read_to_string(path) {
+                if content.ends_with('\n') {
+                    return Ok(content);
+                }
+            }
+            ensure!(Instant::now() < deadline, "{path:?} was not written");
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    #[test]
+    fn exchange_command_receives_key_and_environment() -> anyhow::Result<()> {
+        rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+        let dir = tempfile::tempdir()?;
+        let out = dir.path().join("out");
+        let script = format!(
+            "key=$(cat); echo \"$key ${PEER_ID_ENV} ${REASON_ENV}\" > {out:?}.tmp; mv {out:?}.tmp {out:?}"
+        );
+        let cmd = ExchangeCommand::new(vec!["sh".into(), "-c".into(), script])?;
+
+        let key = SymKey::random();
+        let peer_id = PeerId::from_slice(&[7; 32]);
+        ExchangeCommandRunner::default().run(&cmd, peer_id, KeyOutputReason::Exchanged, &key)?;
+
+        let mut expected = Vec::new();
+        key.store_b64_writer::<MAX_B64_KEY_SIZE, _>(&mut expected)?;
+        let expected = format!(
+            "{} {} exchanged\n",
+            String::from_utf8(expected)?,
+            peer_id.fmt_b64::<MAX_B64_PEER_ID_SIZE>()
+        );
+        assert_eq!(await_file(&out)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn hanging_exchange_command_is_killed() -> anyhow::Result<()> {
+        rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+        let dir = tempfile::tempdir()?;
+        let out = dir.path().join("out");
+        let runner = ExchangeCommandRunner::new(1, Duration::from_millis(100));
+
+        let hang = ExchangeCommand::new(vec!["sleep".into(), "60".into()])?;
+        let peer_id = PeerId::from_slice(&[1; 32]);
+        runner.run(&hang, peer_id, KeyOutputReason::Stale, &SymKey::random())?;
+
+        // Only one command may run at a time, so this has to wait for the first to be killed
+        let script = format!("echo done > {out:?}.tmp; mv {out:?}.tmp {out:?}");
+        let done = ExchangeCommand::new(vec!["sh".into(), "-c".into(), script])?;
+        let start = Instant::now();
+        runner.run(&done, peer_id, KeyOutputReason::Stale, &SymKey::random())?;
+
+        assert_eq!(await_file(&out)?, "done\n");
+        assert!(start.elapsed() < Duration::from_secs(10));
+        Ok(())
+    }
+
+    #[test]
+    fn wait_idle_waits_for_running_commands() -> anyhow::Result<()> {
+        rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+        let dir = tempfile::tempdir()?;
+        let out = dir.path().join("out");
+        let runner = ExchangeCommandRunner::new(1, Duration::from_secs(10));
+
+        let script = format!("sleep 0.2; echo done > {out:?}");
+        let cmd = ExchangeCommand::new(vec!["sh".into(), "-c".into(), script])?;
+        let peer_id = PeerId::from_slice(&[2; 32]);
+        runner.run(&cmd, peer_id, KeyOutputReason::Stale, &SymKey::random())?;
+
+        assert!(runner.wait_idle());
+        assert_eq!(fs::read_to_string(&out)?, "done\n");
+        Ok(())
+    }
+
+    #[test]
+    fn empty_exchange_command_is_rejected() {
+        assert!(ExchangeCommand::new(vec![]).is_err());
+    }
+}
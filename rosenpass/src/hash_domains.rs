@@ -12,6 +12,7 @@
 //! ```
 //! use rosenpass::{hash_domain, hash_domain_ns};
 //! use rosenpass::hash_domains::protocol;
+//! use rosenpass::protocol::SuiteId;
 //!
 //! // Declaring a custom hash domain
 //! hash_domain_ns!(protocol, custom_domain, "my custom hash domain label");
@@ -26,15 +27,18 @@
 //! hash_domain!(domain_separators, sep1, "1");
 //! hash_domain!(domain_separators, sep2, "2");
 //!
+//! // All hash domains are derived from the protocol suite's protocol identifier
+//! let suite = SuiteId::default();
+//!
 //! // Generating values under hasher1 with both domain separators
-//! let h1 = hasher1()?.mix(b"some data")?.dup();
-//! let h1v1 = h1.mix(&sep1()?)?.mix(b"More data")?.into_value();
-//! let h1v2 = h1.mix(&sep2()?)?.mix(b"More data")?.into_value();
+//! let h1 = hasher1(suite)?.mix(b"some data")?.dup();
+//! let h1v1 = h1.mix(&sep1(suite)?)?.mix(b"More data")?.into_value();
+//! let h1v2 = h1.mix(&sep2(suite)?)?.mix(b"More data")?.into_value();
 //!
 //! // Generating values under hasher2 with both domain separators
-//! let h2 = hasher2()?.mix(b"some data")?.dup();
-//! let h2v1 = h2.mix(&sep1()?)?.mix(b"More data")?.into_value();
-//! let h2v2 = h2.mix(&sep2()?)?.mix(b"More data")?.into_value();
+//! let h2 = hasher2(suite)?.mix(b"some data")?.dup();
+//! let h2v1 = h2.mix(&sep1(suite)?)?.mix(b"More data")?.into_value();
+//! let h2v2 = h2.mix(&sep2(suite)?)?.mix(b"More data")?.into_value();
 //!
 //! // All of the domain separators are now different, random strings
 //! let values = [h1v1, h1v2, h2v1, h2v2];
@@ -50,6 +54,8 @@
 use anyhow::Result;
 use rosenpass_ciphers::hash_domain::HashDomain;
 
+use crate::protocol::SuiteId;
+
 /// Declare a hash function
 ///
 /// # Examples
@@ -62,8 +68,8 @@ use rosenpass_ciphers::hash_domain::HashDomain;
 macro_rules! hash_domain_ns {
     ($(#[$($attrss:tt)*])* $base:ident, $name:ident, $($lbl:expr),+ ) => {
         $(#[$($attrss)*])*
-        pub fn $name() -> ::anyhow::Result<::rosenpass_ciphers::hash_domain::HashDomain> {
-            let t = $base()?;
+        pub fn $name(suite: $crate::protocol::SuiteId) -> ::anyhow::Result<::rosenpass_ciphers::hash_domain::HashDomain> {
+            let t = $base(suite)?;
             $( let t = t.mix($lbl.as_bytes())?; )*
             Ok(t)
         }
@@ -81,8 +87,8 @@ macro_rules! hash_domain_ns {
 macro_rules! hash_domain {
     ($(#[$($attrss:tt)*])* $base:ident, $name:ident, $($lbl:expr),+ ) => {
         $(#[$($attrss)*])*
-        pub fn $name() -> ::anyhow::Result<[u8; ::rosenpass_ciphers::KEY_LEN]> {
-            let t = $base()?;
+        pub fn $name(suite: $crate::protocol::SuiteId) -> ::anyhow::Result<[u8; ::rosenpass_ciphers::KEY_LEN]> {
+            let t = $base(suite)?;
             $( let t = t.mix($lbl.as_bytes())?; )*
             Ok(t.into_value())
         }
@@ -101,8 +107,42 @@ macro_rules! hash_domain {
 /// See the source file for details about how this is used concretely.
 ///
 /// See the [module](self) documentation on how to use the hash domains in general
-pub fn protocol() -> Result<HashDomain> {
-    HashDomain::zero().mix("Rosenpass v1 mceliece460896 Kyber512 ChaChaPoly1305 BLAKE2s".as_bytes())
+///
+/// Each [SuiteId] uses its own protocol string ([crate::protocol::ProtocolSuite::PROTOCOL_ID]),
+/// so all hash domains and thus all messages differ between suites; peers using different
+/// suites can never interoperate by accident.
+///
+/// The suite also selects the keyed hash used for this and all derived hash domains
+/// ([crate::protocol::ProtocolSuite::KEYED_HASH]). Version one of the protocol uses
+/// [rosenpass_ciphers::keyed_hash::hash]; version two uses a proper HMAC
+/// ([rosenpass_ciphers::subtle::hmac_blake2b]) or, in the `-shake256` suites,
+/// [KeyedHashChoice::Shake256](rosenpass_ciphers::keyed_hash::KeyedHashChoice::Shake256).
+///
+/// ```
+/// use rosenpass::hash_domains::{protocol, mac};
+/// use rosenpass::protocol::SuiteId;
+/// use rosenpass_ciphers::keyed_hash::KeyedHashChoice;
+///
+/// for suite in SuiteId::all() {
+///     assert_eq!(protocol(suite)?.keyed_hash(), suite.keyed_hash());
+///     assert_eq!(mac(suite)?.keyed_hash(), suite.keyed_hash());
+/// }
+/// assert!(SuiteId::all().any(|suite| suite.keyed_hash() == KeyedHashChoice::HmacBlake2b));
+/// assert!(SuiteId::all().any(|suite| suite.keyed_hash() == KeyedHashChoice::Shake256));
+///
+/// let domains = SuiteId::all()
+///     .map(|suite| Ok(protocol(suite)?.into_value()))
+///     .collect::<anyhow::Result<Vec<_>>>()?;
+/// for i in 0..domains.len() {
+///     for j in (i+1)..domains.len() {
+///         assert_ne!(domains[i], domains[j]);
+///     }
+/// }
+///
+/// Ok::<(), anyhow::Error>(())
+/// ```
+pub fn protocol(suite: SuiteId) -> Result<HashDomain> {
+    HashDomain::zero_with(suite.keyed_hash()).mix(suite.protocol_id().as_bytes())
 }
 
 hash_domain_ns!(
@@ -313,3 +353,17 @@ hash_domain!(
     ///
     /// See the [module](self) documentation on how to use the hash domains in general.
     _rp, osk, "wireguard psk");
+hash_domain_ns!(
+    /// Hash domain based on [protocol] for deriving the key used to encrypt
+    /// [crate::protocol::CryptoServer] state snapshots written to disk.
+    ///
+    /// The key is derived by mixing the server's static secret key into this hash domain,
+    /// so a snapshot can only be restored by a server using the same key pair.
+    ///
+    /// # Examples
+    ///
+    /// See the source of [crate::protocol::CryptoServer::store_snapshot] and
+    /// [crate::protocol::CryptoServer::restore_snapshot].
+    ///
+    /// See the [module](self) documentation on how to use the hash domains in general.
+    protocol, state_snapshot, "state snapshot");
@@ -5,12 +5,24 @@
 //!   main function quickly hands over to [crate::cli::CliArgs::run] which contains quite a bit
 //!   of our startup logic
 //! - [crate::config] has the code to parse and generate configuration files
+//! - [crate::endpoint_refresh] resolves the hostnames of peer endpoints again, e.g. for dynamic
+//!   DNS
+//! - [crate::events] keeps the events raised by the server, such as endpoint changes
+//! - [crate::exchange_command] runs a user supplied command whenever a key is exchanged
 //! - [crate::hash_domains] lists the different hash function domains used in the Rosenpass
 //!   protocol
+//! - [crate::logging] writes log records as text or JSON
+//! - [crate::metrics] collects metrics about the operation of the daemon and exports them
 //! - [crate::msgs] provides declarations of the Rosenpass protocol network messages and facilities
 //!   to parse those messages through the [::zerocopy] crate
 //! - [crate::protocol] this is where the bulk of our code lives; this module contains the actual
 //!   cryptographic protocol logic
+//! - [crate::roaming] controls which addresses the endpoint of a peer may change to
+//! - [crate::reload] re-reads the configuration file upon SIGHUP
+//! - [crate::sd_notify] notifies systemd about readiness and feeds its watchdog
+//! - [crate::socket_activation] picks up listen sockets passed by systemd
+//! - [crate::socket_options] binds the listen sockets to a network device and sets their
+//!   firewall mark
 //! - crate::api implements the Rosenpass unix socket API, if feature "experiment_api" is active
 
 #[cfg(feature = "experiment_api")]
@@ -18,9 +30,19 @@ pub mod api;
 pub mod app_server;
 pub mod cli;
 pub mod config;
+pub mod endpoint_refresh;
+pub mod events;
+pub mod exchange_command;
 pub mod hash_domains;
+pub mod logging;
+pub mod metrics;
 pub mod msgs;
 pub mod protocol;
+pub mod reload;
+pub mod roaming;
+pub mod sd_notify;
+pub mod socket_activation;
+pub mod socket_options;
 
 /// Error types used in diverse places across Rosenpass
 #[derive(thiserror::Error, Debug)]
@@ -43,4 +65,11 @@ pub enum RosenpassError {
         /// The message type that could not be parsed
         u128,
     ),
+    /// Raised by the `TryFrom<u8>` implementation for [crate::protocol::SuiteId]
+    /// to indicate that a protocol suite is not supported
+    #[error("unsupported protocol suite")]
+    InvalidSuite(
+        /// The suite id that could not be parsed
+        u8,
+    ),
 }
@@ -0,0 +1,615 @@
+//! Log output of the Rosenpass daemon
+//!
+//! Which records are logged is decided by a [LogFilter]: a default log level, optionally
+//! followed by levels for specific modules, e.g. `warn,protocol=debug,app_server=info`. Module
+//! names may be given with or without the `rosenpass::` prefix; as with [env_logger],
+//! `rosenpass=debug` applies to all modules of Rosenpass. The filter is set through
+//! `--log-level`, `--verbose` or `--quiet` on the command line, the `log_level` key of the
+//! configuration file or, if neither is given, the `RUST_LOG` environment variable:
+//!
+//! ```toml
+//! log_level = "warn,app_server=info"
+//! ```
+//!
+//! The legacy `verbosity = "Verbose"` of the configuration file logs records of level `info` and
+//! above, but only if none of these give a filter.
+//!
+//! The filter can be changed while Rosenpass is running: SIGUSR1 makes logging one level more
+//! verbose (see [raise_log_level]), SIGUSR2 restores the configured filter (see
+//! [reset_log_filter]), and the API offers [crate::api::RequestMsgType::SetLogLevel]. Reloading
+//! the configuration file (see [crate::reload]) applies its `log_level` again.
+//!
+//! How the records are written is chosen through the [LogFormat]: free-form text (the default),
+//! or one JSON object per line for log pipelines. The format is set through `--log-format` on
+//! the command line or the `log_format` key of the configuration file:
+//!
+//! ```toml
+//! log_format = "json"
+//! ```
+//!
+//! JSON records always contain these fields:
+//!
+//! - `time` – seconds since the UNIX epoch, as a floating point number
+//! - `level` – one of `error`, `warn`, `info`, `debug` and `trace`
+//! - `target` – the module that produced the record, e.g. `rosenpass::app_server`
+//! - `message` – the free-form text also printed in the text format
+//!
+//! Records about a specific occurrence carry some of the following fields as well:
+//!
+//! - `event` – what happened; one of `key_exchanged`, `key_stale`, `handshake_failed`,
+//!   `endpoint_changed`, `endpoint_rejected`, `message_rejected`, `dos_mode_changed`,
+//!   `exchange_command_failed`, `reload_failed`, `keypair_rotated` and `error`
+//! - `peer_id` – the id of the peer concerned, in base64
+//! - `endpoint` – the address of the peer, e.g. `192.0.2.1:9999`
+//! - `session_id` – the id of our side of the session with the peer, in base64
+//! - `under_load` – whether the server switched to DoS mitigation mode, as a boolean
+//! - `error` – the error followed by its causes, separated by `: ` (see [ErrorChain])
+//!
+//! These field names are stable. They are attached to log records as [log::kv] key-value pairs,
+//! e.g. `info!(event = "key_exchanged", peer_id:% = pid; "...")`; the text format leaves them
+//! out.
+
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context};
+use log::kv::{self, Key, Value, VisitSource, VisitValue};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::app_server::AppServer;
+
+/// The [LogFormat] currently in use; see [set_log_format]
+static LOG_FORMAT: AtomicU8 = AtomicU8::new(LogFormat::Text as u8);
+
+/// The [LogFilter] currently in use; see [log_filter]
+static LOG_FILTER: RwLock<LogFilter> = RwLock::new(LogFilter::off());
+
+/// The [LogFilter] set through [set_log_filter]; restored by [reset_log_filter]
+static CONFIGURED_LOG_FILTER: Mutex<LogFilter> = Mutex::new(LogFilter::off());
+
+/// Whether a [LogFilter] was passed to [init]; see [filter_given_on_startup]
+static FILTER_GIVEN_ON_STARTUP: AtomicBool = AtomicBool::new(false);
+
+/// Prefix of the targets of records logged by Rosenpass itself; may be left out in a [LogFilter]
+const CRATE_PREFIX: &str = "rosenpass::";
+
+/// Formats an error along with its causes, for the `error` field of log records
+///
+/// This is the alternate format of [anyhow::Error], e.g. `outer: cause: root cause`.
+pub struct ErrorChain<'a>(pub &'a anyhow::Error);
+
+impl fmt::Display for ErrorChain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.0)
+    }
+}
+
+/// How log records are written; see the [module documentation](self)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum LogFormat {
+    /// Free-form text, as printed by [env_logger]
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// Change the format of all records logged from now on
+pub fn set_log_format(format: LogFormat) {
+    LOG_FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+/// The format records are currently logged in
+pub fn log_format() -> LogFormat {
+    match LOG_FORMAT.load(Ordering::Relaxed) {
+        x if x == LogFormat::Json as u8 => LogFormat::Json,
+        _ => LogFormat::Text,
+    }
+}
+
+/// Which records to log; see the [module documentation](self)
+///
+/// A filter is written as a comma separated list of directives. Each directive is either a log
+/// level (`off`, `error`, `warn`, `info`, `debug` or `trace`), which applies to all modules
+/// without a directive of their own, or `module=level`. A directive for a module applies to its
+/// submodules as well; the most specific directive wins. Like with [env_logger], modules without
+/// a directive are not logged if there is no default level, and an empty filter logs errors.
+///
+/// # Examples
+///
+/// ```
+/// use log::Level;
+/// use rosenpass::logging::LogFilter;
+///
+/// let filter: LogFilter = "warn,protocol=debug,api=error,api::mio=trace".parse()?;
+/// assert!(filter.enabled("rosenpass::protocol", Level::Debug));
+/// assert!(filter.enabled("rosenpass::protocol::build_crypto_server", Level::Debug));
+/// assert!(!filter.enabled("rosenpass::app_server", Level::Info));
+/// assert!(filter.enabled("rosenpass::app_server", Level::Warn));
+/// assert!(!filter.enabled("rosenpass::api", Level::Warn));
+/// assert!(filter.enabled("rosenpass::api::mio", Level::Trace));
+/// // Module names may carry the crate name, too
+/// assert!(filter.enabled("mio::poll", Level::Warn));
+/// assert_eq!(filter, "warn,rosenpass::protocol=debug,api=error,api::mio=trace".parse()?);
+///
+/// assert_eq!(filter.to_string(), "warn,protocol=debug,api=error,api::mio=trace");
+/// assert!("protocol=loud".parse::<LogFilter>().is_err());
+///
+/// // The crate name on its own covers all modules of Rosenpass
+/// let filter: LogFilter = "rosenpass=debug,protocol=trace".parse()?;
+/// assert!(filter.enabled("rosenpass", Level::Debug));
+/// assert!(filter.enabled("rosenpass::app_server", Level::Debug));
+/// assert!(filter.enabled("rosenpass::protocol", Level::Trace));
+/// assert!(!filter.enabled("rosenpass::app_server", Level::Trace));
+/// assert!(!filter.enabled("mio::poll", Level::Error));
+/// assert_eq!(filter.to_string(), "rosenpass=debug,protocol=trace");
+///
+/// assert_eq!("".parse::<LogFilter>()?, LogFilter::default());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LogFilter {
+    /// Level for modules without a directive of their own; `None` logs nothing
+    default: Option<LevelFilter>,
+    /// Module names along with their levels; modules of Rosenpass without the [CRATE_PREFIX]
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// A filter that logs nothing
+    pub const fn off() -> Self {
+        Self {
+            default: None,
+            modules: Vec::new(),
+        }
+    }
+
+    /// The level for records from the module `target`
+    pub fn level(&self, target: &str) -> LevelFilter {
+        // How much of the target `module` covers, if it applies to the target at all
+        let covered = |module: &str| {
+            let is_within = |target: &str| {
+                target
+                    .strip_prefix(module)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            };
+            if is_within(target) {
+                return Some(module.len());
+            }
+            let local = target.strip_prefix(CRATE_PREFIX)?;
+            is_within(local).then_some(CRATE_PREFIX.len() + module.len())
+        };
+        self.modules
+            .iter()
+            .filter_map(|(module, level)| Some((covered(module)?, *level)))
+            .max_by_key(|(covered, _)| *covered)
+            .map(|(_, level)| level)
+            .or(self.default)
+            .unwrap_or(LevelFilter::Off)
+    }
+
+    /// Whether records from the module `target` with the given level are logged
+    pub fn enabled(&self, target: &str, level: log::Level) -> bool {
+        level <= self.level(target)
+    }
+
+    /// The most verbose level of any module
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .chain(self.default)
+            .max()
+            .unwrap_or(LevelFilter::Off)
+    }
+
+    /// Make logging one level more verbose for all modules, e.g. from `warn,api=info` to
+    /// `info,api=debug`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::logging::LogFilter;
+    ///
+    /// let mut filter: LogFilter = "api=info".parse()?;
+    /// filter.raise();
+    /// assert_eq!(filter.to_string(), "error,api=debug");
+    /// filter.raise();
+    /// filter.raise();
+    /// assert_eq!(filter.to_string(), "info,api=trace");
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn raise(&mut self) {
+        let raise =
+            |level: LevelFilter| LevelFilter::iter().nth(level as usize + 1).unwrap_or(level);
+        self.default = Some(raise(self.default.unwrap_or(LevelFilter::Off)));
+        for (_, level) in self.modules.iter_mut() {
+            *level = raise(*level);
+        }
+    }
+}
+
+impl Default for LogFilter {
+    /// Log errors only
+    fn default() -> Self {
+        LevelFilter::Error.into()
+    }
+}
+
+impl From<LevelFilter> for LogFilter {
+    /// A filter applying the same level to all modules
+    fn from(level: LevelFilter) -> Self {
+        Self {
+            default: Some(level),
+            modules: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let default = self
+            .default
+            .map(|level| level.as_str().to_ascii_lowercase());
+        let modules = self
+            .modules
+            .iter()
+            .map(|(module, level)| format!("{module}={}", level.as_str().to_ascii_lowercase()));
+        let directives = default.into_iter().chain(modules).collect::<Vec<_>>();
+        match directives.is_empty() {
+            true => f.write_str("off"),
+            false => f.write_str(&directives.join(",")),
+        }
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let parse_level = |level: &str| {
+            LevelFilter::from_str(level.trim())
+                .with_context(|| format!("Invalid log level {level:?} in log filter {s:?}"))
+        };
+        let mut filter = Self::off();
+        for directive in s.split(',').filter(|d| !d.trim().is_empty()) {
+            let Some((module, level)) = directive.split_once('=') else {
+                filter.default = Some(parse_level(directive)?);
+                continue;
+            };
+            let module = module.trim();
+            let module = module.strip_prefix(CRATE_PREFIX).unwrap_or(module);
+            if module.is_empty() {
+                bail!("Missing module name in log filter {s:?}");
+            }
+            let level = parse_level(level)?;
+            match filter.modules.iter_mut().find(|(m, _)| m == module) {
+                Some((_, l)) => *l = level,
+                None => filter.modules.push((module.to_owned(), level)),
+            }
+        }
+        if filter == Self::off() {
+            return Ok(Self::default());
+        }
+        Ok(filter)
+    }
+}
+
+impl TryFrom<String> for LogFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> anyhow::Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<LogFilter> for String {
+    fn from(filter: LogFilter) -> Self {
+        filter.to_string()
+    }
+}
+
+/// Set the [LogFilter] for all records logged from now on
+///
+/// This is the filter restored by [reset_log_filter].
+pub fn set_log_filter(filter: LogFilter) {
+    *CONFIGURED_LOG_FILTER.lock().unwrap() = filter.clone();
+    override_log_filter(filter);
+}
+
+/// Set the [LogFilter] until [reset_log_filter] is called
+pub fn override_log_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    *LOG_FILTER.write().unwrap() = filter;
+}
+
+/// Make logging one level more verbose until [reset_log_filter] is called; see
+/// [LogFilter::raise]
+///
+/// Returns the new filter.
+pub fn raise_log_level() -> LogFilter {
+    let mut filter = log_filter();
+    filter.raise();
+    override_log_filter(filter.clone());
+    filter
+}
+
+/// Restore the [LogFilter] set through [set_log_filter]
+///
+/// Returns the restored filter.
+pub fn reset_log_filter() -> LogFilter {
+    let filter = CONFIGURED_LOG_FILTER.lock().unwrap().clone();
+    override_log_filter(filter.clone());
+    filter
+}
+
+/// The [LogFilter] currently in use
+pub fn log_filter() -> LogFilter {
+    LOG_FILTER.read().unwrap().clone()
+}
+
+/// Whether a [LogFilter] was given on the command line or through `RUST_LOG`, i.e. passed to
+/// [init]
+///
+/// If so, the legacy [crate::config::Verbosity] of the configuration file is ignored.
+pub fn filter_given_on_startup() -> bool {
+    FILTER_GIVEN_ON_STARTUP.load(Ordering::Relaxed)
+}
+
+/// The [LogFilter] given through the `RUST_LOG` environment variable, if set
+pub fn env_log_filter() -> anyhow::Result<Option<LogFilter>> {
+    match std::env::var("RUST_LOG") {
+        Ok(filter) => Ok(Some(filter.parse().context("Invalid RUST_LOG")?)),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(e).context("Invalid RUST_LOG"),
+    }
+}
+
+/// Automatically register signal handlers for SIGUSR1 and SIGUSR2, which change the
+/// [LogFilter] at runtime; see [AppServer::enable_log_level_signals]
+///
+/// The signal handlers are not removed when this struct goes out of scope.
+#[derive(Debug)]
+pub struct LogLevelSignals {
+    raise: Arc<AtomicBool>,
+    reset: Arc<AtomicBool>,
+}
+
+impl LogLevelSignals {
+    /// Register signal handlers watching for SIGUSR1 and SIGUSR2
+    pub fn new() -> anyhow::Result<Self> {
+        let raise = Arc::new(AtomicBool::new(false));
+        let reset = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(signal_hook::consts::SIGUSR1, Arc::clone(&raise))?;
+        signal_hook::flag::register(signal_hook::consts::SIGUSR2, Arc::clone(&reset))?;
+        Ok(Self { raise, reset })
+    }
+
+    /// Check whether SIGUSR1 or SIGUSR2 was received since the last call to [Self::handle]
+    pub fn value(&self) -> bool {
+        self.raise.load(Ordering::Relaxed) || self.reset.load(Ordering::Relaxed)
+    }
+
+    /// Apply the changes requested through the signals received since the last call
+    ///
+    /// SIGUSR2 is handled first, so sending both restores the configured filter and raises the
+    /// level from there.
+    pub fn handle(&self) {
+        if self.reset.swap(false, Ordering::Relaxed) {
+            let filter = reset_log_filter();
+            log::info!(
+                "Restored the configured log filter {:?}",
+                filter.to_string()
+            );
+        }
+        if self.raise.swap(false, Ordering::Relaxed) {
+            let filter = raise_log_level();
+            log::info!(
+                "Raised the log level; the log filter is now {:?}",
+                filter.to_string()
+            );
+        }
+    }
+}
+
+impl AppServer {
+    /// Change the [LogFilter] whenever SIGUSR1 or SIGUSR2 is received: SIGUSR1 makes logging one
+    /// level more verbose (see [raise_log_level]), SIGUSR2 restores the configured filter (see
+    /// [reset_log_filter])
+    pub fn enable_log_level_signals(&mut self) -> anyhow::Result<()> {
+        if self.log_level_signals.is_none() {
+            self.log_level_signals = Some(LogLevelSignals::new()?);
+        }
+        Ok(())
+    }
+
+    /// Check whether a change of the log filter was requested through SIGUSR1 or SIGUSR2
+    pub fn log_level_change_requested(&self) -> bool {
+        self.log_level_signals.as_ref().is_some_and(|s| s.value())
+    }
+
+    /// Apply the changes of the log filter requested through SIGUSR1 or SIGUSR2
+    pub(crate) fn handle_log_level_signals(&self) {
+        if let Some(signals) = &self.log_level_signals {
+            signals.handle();
+        }
+    }
+}
+
+/// Install the logger
+///
+/// `filter` is the filter given on the command line or through `RUST_LOG`, if any; errors are
+/// logged otherwise. The text format honors the `RUST_LOG_STYLE` environment variable. Fails if
+/// a logger was installed already.
+pub fn init(filter: Option<LogFilter>, format: LogFormat) -> anyhow::Result<()> {
+    FILTER_GIVEN_ON_STARTUP.store(filter.is_some(), Ordering::Relaxed);
+    set_log_format(format);
+    set_log_filter(filter.unwrap_or_default());
+
+    let mut builder = env_logger::Builder::new();
+    if let Ok(style) = std::env::var("RUST_LOG_STYLE") {
+        builder.parse_write_style(&style);
+    }
+    // Records are filtered by the [LogFilter]; env_logger just writes them
+    builder.filter_level(LevelFilter::Trace);
+    log::set_boxed_logger(Box::new(Logger {
+        text: builder.build(),
+    }))?;
+    Ok(())
+}
+
+/// Writes records passing the current [LogFilter] in the current [LogFormat]
+struct Logger {
+    /// Writes records in the text format
+    text: env_logger::Logger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        LOG_FILTER
+            .read()
+            .unwrap()
+            .enabled(metadata.target(), metadata.level())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match log_format() {
+            LogFormat::Text => self.text.log(record),
+            LogFormat::Json => {
+                // There is no one to report failures of the logger to
+                let _ = write_json_record(&mut std::io::stderr().lock(), record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        self.text.flush()
+    }
+}
+
+/// Write a record as one line of JSON; see the [module documentation](self) for the fields
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::logging::{write_json_record, ErrorChain};
+///
+/// let error = anyhow::anyhow!("connection refused").context("Could not send message");
+/// let error = ErrorChain(&error);
+/// let fields: &[(&str, log::kv::Value)] = &[
+///     ("event", "message_rejected".into()),
+///     ("endpoint", log::kv::Value::from_display(&"192.0.2.1:9999")),
+///     ("session_id", log::kv::Value::null()),
+///     ("error", log::kv::Value::from_display(&error)),
+/// ];
+/// let mut out = Vec::new();
+/// write_json_record(
+///     &mut out,
+///     &log::Record::builder()
+///         .level(log::Level::Warn)
+///         .target("rosenpass::app_server")
+///         .args(format_args!("Could not process message"))
+///         .key_values(&fields)
+///         .build(),
+/// )?;
+///
+/// let line: serde_json::Value = serde_json::from_slice(&out)?;
+/// assert!(out.ends_with(b"}\n"));
+/// assert_eq!(line["level"], "warn");
+/// assert_eq!(line["target"], "rosenpass::app_server");
+/// assert_eq!(line["message"], "Could not process message");
+/// assert_eq!(line["event"], "message_rejected");
+/// assert_eq!(line["endpoint"], "192.0.2.1:9999");
+/// assert_eq!(line["error"], "Could not send message: connection refused");
+/// // Absent values are left out
+/// assert!(line.get("session_id").is_none());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn write_json_record<W: Write>(out: &mut W, record: &Record) -> anyhow::Result<()> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+
+    let mut line = Map::new();
+    line.insert("time".to_owned(), time.into());
+    line.insert(
+        "level".to_owned(),
+        record.level().as_str().to_ascii_lowercase().into(),
+    );
+    line.insert("target".to_owned(), record.target().into());
+    line.insert("message".to_owned(), record.args().to_string().into());
+    record.key_values().visit(&mut JsonFields(&mut line))?;
+
+    let mut buf = serde_json::to_vec(&line)?;
+    buf.push(b'\n');
+    out.write_all(&buf)?;
+    Ok(())
+}
+
+/// Adds the key-value pairs of a record to a JSON object
+struct JsonFields<'a>(&'a mut Map<String, JsonValue>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let mut json = ToJson(None);
+        value.visit(&mut json)?;
+        // Absent optional values are left out
+        if let Some(json) = json.0 {
+            self.0.insert(key.as_str().to_owned(), json);
+        }
+        Ok(())
+    }
+}
+
+/// Converts a [Value] to JSON
+struct ToJson(Option<JsonValue>);
+
+impl VisitValue<'_> for ToJson {
+    fn visit_any(&mut self, value: Value) -> Result<(), kv::Error> {
+        self.0 = Some(value.to_string().into());
+        Ok(())
+    }
+
+    fn visit_null(&mut self) -> Result<(), kv::Error> {
+        self.0 = None;
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+
+    fn visit_f64(&mut self, value: f64) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+
+    fn visit_str(&mut self, value: &str) -> Result<(), kv::Error> {
+        self.0 = Some(value.into());
+        Ok(())
+    }
+}
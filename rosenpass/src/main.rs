@@ -5,6 +5,7 @@ use clap::Parser;
 use clap_mangen::roff::{roman, Roff};
 use log::error;
 use rosenpass::cli::CliArgs;
+use rosenpass::logging::{self, ErrorChain};
 use std::process::exit;
 
 /// Printing custom man sections when generating the man page
@@ -61,27 +62,25 @@ pub fn main() {
 
     // init logging
     {
-        let mut log_builder = env_logger::Builder::from_default_env(); // sets log level filter from environment (or defaults)
-        if let Some(level) = args.get_log_level() {
-            log::debug!("setting log level to {:?} (set via CLI parameter)", level);
-            log_builder.filter_level(level); // set log level filter from CLI args if available
+        // the filter may still be changed by the configuration file
+        let (filter, env_err) = match (args.get_log_level(), logging::env_log_filter()) {
+            (Some(filter), _) => (Some(filter), None),
+            (None, Ok(filter)) => (filter, None),
+            (None, Err(e)) => (None, Some(e)),
+        };
+        // the format may still be changed by the configuration file
+        logging::init(filter, args.get_log_format().unwrap_or_default())
+            .expect("logger is only initialized once");
+        if let Some(e) = env_err {
+            error!("Ignoring the log filter given through the environment: {e:#}");
         }
-        log_builder.init();
-
-        // // check the effectiveness of the log level filter with the following lines:
-        // use log::{debug, error, info, trace, warn};
-        // trace!("trace dummy");
-        // debug!("debug dummy");
-        // info!("info dummy");
-        // warn!("warn dummy");
-        // error!("error dummy");
     }
 
     let broker_interface = args.get_broker_interface();
     match args.run(broker_interface, None) {
         Ok(_) => {}
         Err(e) => {
-            error!("{e:?}");
+            error!(event = "error", error:% = ErrorChain(&e); "{e:?}");
             exit(1);
         }
     }
@@ -0,0 +1,550 @@
+//! Metrics exporter for the Rosenpass daemon
+//!
+//! [Metrics] collects counters about the operation of the [AppServer]: handshakes per peer,
+//! cookie replies, dropped messages, WireGuard PSK broker performance and transitions between
+//! normal operation and DoS mitigation mode (see [DoSOperation]).
+//!
+//! The metrics can be served in the
+//! [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/)
+//! on a local TCP or unix socket; see [MetricsConfig].
+//!
+//! # Examples
+//!
+//! ```
+//! use rosenpass::app_server::DoSOperation;
+//! use rosenpass::metrics::{DropReason, Metrics};
+//! use rosenpass::protocol::PeerId;
+//! use rosenpass_util::time::{ManualClock, Timebase};
+//!
+//! let clock = ManualClock::default();
+//! let metrics = Metrics::new(Timebase::new(clock.clone()));
+//!
+//! let peer = PeerId::from_slice(&[0u8; 32]);
+//! metrics.handshake_started(peer);
+//! metrics.handshake_completed(peer);
+//! metrics.message_dropped(DropReason::Malformed);
+//! metrics.dos_transition(DoSOperation::UnderLoad);
+//! clock.advance(5.0);
+//!
+//! let text = metrics.render();
+//! let peer_label = r#"peer="AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=""#;
+//! assert!(text.contains(&format!("rosenpass_handshakes_started_total{{{peer_label}}} 1\n")));
+//! assert!(text.contains(&format!("rosenpass_seconds_since_last_key{{{peer_label}}} 5\n")));
+//! assert!(text.contains("rosenpass_messages_dropped_total{reason=\"malformed\"} 1\n"));
+//! assert!(text.contains("rosenpass_under_load 1\n"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use anyhow::{ensure, Context};
+use rosenpass_util::b64::B64Display;
+use rosenpass_util::time::Timebase;
+use serde::{Deserialize, Serialize};
+
+use crate::app_server::{AppServer, DoSOperation, MAX_B64_PEER_ID_SIZE};
+use crate::protocol::{PeerId, Timing};
+
+/// Time after which connections to the metrics endpoint are dropped if the client does not send
+/// a complete request
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum size of a request to the metrics endpoint
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Configuration of the metrics exporter
+///
+/// At least one of the listen options must be set.
+///
+/// ```toml
+/// [metrics]
+/// listen_addr = "127.0.0.1:9587"
+/// listen_path = "/run/rosenpass/metrics.sock"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+pub struct MetricsConfig {
+    /// Local TCP address to serve metrics on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen_addr: Option<SocketAddr>,
+
+    /// Where in the file-system to create a unix socket to serve metrics on
+    ///
+    /// A stale socket left behind at this path is replaced; the socket is removed on exit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub listen_path: Option<PathBuf>,
+}
+
+impl MetricsConfig {
+    /// Check that at least one listen option is set
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.listen_addr.is_some() || self.listen_path.is_some(),
+            "metrics require either listen_addr or listen_path to be set"
+        );
+        if let Some(addr) = self.listen_addr.filter(|a| !a.ip().is_loopback()) {
+            log::warn!(
+                "Metrics are served on {addr}, which is not a loopback address; \
+                 metrics reveal the peers' ids and activity."
+            );
+        }
+        Ok(())
+    }
+
+    /// Enable metrics collection in the [AppServer] (see [AppServer::enable_metrics]) and serve
+    /// them on the configured sockets
+    pub fn apply_to_app_server(&self, srv: &mut AppServer) -> anyhow::Result<()> {
+        self.validate()?;
+        let metrics = srv.enable_metrics();
+
+        if let Some(addr) = self.listen_addr {
+            let listener = TcpListener::bind(addr)
+                .with_context(|| format!("Could not bind metrics socket {addr}"))?;
+            metrics.serve(MetricsListener::Tcp(listener))?;
+        }
+
+        if let Some(path) = self.listen_path.as_ref() {
+            let listener = bind_unix(path)
+                .with_context(|| format!("Could not bind metrics socket {path:?}"))?;
+            srv.metrics_socket = Some(SocketPathGuard(path.clone()));
+            metrics.serve(MetricsListener::Unix(listener))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Bind a unix socket at `path`, replacing a stale socket left behind by a previous process
+///
+/// Fails if another process is still listening on the socket.
+fn bind_unix(path: &Path) -> anyhow::Result<UnixListener> {
+    let is_socket = std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket());
+    if is_socket {
+        ensure!(
+            UnixStream::connect(path).is_err(),
+            "Another process is listening on {path:?}"
+        );
+        std::fs::remove_file(path)?;
+    }
+    Ok(UnixListener::bind(path)?)
+}
+
+/// Removes the unix socket the metrics are served on when dropped; see
+/// [MetricsConfig::listen_path] and [AppServer::metrics_socket]
+#[derive(Debug)]
+pub struct SocketPathGuard(PathBuf);
+
+impl Drop for SocketPathGuard {
+    fn drop(&mut self) {
+        match std::fs::remove_file(&self.0) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!("Could not remove metrics socket {:?}: {e}", self.0),
+        }
+    }
+}
+
+/// A socket the metrics can be served on; see [Metrics::serve]
+#[derive(Debug)]
+pub enum MetricsListener {
+    /// A TCP socket
+    Tcp(TcpListener),
+    /// A unix socket
+    Unix(UnixListener),
+}
+
+/// Why a network message was dropped without being processed successfully
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DropReason {
+    /// The message was received before the keypair was configured
+    CryptoServerMissing,
+    /// The message could not be parsed
+    Malformed,
+    /// The message was rejected by DoS mitigation
+    UnderLoad,
+    /// The message was rejected by the protocol, e.g. because its seal is broken,
+    /// it refers to an unknown session or it has been replayed
+    Rejected,
+}
+
+impl DropReason {
+    /// The label used for this reason in the exported metrics
+    pub fn label(&self) -> &'static str {
+        match self {
+            DropReason::CryptoServerMissing => "crypto_server_missing",
+            DropReason::Malformed => "malformed",
+            DropReason::UnderLoad => "under_load",
+            DropReason::Rejected => "rejected",
+        }
+    }
+
+    /// Determine the reason from the error returned by
+    /// [crate::protocol::CryptoServer::handle_msg]
+    pub fn classify(err: &anyhow::Error, under_load: DoSOperation) -> Self {
+        match err.downcast_ref::<crate::RosenpassError>() {
+            Some(_) => DropReason::Malformed,
+            None if under_load == DoSOperation::UnderLoad => DropReason::UnderLoad,
+            None => DropReason::Rejected,
+        }
+    }
+}
+
+/// Metrics kept for each peer
+#[derive(Debug, Default, Clone)]
+struct PeerMetrics {
+    handshakes_started: u64,
+    handshakes_completed: u64,
+    handshakes_failed: u64,
+    last_key: Option<Timing>,
+}
+
+/// Metrics shared by all peers
+#[derive(Debug, Default)]
+struct Registry {
+    peers: BTreeMap<PeerId, PeerMetrics>,
+    cookie_replies_sent: u64,
+    messages_dropped: BTreeMap<DropReason, u64>,
+    set_psk_seconds: f64,
+    set_psk_count: u64,
+    set_psk_failures: u64,
+    to_under_load: u64,
+    to_normal: u64,
+    under_load: bool,
+}
+
+/// Collects the metrics of the Rosenpass daemon; see the [module](self) documentation
+///
+/// The counters are protected by a mutex, so the metrics can be rendered from another thread
+/// (see [Self::serve]).
+#[derive(Debug)]
+pub struct Metrics {
+    timebase: Timebase,
+    registry: Mutex<Registry>,
+}
+
+impl Metrics {
+    /// Create a new, empty set of metrics
+    ///
+    /// The `timebase` is used to determine the time since the last key exchange.
+    pub fn new(timebase: Timebase) -> Self {
+        Self {
+            timebase,
+            registry: Mutex::default(),
+        }
+    }
+
+    fn registry(&self) -> MutexGuard<'_, Registry> {
+        self.registry.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn peer<R, F: FnOnce(&mut PeerMetrics) -> R>(&self, peer: PeerId, f: F) -> R {
+        f(self.registry().peers.entry(peer).or_default())
+    }
+
+    /// We sent an [crate::msgs::InitHello] to the peer
+    pub fn handshake_started(&self, peer: PeerId) {
+        self.peer(peer, |p| p.handshakes_started += 1);
+    }
+
+    /// A key was exchanged with the peer
+    pub fn handshake_completed(&self, peer: PeerId) {
+        let now = self.timebase.now();
+        self.peer(peer, |p| {
+            p.handshakes_completed += 1;
+            p.last_key = Some(now);
+        });
+    }
+
+    /// No key could be exchanged with the peer before the previous key expired
+    pub fn handshake_failed(&self, peer: PeerId) {
+        self.peer(peer, |p| p.handshakes_failed += 1);
+    }
+
+    /// Forget the metrics kept for a peer that was removed
+    pub fn remove_peer(&self, peer: PeerId) {
+        self.registry().peers.remove(&peer);
+    }
+
+    /// We replied with a [crate::msgs::CookieReply] because we are under load
+    pub fn cookie_reply_sent(&self) {
+        self.registry().cookie_replies_sent += 1;
+    }
+
+    /// A network message was dropped
+    pub fn message_dropped(&self, reason: DropReason) {
+        *self.registry().messages_dropped.entry(reason).or_default() += 1;
+    }
+
+    /// A key was handed to the WireGuard PSK broker, taking `duration`
+    pub fn set_psk_done(&self, duration: Duration, success: bool) {
+        let mut reg = self.registry();
+        reg.set_psk_seconds += duration.as_secs_f64();
+        reg.set_psk_count += 1;
+        if !success {
+            reg.set_psk_failures += 1;
+        }
+    }
+
+    /// The [AppServer] switched to the given [DoSOperation] mode
+    pub fn dos_transition(&self, to: DoSOperation) {
+        let mut reg = self.registry();
+        match to {
+            DoSOperation::UnderLoad => reg.to_under_load += 1,
+            DoSOperation::Normal => reg.to_normal += 1,
+        }
+        reg.under_load = to == DoSOperation::UnderLoad;
+    }
+
+    /// Render the metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let now = self.timebase.now();
+        let reg = self.registry();
+        let mut out = String::new();
+
+        macro_rules! header {
+            ($name:literal, $ty:literal, $help:literal) => {
+                let _ = writeln!(out, concat!("# HELP ", $name, " ", $help));
+                let _ = writeln!(out, concat!("# TYPE ", $name, " ", $ty));
+            };
+        }
+
+        macro_rules! per_peer {
+            ($name:literal, $ty:literal, $help:literal, $value:expr) => {
+                header!($name, $ty, $help);
+                for (peer, p) in reg.peers.iter() {
+                    if let Some(value) = ($value)(p) {
+                        let peer = peer.fmt_b64::<MAX_B64_PEER_ID_SIZE>();
+                        let _ = writeln!(out, concat!($name, "{{peer=\"{}\"}} {}"), peer, value);
+                    }
+                }
+            };
+        }
+
+        per_peer!(
+            "rosenpass_handshakes_started_total",
+            "counter",
+            "Handshakes initiated with the peer",
+            |p: &PeerMetrics| Some(p.handshakes_started)
+        );
+        per_peer!(
+            "rosenpass_handshakes_completed_total",
+            "counter",
+            "Keys successfully exchanged with the peer",
+            |p: &PeerMetrics| Some(p.handshakes_completed)
+        );
+        per_peer!(
+            "rosenpass_handshakes_failed_total",
+            "counter",
+            "Times the key of the peer expired without a new key being exchanged",
+            |p: &PeerMetrics| Some(p.handshakes_failed)
+        );
+        per_peer!(
+            "rosenpass_seconds_since_last_key",
+            "gauge",
+            "Seconds since the last key was exchanged with the peer",
+            |p: &PeerMetrics| p.last_key.map(|t| now - t)
+        );
+
+        header!(
+            "rosenpass_cookie_replies_sent_total",
+            "counter",
+            "Cookie replies sent because the server was under load"
+        );
+        let _ = writeln!(
+            out,
+            "rosenpass_cookie_replies_sent_total {}",
+            reg.cookie_replies_sent
+        );
+
+        header!(
+            "rosenpass_messages_dropped_total",
+            "counter",
+            "Network messages that were dropped, by reason"
+        );
+        for (reason, count) in reg.messages_dropped.iter() {
+            let _ = writeln!(
+                out,
+                "rosenpass_messages_dropped_total{{reason=\"{}\"}} {count}",
+                reason.label()
+            );
+        }
+
+        header!(
+            "rosenpass_broker_set_psk_duration_seconds",
+            "summary",
+            "Time spent handing keys to the WireGuard PSK broker"
+        );
+        let _ = writeln!(
+            out,
+            "rosenpass_broker_set_psk_duration_seconds_sum {}",
+            reg.set_psk_seconds
+        );
+        let _ = writeln!(
+            out,
+            "rosenpass_broker_set_psk_duration_seconds_count {}",
+            reg.set_psk_count
+        );
+
+        header!(
+            "rosenpass_broker_set_psk_failures_total",
+            "counter",
+            "Keys the WireGuard PSK broker failed to accept"
+        );
+        let _ = writeln!(
+            out,
+            "rosenpass_broker_set_psk_failures_total {}",
+            reg.set_psk_failures
+        );
+
+        header!(
+            "rosenpass_dos_mode_transitions_total",
+            "counter",
+            "Transitions between normal operation and DoS mitigation"
+        );
+        let _ = writeln!(
+            out,
+            "rosenpass_dos_mode_transitions_total{{to=\"under_load\"}} {}",
+            reg.to_under_load
+        );
+        let _ = writeln!(
+            out,
+            "rosenpass_dos_mode_transitions_total{{to=\"normal\"}} {}",
+            reg.to_normal
+        );
+
+        header!(
+            "rosenpass_under_load",
+            "gauge",
+            "Whether DoS mitigation is currently active"
+        );
+        let _ = writeln!(out, "rosenpass_under_load {}", reg.under_load as u8);
+
+        out
+    }
+
+    /// Serve the metrics over HTTP on the given socket from a background thread
+    ///
+    /// Every request is answered with the output of [Self::render], regardless of its path.
+    pub fn serve(self: &Arc<Self>, listener: MetricsListener) -> anyhow::Result<()> {
+        let metrics = Arc::clone(self);
+        std::thread::Builder::new()
+            .name("rosenpass-metrics".to_string())
+            .spawn(move || loop {
+                let res = match &listener {
+                    MetricsListener::Tcp(l) => l.accept().and_then(|(mut conn, _)| {
+                        conn.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+                        conn.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+                        metrics.respond(&mut conn)
+                    }),
+                    MetricsListener::Unix(l) => l.accept().and_then(|(mut conn, _)| {
+                        conn.set_read_timeout(Some(CONNECTION_TIMEOUT))?;
+                        conn.set_write_timeout(Some(CONNECTION_TIMEOUT))?;
+                        metrics.respond(&mut conn)
+                    }),
+                };
+                if let Err(e) = res {
+                    log::debug!("Error serving metrics: {e:?}");
+                }
+            })?;
+        Ok(())
+    }
+
+    /// Read a HTTP request from `conn` and respond with the rendered metrics
+    fn respond<C: Read + Write>(&self, conn: &mut C) -> io::Result<()> {
+        let mut req = [0u8; MAX_REQUEST_SIZE];
+        let mut len = 0;
+        while !req[..len].windows(4).any(|w| w == b"\r\n\r\n") && len < req.len() {
+            match conn.read(&mut req[len..])? {
+                0 => break,
+                n => len += n,
+            }
+        }
+
+        let body = self.render();
+        write!(
+            conn,
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        conn.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rosenpass_util::time::ManualClock;
+    use std::net::{Ipv4Addr, TcpStream};
+
+    #[test]
+    fn metrics_are_served_over_http() -> anyhow::Result<()> {
+        let metrics = Arc::new(Metrics::new(Timebase::new(ManualClock::default())));
+        metrics.cookie_reply_sent();
+        metrics.set_psk_done(Duration::from_millis(250), false);
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let addr = listener.local_addr()?;
+        metrics.serve(MetricsListener::Tcp(listener))?;
+
+        let mut conn = TcpStream::connect(addr)?;
+        conn.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+        let mut resp = String::new();
+        conn.read_to_string(&mut resp)?;
+
+        let (head, body) = resp.split_once("\r\n\r\n").context("no header")?;
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains(&format!("Content-Length: {}", body.len())));
+        assert!(body.contains("rosenpass_cookie_replies_sent_total 1\n"));
+        assert!(body.contains("rosenpass_broker_set_psk_duration_seconds_sum 0.25\n"));
+        assert!(body.contains("rosenpass_broker_set_psk_duration_seconds_count 1\n"));
+        assert!(body.contains("rosenpass_broker_set_psk_failures_total 1\n"));
+        assert!(body.contains("rosenpass_under_load 0\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn stale_unix_socket_is_replaced() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("metrics.sock");
+
+        // Left behind by a previous process that was killed
+        drop(UnixListener::bind(&path)?);
+        let listener = bind_unix(&path)?;
+
+        // A socket in use is not replaced
+        assert!(bind_unix(&path).is_err());
+
+        drop(listener);
+        drop(SocketPathGuard(path.clone()));
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn removed_peers_are_not_exported() {
+        let metrics = Metrics::new(Timebase::new(ManualClock::default()));
+        let (a, b) = (PeerId::from_slice(&[1; 32]), PeerId::from_slice(&[2; 32]));
+        metrics.handshake_failed(a);
+        metrics.handshake_failed(b);
+        metrics.remove_peer(a);
+
+        let text = metrics.render();
+        let label = |p: PeerId| format!("peer=\"{}\"", p.fmt_b64::<MAX_B64_PEER_ID_SIZE>());
+        assert!(!text.contains(&label(a)));
+        assert!(text.contains(&format!(
+            "rosenpass_handshakes_failed_total{{{}}} 1\n",
+            label(b)
+        )));
+        // No key was exchanged with b yet
+        assert!(!text.contains("rosenpass_seconds_since_last_key{"));
+    }
+}
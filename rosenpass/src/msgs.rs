@@ -81,8 +81,10 @@ pub type MsgEnvelopeCookie = [u8; COOKIE_SIZE];
 pub struct Envelope<M: AsBytes + FromBytes> {
     /// [MsgType] of this message
     pub msg_type: u8,
+    /// The protocol suite ([crate::protocol::SuiteId]) used by the sender
+    pub suite: u8,
     /// Reserved for future use
-    pub reserved: [u8; 3],
+    pub reserved: [u8; 2],
     /// The actual Paylod
     pub payload: M,
     /// Message Authentication Code (mac) over all bytes until (exclusive)
@@ -1,11 +1,20 @@
+use rosenpass_secret_memory::Public;
 use rosenpass_util::{
     build::Build,
     mem::{DiscardResultExt, SwapWithDefaultExt},
     result::ensure_or,
+    time::Timebase,
 };
+use std::ops::Deref;
+use std::sync::Arc;
 use thiserror::Error;
 
-use super::{CryptoServer, PeerPtr, SPk, SSk, SymKey};
+use crate::hash_domains;
+
+use super::{
+    CryptoServer, HandshakeTracer, PeerId, PeerPtr, SPk, SSk, SuiteId, SymKey, Timing,
+    TimingProfile, COOKIE_SECRET_EPOCH,
+};
 
 #[derive(Debug, Clone)]
 /// A pair of matching public/secret keys used to launch the crypto server.
@@ -152,8 +161,8 @@ pub struct MissingKeypair;
 /// secret_policy_use_only_malloc_secrets();
 ///
 /// let keypair = Keypair::random();
-/// let peer1 = PeerParams { psk: Some(SymKey::random()), pk: SPk::random() };
-/// let peer2 = PeerParams { psk: None, pk: SPk::random() };
+/// let peer1 = PeerParams::new(Some(SymKey::random()), SPk::random());
+/// let peer2 = PeerParams::new(None, SPk::random());
 ///
 /// let mut builder = BuildCryptoServer::new(Some(keypair.clone()), vec![peer1]);
 /// builder.add_peer(peer2.psk.clone(), peer2.pk);
@@ -168,6 +177,12 @@ pub struct BuildCryptoServer {
     pub keypair: Option<Keypair>,
     /// A list of network peers that should be registered when launching the server.
     pub peers: Vec<PeerParams>,
+    /// The time source used by the server; see [CryptoServer::with_timebase].
+    pub timebase: Timebase,
+    /// The life time of the cookie secret; see [CryptoServer::set_cookie_secret_epoch].
+    pub cookie_secret_epoch: Timing,
+    /// Receives handshake events; see [CryptoServer::set_tracer].
+    pub tracer: Option<Arc<dyn HandshakeTracer>>,
 }
 
 impl Build<CryptoServer> for BuildCryptoServer {
@@ -184,11 +199,22 @@ impl Build<CryptoServer> for BuildCryptoServer {
             return Err(MissingKeypair)?;
         };
 
-        let mut srv = CryptoServer::new(sk, pk);
+        let mut srv = CryptoServer::with_timebase(sk, pk, self.timebase);
+        srv.set_cookie_secret_epoch(self.cookie_secret_epoch)?;
+        srv.set_tracer(self.tracer);
 
-        for (idx, PeerParams { psk, pk }) in self.peers.into_iter().enumerate() {
-            let PeerPtr(idx2) = srv.add_peer(psk, pk)?;
-            assert!(idx == idx2, "Peer id changed during CryptoServer construction from {idx} to {idx2}. This is a developer error.")
+        for (idx, params) in self.peers.into_iter().enumerate() {
+            let PeerParams {
+                psk,
+                pk,
+                timing,
+                suite,
+            } = params;
+            let peer = srv.add_peer(psk, pk)?;
+            let PeerPtr(idx2) = peer;
+            assert!(idx == idx2, "Peer id changed during CryptoServer construction from {idx} to {idx2}. This is a developer error.");
+            srv.set_peer_suite(peer, suite)?;
+            srv.set_peer_timing(peer, timing)?;
         }
 
         Ok(srv)
@@ -208,12 +234,45 @@ pub struct PeerParams {
     pub psk: Option<SymKey>,
     /// Public key identifying the peer.
     pub pk: SPk,
+    /// The timers used for this peer; see [CryptoServer::set_peer_timing].
+    pub timing: TimingProfile,
+    /// The protocol suite used with this peer; see [CryptoServer::set_peer_suite].
+    pub suite: SuiteId,
+}
+
+impl PeerParams {
+    /// Creates peer parameters using the default [TimingProfile] and [SuiteId]
+    pub fn new(psk: Option<SymKey>, pk: SPk) -> Self {
+        let timing = TimingProfile::default();
+        let suite = SuiteId::default();
+        Self {
+            psk,
+            pk,
+            timing,
+            suite,
+        }
+    }
+
+    /// The peer id this peer will have once the [CryptoServer] is built; see [super::Peer::pidt]
+    pub fn pidt(&self) -> anyhow::Result<PeerId> {
+        Ok(Public::new(
+            hash_domains::peerid(self.suite)?
+                .mix(self.pk.deref())?
+                .into_value(),
+        ))
+    }
 }
 
 impl BuildCryptoServer {
     /// Creates a new builder instance using the given key pair and peer list.
     pub fn new(keypair: Option<Keypair>, peers: Vec<PeerParams>) -> Self {
-        Self { keypair, peers }
+        Self {
+            keypair,
+            peers,
+            timebase: Timebase::default(),
+            cookie_secret_epoch: COOKIE_SECRET_EPOCH,
+            tracer: None,
+        }
     }
 
     /// Creates an "incomplete" builder instance, without assigning a key pair.
@@ -223,10 +282,7 @@ impl BuildCryptoServer {
 
     /// Creates a builder instance from the given key pair and peer list components.
     pub fn from_parts(parts: (Option<Keypair>, Vec<PeerParams>)) -> Self {
-        Self {
-            keypair: parts.0,
-            peers: parts.1,
-        }
+        Self::new(parts.0, parts.1)
     }
 
     /// Deconstructs the current builder instance, taking ownership of its key pair and peer list.
@@ -328,14 +384,14 @@ impl BuildCryptoServer {
     /// // New server instances will then start with the peer being registered already
     /// let server = builder.build().expect("build failed");
     /// assert_eq!(server.peers.len(), 1);
-    /// let peer = &server.peers[0];
+    /// let peer = server.peers[0].as_ref().expect("peer is vacant");
     /// let peer_psk = Some(peer.psk.clone()).expect("PSK is None");
     /// assert_eq!(peer.spkt, public_key);
     /// assert_eq!(peer_psk.secret(), pre_shared_key.secret());
     /// ```
     pub fn with_added_peer(&mut self, psk: Option<SymKey>, pk: SPk) -> &mut Self {
         // TODO: Check here already whether peer was already added
-        self.peers.push(PeerParams { psk, pk });
+        self.peers.push(PeerParams::new(psk, pk));
         self
     }
 
@@ -346,6 +402,71 @@ impl BuildCryptoServer {
         id
     }
 
+    /// Change the [TimingProfile] used for a peer registered with [Self::add_peer]
+    ///
+    /// The profile is validated right away (see [TimingProfile::validate]) so errors surface
+    /// before the server is built.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rosenpass::protocol::{BuildCryptoServer, Keypair, SPk, TimingProfile};
+    /// use rosenpass_util::build::Build;
+    ///
+    /// rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+    ///
+    /// let mut builder = BuildCryptoServer::new(Some(Keypair::random()), Vec::new());
+    /// let peer = builder.add_peer(None, SPk::random());
+    ///
+    /// let timing = TimingProfile {
+    ///     rekey_after_time_responder: 600.0,
+    ///     rekey_after_time_initiator: 610.0,
+    ///     reject_after_time: 660.0,
+    ///     ..TimingProfile::default()
+    /// };
+    /// builder.set_peer_timing(peer, timing).expect("invalid timing");
+    ///
+    /// let server = builder.build().expect("build failed");
+    /// assert_eq!(peer.get(&server).timing, timing);
+    /// ```
+    pub fn set_peer_timing(&mut self, peer: PeerPtr, timing: TimingProfile) -> anyhow::Result<()> {
+        timing.validate()?;
+        let PeerPtr(idx) = peer;
+        let Some(params) = self.peers.get_mut(idx) else {
+            anyhow::bail!("Cannot set timing for peer {idx}; no such peer");
+        };
+        params.timing = timing;
+        Ok(())
+    }
+
+    /// Change the [SuiteId] used for a peer registered with [Self::add_peer]
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rosenpass::protocol::{BuildCryptoServer, Keypair, SPk, SuiteId};
+    /// use rosenpass_util::build::Build;
+    ///
+    /// rosenpass_secret_memory::secret_policy_use_only_malloc_secrets();
+    ///
+    /// let mut builder = BuildCryptoServer::new(Some(Keypair::random()), Vec::new());
+    /// let peer = builder.add_peer(None, SPk::random());
+    ///
+    /// let suite = SuiteId::all().last().unwrap();
+    /// builder.set_peer_suite(peer, suite).expect("no such peer");
+    ///
+    /// let server = builder.build().expect("build failed");
+    /// assert_eq!(peer.get(&server).suite, suite);
+    /// ```
+    pub fn set_peer_suite(&mut self, peer: PeerPtr, suite: SuiteId) -> anyhow::Result<()> {
+        let PeerPtr(idx) = peer;
+        let Some(params) = self.peers.get_mut(idx) else {
+            anyhow::bail!("Cannot set protocol suite for peer {idx}; no such peer");
+        };
+        params.suite = suite;
+        Ok(())
+    }
+
     /// Creates a new builder, taking ownership of another instance's key pair and peer list.
     /// Allows duplicating the current set of launch parameters, which can then be used to
     /// start multiple servers with the exact same configuration (or variants using it as a base).
@@ -389,6 +510,10 @@ impl BuildCryptoServer {
     /// assert_eq!(peers.len(), 1);
     /// ```
     pub fn emancipate(&mut self) -> Self {
-        Self::from_parts(self.take_parts())
+        let mut r = Self::from_parts(self.take_parts());
+        r.timebase = self.timebase.clone();
+        r.cookie_secret_epoch = self.cookie_secret_epoch;
+        r.tracer = self.tracer.clone();
+        r
     }
 }
@@ -77,6 +77,14 @@
 mod build_crypto_server;
 #[allow(clippy::module_inception)]
 mod protocol;
+mod snapshot;
+mod suite;
+mod timing;
+mod trace;
 
 pub use build_crypto_server::*;
 pub use protocol::*;
+pub use snapshot::*;
+pub use suite::*;
+pub use timing::*;
+pub use trace::*;
@@ -14,13 +14,14 @@ use std::{
         HashMap,
     },
     fmt::Display,
+    sync::Arc,
 };
 
 use anyhow::{bail, ensure, Context, Result};
 use rand::Fill as Randomize;
 
 use memoffset::span_of;
-use rosenpass_cipher_traits::Kem;
+use rosenpass_cipher_traits::{Aead, Kem};
 use rosenpass_ciphers::hash_domain::{SecretHashDomain, SecretHashDomainNamespace};
 use rosenpass_ciphers::kem::{EphemeralKem, StaticKem};
 use rosenpass_ciphers::keyed_hash;
@@ -31,11 +32,14 @@ use rosenpass_to::ops::copy_slice;
 use rosenpass_to::To;
 use rosenpass_util::functional::ApplyExt;
 use rosenpass_util::mem::DiscardResultExt;
-use rosenpass_util::{cat, mem::cpy_min, time::Timebase};
+use rosenpass_util::{mem::cpy_min, time::Timebase};
 use zerocopy::{AsBytes, FromBytes, Ref};
 
 use crate::{hash_domains, msgs::*, RosenpassError};
 
+use super::suite::with_suite;
+use super::{HandshakeEvent, HandshakeTracer, ProtocolSuite, SuiteId, TimingProfile};
+
 // CONSTANTS & SETTINGS //////////////////////////
 
 /// A type for time, e.g. for backoff before re-tries
@@ -77,6 +81,9 @@ pub const REKEY_AFTER_TIME_INITIATOR: Timing = 130.0;
 ///
 /// From the wireguard paper: rekey every two minutes,
 /// discard the key if no rekey is achieved within three
+///
+/// This and the other rekey and retransmission timings are defaults; they can be changed
+/// for each peer using a [TimingProfile].
 pub const REJECT_AFTER_TIME: Timing = 180.0;
 
 /// Maximum period between sending rekey initiation messages
@@ -87,6 +94,8 @@ pub const REKEY_TIMEOUT: Timing = 5.0;
 /// The length of the `cookie_secret` in the [whitepaper](https://rosenpass.eu/whitepaper.pdf)
 pub const COOKIE_SECRET_LEN: usize = MAC_SIZE;
 /// The life time of the `cookie_secret` in the [whitepaper](https://rosenpass.eu/whitepaper.pdf)
+///
+/// This is the default; see [CryptoServer::set_cookie_secret_epoch].
 pub const COOKIE_SECRET_EPOCH: Timing = 120.0;
 
 /// Length of a cookie value (see info about the cookie mechanism in the [whitepaper](https://rosenpass.eu/whitepaper.pdf))
@@ -206,12 +215,22 @@ pub struct CryptoServer {
     ///
     /// We store most timing information in the form of f64 values, relative to a point stored in
     /// this field.
+    ///
+    /// The underlying [rosenpass_util::time::Clock] can be chosen using [Self::with_timebase];
+    /// by default, the real monotonic clock is used.
     pub timebase: Timebase,
+    /// Receives [HandshakeEvent]s for debugging; see [Self::set_tracer]
+    pub tracer: Option<Arc<dyn HandshakeTracer>>,
 
     /// Static Secret Key Mine (our secret key)
     pub sskm: SSk,
     /// Static Public Key Mine (our public key)
     pub spkm: SPk,
+    /// Our previous static keypair, replaced through [Self::rotate_keypair]
+    ///
+    /// Messages addressed to this keypair are still processed until it expires, so peers can
+    /// switch over to our new public key; see [Self::handle_msg].
+    pub retired_keypair: Option<RetiredKeypair>,
     /// Counter used to fill the [Biscuit::biscuit_no] field for biscuits issued.
     ///
     /// Every [Biscuit] issued contains a biscuit number; this is the counter used to generate
@@ -229,7 +248,10 @@ pub struct CryptoServer {
     pub biscuit_keys: [BiscuitKey; 2],
 
     /// List of peers and their session and handshake states
-    pub peers: Vec<Peer>,
+    ///
+    /// Peers removed through [CryptoServer::remove_peer] leave a vacant (`None`) slot
+    /// behind, so the [PeerPtr]s of all other peers remain valid. Slots are never reused.
+    pub peers: Vec<Option<Peer>>,
     /// Index into the list of peers. See [IndexKey] for details.
     pub index: HashMap<IndexKey, PeerNo>,
     /// Hash key for known responder confirmation responses.
@@ -262,6 +284,22 @@ pub struct CryptoServer {
     ///
     /// See [CryptoServer::handle_msg_under_load], and [CryptoServer::active_or_retired_cookie_secrets].
     pub cookie_secrets: [CookieSecret; 2],
+    /// Life time of each of the [Self::cookie_secrets]
+    ///
+    /// Defaults to [COOKIE_SECRET_EPOCH]; see [CryptoServer::set_cookie_secret_epoch].
+    pub cookie_secret_epoch: Timing,
+}
+
+/// A static keypair replaced through [CryptoServer::rotate_keypair]; see
+/// [CryptoServer::retired_keypair]
+#[derive(Debug)]
+pub struct RetiredKeypair {
+    /// The previous [CryptoServer::sskm]
+    pub sk: SSk,
+    /// The previous [CryptoServer::spkm]
+    pub pk: SPk,
+    /// Point in time (see [CryptoServer::timebase]) at which the keypair is erased
+    pub expires_at: Timing,
 }
 
 /// Container for storing cookie secrets like [BiscuitKey] or [CookieSecret].
@@ -452,6 +490,14 @@ pub struct Peer {
     /// This allows us to perform retransmission for the purpose of dealing with packet loss
     /// on the network without having to account for it in the cryptographic code itself.
     pub known_init_conf_response: Option<KnownInitConfResponse>,
+    /// The timers used for this peer
+    ///
+    /// See [CryptoServer::set_peer_timing].
+    pub timing: TimingProfile,
+    /// The protocol suite used for handshakes with this peer
+    ///
+    /// See [CryptoServer::set_peer_suite].
+    pub suite: SuiteId,
 }
 
 impl Peer {
@@ -476,6 +522,8 @@ impl Peer {
             initiation_requested: false,
             handshake: None,
             known_init_conf_response: None,
+            timing: TimingProfile::default(),
+            suite: SuiteId::default(),
         }
     }
 }
@@ -503,6 +551,8 @@ pub struct HandshakeState {
     pub sidr: SessionId,
     /// Chaining Key; i.e. the core cryptographic state
     pub ck: SecretHashDomainNamespace, // TODO: We should probably add an abstr
+    /// The protocol suite used for this handshake; see [Peer::suite]
+    pub suite: SuiteId,
 }
 
 /// Indicates which role a party takes (or took) during the handshake
@@ -746,7 +796,7 @@ pub struct Session {
 /// we impose very particular semantics: The order implies the readiness for usage of a secret, the highest/biggest
 /// variant ([Lifecycle::Young]) is the most preferable one in a class of
 /// equal-role secrets.
-#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Debug, Copy, Clone)]
 pub enum Lifecycle {
     /// Empty value
     Void = 0,
@@ -766,6 +816,50 @@ pub enum Lifecycle {
     Young,
 }
 
+/// Summary of the state of a [Peer], as produced by [PeerPtr::status]
+///
+/// This is meant for introspection, e.g. through the API, and deliberately
+/// contains no key material.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeerStatus {
+    /// State of [Peer::session], if a session is established
+    pub session: Option<SessionStatus>,
+    /// State of [Peer::handshake], if a handshake in initiator role is ongoing
+    pub handshake: Option<HandshakeStatus>,
+}
+
+impl PeerStatus {
+    /// Whether the peer holds a key that is still in use; i.e. the session is neither
+    /// absent nor [Lifecycle::Dead]
+    pub fn has_live_key(&self) -> bool {
+        self.session.is_some_and(|s| s.lifecycle > Lifecycle::Dead)
+    }
+}
+
+/// Summary of a [Session]; see [PeerStatus]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionStatus {
+    /// Seconds since the handshake finished; see [Session::created_at]
+    pub age: Timing,
+    /// The role we took during the handshake; see [Session::handshake_role]
+    pub role: HandshakeRole,
+    /// Whether the session is due for renegotiation or about to be erased
+    pub lifecycle: Lifecycle,
+}
+
+/// Summary of an [InitiatorHandshake]; see [PeerStatus]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HandshakeStatus {
+    /// Seconds since the handshake was started; see [InitiatorHandshake::created_at]
+    pub age: Timing,
+    /// The message expected from the responder; see [InitiatorHandshake::next]
+    pub next: HandshakeStateMachine,
+    /// Number of retransmissions so far; see [InitiatorHandshake::tx_count]
+    pub retransmissions: usize,
+    /// Seconds until the next retransmission; see [InitiatorHandshake::tx_retry_at]
+    pub retransmit_in: Timing,
+}
+
 /// Life cycle management for values
 ///
 /// # Examples
@@ -898,26 +992,42 @@ impl PeerPtr {
     ///
     /// # Panic & Safety
     ///
-    /// The function panics if the peer referenced by this PeerPtr does not exist.
+    /// The function panics if the peer referenced by this PeerPtr does not exist
+    /// or has been removed.
     ///
     /// # Examples
     ///
     /// See [Self]
     pub fn get<'a>(&self, srv: &'a CryptoServer) -> &'a Peer {
-        &srv.peers[self.0]
+        srv.peers[self.0]
+            .as_ref()
+            .expect("PeerPtr refers to a removed peer")
     }
 
     /// Mutable access to a peer.
     ///
     /// # Panic & Safety
     ///
-    /// The function panics if the peer referenced by this PeerPtr does not exist.
+    /// The function panics if the peer referenced by this PeerPtr does not exist
+    /// or has been removed.
     ///
     /// # Examples
     ///
     /// See [Self]
     pub fn get_mut<'a>(&self, srv: &'a mut CryptoServer) -> &'a mut Peer {
-        &mut srv.peers[self.0]
+        srv.peers[self.0]
+            .as_mut()
+            .expect("PeerPtr refers to a removed peer")
+    }
+
+    /// Check whether the peer referenced by this PeerPtr exists and has not been removed
+    /// through [CryptoServer::remove_peer]
+    ///
+    /// # Examples
+    ///
+    /// See [CryptoServer::remove_peer]
+    pub fn exists(&self, srv: &CryptoServer) -> bool {
+        matches!(srv.peers.get(self.0), Some(Some(_)))
     }
 
     /// Produce pointer to associated session
@@ -955,6 +1065,38 @@ impl PeerPtr {
     pub fn known_init_conf_response(&self) -> KnownInitConfResponsePtr {
         KnownInitConfResponsePtr(self.0)
     }
+
+    /// Summarize the state of the peer's session and handshake; see [PeerStatus]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::protocol::{testutils::ServerForTesting, PeerStatus};
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (peer, _, srv) = ServerForTesting::new()?.tuple();
+    /// let status = peer.status(&srv);
+    /// assert_eq!(status, PeerStatus { session: None, handshake: None });
+    /// assert!(!status.has_live_key());
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn status(&self, srv: &CryptoServer) -> PeerStatus {
+        let now = srv.timebase.now();
+        let session = self.session().get(srv).as_ref().map(|s| SessionStatus {
+            age: now - s.created_at,
+            role: s.handshake_role,
+            lifecycle: self.session().lifecycle(srv),
+        });
+        let handshake = self.hs().get(srv).as_ref().map(|hs| HandshakeStatus {
+            age: now - hs.created_at,
+            next: hs.next,
+            retransmissions: hs.tx_count,
+            retransmit_in: hs.tx_retry_at - now,
+        });
+        PeerStatus { session, handshake }
+    }
 }
 
 impl IniHsPtr {
@@ -968,7 +1110,7 @@ impl IniHsPtr {
     ///
     /// See [PeerPtr]
     pub fn get<'a>(&self, srv: &'a CryptoServer) -> &'a Option<InitiatorHandshake> {
-        &srv.peers[self.0].handshake
+        &self.peer().get(srv).handshake
     }
 
     /// Mutable access to the handshake value
@@ -981,7 +1123,7 @@ impl IniHsPtr {
     ///
     /// See [PeerPtr]
     pub fn get_mut<'a>(&self, srv: &'a mut CryptoServer) -> &'a mut Option<InitiatorHandshake> {
-        &mut srv.peers[self.0].handshake
+        &mut self.peer().get_mut(srv).handshake
     }
 
     /// Access the associated peer
@@ -1043,7 +1185,7 @@ impl SessionPtr {
     ///
     /// See [PeerPtr]
     pub fn get<'a>(&self, srv: &'a CryptoServer) -> &'a Option<Session> {
-        &srv.peers[self.0].session
+        &self.peer().get(srv).session
     }
 
     /// Mutable access to the session value
@@ -1056,7 +1198,7 @@ impl SessionPtr {
     ///
     /// See [PeerPtr]
     pub fn get_mut<'a>(&self, srv: &'a mut CryptoServer) -> &'a mut Option<Session> {
-        &mut srv.peers[self.0].session
+        &mut self.peer().get_mut(srv).session
     }
 
     /// Access the associated peer
@@ -1133,7 +1275,8 @@ impl PeerCookieValuePtr {
     ///
     /// See [PeerPtr]
     pub fn get<'a>(&self, srv: &'a CryptoServer) -> Option<&'a CookieStore<COOKIE_SECRET_LEN>> {
-        srv.peers[self.0]
+        PeerPtr(self.0)
+            .get(srv)
             .handshake
             .as_ref()
             .map(|v| &v.cookie_value)
@@ -1359,13 +1502,47 @@ impl CryptoServer {
     /// Ok::<(), anyhow::Error>(())
     /// ```
     pub fn new(sk: SSk, pk: SPk) -> CryptoServer {
-        let tb = Timebase::default();
+        Self::with_timebase(sk, pk, Timebase::default())
+    }
+
+    /// Constructing a CryptoServer using a custom time source
+    ///
+    /// This allows driving all timing based behavior of the protocol (rekeying, key expiry,
+    /// biscuit and cookie key rotation, and retransmissions) deterministically, e.g. through a
+    /// [rosenpass_util::time::ManualClock].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::DerefMut;
+    /// use rosenpass::protocol::{SSk, SPk, CryptoServer};
+    /// use rosenpass_ciphers::kem::StaticKem;
+    /// use rosenpass_cipher_traits::Kem;
+    /// use rosenpass_util::time::{ManualClock, Timebase};
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (mut sskm, mut spkm) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm.secret_mut(), spkm.deref_mut())?;
+    ///
+    /// let clock = ManualClock::default();
+    /// let srv = CryptoServer::with_timebase(sskm, spkm, Timebase::new(clock.clone()));
+    /// assert_eq!(srv.timebase.now(), 0.0);
+    ///
+    /// clock.advance(30.0);
+    /// assert_eq!(srv.timebase.now(), 30.0);
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn with_timebase(sk: SSk, pk: SPk, tb: Timebase) -> CryptoServer {
         CryptoServer {
             sskm: sk,
             spkm: pk,
+            retired_keypair: None,
 
             // Defaults
             timebase: tb,
+            tracer: None,
             biscuit_ctr: BiscuitId::new([1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]), // 1, LSB
             biscuit_keys: [CookieStore::new(), CookieStore::new()],
             peers: Vec::new(),
@@ -1373,9 +1550,101 @@ impl CryptoServer {
             known_response_hasher: KnownResponseHasher::new(),
             peer_poll_off: 0,
             cookie_secrets: [CookieStore::new(), CookieStore::new()],
+            cookie_secret_epoch: COOKIE_SECRET_EPOCH,
+        }
+    }
+
+    /// Replace our static keypair ([Self::sskm] and [Self::spkm])
+    ///
+    /// For `grace` seconds, messages addressed to the previous keypair are still processed using
+    /// the previous keypair, so peers can switch over to the new public key without losing
+    /// their sessions; the previous keypair is then erased. Rotating again before the grace
+    /// period is over erases the previous keypair right away.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::DerefMut;
+    /// use rosenpass::protocol::{SSk, SPk, CryptoServer};
+    /// use rosenpass_ciphers::kem::StaticKem;
+    /// use rosenpass_cipher_traits::Kem;
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (mut sskm, mut spkm) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm.secret_mut(), spkm.deref_mut())?;
+    /// let mut srv = CryptoServer::new(sskm, spkm.clone());
+    ///
+    /// let (mut sskm2, mut spkm2) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm2.secret_mut(), spkm2.deref_mut())?;
+    /// srv.rotate_keypair(sskm2, spkm2.clone(), 60.0);
+    /// assert_eq!(srv.spkm, spkm2);
+    /// assert_eq!(srv.retired_keypair.as_ref().map(|k| &k.pk), Some(&spkm));
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn rotate_keypair(&mut self, sk: SSk, pk: SPk, grace: Timing) {
+        let sk = std::mem::replace(&mut self.sskm, sk);
+        let pk = std::mem::replace(&mut self.spkm, pk);
+        self.retired_keypair = (grace > 0.0).then(|| RetiredKeypair {
+            sk,
+            pk,
+            expires_at: self.timebase.now() + grace,
+        });
+    }
+
+    /// Time left until [Self::retired_keypair] is erased; [UNENDING] if there is none
+    pub fn retired_keypair_life_left(&self) -> Timing {
+        match &self.retired_keypair {
+            Some(retired) => retired.expires_at - self.timebase.now(),
+            None => UNENDING,
         }
     }
 
+    /// Check whether the message in `rx_buf` is sealed (see [Envelope::check_seal]) for
+    /// [Self::retired_keypair] rather than for our current keypair
+    fn addressed_to_retired_keypair(&self, rx_buf: &[u8]) -> Result<bool> {
+        let Some(retired) = self.retired_keypair.as_ref() else {
+            return Ok(false);
+        };
+        if has_happened(retired.expires_at, self.timebase.now()) {
+            return Ok(false);
+        }
+
+        // Every envelope ends with the mac and the cookie; the mac covers everything before it
+        let Some(mac_off) = rx_buf.len().checked_sub(MAC_SIZE + COOKIE_SIZE) else {
+            return Ok(false);
+        };
+        if mac_off < 2 {
+            return Ok(false);
+        }
+        let Ok(suite) = SuiteId::try_from(rx_buf[1]) else {
+            return Ok(false);
+        };
+
+        let expected = hash_domains::mac(suite)?
+            .mix(retired.pk.deref())?
+            .mix(&rx_buf[..mac_off])?;
+        Ok(constant_time::memcmp(
+            &rx_buf[mac_off..mac_off + MAC_SIZE],
+            &expected.into_value()[..MAC_SIZE],
+        ))
+    }
+
+    /// Call `f` with [Self::retired_keypair] temporarily swapped in as our current keypair
+    fn with_retired_keypair<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let Some(mut retired) = self.retired_keypair.take() else {
+            return f(self);
+        };
+        std::mem::swap(&mut self.sskm, &mut retired.sk);
+        std::mem::swap(&mut self.spkm, &mut retired.pk);
+        let res = f(self);
+        std::mem::swap(&mut self.sskm, &mut retired.sk);
+        std::mem::swap(&mut self.spkm, &mut retired.pk);
+        self.retired_keypair = Some(retired);
+        res
+    }
+
     /// Iterate over the available biscuit keys by their pointers [BiscuitKeyPtr]
     pub fn biscuit_key_ptrs(&self) -> impl Iterator<Item = BiscuitKeyPtr> {
         (0..self.biscuit_keys.len()).map(BiscuitKeyPtr)
@@ -1386,11 +1655,16 @@ impl CryptoServer {
         (0..self.cookie_secrets.len()).map(ServerCookieSecretPtr)
     }
 
-    /// Calculate the peer ID of this CryptoServer
-    #[rustfmt::skip]
+    /// Calculate the peer ID of this CryptoServer for the default [SuiteId]
     pub fn pidm(&self) -> Result<PeerId> {
+        self.pidm_for(SuiteId::default())
+    }
+
+    /// Calculate the peer ID of this CryptoServer as used with the given protocol suite
+    #[rustfmt::skip]
+    pub fn pidm_for(&self, suite: SuiteId) -> Result<PeerId> {
         Ok(Public::new(
-            hash_domains::peerid()?
+            hash_domains::peerid(suite)?
                 .mix(self.spkm.deref())?
                 .into_value()))
     }
@@ -1398,11 +1672,26 @@ impl CryptoServer {
     /// Iterate over all peers, starting with the `n`th peer, wrapping at the
     /// end of the peers vec so that also all peers from index 0 to `n - 1` are
     /// yielded
-    pub fn peer_ptrs_off(&self, n: usize) -> impl Iterator<Item = PeerPtr> {
+    ///
+    /// Vacant slots left behind by [Self::remove_peer] are skipped.
+    pub fn peer_ptrs_off(&self, n: usize) -> impl Iterator<Item = PeerPtr> + '_ {
+        self.peer_slots_off(n).filter(|p| p.exists(self))
+    }
+
+    /// Like [Self::peer_ptrs_off], but including vacant slots
+    ///
+    /// The iterator does not borrow the server, so [Self::poll] can use it while modifying
+    /// the peers; [PeerPtr::poll] skips vacant slots.
+    fn peer_slots_off(&self, n: usize) -> impl Iterator<Item = PeerPtr> {
         let l = self.peers.len();
         (0..l).map(move |i| PeerPtr((i + n) % l))
     }
 
+    /// Iterate over all peers that have not been removed
+    pub fn peer_ptrs(&self) -> impl Iterator<Item = PeerPtr> + '_ {
+        self.peer_ptrs_off(0)
+    }
+
     /// Add a peer with an optional pre shared key (`psk`) and its public key (`pk`)
     ///
     /// ```
@@ -1437,6 +1726,8 @@ impl CryptoServer {
             handshake: None,
             known_init_conf_response: None,
             initiation_requested: false,
+            timing: TimingProfile::default(),
+            suite: SuiteId::default(),
         };
         let peerid = peer.pidt()?;
         let peerno = self.peers.len();
@@ -1447,10 +1738,76 @@ impl CryptoServer {
             ),
             Vacant(e) => e.insert(peerno),
         };
-        self.peers.push(peer);
+        self.peers.push(Some(peer));
         Ok(PeerPtr(peerno))
     }
 
+    /// Remove a peer previously added with [Self::add_peer]
+    ///
+    /// Erases the peer's session, ongoing handshake, and cached responses, and removes all
+    /// entries referring to the peer from [Self::index]. The peer's slot in [Self::peers] is
+    /// left vacant, so the [PeerPtr]s of all other peers stay valid; the removed peer's
+    /// [PeerPtr] must not be used anymore (see [PeerPtr::exists]).
+    ///
+    /// Returns the removed peer.
+    ///
+    /// ```
+    /// use std::ops::DerefMut;
+    /// use rosenpass::protocol::{SSk, SPk, CryptoServer};
+    /// use rosenpass_ciphers::kem::StaticKem;
+    /// use rosenpass_cipher_traits::Kem;
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (mut sskm, mut spkm) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm.secret_mut(), spkm.deref_mut())?;
+    /// let mut srv = CryptoServer::new(sskm, spkm);
+    ///
+    /// let (mut sskt, mut spkt) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskt.secret_mut(), spkt.deref_mut())?;
+    /// let (mut sskt2, mut spkt2) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskt2.secret_mut(), spkt2.deref_mut())?;
+    ///
+    /// let peer = srv.add_peer(None, spkt.clone())?;
+    /// let peer2 = srv.add_peer(None, spkt2.clone())?;
+    ///
+    /// let removed = srv.remove_peer(peer)?;
+    /// assert_eq!(removed.spkt, spkt);
+    /// assert!(!peer.exists(&srv));
+    /// assert!(srv.find_peer(removed.pidt()?).is_none());
+    ///
+    /// // Other peers are unaffected
+    /// assert_eq!(peer2.get(&srv).spkt, spkt2);
+    /// assert_eq!(srv.peer_ptrs().collect::<Vec<_>>(), vec![peer2]);
+    ///
+    /// // Removed peers can be added again; they receive a new PeerPtr
+    /// let peer3 = srv.add_peer(None, spkt)?;
+    /// assert_ne!(peer, peer3);
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn remove_peer(&mut self, peer: PeerPtr) -> Result<Peer> {
+        ensure!(
+            peer.exists(self),
+            "Cannot remove peer {peer:?}; no such peer"
+        );
+
+        // Take care of the indices through the usual pointer facilities…
+        peer.session().take(self).discard_result();
+        peer.hs().take(self).discard_result();
+        peer.known_init_conf_response()
+            .remove(self)
+            .discard_result();
+
+        let removed = self.peers[peer.0].take().unwrap();
+        self.index.remove(&IndexKey::Peer(removed.pidt()?));
+
+        // …and make sure no dangling index entries remain
+        self.index.retain(|_, &mut no| no != peer.0);
+
+        Ok(removed)
+    }
+
     /// Register a new session
     ///
     /// Used in [SessionPtr::insert] and [IniHsPtr::insert].
@@ -1633,6 +1990,8 @@ impl Peer {
             handshake: None,
             known_init_conf_response: None,
             initiation_requested: false,
+            timing: TimingProfile::default(),
+            suite: SuiteId::default(),
         }
     }
 
@@ -1642,10 +2001,17 @@ impl Peer {
     /// # Examples
     ///
     /// See example in [Self].
-    #[rustfmt::skip]
     pub fn pidt(&self) -> Result<PeerId> {
+        self.pidt_for(self.suite)
+    }
+
+    /// Compute the peer ID the peer would have when using the given protocol suite
+    ///
+    /// See [Self::pidt] and [CryptoServer::set_peer_suite].
+    #[rustfmt::skip]
+    pub fn pidt_for(&self, suite: SuiteId) -> Result<PeerId> {
         Ok(Public::new(
-            hash_domains::peerid()?
+            hash_domains::peerid(suite)?
                 .mix(self.spkt.deref())?
                 .into_value()))
     }
@@ -1753,9 +2119,10 @@ impl Mortal for IniHsPtr {
         self.die_at(srv)
     }
 
-    /// [Self::created_at] plus [REJECT_AFTER_TIME]
+    /// [Self::created_at] plus [TimingProfile::reject_after_time]
     fn die_at(&self, srv: &CryptoServer) -> Option<Timing> {
-        self.created_at(srv).map(|t| t + REJECT_AFTER_TIME)
+        let reject_after = self.peer().get(srv).timing.reject_after_time;
+        self.created_at(srv).map(|t| t + reject_after)
     }
 }
 
@@ -1765,8 +2132,8 @@ impl Mortal for SessionPtr {
         self.get(srv).as_ref().map(|p| p.created_at)
     }
 
-    /// [Self::created_at] plus [REKEY_AFTER_TIME_INITIATOR] or [REKEY_AFTER_TIME_RESPONDER]
-    /// as appropriate.
+    /// [Self::created_at] plus [TimingProfile::rekey_after_time_initiator] or
+    /// [TimingProfile::rekey_after_time_responder] as appropriate.
     fn retire_at(&self, srv: &CryptoServer) -> Option<Timing> {
         // If we were the initiator, wait an extra ten seconds to avoid
         // both parties starting the handshake at the same time. In most situations
@@ -1775,18 +2142,20 @@ impl Mortal for SessionPtr {
         // This also has the peers going back and forth taking the initiator role
         // and responder role.
         use HandshakeRole::*;
+        let timing = &self.peer().get(srv).timing;
         self.get(srv).as_ref().map(|p| {
             let wait = match p.handshake_role {
-                Initiator => REKEY_AFTER_TIME_INITIATOR,
-                Responder => REKEY_AFTER_TIME_RESPONDER,
+                Initiator => timing.rekey_after_time_initiator,
+                Responder => timing.rekey_after_time_responder,
             };
             p.created_at + wait
         })
     }
 
-    /// [Self::created_at] plus [REJECT_AFTER_TIME]
+    /// [Self::created_at] plus [TimingProfile::reject_after_time]
     fn die_at(&self, srv: &CryptoServer) -> Option<Timing> {
-        self.created_at(srv).map(|t| t + REJECT_AFTER_TIME)
+        let reject_after = self.peer().get(srv).timing.reject_after_time;
+        self.created_at(srv).map(|t| t + reject_after)
     }
 }
 
@@ -1823,14 +2192,14 @@ impl Mortal for ServerCookieSecretPtr {
         }
     }
 
-    /// At [Self::created_at] plus [COOKIE_SECRET_EPOCH]
+    /// At [Self::created_at] plus [CryptoServer::cookie_secret_epoch]
     fn retire_at(&self, srv: &CryptoServer) -> Option<Timing> {
-        self.created_at(srv).map(|t| t + COOKIE_SECRET_EPOCH)
+        self.created_at(srv).map(|t| t + srv.cookie_secret_epoch)
     }
 
-    /// At [Self::retire_at] plus [COOKIE_SECRET_EPOCH]
+    /// At [Self::retire_at] plus [CryptoServer::cookie_secret_epoch]
     fn die_at(&self, srv: &CryptoServer) -> Option<Timing> {
-        self.retire_at(srv).map(|t| t + COOKIE_SECRET_EPOCH)
+        self.retire_at(srv).map(|t| t + srv.cookie_secret_epoch)
     }
 }
 
@@ -1874,9 +2243,10 @@ impl Mortal for KnownInitConfResponsePtr {
         self.die_at(srv)
     }
 
-    /// [Self::created_at] plus [REKEY_AFTER_TIME_RESPONDER]
+    /// [Self::created_at] plus [TimingProfile::rekey_after_time_responder]
     fn die_at(&self, srv: &CryptoServer) -> Option<Timing> {
-        self.created_at(srv).map(|t| t + REKEY_AFTER_TIME_RESPONDER)
+        let rekey_after = self.peer().get(srv).timing.rekey_after_time_responder;
+        self.created_at(srv).map(|t| t + rekey_after)
     }
 }
 
@@ -2104,6 +2474,12 @@ impl CryptoServer {
         tx_buf: &mut [u8],
         host_identification: &H,
     ) -> Result<HandleMsgResult> {
+        if self.addressed_to_retired_keypair(rx_buf)? {
+            return self.with_retired_keypair(|srv| {
+                srv.handle_msg_under_load(rx_buf, tx_buf, host_identification)
+            });
+        }
+
         let mut active_cookie_value: Option<[u8; COOKIE_SIZE]> = None;
         let mut rx_cookie = [0u8; COOKIE_SIZE];
         let mut rx_mac = [0u8; MAC_SIZE];
@@ -2131,12 +2507,16 @@ impl CryptoServer {
             }
         }
 
+        let msg_in = Ref::<&[u8], Envelope<InitHello>>::new(rx_buf)
+            .ok_or(RosenpassError::BufferSizeMismatch)?;
+        let suite = SuiteId::try_from(msg_in.suite)?;
+
         for cookie_secret in self.active_or_retired_cookie_secrets() {
             if let Some(cookie_secret) = cookie_secret {
                 let cookie_secret = cookie_secret.get(self).value.secret();
                 let mut cookie_value = [0u8; 16];
                 cookie_value.copy_from_slice(
-                    &hash_domains::cookie_value()?
+                    &hash_domains::cookie_value(suite)?
                         .mix(cookie_secret)?
                         .mix(host_identification.encode())?
                         .into_value()[..16],
@@ -2148,11 +2528,8 @@ impl CryptoServer {
                 }
 
                 let mut expected = [0u8; COOKIE_SIZE];
-
-                let msg_in = Ref::<&[u8], Envelope<InitHello>>::new(rx_buf)
-                    .ok_or(RosenpassError::BufferSizeMismatch)?;
                 expected.copy_from_slice(
-                    &hash_domains::cookie()?
+                    &hash_domains::cookie(suite)?
                         .mix(&cookie_value)?
                         .mix(&msg_in.as_bytes()[span_of!(Envelope<InitHello>, msg_type..cookie)])?
                         .into_value()[..16],
@@ -2187,9 +2564,13 @@ impl CryptoServer {
             msg_type,
             host_identification
         );
+        self.trace(|| HandshakeEvent::CookieRequired {
+            msg_type: MsgType::InitHello,
+            sidi: SessionId::new(rx_sid),
+        });
 
         let cookie_value = active_cookie_value.unwrap();
-        let cookie_key = hash_domains::cookie_key()?
+        let cookie_key = hash_domains::cookie_key(suite)?
             .mix(self.spkm.deref())?
             .into_value();
 
@@ -2200,13 +2581,12 @@ impl CryptoServer {
         msg_out.inner.msg_type = MsgType::CookieReply.into();
         msg_out.inner.sid = rx_sid;
 
-        xaead::encrypt(
-            &mut msg_out.inner.cookie_encrypted[..],
-            &cookie_key,
-            &nonce.value,
-            &rx_mac,
-            &cookie_value,
-        )?;
+        with_suite!(suite, S => {
+            type A = <S as ProtocolSuite>::BiscuitAead;
+            let (n, ct) = msg_out.inner.cookie_encrypted.split_at_mut(A::NONCE_LEN);
+            n.copy_from_slice(&nonce.value);
+            A::encrypt(ct, &cookie_key, &nonce.value, &rx_mac, &cookie_value)
+        })?;
 
         msg_out
             .padding
@@ -2252,12 +2632,19 @@ impl CryptoServer {
     /// | t2   | `InitConf`  | ->        |             |
     /// | t3   |             | <-        | `EmptyData` |
     ///
+    /// Messages addressed to [Self::retired_keypair] are processed as if we were still
+    /// using that keypair; see [Self::rotate_keypair].
+    ///
     /// # Examples
     ///
     /// See the example on how to use this function without [Self::poll] in [crate::protocol].
     ///
     /// See [Self::poll] on how to use this function with poll.
     pub fn handle_msg(&mut self, rx_buf: &[u8], tx_buf: &mut [u8]) -> Result<HandleMsgResult> {
+        if self.addressed_to_retired_keypair(rx_buf)? {
+            return self.with_retired_keypair(|srv| srv.handle_msg(rx_buf, tx_buf));
+        }
+
         let seal_broken = "Message seal broken!";
         // length of the response. We assume no response, so None for now
         let mut len = 0;
@@ -2265,28 +2652,59 @@ impl CryptoServer {
 
         ensure!(!rx_buf.is_empty(), "received empty message, ignoring it");
 
-        let msg_type = rx_buf[0].try_into();
+        let msg_type: Result<MsgType, _> = rx_buf[0].try_into();
 
         log::debug!("Rx {:?}, processing", msg_type);
+        self.trace(|| HandshakeEvent::MessageReceived {
+            msg_type: msg_type.as_ref().ok().copied(),
+            len: rx_buf.len(),
+        });
 
         let peer = match msg_type {
             Ok(MsgType::InitHello) => {
                 let msg_in: Ref<&[u8], Envelope<InitHello>> =
                     Ref::new(rx_buf).ok_or(RosenpassError::BufferSizeMismatch)?;
-                ensure!(msg_in.check_seal(self)?, seal_broken);
+                let valid = msg_in.check_seal(self)?;
+                self.trace(|| HandshakeEvent::SealChecked {
+                    msg_type: MsgType::InitHello,
+                    valid,
+                });
+                ensure!(valid, seal_broken);
 
                 let mut msg_out = truncating_cast_into::<Envelope<RespHello>>(tx_buf)?;
-                let peer = self.handle_init_hello(&msg_in.payload, &mut msg_out.payload)?;
+                let peer = self.handle_init_hello(
+                    &msg_in.payload,
+                    &mut msg_out.payload,
+                    msg_in.suite_id()?,
+                )?;
                 len = self.seal_and_commit_msg(peer, MsgType::RespHello, &mut msg_out)?;
                 peer
             }
             Ok(MsgType::RespHello) => {
                 let msg_in: Ref<&[u8], Envelope<RespHello>> =
                     Ref::new(rx_buf).ok_or(RosenpassError::BufferSizeMismatch)?;
-                ensure!(msg_in.check_seal(self)?, seal_broken);
+                let valid = msg_in.check_seal(self)?;
+                self.trace(|| HandshakeEvent::SealChecked {
+                    msg_type: MsgType::RespHello,
+                    valid,
+                });
+                ensure!(valid, seal_broken);
 
                 let mut msg_out = truncating_cast_into::<Envelope<InitConf>>(tx_buf)?;
-                let peer = self.handle_resp_hello(&msg_in.payload, &mut msg_out.payload)?;
+                let peer = match self.handle_resp_hello(&msg_in.payload, &mut msg_out.payload) {
+                    Ok(peer) => peer,
+                    Err(e) => {
+                        self.trace(|| HandshakeEvent::RespHelloFailed {
+                            peer: self
+                                .lookup_handshake(SessionId::from_slice(&msg_in.payload.sidi))
+                                .and_then(|hs| self.trace_peer_id(hs.peer())),
+                            sidi: SessionId::from_slice(&msg_in.payload.sidi),
+                            sidr: SessionId::from_slice(&msg_in.payload.sidr),
+                            reason: format!("{e:#}"),
+                        });
+                        return Err(e);
+                    }
+                };
                 len = self.seal_and_commit_msg(peer, MsgType::InitConf, &mut msg_out)?;
                 peer.hs()
                     .store_msg_for_retransmission(self, &msg_out.as_bytes()[..len])?;
@@ -2296,7 +2714,12 @@ impl CryptoServer {
             Ok(MsgType::InitConf) => {
                 let msg_in: Ref<&[u8], Envelope<InitConf>> =
                     Ref::new(rx_buf).ok_or(RosenpassError::BufferSizeMismatch)?;
-                ensure!(msg_in.check_seal(self)?, seal_broken);
+                let valid = msg_in.check_seal(self)?;
+                self.trace(|| HandshakeEvent::SealChecked {
+                    msg_type: MsgType::InitConf,
+                    valid,
+                });
+                ensure!(valid, seal_broken);
 
                 let mut msg_out = truncating_cast_into::<Envelope<EmptyData>>(tx_buf)?;
 
@@ -2316,7 +2739,11 @@ impl CryptoServer {
 
                     // No cached response, actually call cryptographic handler
                     None => {
-                        let peer = self.handle_init_conf(&msg_in.payload, &mut msg_out.payload)?;
+                        let peer = self.handle_init_conf(
+                            &msg_in.payload,
+                            &mut msg_out.payload,
+                            msg_in.suite_id()?,
+                        )?;
 
                         KnownInitConfResponsePtr::insert_for_request_msg(
                             self,
@@ -2336,7 +2763,12 @@ impl CryptoServer {
             Ok(MsgType::EmptyData) => {
                 let msg_in: Ref<&[u8], Envelope<EmptyData>> =
                     Ref::new(rx_buf).ok_or(RosenpassError::BufferSizeMismatch)?;
-                ensure!(msg_in.check_seal(self)?, seal_broken);
+                let valid = msg_in.check_seal(self)?;
+                self.trace(|| HandshakeEvent::SealChecked {
+                    msg_type: MsgType::EmptyData,
+                    valid,
+                });
+                ensure!(valid, seal_broken);
 
                 self.handle_resp_conf(&msg_in.payload)?
             }
@@ -2904,9 +3336,13 @@ impl CryptoServer {
     #[doc = "```"]
     pub fn poll(&mut self) -> Result<PollResult> {
         let r = begin_poll() // Poll each biscuit and peer until an event is found
+            .sched(
+                self.retired_keypair_life_left(),
+                void_poll(|| self.retired_keypair = None), // Erase the retired keypair
+            )
             .poll_children(self, self.biscuit_key_ptrs())?
             .poll_children(self, self.cookie_secret_ptrs())?
-            .poll_children(self, self.peer_ptrs_off(self.peer_poll_off))?;
+            .poll_children(self, self.peer_slots_off(self.peer_poll_off))?;
         self.peer_poll_off = match r.peer() {
             Some(p) => p.0 + 1, // Event found while polling peer p; will poll peer p+1 next
             None => 0, // No peer ev found. Resetting to 0 out of an irrational fear of non-zero numbers
@@ -2933,6 +3369,11 @@ impl Pollable for ServerCookieSecretPtr {
 
 impl Pollable for PeerPtr {
     fn poll(&self, srv: &mut CryptoServer) -> Result<PollResult> {
+        // Vacant slot left behind by [CryptoServer::remove_peer]
+        if !self.exists(srv) {
+            return Ok(begin_poll());
+        }
+
         let (ses, hs) = (self.session(), self.hs());
         begin_poll()
             .sched(hs.life_left(srv), void_poll(|| hs.take(srv))) // Silently erase old handshakes
@@ -3048,19 +3489,20 @@ impl IniHsPtr {
     /// Internal business logic; used to register the fact that a retransmission has happened.
     pub fn register_retransmission(&self, srv: &mut CryptoServer) -> Result<()> {
         let tb = srv.timebase.clone();
+        let t = self.peer().get(srv).timing;
         let ih = self
             .get_mut(srv)
             .as_mut()
             .with_context(|| format!("No current handshake for peer {:?}", self.peer()))?;
         // Base delay, exponential increase, ±50% jitter
         ih.tx_retry_at = tb.now()
-            + RETRANSMIT_DELAY_BEGIN
-                * RETRANSMIT_DELAY_GROWTH.powf(
-                    (RETRANSMIT_DELAY_END / RETRANSMIT_DELAY_BEGIN)
-                        .log(RETRANSMIT_DELAY_GROWTH)
+            + t.retransmit_delay_begin
+                * t.retransmit_delay_growth.powf(
+                    (t.retransmit_delay_end / t.retransmit_delay_begin)
+                        .log(t.retransmit_delay_growth)
                         .min(ih.tx_count as f64),
                 )
-                * RETRANSMIT_DELAY_JITTER
+                * t.retransmit_delay_jitter
                 * (rand::random::<f64>() + 1.0);
         ih.tx_count += 1;
         Ok(())
@@ -3095,8 +3537,12 @@ where
     M: AsBytes + FromBytes,
 {
     /// Internal business logic: Calculate the message authentication code (`mac`) and also append cookie value
+    ///
+    /// This also sets [Self::suite] to the protocol suite used with the peer.
     pub fn seal(&mut self, peer: PeerPtr, srv: &CryptoServer) -> Result<()> {
-        let mac = hash_domains::mac()?
+        let suite = peer.get(srv).suite;
+        self.suite = suite.into();
+        let mac = hash_domains::mac(suite)?
             .mix(peer.get(srv).spkt.deref())?
             .mix(&self.as_bytes()[span_of!(Self, msg_type..mac)])?;
         self.mac.copy_from_slice(mac.into_value()[..16].as_ref());
@@ -3109,7 +3555,7 @@ where
     /// This is called inside [Self::seal] and does not need to be called again separately.
     pub fn seal_cookie(&mut self, peer: PeerPtr, srv: &CryptoServer) -> Result<()> {
         if let Some(cookie_key) = &peer.cv().get(srv) {
-            let cookie = hash_domains::cookie()?
+            let cookie = hash_domains::cookie(peer.get(srv).suite)?
                 .mix(cookie_key.value.secret())?
                 .mix(&self.as_bytes()[span_of!(Self, msg_type..cookie)])?;
             self.cookie
@@ -3123,9 +3569,16 @@ impl<M> Envelope<M>
 where
     M: AsBytes + FromBytes,
 {
+    /// The protocol suite indicated by [Self::suite]
+    ///
+    /// Fails if the suite is not supported by this build.
+    pub fn suite_id(&self) -> Result<SuiteId> {
+        Ok(SuiteId::try_from(self.suite)?)
+    }
+
     /// Internal business logic: Check the message authentication code produced by [Self::seal]
     pub fn check_seal(&self, srv: &CryptoServer) -> Result<bool> {
-        let expected = hash_domains::mac()?
+        let expected = hash_domains::mac(self.suite_id()?)?
             .mix(srv.spkm.deref())?
             .mix(&self.as_bytes()[span_of!(Self, msg_type..mac)])?;
         Ok(constant_time::memcmp(
@@ -3161,6 +3614,7 @@ impl HandshakeState {
             sidi: SessionId::zero(),
             sidr: SessionId::zero(),
             ck: SecretHashDomain::zero().dup(),
+            suite: SuiteId::default(),
         }
     }
 
@@ -3170,24 +3624,31 @@ impl HandshakeState {
     }
 
     /// Initialize the handshake state with the responder public key and the protocol domain
-    /// separator
-    pub fn init(&mut self, spkr: &[u8]) -> Result<&mut Self> {
-        self.ck = hash_domains::ckinit()?.turn_secret().mix(spkr)?.dup();
+    /// separator of the given protocol suite
+    pub fn init(&mut self, suite: SuiteId, spkr: &[u8]) -> Result<&mut Self> {
+        self.suite = suite;
+        self.ck = hash_domains::ckinit(suite)?.turn_secret().mix(spkr)?.dup();
         Ok(self)
     }
 
     /// Mix some data into the chaining key. This is used for mixing cryptographic keys and public
     /// data alike into the chaining key
     pub fn mix(&mut self, a: &[u8]) -> Result<&mut Self> {
-        self.ck = self.ck.mix(&hash_domains::mix()?)?.mix(a)?.dup();
+        self.ck = self.ck.mix(&hash_domains::mix(self.suite)?)?.mix(a)?.dup();
         Ok(self)
     }
 
     /// Encrypt some data with a value derived from the current chaining key and mix that data
     /// into the protocol state.
     pub fn encrypt_and_mix(&mut self, ct: &mut [u8], pt: &[u8]) -> Result<&mut Self> {
-        let k = self.ck.mix(&hash_domains::hs_enc()?)?.into_secret();
-        aead::encrypt(ct, k.secret(), &[0u8; aead::NONCE_LEN], &[], pt)?;
+        let k = self
+            .ck
+            .mix(&hash_domains::hs_enc(self.suite)?)?
+            .into_secret();
+        with_suite!(self.suite, S => {
+            type A = <S as ProtocolSuite>::Aead;
+            A::encrypt(ct, k.secret(), &[0u8; A::NONCE_LEN], &[], pt)
+        })?;
         self.mix(ct)
     }
 
@@ -3196,8 +3657,14 @@ impl HandshakeState {
     /// Makes sure that the same values are mixed into the chaining that where mixed in on the
     /// sender side.
     pub fn decrypt_and_mix(&mut self, pt: &mut [u8], ct: &[u8]) -> Result<&mut Self> {
-        let k = self.ck.mix(&hash_domains::hs_enc()?)?.into_secret();
-        aead::decrypt(pt, k.secret(), &[0u8; aead::NONCE_LEN], &[], ct)?;
+        let k = self
+            .ck
+            .mix(&hash_domains::hs_enc(self.suite)?)?
+            .into_secret();
+        with_suite!(self.suite, S => {
+            type A = <S as ProtocolSuite>::Aead;
+            A::decrypt(pt, k.secret(), &[0u8; A::NONCE_LEN], &[], ct)
+        })?;
         self.mix(ct)
     }
 
@@ -3259,7 +3726,7 @@ impl HandshakeState {
             .copy_from_slice(self.ck.clone().danger_into_secret().secret());
 
         // calculate ad contents
-        let ad = hash_domains::biscuit_ad()?
+        let ad = hash_domains::biscuit_ad(self.suite)?
             .mix(srv.spkm.deref())?
             .mix(self.sidi.as_slice())?
             .mix(self.sidr.as_slice())?
@@ -3277,23 +3744,30 @@ impl HandshakeState {
 
         let k = bk.get(srv).value.secret();
         let pt = biscuit.as_bytes();
-        xaead::encrypt(biscuit_ct, k, &*n, &ad, pt)?;
+        let (biscuit_n, ct) = biscuit_ct.split_at_mut(n.len());
+        biscuit_n.copy_from_slice(&*n);
+        with_suite!(self.suite, S => {
+            <S as ProtocolSuite>::BiscuitAead::encrypt(ct, k, &*n, &ad, pt)
+        })?;
 
         self.mix(biscuit_ct)
     }
 
     /// This is the counterpart to [Self::store_biscuit] that restores a stored biscuit
+    ///
+    /// Fails if the peer the biscuit belongs to does not use the given protocol `suite`.
     pub fn load_biscuit(
         srv: &CryptoServer,
         biscuit_ct: &[u8],
         sidi: SessionId,
         sidr: SessionId,
+        suite: SuiteId,
     ) -> Result<(PeerPtr, BiscuitId, HandshakeState)> {
         // The first bit of the biscuit indicates which biscuit key was used
         let bk = BiscuitKeyPtr(((biscuit_ct[0] & 0b1000_0000) >> 7) as usize);
 
         // Calculate additional data fields
-        let ad = hash_domains::biscuit_ad()?
+        let ad = hash_domains::biscuit_ad(suite)?
             .mix(srv.spkm.deref())?
             .mix(sidi.as_slice())?
             .mix(sidr.as_slice())?
@@ -3303,26 +3777,36 @@ impl HandshakeState {
         let mut biscuit = Secret::<BISCUIT_PT_LEN>::zero(); // pt buf
         let mut biscuit: Ref<&mut [u8], Biscuit> =
             Ref::new(biscuit.secret_mut().as_mut_slice()).unwrap();
-        xaead::decrypt(
-            biscuit.as_bytes_mut(),
-            bk.get(srv).value.secret(),
-            &ad,
-            biscuit_ct,
-        )?;
+        let k = bk.get(srv).value.secret();
+        with_suite!(suite, S => {
+            type A = <S as ProtocolSuite>::BiscuitAead;
+            let (n, ct) = biscuit_ct.split_at(A::NONCE_LEN);
+            A::decrypt(biscuit.as_bytes_mut(), k, n, &ad, ct)
+        })?;
 
         // Reconstruct the biscuit fields
         let no = BiscuitId::from_slice(&biscuit.biscuit_no);
-        let ck = SecretHashDomain::danger_from_secret(Secret::from_slice(&biscuit.ck)).dup();
+        let ck = SecretHashDomain::danger_from_secret_with(
+            Secret::from_slice(&biscuit.ck),
+            suite.keyed_hash(),
+        )
+        .dup();
         let pid = PeerId::from_slice(&biscuit.pidi);
 
         // Reconstruct the handshake state
-        let mut hs = Self { sidi, sidr, ck };
+        let mut hs = Self {
+            sidi,
+            sidr,
+            ck,
+            suite,
+        };
         hs.mix(biscuit_ct)?;
 
         // Look up the associated peer
         let peer = srv
             .find_peer(pid) // TODO: FindPeer should return a Result<()>
             .with_context(|| format!("Could not decode biscuit for peer {pid:?}: No such peer."))?;
+        srv.ensure_peer_suite(peer, suite)?;
 
         Ok((peer, no, hs))
     }
@@ -3333,9 +3817,14 @@ impl HandshakeState {
     ///
     /// `role` indicates whether the local peer was an initiator or responder in the handshake.
     pub fn enter_live(self, srv: &CryptoServer, role: HandshakeRole) -> Result<Session> {
-        let HandshakeState { ck, sidi, sidr } = self;
-        let tki = ck.mix(&hash_domains::ini_enc()?)?.into_secret();
-        let tkr = ck.mix(&hash_domains::res_enc()?)?.into_secret();
+        let HandshakeState {
+            ck,
+            sidi,
+            sidr,
+            suite,
+        } = self;
+        let tki = ck.mix(&hash_domains::ini_enc(suite)?)?.into_secret();
+        let tkr = ck.mix(&hash_domains::res_enc(suite)?)?.into_secret();
         let created_at = srv.timebase.now();
         let (ntx, nrx) = (0, 0);
         let (mysid, peersid, ktx, krx) = match role {
@@ -3373,7 +3862,8 @@ impl CryptoServer {
             .get(self)
             .as_ref()
             .with_context(|| format!("No current session for peer {:?}", peer))?;
-        Ok(session.ck.mix(&hash_domains::osk()?)?.into_secret())
+        let suite = peer.get(self).suite;
+        Ok(session.ck.mix(&hash_domains::osk(suite)?)?.into_secret())
     }
 }
 
@@ -3382,16 +3872,17 @@ impl CryptoServer {
     /// on the initiator side, producing the InitHello message.
     pub fn handle_initiation(&mut self, peer: PeerPtr, ih: &mut InitHello) -> Result<PeerPtr> {
         let mut hs = InitiatorHandshake::zero_with_timestamp(self);
+        let suite = peer.get(self).suite;
 
         // IHI1
-        hs.core.init(peer.get(self).spkt.deref())?;
+        hs.core.init(suite, peer.get(self).spkt.deref())?;
 
         // IHI2
         hs.core.sidi.randomize();
         ih.sidi.copy_from_slice(&hs.core.sidi.value);
 
         // IHI3
-        EphemeralKem::keygen(hs.eski.secret_mut(), &mut *hs.epki)?;
+        suite.ephemeral_keygen(hs.eski.secret_mut(), &mut *hs.epki)?;
         ih.epki.copy_from_slice(&hs.epki.value);
 
         // IHI4
@@ -3406,7 +3897,7 @@ impl CryptoServer {
 
         // IHI6
         hs.core
-            .encrypt_and_mix(ih.pidic.as_mut_slice(), self.pidm()?.as_ref())?;
+            .encrypt_and_mix(ih.pidic.as_mut_slice(), self.pidm_for(suite)?.as_ref())?;
 
         // IHI7
         hs.core
@@ -3424,13 +3915,21 @@ impl CryptoServer {
 
     /// Core cryptographic protocol implementation: Parses an [InitHello] message and produces a
     /// [RespHello] message on the responder side.
-    pub fn handle_init_hello(&mut self, ih: &InitHello, rh: &mut RespHello) -> Result<PeerPtr> {
+    ///
+    /// `suite` is the protocol suite indicated in the message envelope ([Envelope::suite]); the
+    /// message is rejected if the initiator is configured to use a different suite.
+    pub fn handle_init_hello(
+        &mut self,
+        ih: &InitHello,
+        rh: &mut RespHello,
+        suite: SuiteId,
+    ) -> Result<PeerPtr> {
         let mut core = HandshakeState::zero();
 
         core.sidi = SessionId::from_slice(&ih.sidi);
 
         // IHR1
-        core.init(self.spkm.deref())?;
+        core.init(suite, self.spkm.deref())?;
 
         // IHR4
         core.mix(&ih.sidi)?.mix(&ih.epki)?;
@@ -3449,6 +3948,7 @@ impl CryptoServer {
             self.find_peer(peerid)
                 .with_context(|| format!("No such peer {peerid:?}."))?
         };
+        self.ensure_peer_suite(peer, suite)?;
 
         // IHR7
         core.mix(peer.get(self).spkt.deref())?
@@ -3466,7 +3966,10 @@ impl CryptoServer {
         core.mix(&rh.sidr)?.mix(&rh.sidi)?;
 
         // RHR4
-        core.encaps_and_mix::<EphemeralKem, { EphemeralKem::SHK_LEN }>(&mut rh.ecti, &ih.epki)?;
+        with_suite!(suite, S => core.encaps_and_mix::<
+            <S as ProtocolSuite>::EphemeralKem,
+            { EphemeralKem::SHK_LEN },
+        >(&mut rh.ecti, &ih.epki)?);
 
         // RHR5
         core.encaps_and_mix::<StaticKem, { StaticKem::SHK_LEN }>(
@@ -3532,11 +4035,11 @@ impl CryptoServer {
         core.mix(&rh.sidr)?.mix(&rh.sidi)?;
 
         // RHI4
-        core.decaps_and_mix::<EphemeralKem, { EphemeralKem::SHK_LEN }>(
-            hs!().eski.secret(),
-            hs!().epki.deref(),
-            &rh.ecti,
-        )?;
+        let suite = core.suite;
+        with_suite!(suite, S => core.decaps_and_mix::<
+            <S as ProtocolSuite>::EphemeralKem,
+            { EphemeralKem::SHK_LEN },
+        >(hs!().eski.secret(), hs!().epki.deref(), &rh.ecti)?);
 
         // RHI5
         core.decaps_and_mix::<StaticKem, { StaticKem::SHK_LEN }>(
@@ -3571,6 +4074,12 @@ impl CryptoServer {
         // ICI7
         peer.session()
             .insert(self, core.enter_live(self, HandshakeRole::Initiator)?)?;
+        self.trace(|| HandshakeEvent::SessionEntered {
+            peer: self.trace_peer_id(peer),
+            sidm: SessionId::from_slice(&ic.sidi),
+            sidt: SessionId::from_slice(&ic.sidr),
+            role: HandshakeRole::Initiator,
+        });
         hs_mut!().core.erase();
         hs_mut!().next = HandshakeStateMachine::RespConf;
 
@@ -3582,7 +4091,15 @@ impl CryptoServer {
     ///
     /// This concludes the handshake on the cryptographic level; the [EmptyData] message is just
     /// an acknowledgement message telling the initiator to stop performing retransmissions.
-    pub fn handle_init_conf(&mut self, ic: &InitConf, rc: &mut EmptyData) -> Result<PeerPtr> {
+    ///
+    /// `suite` is the protocol suite indicated in the message envelope ([Envelope::suite]); see
+    /// [Self::handle_init_hello].
+    pub fn handle_init_conf(
+        &mut self,
+        ic: &InitConf,
+        rc: &mut EmptyData,
+        suite: SuiteId,
+    ) -> Result<PeerPtr> {
         // (peer, bn) ← LoadBiscuit(InitConf.biscuit)
         // ICR1
         let (peer, biscuit_no, mut core) = HandshakeState::load_biscuit(
@@ -3590,7 +4107,13 @@ impl CryptoServer {
             &ic.biscuit,
             SessionId::from_slice(&ic.sidi),
             SessionId::from_slice(&ic.sidr),
+            suite,
         )?;
+        self.trace(|| HandshakeEvent::BiscuitDecrypted {
+            peer: self.trace_peer_id(peer),
+            sidi: SessionId::from_slice(&ic.sidi),
+            sidr: SessionId::from_slice(&ic.sidr),
+        });
 
         // ICR2
         core.encrypt_and_mix(&mut [0u8; aead::TAG_LEN], &[])?;
@@ -3616,6 +4139,12 @@ impl CryptoServer {
         // ICR7
         peer.session()
             .insert(self, core.enter_live(self, HandshakeRole::Responder)?)?;
+        self.trace(|| HandshakeEvent::SessionEntered {
+            peer: self.trace_peer_id(peer),
+            sidm: SessionId::from_slice(&ic.sidr),
+            sidt: SessionId::from_slice(&ic.sidi),
+            role: HandshakeRole::Responder,
+        });
         // TODO: This should be part of the protocol specification.
         // Abort any ongoing handshake from initiator role
         peer.hs().take(self);
@@ -3652,9 +4181,13 @@ impl CryptoServer {
         rc.ctr.copy_from_slice(&ses.txnm.to_le_bytes());
         ses.txnm += 1; // Increment nonce before encryption, just in case an error is raised
 
-        let n = cat!(aead::NONCE_LEN; &rc.ctr, &[0u8; 4]);
         let k = ses.txkm.secret();
-        aead::encrypt(&mut rc.auth, k, &n, &[], &[])?; // ct, k, n, ad, pt
+        with_suite!(suite, S => {
+            type A = <S as ProtocolSuite>::Aead;
+            let mut n = [0u8; A::NONCE_LEN];
+            n[..rc.ctr.len()].copy_from_slice(&rc.ctr);
+            A::encrypt(&mut rc.auth, k, &n, &[], &[]) // ct, k, n, ad, pt
+        })?;
 
         Ok(peer)
     }
@@ -3669,6 +4202,7 @@ impl CryptoServer {
             .lookup_handshake(sid)
             .with_context(|| format!("Got RespConf packet for non-existent session {sid:?}"))?;
         let ses = hs.peer().session();
+        let suite = hs.peer().get(self).suite;
 
         let exp = hs.get(self).as_ref().map(|h| h.next);
         let got = Some(HandshakeStateMachine::RespConf);
@@ -3690,14 +4224,13 @@ impl CryptoServer {
             let n = u64::from_le_bytes(rc.ctr);
             ensure!(n >= s.txnt, "Stale nonce");
             s.txnt = n;
-            aead::decrypt(
+            with_suite!(suite, S => {
+                type A = <S as ProtocolSuite>::Aead;
+                let mut n = [0u8; A::NONCE_LEN];
+                n[..rc.ctr.len()].copy_from_slice(&rc.ctr);
                 // pt, k, n, ad, ct
-                &mut [0u8; 0],
-                s.txkt.secret(),
-                &cat!(aead::NONCE_LEN; &rc.ctr, &[0u8; 4]),
-                &[],
-                &rc.auth,
-            )?;
+                A::decrypt(&mut [0u8; 0], s.txkt.secret(), &n, &[], &rc.auth)
+            })?;
         }
 
         // We can now stop retransmitting RespConf
@@ -3750,10 +4283,19 @@ impl CryptoServer {
                 }?;
 
                 let spkt = peer.get(self).spkt.deref();
-                let cookie_key = hash_domains::cookie_key()?.mix(spkt)?.into_value();
+                let suite = peer.get(self).suite;
+                let cookie_key = hash_domains::cookie_key(suite)?.mix(spkt)?.into_value();
                 let cookie_value = peer.cv().update_mut(self).unwrap();
 
-                xaead::decrypt(cookie_value, &cookie_key, &mac, &cr.inner.cookie_encrypted)?;
+                with_suite!(suite, S => {
+                    type A = <S as ProtocolSuite>::BiscuitAead;
+                    let (n, ct) = cr.inner.cookie_encrypted.split_at(A::NONCE_LEN);
+                    A::decrypt(cookie_value, &cookie_key, n, &mac, ct)
+                })?;
+                self.trace(|| HandshakeEvent::CookieReceived {
+                    peer: self.trace_peer_id(peer),
+                    sidi: Public::new(cr.inner.sid),
+                });
 
                 // Immediately retransmit on recieving a cookie reply message
                 peer.hs().register_immediate_retransmission(self)?;
@@ -3819,9 +4361,12 @@ pub mod testutils {
     }
 
     /// Time travel forward in time
+    ///
+    /// This only affects the given server, not other users of the same clock;
+    /// to move time forward for multiple servers, use a shared
+    /// [rosenpass_util::time::ManualClock] with [CryptoServer::with_timebase].
     pub fn time_travel_forward(srv: &mut CryptoServer, secs: f64) {
-        let dur = std::time::Duration::from_secs_f64(secs);
-        srv.timebase.0 = srv.timebase.0.checked_sub(dur).unwrap();
+        srv.timebase.travel_forward(secs);
     }
 }
 
@@ -3830,6 +4375,9 @@ mod test {
     use std::{borrow::BorrowMut, net::SocketAddrV4, ops::DerefMut, thread::sleep, time::Duration};
 
     use super::*;
+    use rosenpass_ciphers::keyed_hash::KeyedHashChoice;
+    use rosenpass_util::b64::B64Display;
+    use rosenpass_util::time::ManualClock;
     use serial_test::serial;
     use zerocopy::FromZeroes;
 
@@ -4136,7 +4684,7 @@ mod test {
 
             assert_eq!(PeerPtr(0).cv().lifecycle(&a), Lifecycle::Young);
 
-            let expected_cookie_value = hash_domains::cookie_value()
+            let expected_cookie_value = hash_domains::cookie_value(SuiteId::default())
                 .unwrap()
                 .mix(
                     b.active_or_retired_cookie_secrets()[0]
@@ -4345,7 +4893,7 @@ mod test {
             // Though if we directly call handle_resp_hello() we get an error since
             // retransmission is not being handled by the cryptographic code
             let mut discard_resp_conf = EmptyData::new_zeroed();
-            let res = srv.handle_init_conf(&ic.payload, &mut discard_resp_conf);
+            let res = srv.handle_init_conf(&ic.payload, &mut discard_resp_conf, SuiteId::default());
             assert!(res.is_err());
 
             // Obviously, a broken InitConf message should still be rejected
@@ -4420,8 +4968,384 @@ mod test {
         // since the biscuit is stale
         poll(&mut b)?;
         check_faulty_proc_init_conf(&mut b, &ic1); // ic1 is now effectively broken
-        assert!(b.peers[0].known_init_conf_response.is_none()); // The cache is gone
+        assert!(PeerPtr(0).get(&b).known_init_conf_response.is_none()); // The cache is gone
 
         Ok(())
     }
+
+    #[test]
+    #[serial]
+    fn remove_peer_erases_state() {
+        setup_logging();
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            type MsgBufPlus = Public<MAX_MESSAGE_LEN>;
+            let (mut a, mut b) = make_server_pair().unwrap();
+            let (_, pkc) = keygen().unwrap();
+            let c_in_a = a.add_peer(None, pkc).unwrap();
+
+            // Perform a full handshake so sessions, handshakes, and known responses exist
+            let (mut a_buf, mut b_buf) = (MsgBufPlus::zero(), MsgBufPlus::zero());
+            let mut maybe_len = Some(a.initiate_handshake(PeerPtr(0), &mut *a_buf).unwrap());
+            while let Some(len) = maybe_len {
+                maybe_len = b.handle_msg(&a_buf[..len], &mut *b_buf).unwrap().resp;
+                std::mem::swap(&mut a, &mut b);
+                std::mem::swap(&mut a_buf, &mut b_buf);
+            }
+            assert!(PeerPtr(0).session().get(&a).is_some());
+            assert!(PeerPtr(0).known_init_conf_response().get(&b).is_some());
+
+            // Remove the peer on the responder side
+            let removed = b.remove_peer(PeerPtr(0)).unwrap();
+            assert!(!PeerPtr(0).exists(&b));
+            assert!(b.index.is_empty());
+            assert!(b.find_peer(removed.pidt().unwrap()).is_none());
+            assert!(b.remove_peer(PeerPtr(0)).is_err());
+            assert!(b.poll().is_ok());
+
+            // Remove the peer on the initiator side; the unrelated peer must remain valid
+            let removed = a.remove_peer(PeerPtr(0)).unwrap();
+            assert!(a.find_peer(removed.pidt().unwrap()).is_none());
+            assert_eq!(a.find_peer(c_in_a.get(&a).pidt().unwrap()), Some(c_in_a));
+            assert_eq!(a.index.len(), 1);
+            assert_eq!(a.peer_ptrs().collect::<Vec<_>>(), vec![c_in_a]);
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn manual_clock_drives_session_expiry() {
+        setup_logging();
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            type MsgBufPlus = Public<MAX_MESSAGE_LEN>;
+            let clock = ManualClock::default();
+            let psk = SymKey::random();
+            let ((ska, pka), (skb, pkb)) = (keygen().unwrap(), keygen().unwrap());
+            let mut a = CryptoServer::with_timebase(ska, pka.clone(), Timebase::new(clock.clone()));
+            let mut b = CryptoServer::with_timebase(skb, pkb.clone(), Timebase::new(clock.clone()));
+            a.add_peer(Some(psk.clone()), pkb).unwrap();
+            b.add_peer(Some(psk), pka).unwrap();
+
+            let (mut a_buf, mut b_buf) = (MsgBufPlus::zero(), MsgBufPlus::zero());
+            let mut maybe_len = Some(a.initiate_handshake(PeerPtr(0), &mut *a_buf).unwrap());
+            while let Some(len) = maybe_len {
+                maybe_len = b.handle_msg(&a_buf[..len], &mut *b_buf).unwrap().resp;
+                std::mem::swap(&mut a, &mut b);
+                std::mem::swap(&mut a_buf, &mut b_buf);
+            }
+            assert!(PeerPtr(0).session().get(&a).is_some());
+            assert!(PeerPtr(0).session().get(&b).is_some());
+
+            // No real time passes; the key is only deleted because the clock was advanced
+            clock.advance(REJECT_AFTER_TIME + 1.0);
+            let deleted = (0..16)
+                .any(|_| matches!(b.poll().unwrap(), PollResult::DeleteKey(p) if p == PeerPtr(0)));
+            assert!(deleted);
+            assert!(PeerPtr(0).session().get(&b).is_none());
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn retired_keypair_accepted_during_grace_period() {
+        setup_logging();
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            type MsgBufPlus = Public<MAX_MESSAGE_LEN>;
+            let clock = ManualClock::default();
+            let psk = SymKey::random();
+            let ((ska, pka), (skb, pkb)) = (keygen().unwrap(), keygen().unwrap());
+            let mut a = CryptoServer::with_timebase(ska, pka.clone(), Timebase::new(clock.clone()));
+            let mut b = CryptoServer::with_timebase(skb, pkb.clone(), Timebase::new(clock.clone()));
+            a.add_peer(Some(psk.clone()), pkb).unwrap();
+            b.add_peer(Some(psk), pka).unwrap();
+
+            // Peer a still uses the previous public key of b
+            let (skb2, pkb2) = keygen().unwrap();
+            b.rotate_keypair(skb2, pkb2, 60.0);
+
+            let (mut a_buf, mut b_buf) = (MsgBufPlus::zero(), MsgBufPlus::zero());
+            let mut maybe_len = Some(a.initiate_handshake(PeerPtr(0), &mut *a_buf).unwrap());
+            while let Some(len) = maybe_len {
+                maybe_len = b.handle_msg(&a_buf[..len], &mut *b_buf).unwrap().resp;
+                std::mem::swap(&mut a, &mut b);
+                std::mem::swap(&mut a_buf, &mut b_buf);
+            }
+            assert_eq!(
+                a.osk(PeerPtr(0)).unwrap().secret(),
+                b.osk(PeerPtr(0)).unwrap().secret()
+            );
+
+            // The previous keypair is erased after the grace period
+            clock.advance(61.0);
+            b.poll().unwrap();
+            assert!(b.retired_keypair.is_none());
+            let len = a.initiate_handshake(PeerPtr(0), &mut *a_buf).unwrap();
+            assert!(b.handle_msg(&a_buf[..len], &mut *b_buf).is_err());
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn handshake_uses_negotiated_suite() {
+        setup_logging();
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            type MsgBufPlus = Public<MAX_MESSAGE_LEN>;
+            // Every keyed hash must be covered by at least one suite
+            for hash in [
+                KeyedHashChoice::IncorrectHmacBlake2b,
+                KeyedHashChoice::HmacBlake2b,
+                KeyedHashChoice::Shake256,
+            ] {
+                assert!(SuiteId::all().any(|suite| suite.keyed_hash() == hash));
+            }
+
+            // Key generation is slow; reuse the same keys for all combinations of suites
+            let ((ska, pka), (skb, pkb)) = (keygen().unwrap(), keygen().unwrap());
+            for (suite_a, suite_b) in
+                SuiteId::all().flat_map(|a| SuiteId::all().map(move |b| (a, b)))
+            {
+                let (mut a, mut b) = (
+                    CryptoServer::new(ska.clone(), pka.clone()),
+                    CryptoServer::new(skb.clone(), pkb.clone()),
+                );
+                let psk = SymKey::random();
+                a.add_peer(Some(psk.clone()), pkb.clone()).unwrap();
+                b.add_peer(Some(psk), pka.clone()).unwrap();
+                a.set_peer_suite(PeerPtr(0), suite_a).unwrap();
+                b.set_peer_suite(PeerPtr(0), suite_b).unwrap();
+
+                let (mut a_buf, mut b_buf) = (MsgBufPlus::zero(), MsgBufPlus::zero());
+                let len = a.initiate_handshake(PeerPtr(0), &mut *a_buf).unwrap();
+                // The suite is transmitted in the second byte of the envelope
+                assert_eq!(SuiteId::try_from(a_buf.value[1]).unwrap(), suite_a);
+
+                // The responder must refuse handshakes using a suite other than the configured one
+                if suite_a != suite_b {
+                    assert!(b.handle_msg(&a_buf[..len], &mut *b_buf).is_err());
+                    assert!(PeerPtr(0).session().get(&b).is_none());
+                    continue;
+                }
+
+                let mut maybe_len = Some(len);
+                while let Some(len) = maybe_len {
+                    maybe_len = b.handle_msg(&a_buf[..len], &mut *b_buf).unwrap().resp;
+                    std::mem::swap(&mut a, &mut b);
+                    std::mem::swap(&mut a_buf, &mut b_buf);
+                }
+                assert_eq!(
+                    a.osk(PeerPtr(0)).unwrap().secret(),
+                    b.osk(PeerPtr(0)).unwrap().secret()
+                );
+            }
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn handshake_uses_suite_aead() {
+        setup_logging();
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            type MsgBufPlus = Public<MAX_MESSAGE_LEN>;
+            // ChaCha20-Poly1305 and XChaCha20-Poly1305 for the handshake messages
+            let suites = [SuiteId::default(), SuiteId::TestXChaCha20Poly1305];
+
+            for suite in suites {
+                let (mut a, mut b) = make_server_pair().unwrap();
+                a.set_peer_suite(PeerPtr(0), suite).unwrap();
+                b.set_peer_suite(PeerPtr(0), suite).unwrap();
+
+                // InitHello, RespHello, InitConf; afterwards a is the responder and a_buf
+                // holds the EmptyData message
+                let (mut a_buf, mut b_buf) = (MsgBufPlus::zero(), MsgBufPlus::zero());
+                let mut len = a.initiate_handshake(PeerPtr(0), &mut *a_buf).unwrap();
+                for _ in 0..3 {
+                    len = b
+                        .handle_msg(&a_buf[..len], &mut *b_buf)
+                        .unwrap()
+                        .resp
+                        .unwrap();
+                    std::mem::swap(&mut a, &mut b);
+                    std::mem::swap(&mut a_buf, &mut b_buf);
+                }
+
+                // The EmptyData message is only authentic under the AEAD of the suite in use
+                let ed = Ref::<&[u8], Envelope<EmptyData>>::new(&a_buf[..len]).unwrap();
+                let ses = PeerPtr(0).session().get(&a).as_ref().unwrap();
+                for other in suites {
+                    let valid = with_suite!(other, S => {
+                        type A = <S as ProtocolSuite>::Aead;
+                        let mut n = [0u8; A::NONCE_LEN];
+                        n[..ed.payload.ctr.len()].copy_from_slice(&ed.payload.ctr);
+                        A::decrypt(&mut [0u8; 0], ses.txkm.secret(), &n, &[], &ed.payload.auth)
+                            .is_ok()
+                    });
+                    assert_eq!(valid, other == suite);
+                }
+
+                assert!(b
+                    .handle_msg(&a_buf[..len], &mut *b_buf)
+                    .unwrap()
+                    .resp
+                    .is_none());
+                assert_eq!(
+                    a.osk(PeerPtr(0)).unwrap().secret(),
+                    b.osk(PeerPtr(0)).unwrap().secret()
+                );
+            }
+        });
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingTracer(std::sync::Mutex<Vec<HandshakeEvent>>);
+
+    impl HandshakeTracer for RecordingTracer {
+        fn trace(&self, event: &HandshakeEvent) {
+            self.0.lock().unwrap().push(event.clone());
+        }
+    }
+
+    impl RecordingTracer {
+        fn take(&self) -> Vec<HandshakeEvent> {
+            std::mem::take(&mut *self.0.lock().unwrap())
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn tracer_records_handshake_steps() {
+        setup_logging();
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            type MsgBufPlus = Public<MAX_MESSAGE_LEN>;
+            let (mut a, mut b) = make_server_pair().unwrap();
+            let (trace_a, trace_b) = (
+                Arc::new(RecordingTracer::default()),
+                Arc::new(RecordingTracer::default()),
+            );
+            a.set_tracer(Some(trace_a.clone()));
+            b.set_tracer(Some(trace_b.clone()));
+            let pid_a = Some(PeerPtr(0).get(&a).pidt().unwrap());
+            let pid_b = Some(PeerPtr(0).get(&b).pidt().unwrap());
+
+            let (mut a_buf, mut b_buf) = (MsgBufPlus::zero(), MsgBufPlus::zero());
+            let init_hello_len = a.initiate_handshake(PeerPtr(0), &mut *a_buf).unwrap();
+            let resp_hello_len = b
+                .handle_msg(&a_buf[..init_hello_len], &mut *b_buf)
+                .unwrap()
+                .resp
+                .unwrap();
+            let resp_hello = b_buf.clone();
+            let init_conf_len = a
+                .handle_msg(&b_buf[..resp_hello_len], &mut *a_buf)
+                .unwrap()
+                .resp
+                .unwrap();
+            let empty_data_len = b
+                .handle_msg(&a_buf[..init_conf_len], &mut *b_buf)
+                .unwrap()
+                .resp
+                .unwrap();
+            a.handle_msg(&b_buf[..empty_data_len], &mut *a_buf).unwrap();
+
+            let ses_a = PeerPtr(0).session().get(&a).as_ref().unwrap();
+            let ses_b = PeerPtr(0).session().get(&b).as_ref().unwrap();
+            let (sidi, sidr) = (ses_a.sidm, ses_b.sidm);
+
+            let events_a = trace_a.take();
+            assert_eq!(
+                events_a,
+                [
+                    HandshakeEvent::MessageReceived {
+                        msg_type: Some(MsgType::RespHello),
+                        len: resp_hello_len
+                    },
+                    HandshakeEvent::SealChecked {
+                        msg_type: MsgType::RespHello,
+                        valid: true
+                    },
+                    HandshakeEvent::SessionEntered {
+                        peer: pid_a,
+                        sidm: sidi,
+                        sidt: sidr,
+                        role: HandshakeRole::Initiator
+                    },
+                    HandshakeEvent::MessageReceived {
+                        msg_type: Some(MsgType::EmptyData),
+                        len: empty_data_len
+                    },
+                    HandshakeEvent::SealChecked {
+                        msg_type: MsgType::EmptyData,
+                        valid: true
+                    },
+                ]
+            );
+
+            let events_b = trace_b.take();
+            assert_eq!(
+                events_b,
+                [
+                    HandshakeEvent::MessageReceived {
+                        msg_type: Some(MsgType::InitHello),
+                        len: init_hello_len
+                    },
+                    HandshakeEvent::SealChecked {
+                        msg_type: MsgType::InitHello,
+                        valid: true
+                    },
+                    HandshakeEvent::MessageReceived {
+                        msg_type: Some(MsgType::InitConf),
+                        len: init_conf_len
+                    },
+                    HandshakeEvent::SealChecked {
+                        msg_type: MsgType::InitConf,
+                        valid: true
+                    },
+                    HandshakeEvent::BiscuitDecrypted {
+                        peer: pid_b,
+                        sidi,
+                        sidr
+                    },
+                    HandshakeEvent::SessionEntered {
+                        peer: pid_b,
+                        sidm: sidr,
+                        sidt: sidi,
+                        role: HandshakeRole::Responder
+                    },
+                ]
+            );
+
+            // The traces must not leak the shared key
+            let osk = a
+                .osk(PeerPtr(0))
+                .unwrap()
+                .secret()
+                .fmt_b64::<64>()
+                .to_string();
+            for ev in events_a.iter().chain(events_b.iter()) {
+                assert!(!serde_json::to_string(ev).unwrap().contains(&osk));
+            }
+
+            // A replayed RespHello is rejected; the reason is traced
+            assert!(a
+                .handle_msg(&resp_hello[..resp_hello_len], &mut *a_buf)
+                .is_err());
+            match trace_a.take().last() {
+                Some(HandshakeEvent::RespHelloFailed {
+                    peer,
+                    sidi: failed_sidi,
+                    reason,
+                    ..
+                }) => {
+                    assert_eq!(*peer, pid_a);
+                    assert_eq!(*failed_sidi, sidi);
+                    assert!(reason.contains("Unexpected package"));
+                }
+                ev => panic!("Expected RespHelloFailed, got {ev:?}"),
+            }
+        });
+    }
 }
@@ -0,0 +1,569 @@
+//! Persisting [CryptoServer] state across restarts
+//!
+//! Restarting the Rosenpass daemon would normally discard every established [Session] and the
+//! [super::BiscuitKey]s, forcing all peers to perform a full handshake. This module provides an
+//! encrypted, versioned snapshot format that can be written on shutdown and restored on startup.
+//!
+//! # Format
+//!
+//! The snapshot consists of a public header, which is used as additional data, followed
+//! by a random nonce and the XChaCha20-Poly1305 encrypted state:
+//!
+//! ```text
+//! snapshot := header | nonce (24) | payload
+//! header   := magic (8) | version u32 | pidm (32) | written_at f64
+//! payload  := xaead(biscuit_ctr | biscuit_key{2} | n_peers u32 | peer{n_peers})
+//! biscuit_key := present u8 | age f64 | value (32)
+//! peer     := pidt (32) | biscuit_used (12) | has_session u8 | session?
+//! session  := age f64 | sidm (4) | sidt (4) | role u8 | ck (32) | txkm (32) | txkt (32)
+//!             | txnm u64 | txnt u64
+//! ```
+//!
+//! All integers are little endian. Timestamps are stored as ages (seconds before
+//! `written_at`) because [CryptoServer::timebase] is not meaningful across process restarts;
+//! `written_at` is taken from the wall clock.
+//!
+//! The encryption key is derived from our static secret key through
+//! [hash_domains::state_snapshot], so a snapshot can only be restored with the key pair that
+//! produced it.
+//!
+//! # Expiry
+//!
+//! Restoring is strict: Sessions older than the peer's [super::TimingProfile::reject_after_time]
+//! (by default [super::REJECT_AFTER_TIME]) and biscuit keys older than
+//! twice the [BISCUIT_EPOCH] are discarded, and a snapshot that seems to originate from the future
+//! (i.e. the wall clock went backwards) is rejected entirely.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, ensure, Context, Result};
+use rosenpass_ciphers::hash_domain::SecretHashDomain;
+use rosenpass_ciphers::{xaead, KEY_LEN};
+use zeroize::Zeroizing;
+
+use crate::hash_domains;
+use crate::msgs::{BISCUIT_ID_LEN, SESSION_ID_LEN};
+
+use super::{
+    BiscuitId, BiscuitKeyPtr, CryptoServer, HandshakeRole, Mortal, PeerId, PeerPtr, Session,
+    SessionId, SuiteId, SymKey, Timing, BISCUIT_EPOCH,
+};
+
+/// Magic bytes at the start of every snapshot
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"RPSTATE\0";
+
+/// The current version of the snapshot format
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Length of the public snapshot header
+const HEADER_LEN: usize = SNAPSHOT_MAGIC.len() + 4 + KEY_LEN + 8;
+
+/// Length of a serialized biscuit key
+const BISCUIT_KEY_LEN: usize = 1 + 8 + KEY_LEN;
+
+/// Length of a serialized peer, excluding its session
+const PEER_LEN: usize = KEY_LEN + BISCUIT_ID_LEN + 1;
+
+/// Length of a serialized session
+const SESSION_LEN: usize = 8 + 2 * SESSION_ID_LEN + 1 + 3 * KEY_LEN + 8 + 8;
+
+/// Summary of the state restored by [CryptoServer::restore_snapshot]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RestoredSnapshot {
+    /// Number of sessions restored
+    pub sessions: usize,
+    /// Number of sessions discarded because they expired or belong to unknown peers
+    pub sessions_discarded: usize,
+    /// Number of biscuit keys restored
+    pub biscuit_keys: usize,
+}
+
+/// Current wall clock time in seconds since the unix epoch
+fn wall_clock_now() -> Result<Timing> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("System clock is set before the unix epoch")?
+        .as_secs_f64())
+}
+
+/// Append-only serializer for the snapshot payload
+///
+/// The buffer is zeroized on drop since it contains secret keys. It is allocated once with
+/// its final size and never grows; growing would leave copies of the secrets written so far
+/// behind in freed memory.
+struct SnapshotWriter(Zeroizing<Vec<u8>>);
+
+impl SnapshotWriter {
+    fn with_capacity(len: usize) -> Self {
+        Self(Zeroizing::new(Vec::with_capacity(len)))
+    }
+
+    fn bytes(&mut self, v: &[u8]) {
+        assert!(
+            self.0.capacity() - self.0.len() >= v.len(),
+            "SnapshotWriter must not reallocate"
+        );
+        self.0.extend_from_slice(v);
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn f64(&mut self, v: f64) {
+        self.bytes(&v.to_le_bytes());
+    }
+}
+
+/// Deserializer counterpart to [SnapshotWriter]
+struct SnapshotReader<'a>(&'a [u8]);
+
+impl<'a> SnapshotReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        ensure!(self.0.len() >= len, "State snapshot is truncated");
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        let v = f64::from_le_bytes(self.array()?);
+        ensure!(
+            v.is_finite(),
+            "State snapshot contains an invalid timestamp"
+        );
+        Ok(v)
+    }
+
+    fn finish(&self) -> Result<()> {
+        ensure!(self.0.is_empty(), "State snapshot contains trailing data");
+        Ok(())
+    }
+}
+
+impl CryptoServer {
+    /// Derive the key used to encrypt state snapshots from [Self::sskm]
+    ///
+    /// Snapshots are not bound to any particular peer, so this always uses the default
+    /// protocol suite.
+    fn snapshot_key(&self) -> Result<SymKey> {
+        Ok(hash_domains::state_snapshot(SuiteId::default())?
+            .turn_secret()
+            .mix(self.sskm.secret())?
+            .into_secret())
+    }
+
+    /// Public header of a snapshot; used as additional data during encryption
+    fn snapshot_header(&self, written_at: Timing) -> Result<[u8; HEADER_LEN]> {
+        let mut w = SnapshotWriter::with_capacity(HEADER_LEN);
+        w.bytes(SNAPSHOT_MAGIC);
+        w.u32(SNAPSHOT_VERSION);
+        w.bytes(&self.pidm()?);
+        w.f64(written_at);
+        Ok(w.0.as_slice().try_into().unwrap())
+    }
+
+    /// Serialize and encrypt the persistent parts of this server's state.
+    ///
+    /// This includes established sessions, [Self::biscuit_ctr], [Self::biscuit_keys],
+    /// and [super::Peer::biscuit_used] for every peer. Ongoing handshakes and cookie
+    /// secrets are not included; they are cheap to recreate.
+    ///
+    /// See the [module](self) documentation for details about the format.
+    ///
+    /// # Examples
+    ///
+    /// See [Self::restore_snapshot].
+    pub fn store_snapshot(&self) -> Result<Vec<u8>> {
+        let now = self.timebase.now();
+        let written_at = wall_clock_now()?;
+
+        let peers: Vec<_> = self.peers.iter().flatten().collect();
+        let sessions = peers.iter().filter(|p| p.session.is_some()).count();
+        let mut w = SnapshotWriter::with_capacity(
+            BISCUIT_ID_LEN
+                + self.biscuit_keys.len() * BISCUIT_KEY_LEN
+                + 4
+                + peers.len() * PEER_LEN
+                + sessions * SESSION_LEN,
+        );
+        w.bytes(&self.biscuit_ctr);
+
+        for ptr in self.biscuit_key_ptrs() {
+            match ptr.created_at(self) {
+                Some(t) => {
+                    w.u8(1);
+                    w.f64(now - t);
+                    w.bytes(ptr.get(self).value.secret());
+                }
+                None => {
+                    w.u8(0);
+                    w.f64(0.0);
+                    w.bytes(&[0u8; KEY_LEN]);
+                }
+            }
+        }
+
+        w.u32(peers.len().try_into()?);
+        for peer in peers {
+            w.bytes(&peer.pidt()?);
+            w.bytes(&peer.biscuit_used);
+            let Some(ses) = peer.session.as_ref() else {
+                w.u8(0);
+                continue;
+            };
+            w.u8(1);
+            w.f64(now - ses.created_at);
+            w.bytes(&ses.sidm);
+            w.bytes(&ses.sidt);
+            w.u8(match ses.handshake_role {
+                HandshakeRole::Initiator => 0,
+                HandshakeRole::Responder => 1,
+            });
+            w.bytes(ses.ck.clone().danger_into_secret().secret());
+            w.bytes(ses.txkm.secret());
+            w.bytes(ses.txkt.secret());
+            w.u64(ses.txnm);
+            w.u64(ses.txnt);
+        }
+
+        let header = self.snapshot_header(written_at)?;
+        let mut buf = vec![0u8; HEADER_LEN + xaead::NONCE_LEN + w.0.len() + xaead::TAG_LEN];
+        let (hdr, ct) = buf.split_at_mut(HEADER_LEN);
+        hdr.copy_from_slice(&header);
+
+        let nonce = rand::random::<[u8; xaead::NONCE_LEN]>();
+        xaead::encrypt(ct, self.snapshot_key()?.secret(), &nonce, &header, &w.0)?;
+
+        Ok(buf)
+    }
+
+    /// Decrypt a snapshot produced by [Self::store_snapshot] and load it into this server.
+    ///
+    /// The server must be freshly constructed (peers may be added, but no sessions
+    /// established and no biscuit keys generated). Peers are matched by their peer id;
+    /// sessions for peers that are no longer configured are discarded, as are any sessions or
+    /// keys that have expired since the snapshot was written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::DerefMut;
+    /// use rosenpass_cipher_traits::Kem;
+    /// use rosenpass_ciphers::kem::StaticKem;
+    /// use rosenpass::protocol::{SSk, SPk, CryptoServer};
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (mut sskm, mut spkm) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm.secret_mut(), spkm.deref_mut())?;
+    /// let srv = CryptoServer::new(sskm.clone(), spkm.clone());
+    ///
+    /// let snapshot = srv.store_snapshot()?;
+    ///
+    /// let mut restored = CryptoServer::new(sskm, spkm);
+    /// restored.restore_snapshot(&snapshot)?;
+    /// assert_eq!(restored.biscuit_ctr, srv.biscuit_ctr);
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn restore_snapshot(&mut self, snapshot: &[u8]) -> Result<RestoredSnapshot> {
+        ensure!(
+            self.peers
+                .iter()
+                .flatten()
+                .all(|p| p.session.is_none() && p.handshake.is_none()),
+            "State snapshots can only be restored into a fresh CryptoServer"
+        );
+        ensure!(
+            self.biscuit_key_ptrs()
+                .all(|k| k.created_at(self).is_none()),
+            "State snapshots can only be restored into a fresh CryptoServer"
+        );
+
+        ensure!(
+            snapshot.len() >= HEADER_LEN + xaead::NONCE_LEN + xaead::TAG_LEN,
+            "State snapshot is truncated"
+        );
+        let (header, ct) = snapshot.split_at(HEADER_LEN);
+
+        let mut r = SnapshotReader(header);
+        ensure!(
+            r.bytes(SNAPSHOT_MAGIC.len())? == SNAPSHOT_MAGIC,
+            "Not a Rosenpass state snapshot"
+        );
+        let version = r.u32()?;
+        ensure!(
+            version == SNAPSHOT_VERSION,
+            "Unsupported state snapshot version {version}; expected {SNAPSHOT_VERSION}"
+        );
+        ensure!(
+            r.bytes(KEY_LEN)? == &self.pidm()?[..],
+            "State snapshot was created for a different key pair"
+        );
+        let written_at = r.f64()?;
+
+        // Strict expiry checks: If the clock went backwards, we can not tell how old the
+        // state is, so we must not use it.
+        let elapsed = wall_clock_now()? - written_at;
+        ensure!(
+            elapsed >= 0.0,
+            "State snapshot was written in the future; refusing to use it"
+        );
+
+        let mut pt = Zeroizing::new(vec![0u8; ct.len() - xaead::NONCE_LEN - xaead::TAG_LEN]);
+        xaead::decrypt(&mut pt, self.snapshot_key()?.secret(), header, ct)
+            .context("Could not decrypt state snapshot")?;
+        let mut r = SnapshotReader(&pt);
+
+        // Parse everything before modifying our own state
+        let biscuit_ctr = BiscuitId::new(r.array::<BISCUIT_ID_LEN>()?);
+
+        let mut biscuit_keys = Vec::new();
+        for ptr in self.biscuit_key_ptrs() {
+            let present = r.u8()? != 0;
+            let age = r.f64()? + elapsed;
+            let value = SymKey::from_slice(r.bytes(KEY_LEN)?);
+            if present && age >= 0.0 && age < 2.0 * BISCUIT_EPOCH {
+                biscuit_keys.push((ptr, age, value));
+            }
+        }
+
+        let mut peers = Vec::new();
+        for _ in 0..r.u32()? {
+            let pidt = PeerId::new(r.array::<KEY_LEN>()?);
+            let biscuit_used = BiscuitId::new(r.array::<BISCUIT_ID_LEN>()?);
+            let session = match r.u8()? {
+                0 => None,
+                _ => {
+                    let age = r.f64()? + elapsed;
+                    let sidm = SessionId::new(r.array::<SESSION_ID_LEN>()?);
+                    let sidt = SessionId::new(r.array::<SESSION_ID_LEN>()?);
+                    let handshake_role = match r.u8()? {
+                        0 => HandshakeRole::Initiator,
+                        1 => HandshakeRole::Responder,
+                        role => bail!("State snapshot contains invalid handshake role {role}"),
+                    };
+                    let ck =
+                        SecretHashDomain::danger_from_secret(SymKey::from_slice(r.bytes(KEY_LEN)?))
+                            .dup();
+                    let txkm = SymKey::from_slice(r.bytes(KEY_LEN)?);
+                    let txkt = SymKey::from_slice(r.bytes(KEY_LEN)?);
+                    let txnm = r.u64()?;
+                    let txnt = r.u64()?;
+                    let ses = Session {
+                        created_at: 0.0,
+                        sidm,
+                        sidt,
+                        handshake_role,
+                        ck,
+                        txkm,
+                        txkt,
+                        txnm,
+                        txnt,
+                    };
+                    Some((age, ses))
+                }
+            };
+            peers.push((pidt, biscuit_used, session));
+        }
+        r.finish()?;
+
+        // Make sure the restored timestamps are representable relative to our timebase;
+        // negative creation times are interpreted as "never created".
+        let max_age = biscuit_keys
+            .iter()
+            .map(|(_, age, _)| *age)
+            .chain(
+                peers
+                    .iter()
+                    .filter_map(|(_, _, s)| s.as_ref().map(|(age, _)| *age)),
+            )
+            .fold(0.0, f64::max);
+        let shift = max_age - self.timebase.now();
+        if shift > 0.0 {
+            self.timebase.travel_forward(shift);
+        }
+        let now = self.timebase.now();
+
+        let mut stats = RestoredSnapshot::default();
+
+        self.biscuit_ctr = biscuit_ctr;
+        for (ptr, age, value) in biscuit_keys {
+            let BiscuitKeyPtr(idx) = ptr;
+            self.biscuit_keys[idx].value = value;
+            self.biscuit_keys[idx].created_at = now - age;
+            stats.biscuit_keys += 1;
+        }
+
+        for (pidt, biscuit_used, session) in peers {
+            let Some(peer) = self.find_peer(pidt) else {
+                stats.sessions_discarded += session.is_some() as usize;
+                continue;
+            };
+            peer.get_mut(self).biscuit_used = biscuit_used;
+
+            let Some((age, mut ses)) = session else {
+                continue;
+            };
+            if !(0.0..peer.get(self).timing.reject_after_time).contains(&age) {
+                stats.sessions_discarded += 1;
+                continue;
+            }
+            ses.created_at = now - age;
+            // The chaining key must keep using the keyed hash of the peer's protocol suite
+            ses.ck = SecretHashDomain::danger_from_secret_with(
+                ses.ck.danger_into_secret(),
+                peer.get(self).suite.keyed_hash(),
+            )
+            .dup();
+            peer.session().insert(self, ses)?;
+            stats.sessions += 1;
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::DerefMut;
+
+    use rosenpass_cipher_traits::Kem;
+    use rosenpass_ciphers::kem::StaticKem;
+    use serial_test::serial;
+
+    use super::super::testutils::time_travel_forward;
+    use super::super::{MsgBuf, SPk, SSk, REJECT_AFTER_TIME};
+    use super::*;
+
+    fn keygen() -> Result<(SSk, SPk)> {
+        let (mut sk, mut pk) = (SSk::zero(), SPk::zero());
+        StaticKem::keygen(sk.secret_mut(), pk.deref_mut())?;
+        Ok((sk, pk))
+    }
+
+    /// Perform a full handshake between two fresh servers, returning the servers
+    /// and the key pair of the first one
+    fn exchanged_pair() -> Result<((SSk, SPk), CryptoServer, CryptoServer)> {
+        let psk = SymKey::random();
+        let ((ska, pka), (skb, pkb)) = (keygen()?, keygen()?);
+        let mut a = CryptoServer::new(ska.clone(), pka.clone());
+        let mut b = CryptoServer::new(skb, pkb.clone());
+        a.add_peer(Some(psk.clone()), pkb)?;
+        b.add_peer(Some(psk), pka.clone())?;
+
+        let (mut a_buf, mut b_buf) = (MsgBuf::zero(), MsgBuf::zero());
+        let mut maybe_len = Some(a.initiate_handshake(PeerPtr(0), &mut *a_buf)?);
+        let mut a_turn = false;
+        while let Some(len) = maybe_len {
+            maybe_len = match a_turn {
+                true => a.handle_msg(&b_buf[..len], &mut *a_buf)?.resp,
+                false => b.handle_msg(&a_buf[..len], &mut *b_buf)?.resp,
+            };
+            a_turn = !a_turn;
+        }
+
+        Ok(((ska, pka), a, b))
+    }
+
+    #[test]
+    #[serial]
+    fn snapshot_roundtrip() {
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            let ((sk, pk), a, b) = exchanged_pair().unwrap();
+            let snapshot = a.store_snapshot().unwrap();
+
+            let mut restored = CryptoServer::new(sk, pk);
+            restored.add_peer(None, b.spkm.clone()).unwrap();
+            let stats = restored.restore_snapshot(&snapshot).unwrap();
+
+            assert_eq!(stats.sessions, 1);
+            assert_eq!(stats.sessions_discarded, 0);
+            assert_eq!(restored.biscuit_ctr, a.biscuit_ctr);
+            assert_eq!(
+                restored.osk(PeerPtr(0)).unwrap().secret(),
+                b.osk(PeerPtr(0)).unwrap().secret()
+            );
+
+            let sidm = PeerPtr(0).session().get(&restored).as_ref().unwrap().sidm;
+            assert_eq!(
+                restored.lookup_session(sidm).map(|s| s.peer()),
+                Some(PeerPtr(0))
+            );
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn snapshot_rejects_foreign_key() {
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            let (_, a, _) = exchanged_pair().unwrap();
+            let snapshot = a.store_snapshot().unwrap();
+
+            let (sk, pk) = keygen().unwrap();
+            let mut other = CryptoServer::new(sk, pk);
+            assert!(other.restore_snapshot(&snapshot).is_err());
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn snapshot_rejects_tampering() {
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            let ((sk, pk), a, _) = exchanged_pair().unwrap();
+            let mut snapshot = a.store_snapshot().unwrap();
+            *snapshot.last_mut().unwrap() ^= 1;
+
+            let mut restored = CryptoServer::new(sk, pk);
+            assert!(restored.restore_snapshot(&snapshot).is_err());
+        });
+    }
+
+    #[test]
+    #[serial]
+    fn snapshot_discards_expired_sessions() {
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        stacker::grow(8 * 1024 * 1024, || {
+            let ((sk, pk), mut a, b) = exchanged_pair().unwrap();
+            time_travel_forward(&mut a, REJECT_AFTER_TIME + 1.0);
+            let snapshot = a.store_snapshot().unwrap();
+
+            let mut restored = CryptoServer::new(sk, pk);
+            restored.add_peer(None, b.spkm.clone()).unwrap();
+            let stats = restored.restore_snapshot(&snapshot).unwrap();
+
+            assert_eq!(stats.sessions, 0);
+            assert_eq!(stats.sessions_discarded, 1);
+            assert!(PeerPtr(0).session().get(&restored).is_none());
+            assert_eq!(restored.biscuit_ctr, a.biscuit_ctr);
+        });
+    }
+}
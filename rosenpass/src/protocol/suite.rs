@@ -0,0 +1,621 @@
+//! Protocol suites
+//!
+//! A protocol suite bundles the cryptographic algorithms used during a handshake: the static and
+//! the ephemeral key encapsulation mechanism, the AEAD, and the hash function. Each suite has its
+//! own protocol identifier ([ProtocolSuite::PROTOCOL_ID]); it is mixed into every hash domain
+//! (see [crate::hash_domains::protocol]), so handshakes using different suites can never be
+//! confused with each other.
+//!
+//! The suite used for a handshake is selected per peer (see [CryptoServer::set_peer_suite]) and
+//! transmitted in [crate::msgs::Envelope::suite], so a responder can serve peers using different
+//! suites at the same time. This allows migrating a deployment to new algorithms peer by peer.
+//!
+//! All suites supported by a build share the same message sizes; see
+//! [rosenpass_ciphers::kem]. The AEADs are currently the same for all suites; unit tests add a
+//! suite using another AEAD for the handshake messages, to make sure the handshake really uses
+//! [ProtocolSuite::Aead].
+//!
+//! Version one of the protocol uses [KeyedHashChoice::IncorrectHmacBlake2b] for all hash
+//! domains; version two replaces it with a proper HMAC ([KeyedHashChoice::HmacBlake2b]), or with
+//! [KeyedHashChoice::Shake256] in the `-shake256` suites. Otherwise the versions are identical.
+
+use std::{convert::Infallible, fmt::Display, str::FromStr};
+
+use anyhow::{bail, ensure, Result};
+use rosenpass_cipher_traits::{Aead, Kem};
+use rosenpass_ciphers::aead::ChaCha20Poly1305;
+use rosenpass_ciphers::kem::{EphemeralKem, StaticKem};
+use rosenpass_ciphers::keyed_hash::KeyedHashChoice;
+use rosenpass_ciphers::xaead::XChaCha20Poly1305;
+use rosenpass_util::mem::DiscardResultExt;
+use serde::{Deserialize, Serialize};
+use static_assertions::const_assert_eq;
+
+use super::{CryptoServer, IndexKey, PeerPtr};
+use crate::RosenpassError;
+
+/// A bundle of cryptographic algorithms used for the Rosenpass handshake
+///
+/// The trait is implemented by marker types; [SuiteId] is the runtime representation used to
+/// dispatch to a particular suite.
+pub trait ProtocolSuite {
+    /// The runtime identifier of this suite
+    const ID: SuiteId;
+    /// The protocol string; the root of all hash domains used with this suite
+    const PROTOCOL_ID: &'static str;
+    /// The keyed hash used for all hash domains with this suite
+    const KEYED_HASH: KeyedHashChoice;
+    /// The key encapsulation mechanism used with the long-term keys
+    type StaticKem: Kem<Error = Infallible>;
+    /// The key encapsulation mechanism used with the per-handshake keys
+    type EphemeralKem: Kem<Error = Infallible>;
+    /// The AEAD used to encrypt handshake messages
+    type Aead: Aead<Error = anyhow::Error>;
+    /// The AEAD used with random nonces, i.e. to encrypt biscuits and cookie replies
+    type BiscuitAead: Aead<Error = anyhow::Error>;
+}
+
+/// Rosenpass v1 using Kyber512 (round 3) as the ephemeral KEM; the original protocol
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+pub enum RosenpassV1Kyber512 {}
+
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+impl ProtocolSuite for RosenpassV1Kyber512 {
+    const ID: SuiteId = SuiteId::RosenpassV1Kyber512;
+    const PROTOCOL_ID: &'static str = "Rosenpass v1 mceliece460896 Kyber512 ChaChaPoly1305 BLAKE2s";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::IncorrectHmacBlake2b;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::Kyber512;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v1 using FIPS 203 ML-KEM-512 as the ephemeral KEM
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+pub enum RosenpassV1MlKem512 {}
+
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+impl ProtocolSuite for RosenpassV1MlKem512 {
+    const ID: SuiteId = SuiteId::RosenpassV1MlKem512;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v1 mceliece460896 ML-KEM-512 ChaChaPoly1305 BLAKE2s";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::IncorrectHmacBlake2b;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::MlKem512;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v1 using FIPS 203 ML-KEM-768 as the ephemeral KEM
+///
+/// ML-KEM-768 uses larger keys and ciphertexts than the other suites, so this is only available
+/// in builds using the `experiment_ml_kem_768` feature.
+#[cfg(feature = "experiment_ml_kem_768")]
+pub enum RosenpassV1MlKem768 {}
+
+#[cfg(feature = "experiment_ml_kem_768")]
+impl ProtocolSuite for RosenpassV1MlKem768 {
+    const ID: SuiteId = SuiteId::RosenpassV1MlKem768;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v1 mceliece460896 ML-KEM-768 ChaChaPoly1305 BLAKE2s";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::IncorrectHmacBlake2b;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::MlKem768;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v2 using Kyber512 (round 3) as the ephemeral KEM
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+pub enum RosenpassV2Kyber512 {}
+
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+impl ProtocolSuite for RosenpassV2Kyber512 {
+    const ID: SuiteId = SuiteId::RosenpassV2Kyber512;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v2 mceliece460896 Kyber512 ChaChaPoly1305 HMAC-BLAKE2b";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::HmacBlake2b;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::Kyber512;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v2 using FIPS 203 ML-KEM-512 as the ephemeral KEM
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+pub enum RosenpassV2MlKem512 {}
+
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+impl ProtocolSuite for RosenpassV2MlKem512 {
+    const ID: SuiteId = SuiteId::RosenpassV2MlKem512;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v2 mceliece460896 ML-KEM-512 ChaChaPoly1305 HMAC-BLAKE2b";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::HmacBlake2b;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::MlKem512;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v2 using FIPS 203 ML-KEM-768 as the ephemeral KEM
+///
+/// Only available in builds using the `experiment_ml_kem_768` feature;
+/// see [RosenpassV1MlKem768].
+#[cfg(feature = "experiment_ml_kem_768")]
+pub enum RosenpassV2MlKem768 {}
+
+#[cfg(feature = "experiment_ml_kem_768")]
+impl ProtocolSuite for RosenpassV2MlKem768 {
+    const ID: SuiteId = SuiteId::RosenpassV2MlKem768;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v2 mceliece460896 ML-KEM-768 ChaChaPoly1305 HMAC-BLAKE2b";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::HmacBlake2b;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::MlKem768;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v2 using Kyber512 (round 3) as the ephemeral KEM and SHAKE256 as the keyed hash
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+pub enum RosenpassV2Kyber512Shake256 {}
+
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+impl ProtocolSuite for RosenpassV2Kyber512Shake256 {
+    const ID: SuiteId = SuiteId::RosenpassV2Kyber512Shake256;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v2 mceliece460896 Kyber512 ChaChaPoly1305 SHAKE256";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::Shake256;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::Kyber512;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v2 using FIPS 203 ML-KEM-512 as the ephemeral KEM and SHAKE256 as the keyed hash
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+pub enum RosenpassV2MlKem512Shake256 {}
+
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+impl ProtocolSuite for RosenpassV2MlKem512Shake256 {
+    const ID: SuiteId = SuiteId::RosenpassV2MlKem512Shake256;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v2 mceliece460896 ML-KEM-512 ChaChaPoly1305 SHAKE256";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::Shake256;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::MlKem512;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Rosenpass v2 using FIPS 203 ML-KEM-768 as the ephemeral KEM and SHAKE256 as the keyed hash
+///
+/// Only available in builds using the `experiment_ml_kem_768` feature;
+/// see [RosenpassV1MlKem768].
+#[cfg(feature = "experiment_ml_kem_768")]
+pub enum RosenpassV2MlKem768Shake256 {}
+
+#[cfg(feature = "experiment_ml_kem_768")]
+impl ProtocolSuite for RosenpassV2MlKem768Shake256 {
+    const ID: SuiteId = SuiteId::RosenpassV2MlKem768Shake256;
+    const PROTOCOL_ID: &'static str =
+        "Rosenpass v2 mceliece460896 ML-KEM-768 ChaChaPoly1305 SHAKE256";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::Shake256;
+    type StaticKem = StaticKem;
+    type EphemeralKem = rosenpass_ciphers::kem::MlKem768;
+    type Aead = ChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// A suite only available in unit tests, using XChaCha20-Poly1305 for the handshake messages
+///
+/// Otherwise identical to the default suite using [KeyedHashChoice::HmacBlake2b].
+#[cfg(test)]
+pub enum TestXChaCha20Poly1305 {}
+
+#[cfg(test)]
+impl ProtocolSuite for TestXChaCha20Poly1305 {
+    const ID: SuiteId = SuiteId::TestXChaCha20Poly1305;
+    const PROTOCOL_ID: &'static str = "Rosenpass test mceliece460896 XChaChaPoly1305 HMAC-BLAKE2b";
+    const KEYED_HASH: KeyedHashChoice = KeyedHashChoice::HmacBlake2b;
+    type StaticKem = StaticKem;
+    type EphemeralKem = EphemeralKem;
+    type Aead = XChaCha20Poly1305;
+    type BiscuitAead = XChaCha20Poly1305;
+}
+
+/// Make sure the suites fit the message sizes in [crate::msgs]
+macro_rules! assert_wire_compatible {
+    ($suite:ty) => {
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::StaticKem as Kem>::CT_LEN,
+            StaticKem::CT_LEN
+        );
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::EphemeralKem as Kem>::PK_LEN,
+            EphemeralKem::PK_LEN
+        );
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::EphemeralKem as Kem>::SK_LEN,
+            EphemeralKem::SK_LEN
+        );
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::EphemeralKem as Kem>::CT_LEN,
+            EphemeralKem::CT_LEN
+        );
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::EphemeralKem as Kem>::SHK_LEN,
+            EphemeralKem::SHK_LEN
+        );
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::Aead as Aead>::TAG_LEN,
+            rosenpass_ciphers::aead::TAG_LEN
+        );
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::BiscuitAead as Aead>::NONCE_LEN,
+            rosenpass_ciphers::xaead::NONCE_LEN
+        );
+        const_assert_eq!(
+            <<$suite as ProtocolSuite>::BiscuitAead as Aead>::TAG_LEN,
+            rosenpass_ciphers::xaead::TAG_LEN
+        );
+    };
+}
+
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+assert_wire_compatible!(RosenpassV1Kyber512);
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+assert_wire_compatible!(RosenpassV1MlKem512);
+#[cfg(feature = "experiment_ml_kem_768")]
+assert_wire_compatible!(RosenpassV1MlKem768);
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+assert_wire_compatible!(RosenpassV2Kyber512);
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+assert_wire_compatible!(RosenpassV2MlKem512);
+#[cfg(feature = "experiment_ml_kem_768")]
+assert_wire_compatible!(RosenpassV2MlKem768);
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+assert_wire_compatible!(RosenpassV2Kyber512Shake256);
+#[cfg(not(feature = "experiment_ml_kem_768"))]
+assert_wire_compatible!(RosenpassV2MlKem512Shake256);
+#[cfg(feature = "experiment_ml_kem_768")]
+assert_wire_compatible!(RosenpassV2MlKem768Shake256);
+#[cfg(test)]
+assert_wire_compatible!(TestXChaCha20Poly1305);
+
+/// Runtime identifier of a [ProtocolSuite]
+///
+/// This is transmitted in [crate::msgs::Envelope::suite]. The default suite is the one selected
+/// at build time through the `experiment_ml_kem_*` features (see [rosenpass_ciphers::kem]); it
+/// uses suite id zero, so builds without these features stay compatible with peers that do
+/// not know about protocol suites.
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::protocol::SuiteId;
+///
+/// for suite in SuiteId::all() {
+///     let id: u8 = suite.into();
+///     assert_eq!(SuiteId::try_from(id)?, suite);
+///     assert_eq!(suite.to_string().parse::<SuiteId>()?, suite);
+/// }
+///
+/// assert!(SuiteId::try_from(0xff).is_err());
+/// assert!(SuiteId::default().protocol_id().contains(rosenpass_ciphers::kem::EPHEMERAL_KEM_ID));
+///
+/// Ok::<(), anyhow::Error>(())
+/// ```
+#[repr(u8)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(try_from = "String", into = "String")]
+pub enum SuiteId {
+    /// See [RosenpassV1Kyber512]
+    #[cfg(not(feature = "experiment_ml_kem_768"))]
+    #[cfg_attr(not(feature = "experiment_ml_kem_512"), default)]
+    RosenpassV1Kyber512 = 0,
+    /// See [RosenpassV1MlKem512]
+    #[cfg(not(feature = "experiment_ml_kem_768"))]
+    #[cfg_attr(feature = "experiment_ml_kem_512", default)]
+    RosenpassV1MlKem512 = 1,
+    /// See [RosenpassV1MlKem768]
+    #[cfg(feature = "experiment_ml_kem_768")]
+    #[default]
+    RosenpassV1MlKem768 = 2,
+    /// See [RosenpassV2Kyber512]
+    #[cfg(not(feature = "experiment_ml_kem_768"))]
+    RosenpassV2Kyber512 = 3,
+    /// See [RosenpassV2MlKem512]
+    #[cfg(not(feature = "experiment_ml_kem_768"))]
+    RosenpassV2MlKem512 = 4,
+    /// See [RosenpassV2MlKem768]
+    #[cfg(feature = "experiment_ml_kem_768")]
+    RosenpassV2MlKem768 = 5,
+    /// See [RosenpassV2Kyber512Shake256]
+    #[cfg(not(feature = "experiment_ml_kem_768"))]
+    RosenpassV2Kyber512Shake256 = 6,
+    /// See [RosenpassV2MlKem512Shake256]
+    #[cfg(not(feature = "experiment_ml_kem_768"))]
+    RosenpassV2MlKem512Shake256 = 7,
+    /// See [RosenpassV2MlKem768Shake256]
+    #[cfg(feature = "experiment_ml_kem_768")]
+    RosenpassV2MlKem768Shake256 = 8,
+    /// See [TestXChaCha20Poly1305]
+    #[cfg(test)]
+    TestXChaCha20Poly1305 = 0xf0,
+}
+
+/// Execute some code with a type alias bound to the [ProtocolSuite] identified by a [SuiteId]
+///
+/// ```ignore
+/// with_suite!(suite, S => <S as ProtocolSuite>::PROTOCOL_ID)
+/// ```
+macro_rules! with_suite {
+    ($suite:expr, $S:ident => $body:expr) => {
+        match $suite {
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            $crate::protocol::SuiteId::RosenpassV1Kyber512 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV1Kyber512;
+                $body
+            }
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            $crate::protocol::SuiteId::RosenpassV1MlKem512 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV1MlKem512;
+                $body
+            }
+            #[cfg(feature = "experiment_ml_kem_768")]
+            $crate::protocol::SuiteId::RosenpassV1MlKem768 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV1MlKem768;
+                $body
+            }
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            $crate::protocol::SuiteId::RosenpassV2Kyber512 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV2Kyber512;
+                $body
+            }
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            $crate::protocol::SuiteId::RosenpassV2MlKem512 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV2MlKem512;
+                $body
+            }
+            #[cfg(feature = "experiment_ml_kem_768")]
+            $crate::protocol::SuiteId::RosenpassV2MlKem768 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV2MlKem768;
+                $body
+            }
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            $crate::protocol::SuiteId::RosenpassV2Kyber512Shake256 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV2Kyber512Shake256;
+                $body
+            }
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            $crate::protocol::SuiteId::RosenpassV2MlKem512Shake256 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV2MlKem512Shake256;
+                $body
+            }
+            #[cfg(feature = "experiment_ml_kem_768")]
+            $crate::protocol::SuiteId::RosenpassV2MlKem768Shake256 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::RosenpassV2MlKem768Shake256;
+                $body
+            }
+            #[cfg(test)]
+            $crate::protocol::SuiteId::TestXChaCha20Poly1305 => {
+                #[allow(dead_code)]
+                type $S = $crate::protocol::TestXChaCha20Poly1305;
+                $body
+            }
+        }
+    };
+}
+pub(crate) use with_suite;
+
+impl SuiteId {
+    /// All suites supported by this build
+    pub fn all() -> impl Iterator<Item = SuiteId> {
+        [
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV1Kyber512,
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV1MlKem512,
+            #[cfg(feature = "experiment_ml_kem_768")]
+            SuiteId::RosenpassV1MlKem768,
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2Kyber512,
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2MlKem512,
+            #[cfg(feature = "experiment_ml_kem_768")]
+            SuiteId::RosenpassV2MlKem768,
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2Kyber512Shake256,
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2MlKem512Shake256,
+            #[cfg(feature = "experiment_ml_kem_768")]
+            SuiteId::RosenpassV2MlKem768Shake256,
+            #[cfg(test)]
+            SuiteId::TestXChaCha20Poly1305,
+        ]
+        .into_iter()
+    }
+
+    /// The name of the suite as used in the configuration file
+    pub fn name(self) -> &'static str {
+        match self {
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV1Kyber512 => "rosenpass-v1-kyber512",
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV1MlKem512 => "rosenpass-v1-ml-kem-512",
+            #[cfg(feature = "experiment_ml_kem_768")]
+            SuiteId::RosenpassV1MlKem768 => "rosenpass-v1-ml-kem-768",
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2Kyber512 => "rosenpass-v2-kyber512",
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2MlKem512 => "rosenpass-v2-ml-kem-512",
+            #[cfg(feature = "experiment_ml_kem_768")]
+            SuiteId::RosenpassV2MlKem768 => "rosenpass-v2-ml-kem-768",
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2Kyber512Shake256 => "rosenpass-v2-kyber512-shake256",
+            #[cfg(not(feature = "experiment_ml_kem_768"))]
+            SuiteId::RosenpassV2MlKem512Shake256 => "rosenpass-v2-ml-kem-512-shake256",
+            #[cfg(feature = "experiment_ml_kem_768")]
+            SuiteId::RosenpassV2MlKem768Shake256 => "rosenpass-v2-ml-kem-768-shake256",
+            #[cfg(test)]
+            SuiteId::TestXChaCha20Poly1305 => "test-xchacha20poly1305",
+        }
+    }
+
+    /// See [ProtocolSuite::PROTOCOL_ID]
+    pub fn protocol_id(self) -> &'static str {
+        with_suite!(self, S => <S as ProtocolSuite>::PROTOCOL_ID)
+    }
+
+    /// See [ProtocolSuite::KEYED_HASH]
+    pub fn keyed_hash(self) -> KeyedHashChoice {
+        with_suite!(self, S => <S as ProtocolSuite>::KEYED_HASH)
+    }
+
+    /// Generate an ephemeral key pair using [ProtocolSuite::EphemeralKem]
+    pub fn ephemeral_keygen(self, sk: &mut [u8], pk: &mut [u8]) -> Result<()> {
+        with_suite!(self, S => <<S as ProtocolSuite>::EphemeralKem as Kem>::keygen(sk, pk)?);
+        Ok(())
+    }
+}
+
+impl TryFrom<u8> for SuiteId {
+    type Error = RosenpassError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        SuiteId::all()
+            .find(|s| *s as u8 == value)
+            .ok_or(RosenpassError::InvalidSuite(value))
+    }
+}
+
+impl From<SuiteId> for u8 {
+    fn from(value: SuiteId) -> Self {
+        value as u8
+    }
+}
+
+impl Display for SuiteId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SuiteId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match SuiteId::all().find(|suite| suite.name() == s) {
+            Some(suite) => Ok(suite),
+            None => bail!(
+                "Unknown protocol suite \"{s}\"; supported suites: {}",
+                SuiteId::all()
+                    .map(SuiteId::name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl TryFrom<String> for SuiteId {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<SuiteId> for String {
+    fn from(value: SuiteId) -> Self {
+        value.name().to_owned()
+    }
+}
+
+impl CryptoServer {
+    /// Change the [ProtocolSuite] used for the handshakes with a peer
+    ///
+    /// The peer id depends on the suite, so the peer is re-indexed. Any ongoing handshake,
+    /// session, and cached response of the peer is erased; the next handshake uses the new suite.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::DerefMut;
+    /// use rosenpass::protocol::{SSk, SPk, CryptoServer, SuiteId};
+    /// use rosenpass_ciphers::kem::StaticKem;
+    /// use rosenpass_cipher_traits::Kem;
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (mut sskm, mut spkm) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm.secret_mut(), spkm.deref_mut())?;
+    /// let mut srv = CryptoServer::new(sskm, spkm);
+    ///
+    /// let (mut sskt, mut spkt) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskt.secret_mut(), spkt.deref_mut())?;
+    /// let peer = srv.add_peer(None, spkt)?;
+    /// assert_eq!(peer.get(&srv).suite, SuiteId::default());
+    ///
+    /// for suite in SuiteId::all() {
+    ///     srv.set_peer_suite(peer, suite)?;
+    ///     assert_eq!(peer.get(&srv).suite, suite);
+    ///     assert_eq!(srv.find_peer(peer.get(&srv).pidt()?), Some(peer));
+    /// }
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn set_peer_suite(&mut self, peer: PeerPtr, suite: SuiteId) -> Result<()> {
+        ensure!(
+            peer.exists(self),
+            "Cannot set protocol suite for peer {peer:?}; no such peer"
+        );
+        if peer.get(self).suite == suite {
+            return Ok(());
+        }
+
+        let old_id = peer.get(self).pidt()?;
+        let new_id = peer.get(self).pidt_for(suite)?;
+        ensure!(
+            self.find_peer(new_id).is_none(),
+            "Cannot set protocol suite for peer {peer:?}; peer id {new_id:?} already registered."
+        );
+
+        peer.session().take(self).discard_result();
+        peer.hs().take(self).discard_result();
+        peer.known_init_conf_response()
+            .remove(self)
+            .discard_result();
+
+        self.index.remove(&IndexKey::Peer(old_id));
+        self.index.insert(IndexKey::Peer(new_id), peer.0);
+        peer.get_mut(self).suite = suite;
+
+        Ok(())
+    }
+
+    /// Make sure the given peer is configured to use the protocol suite `suite`
+    ///
+    /// Used while processing incoming handshake messages, to make sure a peer can not be
+    /// downgraded to a different suite.
+    pub(crate) fn ensure_peer_suite(&self, peer: PeerPtr, suite: SuiteId) -> Result<()> {
+        let expected = peer.get(self).suite;
+        ensure!(
+            expected == suite,
+            "Peer {peer:?} uses protocol suite {expected}, but the message uses {suite}"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+//! Per-peer timing parameters
+//!
+//! The protocol's timers default to constants such as [REKEY_AFTER_TIME_RESPONDER] or
+//! [REJECT_AFTER_TIME]. These are sensible for most links,
+//! but some deployments need different values: On satellite links, rekeying every two minutes
+//! wastes bandwidth; for high-security peers, rekeying more often may be desirable.
+//!
+//! A [TimingProfile] holds the timers used for a single [super::Peer]; it can be changed through
+//! [CryptoServer::set_peer_timing]. The life time of the server-wide cookie secret can be changed
+//! through [CryptoServer::set_cookie_secret_epoch].
+//!
+//! All values are validated against the safety bounds defined in this module, so that a
+//! configuration mistake can not disable key rotation or turn retransmissions into a flood.
+
+use anyhow::{ensure, Result};
+
+use super::{
+    CryptoServer, PeerPtr, Timing, COOKIE_SECRET_EPOCH, REJECT_AFTER_TIME,
+    REKEY_AFTER_TIME_INITIATOR, REKEY_AFTER_TIME_RESPONDER, RETRANSMIT_DELAY_BEGIN,
+    RETRANSMIT_DELAY_END, RETRANSMIT_DELAY_GROWTH, RETRANSMIT_DELAY_JITTER,
+};
+
+/// Smallest permissible rekey interval
+pub const MIN_REKEY_AFTER_TIME: Timing = 10.0;
+/// Largest permissible key life time; i.e. upper bound for [TimingProfile::reject_after_time]
+pub const MAX_REJECT_AFTER_TIME: Timing = 3600.0;
+/// Minimum time between the initiator's rekey attempt and the rejection of the key; this leaves
+/// time to complete a handshake before the old key is erased
+pub const MIN_REJECT_GRACE: Timing = 10.0;
+/// Smallest permissible delay between retransmissions
+pub const MIN_RETRANSMIT_DELAY: Timing = 0.1;
+/// Largest permissible delay between retransmissions
+pub const MAX_RETRANSMIT_DELAY: Timing = 120.0;
+/// Largest permissible [TimingProfile::retransmit_delay_growth]
+pub const MAX_RETRANSMIT_DELAY_GROWTH: Timing = 10.0;
+/// Smallest permissible life time of the cookie secret
+pub const MIN_COOKIE_SECRET_EPOCH: Timing = 10.0;
+/// Largest permissible life time of the cookie secret
+pub const MAX_COOKIE_SECRET_EPOCH: Timing = 3600.0;
+
+/// The timers used for a single peer
+///
+/// Use [Self::default] to obtain the protocol defaults and [Self::validate] to check a
+/// profile against the safety bounds. [CryptoServer::set_peer_timing] refuses invalid profiles.
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::protocol::{TimingProfile, REJECT_AFTER_TIME};
+///
+/// let default = TimingProfile::default();
+/// assert_eq!(default.reject_after_time, REJECT_AFTER_TIME);
+/// assert!(default.validate().is_ok());
+///
+/// // Rekey every ten minutes on a slow link
+/// let slow = TimingProfile {
+///     rekey_after_time_responder: 600.0,
+///     rekey_after_time_initiator: 610.0,
+///     reject_after_time: 660.0,
+///     ..TimingProfile::default()
+/// };
+/// assert!(slow.validate().is_ok());
+///
+/// // Keys must be rejected after the peers attempt to rekey
+/// let broken = TimingProfile {
+///     rekey_after_time_responder: 600.0,
+///     rekey_after_time_initiator: 610.0,
+///     ..TimingProfile::default()
+/// };
+/// assert!(broken.validate().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingProfile {
+    /// Time after which the responder attempts to rekey the session; see [REKEY_AFTER_TIME_RESPONDER]
+    pub rekey_after_time_responder: Timing,
+    /// Time after which the initiator attempts to rekey the session; see [REKEY_AFTER_TIME_INITIATOR]
+    pub rekey_after_time_initiator: Timing,
+    /// Time after which either party rejects the current key; see [REJECT_AFTER_TIME]
+    pub reject_after_time: Timing,
+    /// Initial delay between retransmissions; see [RETRANSMIT_DELAY_BEGIN]
+    pub retransmit_delay_begin: Timing,
+    /// Maximum delay between retransmissions; see [RETRANSMIT_DELAY_END]
+    pub retransmit_delay_end: Timing,
+    /// Factor by which the retransmission delay grows; see [RETRANSMIT_DELAY_GROWTH]
+    pub retransmit_delay_growth: Timing,
+    /// Jitter applied to the retransmission delay; see [RETRANSMIT_DELAY_JITTER]
+    pub retransmit_delay_jitter: Timing,
+}
+
+impl Default for TimingProfile {
+    fn default() -> Self {
+        Self {
+            rekey_after_time_responder: REKEY_AFTER_TIME_RESPONDER,
+            rekey_after_time_initiator: REKEY_AFTER_TIME_INITIATOR,
+            reject_after_time: REJECT_AFTER_TIME,
+            retransmit_delay_begin: RETRANSMIT_DELAY_BEGIN,
+            retransmit_delay_end: RETRANSMIT_DELAY_END,
+            retransmit_delay_growth: RETRANSMIT_DELAY_GROWTH,
+            retransmit_delay_jitter: RETRANSMIT_DELAY_JITTER,
+        }
+    }
+}
+
+impl TimingProfile {
+    /// Check that this profile is within the safety bounds
+    ///
+    /// - All rekey intervals must be at least [MIN_REKEY_AFTER_TIME]
+    /// - The initiator must not rekey before the responder; this is what makes the
+    ///   peers take turns in the initiator role
+    /// - Keys must be rejected at least [MIN_REJECT_GRACE] after the initiator's rekey attempt
+    ///   and no later than [MAX_REJECT_AFTER_TIME]
+    /// - Retransmission delays must lie between [MIN_RETRANSMIT_DELAY] and
+    ///   [MAX_RETRANSMIT_DELAY], the growth factor must be greater than one and at most
+    ///   [MAX_RETRANSMIT_DELAY_GROWTH], and the jitter must lie in `(0, 1]`
+    ///
+    /// # Examples
+    ///
+    /// See [Self].
+    pub fn validate(&self) -> Result<()> {
+        let all = [
+            self.rekey_after_time_responder,
+            self.rekey_after_time_initiator,
+            self.reject_after_time,
+            self.retransmit_delay_begin,
+            self.retransmit_delay_end,
+            self.retransmit_delay_growth,
+            self.retransmit_delay_jitter,
+        ];
+        ensure!(
+            all.iter().all(|t| t.is_finite()),
+            "Timing values must be finite numbers"
+        );
+
+        ensure!(
+            self.rekey_after_time_responder >= MIN_REKEY_AFTER_TIME,
+            "rekey_after_time_responder must be at least {MIN_REKEY_AFTER_TIME}s, got {}s",
+            self.rekey_after_time_responder
+        );
+        ensure!(
+            self.rekey_after_time_initiator >= self.rekey_after_time_responder,
+            "rekey_after_time_initiator ({}s) must not be smaller than rekey_after_time_responder ({}s)",
+            self.rekey_after_time_initiator,
+            self.rekey_after_time_responder
+        );
+        ensure!(
+            self.reject_after_time >= self.rekey_after_time_initiator + MIN_REJECT_GRACE,
+            "reject_after_time ({}s) must exceed rekey_after_time_initiator ({}s) by at least {MIN_REJECT_GRACE}s",
+            self.reject_after_time,
+            self.rekey_after_time_initiator
+        );
+        ensure!(
+            self.reject_after_time <= MAX_REJECT_AFTER_TIME,
+            "reject_after_time must be at most {MAX_REJECT_AFTER_TIME}s, got {}s",
+            self.reject_after_time
+        );
+
+        let delay_range = MIN_RETRANSMIT_DELAY..=MAX_RETRANSMIT_DELAY;
+        ensure!(
+            delay_range.contains(&self.retransmit_delay_begin),
+            "retransmit_delay_begin must lie between {MIN_RETRANSMIT_DELAY}s and {MAX_RETRANSMIT_DELAY}s, got {}s",
+            self.retransmit_delay_begin
+        );
+        ensure!(
+            delay_range.contains(&self.retransmit_delay_end),
+            "retransmit_delay_end must lie between {MIN_RETRANSMIT_DELAY}s and {MAX_RETRANSMIT_DELAY}s, got {}s",
+            self.retransmit_delay_end
+        );
+        ensure!(
+            self.retransmit_delay_end >= self.retransmit_delay_begin,
+            "retransmit_delay_end ({}s) must not be smaller than retransmit_delay_begin ({}s)",
+            self.retransmit_delay_end,
+            self.retransmit_delay_begin
+        );
+        ensure!(
+            self.retransmit_delay_growth > 1.0
+                && self.retransmit_delay_growth <= MAX_RETRANSMIT_DELAY_GROWTH,
+            "retransmit_delay_growth must be greater than 1 and at most {MAX_RETRANSMIT_DELAY_GROWTH}, got {}",
+            self.retransmit_delay_growth
+        );
+        ensure!(
+            self.retransmit_delay_jitter > 0.0 && self.retransmit_delay_jitter <= 1.0,
+            "retransmit_delay_jitter must be greater than 0 and at most 1, got {}",
+            self.retransmit_delay_jitter
+        );
+
+        Ok(())
+    }
+}
+
+/// Check that a life time for the cookie secret is within the safety bounds
+///
+/// The value must lie between [MIN_COOKIE_SECRET_EPOCH] and [MAX_COOKIE_SECRET_EPOCH].
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::protocol::{validate_cookie_secret_epoch, COOKIE_SECRET_EPOCH};
+///
+/// assert!(validate_cookie_secret_epoch(COOKIE_SECRET_EPOCH).is_ok());
+/// assert!(validate_cookie_secret_epoch(1.0).is_err());
+/// assert!(validate_cookie_secret_epoch(f64::NAN).is_err());
+/// ```
+pub fn validate_cookie_secret_epoch(epoch: Timing) -> Result<()> {
+    ensure!(
+        (MIN_COOKIE_SECRET_EPOCH..=MAX_COOKIE_SECRET_EPOCH).contains(&epoch),
+        "cookie_secret_epoch must lie between {MIN_COOKIE_SECRET_EPOCH}s and {MAX_COOKIE_SECRET_EPOCH}s, got {epoch}s"
+    );
+    Ok(())
+}
+
+impl CryptoServer {
+    /// Change the [TimingProfile] used for the given peer
+    ///
+    /// The new timers take effect immediately, i.e. they also apply to the currently
+    /// established session and any ongoing handshake.
+    ///
+    /// Fails without changing anything if the profile is invalid (see [TimingProfile::validate])
+    /// or the peer does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::DerefMut;
+    /// use rosenpass::protocol::{SSk, SPk, CryptoServer, TimingProfile};
+    /// use rosenpass_ciphers::kem::StaticKem;
+    /// use rosenpass_cipher_traits::Kem;
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (mut sskm, mut spkm) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm.secret_mut(), spkm.deref_mut())?;
+    /// let mut srv = CryptoServer::new(sskm, spkm);
+    ///
+    /// let (mut sskt, mut spkt) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskt.secret_mut(), spkt.deref_mut())?;
+    /// let peer = srv.add_peer(None, spkt)?;
+    /// assert_eq!(peer.get(&srv).timing, TimingProfile::default());
+    ///
+    /// let fast = TimingProfile {
+    ///     rekey_after_time_responder: 30.0,
+    ///     rekey_after_time_initiator: 40.0,
+    ///     reject_after_time: 60.0,
+    ///     ..TimingProfile::default()
+    /// };
+    /// srv.set_peer_timing(peer, fast)?;
+    /// assert_eq!(peer.get(&srv).timing, fast);
+    ///
+    /// let invalid = TimingProfile {
+    ///     reject_after_time: 0.0,
+    ///     ..TimingProfile::default()
+    /// };
+    /// assert!(srv.set_peer_timing(peer, invalid).is_err());
+    /// assert_eq!(peer.get(&srv).timing, fast);
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn set_peer_timing(&mut self, peer: PeerPtr, timing: TimingProfile) -> Result<()> {
+        timing.validate()?;
+        ensure!(
+            peer.exists(self),
+            "Cannot set timing for peer {peer:?}; no such peer"
+        );
+        peer.get_mut(self).timing = timing;
+        Ok(())
+    }
+
+    /// Change the life time of the cookie secret used to protect against denial of service
+    /// attacks; defaults to [COOKIE_SECRET_EPOCH]
+    ///
+    /// Fails without changing anything if the value is out of bounds (see
+    /// [validate_cookie_secret_epoch]).
+    pub fn set_cookie_secret_epoch(&mut self, epoch: Timing) -> Result<()> {
+        validate_cookie_secret_epoch(epoch)?;
+        self.cookie_secret_epoch = epoch;
+        Ok(())
+    }
+}
@@ -0,0 +1,263 @@
+//! Handshake tracing
+//!
+//! When a handshake fails, [CryptoServer::handle_msg] only reports the error of the step that
+//! failed. For debugging failed exchanges in the field, a [HandshakeTracer] can be installed
+//! using [CryptoServer::set_tracer]; it receives a [HandshakeEvent] for every relevant step of
+//! message processing.
+//!
+//! Events identify peers by their [PeerId] and carry the session ids involved; they never contain
+//! any secrets. [JsonTracer] writes the events to a file as JSON, one event per line.
+//!
+//! Tracing is disabled by default; constructing events has no cost in that case.
+
+use std::fmt::Debug;
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use rosenpass_secret_memory::Public;
+use rosenpass_util::b64::B64Display;
+use serde::{Serialize, Serializer};
+
+use crate::msgs::MsgType;
+
+use super::{CryptoServer, HandshakeRole, PeerId, PeerPtr, SessionId};
+
+/// A step during the processing of a handshake message
+///
+/// See the [module](self) documentation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HandshakeEvent {
+    /// A message was passed to [CryptoServer::handle_msg]
+    MessageReceived {
+        /// The message type; `None` if the type is unknown
+        #[serde(serialize_with = "debug_opt")]
+        msg_type: Option<MsgType>,
+        /// Length of the message in bytes
+        len: usize,
+    },
+    /// The [crate::msgs::Envelope::mac] of a message was checked
+    SealChecked {
+        #[serde(serialize_with = "debug")]
+        msg_type: MsgType,
+        /// Whether the mac is valid
+        valid: bool,
+    },
+    /// We are under load and the message did not carry a valid cookie;
+    /// a [crate::msgs::CookieReply] was sent instead of processing the message
+    CookieRequired {
+        #[serde(serialize_with = "debug")]
+        msg_type: MsgType,
+        /// The initiator's session id from the message
+        #[serde(serialize_with = "b64")]
+        sidi: SessionId,
+    },
+    /// We received a [crate::msgs::CookieReply] from a responder that is under load
+    CookieReceived {
+        #[serde(serialize_with = "b64_opt")]
+        peer: Option<PeerId>,
+        #[serde(serialize_with = "b64")]
+        sidi: SessionId,
+    },
+    /// The biscuit in an [crate::msgs::InitConf] message was decrypted successfully
+    BiscuitDecrypted {
+        #[serde(serialize_with = "b64_opt")]
+        peer: Option<PeerId>,
+        #[serde(serialize_with = "b64")]
+        sidi: SessionId,
+        #[serde(serialize_with = "b64")]
+        sidr: SessionId,
+    },
+    /// Processing a [crate::msgs::RespHello] message failed
+    RespHelloFailed {
+        /// The peer the handshake belongs to; `None` if there is no such handshake
+        #[serde(serialize_with = "b64_opt")]
+        peer: Option<PeerId>,
+        #[serde(serialize_with = "b64")]
+        sidi: SessionId,
+        #[serde(serialize_with = "b64")]
+        sidr: SessionId,
+        /// The error message
+        reason: String,
+    },
+    /// A handshake completed and a new session was established
+    SessionEntered {
+        #[serde(serialize_with = "b64_opt")]
+        peer: Option<PeerId>,
+        /// Our session id
+        #[serde(serialize_with = "b64")]
+        sidm: SessionId,
+        /// The other party's session id
+        #[serde(serialize_with = "b64")]
+        sidt: SessionId,
+        #[serde(serialize_with = "debug")]
+        role: HandshakeRole,
+    },
+}
+
+/// Receives [HandshakeEvent]s; see [CryptoServer::set_tracer]
+pub trait HandshakeTracer: Debug + Send + Sync {
+    /// Called for every event
+    fn trace(&self, event: &HandshakeEvent);
+}
+
+/// A [HandshakeTracer] that writes every event as a single line of JSON
+///
+/// Each event is annotated with the current wall clock time in seconds since the unix epoch
+/// (`"time"`).
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::protocol::{HandshakeEvent, HandshakeTracer, JsonTracer};
+/// use rosenpass::msgs::MsgType;
+///
+/// let tracer = JsonTracer::new(Vec::new());
+/// tracer.trace(&HandshakeEvent::SealChecked { msg_type: MsgType::InitHello, valid: false });
+///
+/// let out = String::from_utf8(tracer.into_inner())?;
+/// assert!(out.ends_with("\n"));
+/// assert!(out.contains(r#""event":"seal_checked","msg_type":"InitHello","valid":false"#));
+///
+/// Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct JsonTracer<W: Write + Debug + Send> {
+    out: Mutex<W>,
+}
+
+impl<W: Write + Debug + Send> JsonTracer<W> {
+    /// Write events to `out`
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Mutex::new(out),
+        }
+    }
+
+    /// Retrieve the underlying writer
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl JsonTracer<LineWriter<File>> {
+    /// Append events to the file at `path`, creating it if necessary
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Could not open trace file {path:?}"))?;
+        Ok(Self::new(LineWriter::new(file)))
+    }
+}
+
+impl<W: Write + Debug + Send> HandshakeTracer for JsonTracer<W> {
+    fn trace(&self, event: &HandshakeEvent) {
+        #[derive(Serialize)]
+        struct Line<'a> {
+            time: f64,
+            #[serde(flatten)]
+            event: &'a HandshakeEvent,
+        }
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        let res = serde_json::to_writer(&mut *out, &Line { time, event })
+            .map_err(anyhow::Error::from)
+            .and_then(|()| Ok(out.write_all(b"\n")?));
+        if let Err(e) = res {
+            log::warn!("Could not write handshake trace event: {e:?}");
+        }
+    }
+}
+
+impl CryptoServer {
+    /// Install a [HandshakeTracer] that receives [HandshakeEvent]s, or remove it with `None`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ops::DerefMut;
+    /// use std::sync::{Arc, Mutex};
+    /// use rosenpass::protocol::{CryptoServer, HandshakeEvent, HandshakeTracer, SPk, SSk};
+    /// use rosenpass_ciphers::kem::StaticKem;
+    /// use rosenpass_cipher_traits::Kem;
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct Recorder(Mutex<Vec<HandshakeEvent>>);
+    ///
+    /// impl HandshakeTracer for Recorder {
+    ///     fn trace(&self, event: &HandshakeEvent) {
+    ///         self.0.lock().unwrap().push(event.clone());
+    ///     }
+    /// }
+    ///
+    /// rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+    ///
+    /// let (mut sskm, mut spkm) = (SSk::zero(), SPk::zero());
+    /// StaticKem::keygen(sskm.secret_mut(), spkm.deref_mut())?;
+    /// let mut srv = CryptoServer::new(sskm, spkm);
+    ///
+    /// let recorder = Arc::new(Recorder::default());
+    /// srv.set_tracer(Some(recorder.clone()));
+    ///
+    /// // Garbage is rejected, but still traced
+    /// let mut tx = [0u8; 16];
+    /// assert!(srv.handle_msg(&[0xff; 16], &mut tx).is_err());
+    /// assert_eq!(
+    ///     recorder.0.lock().unwrap().as_slice(),
+    ///     &[HandshakeEvent::MessageReceived { msg_type: None, len: 16 }]
+    /// );
+    ///
+    /// Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn set_tracer(&mut self, tracer: Option<Arc<dyn HandshakeTracer>>) {
+        self.tracer = tracer;
+    }
+
+    /// Emit a [HandshakeEvent] if a [HandshakeTracer] is installed
+    ///
+    /// The event is only constructed if it is actually needed.
+    pub(crate) fn trace<F: FnOnce() -> HandshakeEvent>(&self, event: F) {
+        if let Some(tracer) = &self.tracer {
+            tracer.trace(&event());
+        }
+    }
+
+    /// The [PeerId] to use in [HandshakeEvent]s
+    pub(crate) fn trace_peer_id(&self, peer: PeerPtr) -> Option<PeerId> {
+        peer.get(self).pidt().ok()
+    }
+}
+
+fn debug<T: Debug, S: Serializer>(v: &T, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(&format_args!("{v:?}"))
+}
+
+fn debug_opt<T: Debug, S: Serializer>(v: &Option<T>, s: S) -> Result<S::Ok, S::Error> {
+    match v {
+        Some(v) => debug(v, s),
+        None => s.serialize_none(),
+    }
+}
+
+fn b64<const N: usize, S: Serializer>(v: &Public<N>, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(&v.value.fmt_b64::<128>())
+}
+
+fn b64_opt<const N: usize, S: Serializer>(v: &Option<Public<N>>, s: S) -> Result<S::Ok, S::Error> {
+    match v {
+        Some(v) => b64(v, s),
+        None => s.serialize_none(),
+    }
+}
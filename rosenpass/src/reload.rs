@@ -0,0 +1,612 @@
+//! Reloading the configuration file at runtime
+//!
+//! When Rosenpass is started from a configuration file (`rosenpass exchange-config`), sending
+//! SIGHUP to the process makes it re-read the file and apply the changes in place; see
+//! [AppServer::reload_config].
+//!
+//! Peers are identified by their public key. Peers that are still configured keep their
+//! sessions; settings such as the endpoint, the key output file or the WireGuard interface
+//! are updated in place. Peers that are no longer configured are removed and new peers are
+//! added. The listen sockets are rebound if the listen addresses changed, unless they were
+//! passed by the service manager (see [crate::socket_activation]); changes to the
+//! [crate::socket_options] apply to the existing sockets as well.
+//!
+//! The new configuration is validated and all the files it references are loaded before
+//! anything is changed. If anything is wrong with it, an error is logged and the previous
+//! configuration stays in effect. This includes peers clashing with peers added through other
+//! means, e.g. the API, and listen sockets that can not be bound or configured.
+//!
+//! Once a new configuration is found to be valid, it is applied as a whole. Supplying keys to
+//! the outputs of changed peers happens along the way; failures to do so are logged, but do
+//! not stop the reload.
+//!
+//! Endpoint hostnames are resolved in the background (see [crate::endpoint_refresh]), so a
+//! reload never blocks on DNS lookups.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, ensure, Context};
+use log::{info, warn};
+use rosenpass_constant_time as constant_time;
+use rosenpass_util::build::ConstructionSite;
+
+use crate::app_server::{
+    AppPeerPtr, AppServer, BrokerPeer, BrokerStorePtr, Endpoint, KeyOutputReason,
+};
+use crate::config::{Rosenpass, RosenpassPeer};
+use crate::logging;
+use crate::protocol::{SPk, SymKey, COOKIE_SECRET_EPOCH};
+
+/// Automatically register a signal handler for SIGHUP; whether the signal was issued can be
+/// polled using [Self::value] and [Self::take].
+///
+/// The signal handler is not removed when this struct goes out of scope.
+#[derive(Debug)]
+pub struct ReloadRequested {
+    value: Arc<AtomicBool>,
+}
+
+impl ReloadRequested {
+    /// Register a signal handler watching for SIGHUP
+    pub fn new() -> anyhow::Result<Self> {
+        let value = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&value))?;
+        Ok(Self { value })
+    }
+
+    /// Check whether SIGHUP was received since the last call to [Self::take]
+    pub fn value(&self) -> bool {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    /// Check whether SIGHUP was received since the last call to [Self::take], resetting the
+    /// flag
+    pub fn take(&self) -> bool {
+        self.value.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// The configuration currently in effect, along with everything needed to apply changes to
+/// it; see [AppServer::enable_config_reload]
+#[derive(Debug)]
+pub struct ConfigReload {
+    /// The configuration currently in effect
+    ///
+    /// [Rosenpass::peers] is always empty; the peers are stored in [Self::peers] instead.
+    config: Rosenpass,
+    /// The configured peers, along with the pointer to the peer in the [AppServer]
+    peers: Vec<(AppPeerPtr, RosenpassPeer)>,
+    /// The broker used to supply keys to WireGuard
+    broker: BrokerStorePtr,
+    /// Watches for SIGHUP
+    signal: ReloadRequested,
+}
+
+impl ConfigReload {
+    /// Stop tracking a peer removed through other means than a reload, e.g. through the API
+    ///
+    /// If the peer is still in the configuration file, the next reload adds it again.
+    pub(crate) fn forget_peer(&mut self, peer: AppPeerPtr) {
+        self.peers.retain(|(ptr, _)| ptr.0 != peer.0);
+    }
+}
+
+/// A peer from a new configuration with all the files it references loaded
+struct PreparedPeer {
+    cfg: RosenpassPeer,
+    pk: SPk,
+    psk: Option<SymKey>,
+    broker_peer: Option<BrokerPeer>,
+    endpoint: Option<Endpoint>,
+}
+
+impl PreparedPeer {
+    fn load(cfg: RosenpassPeer, broker: &BrokerStorePtr) -> anyhow::Result<Self> {
+        let pk = cfg.load_public_key()?;
+        let psk = cfg.load_pre_shared_key()?;
+        let broker_peer = cfg.broker_peer(broker)?;
+        // Literal addresses are used right away; hostnames are resolved in the background
+        let endpoint = cfg
+            .endpoint
+            .as_deref()
+            .and_then(|hostname| hostname.parse::<SocketAddr>().ok())
+            .map(|addr| Endpoint::discovery_from_addresses(vec![addr]));
+        Ok(Self {
+            cfg,
+            pk,
+            psk,
+            broker_peer,
+            endpoint,
+        })
+    }
+}
+
+impl AppServer {
+    /// Re-read the configuration file `config` was loaded from whenever SIGHUP is received
+    ///
+    /// `peers` are the peers created from [Rosenpass::peers], in the same order (see
+    /// [RosenpassPeer::apply_to_app_server]); `broker` is the broker they use.
+    pub fn enable_config_reload(
+        &mut self,
+        mut config: Rosenpass,
+        peers: Vec<AppPeerPtr>,
+        broker: BrokerStorePtr,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            peers.len() == config.peers.len(),
+            "Expected {} peers, got {}",
+            config.peers.len(),
+            peers.len()
+        );
+        let peers = peers.into_iter().zip(config.peers.drain(..)).collect();
+        self.config_reload = Some(ConfigReload {
+            config,
+            peers,
+            broker,
+            signal: ReloadRequested::new()?,
+        });
+        Ok(())
+    }
+
+    /// Check whether a configuration reload was requested through SIGHUP
+    pub fn reload_requested(&self) -> bool {
+        self.config_reload
+            .as_ref()
+            .is_some_and(|r| r.signal.value())
+    }
+
+    /// Like [Self::reload_requested], but resets the request
+    pub(crate) fn take_reload_request(&self) -> bool {
+        self.config_reload.as_ref().is_some_and(|r| r.signal.take())
+    }
+
+    /// Re-read the configuration file and apply the changes; see the [module](crate::reload)
+    /// documentation
+    ///
+    /// Changing the keypair is not possible; neither are changes to the API, the metrics,
+    /// the state file and the trace file applied. These require a restart.
+    ///
+    /// Peers are matched by their public key. If the pre-shared key or the protocol suite of
+    /// a peer changes, the peer is removed and added again, so its session is lost.
+    pub fn reload_config(&mut self) -> anyhow::Result<()> {
+        let mut reload = self
+            .config_reload
+            .take()
+            .context("Configuration reload is not enabled")?;
+        let res = self.reload_config_with(&mut reload);
+        self.config_reload = Some(reload);
+        res
+    }
+
+    fn reload_config_with(&mut self, reload: &mut ConfigReload) -> anyhow::Result<()> {
+        let path = reload.config.config_file_path.clone();
+        info!("Reloading configuration from {path:?}");
+
+        let mut new = Rosenpass::load(&path)?;
+        new.validate()?;
+        new.check_usefullness()?;
+
+        let old = &reload.config;
+        ensure!(
+            new.keypair == old.keypair,
+            "Changing the keypair requires a restart"
+        );
+        let restart_required = [
+            ("state_file", new.state_file != old.state_file),
+            ("trace_file", new.trace_file != old.trace_file),
+            ("metrics", new.metrics != old.metrics),
+            #[cfg(feature = "experiment_api")]
+            ("api", new.api != old.api),
+        ];
+        for (item, _) in restart_required.iter().filter(|(_, changed)| *changed) {
+            warn!("Changing `{item}` requires a restart; ignoring the new value");
+        }
+
+        // Load all peers before changing anything, so invalid configurations are rejected
+        // as a whole
+        let mut prepared: Vec<PreparedPeer> = Vec::with_capacity(new.peers.len());
+        for cfg in new.peers.drain(..) {
+            let peer = PreparedPeer::load(cfg, &reload.broker)?;
+            ensure!(
+                !prepared.iter().any(|p| p.pk == peer.pk),
+                "Peer {:?} is configured more than once",
+                peer.cfg.public_key
+            );
+            prepared.push(peer);
+        }
+
+        // Peers that are gone, or whose pre-shared key or suite changed
+        let outdated = reload
+            .peers
+            .iter()
+            .filter(|(ptr, cfg)| {
+                let Some(peer) = prepared
+                    .iter()
+                    .find(|p| self.peer_public_key(*ptr) == Some(&p.pk))
+                else {
+                    return true;
+                };
+                peer.cfg.suite.unwrap_or_default() != cfg.suite.unwrap_or_default()
+                    || !self.peer_psk_matches(*ptr, peer.psk.as_ref())
+            })
+            .map(|(ptr, _)| *ptr)
+            .collect::<Vec<_>>();
+        if !outdated.is_empty() && !self.crypto_site.is_available() {
+            bail!("Peers can not be removed before the keypair is configured");
+        }
+
+        // Peers added through other means, e.g. the API, are not tracked here; adding a peer
+        // with the same public key would fail
+        for peer in prepared.iter() {
+            let clash = self.peer_ptrs().any(|ptr| {
+                !reload.peers.iter().any(|(p, _)| p.0 == ptr.0)
+                    && self.peer_public_key(ptr) == Some(&peer.pk)
+            });
+            ensure!(
+                !clash,
+                "Peer {:?} is already registered through other means than the configuration file",
+                peer.cfg.public_key
+            );
+        }
+
+        // The configuration is valid; from here on, only the sockets can make the reload fail.
+        // Socket options first, so new sockets get the new options.
+        let socket_options =
+            (new.socket_options() != reload.config.socket_options()).then(|| new.socket_options());
+        if let Some(options) = socket_options.clone() {
+            self.set_socket_options(options)?;
+        }
+        if new.listen != reload.config.listen && !self.socket_activated {
+            if let Err(e) = self.rebind_listen_sockets(new.listen.clone()) {
+                if socket_options.is_some() {
+                    if let Err(e) = self.set_socket_options(reload.config.socket_options()) {
+                        warn!("Could not restore the previous socket options: {e:?}");
+                    }
+                }
+                return Err(e);
+            }
+        }
+        if socket_options.is_some() {
+            reload.config.bind_device = new.bind_device.clone();
+            reload.config.fwmark = new.fwmark;
+        }
+        if new.listen != reload.config.listen {
+            if self.socket_activated {
+                warn!("Ignoring changed listen addresses; the listen sockets were passed by the service manager");
+            }
+            reload.config.listen = new.listen.clone();
+        }
+
+        for ptr in outdated {
+            match self.remove_peer(ptr) {
+                Ok(()) => reload.peers.retain(|(p, _)| p.0 != ptr.0),
+                Err(e) => {
+                    // The peer keeps its previous configuration; the next reload tries again
+                    warn!("Could not remove peer {}: {e:?}", ptr.0);
+                    prepared.retain(|p| self.peer_public_key(ptr) != Some(&p.pk));
+                }
+            }
+        }
+
+        for peer in prepared {
+            let existing = reload
+                .peers
+                .iter()
+                .position(|(ptr, _)| self.peer_public_key(*ptr) == Some(&peer.pk));
+            match existing {
+                Some(idx) => self.update_peer(&mut reload.peers[idx], peer)?,
+                None => {
+                    let resolve = peer.endpoint.is_none() && peer.cfg.endpoint.is_some();
+                    let ptr = self.add_peer(
+                        peer.psk,
+                        peer.pk,
+                        peer.cfg.key_out.clone(),
+                        peer.broker_peer,
+                        None,
+                    )?;
+                    let ap = ptr.get_app_mut(self);
+                    ap.initial_endpoint = peer.endpoint;
+                    ap.hostname = peer.cfg.endpoint.clone();
+                    // Track the peer right away, so it is never left behind untracked
+                    reload.peers.push((ptr, peer.cfg));
+                    let (_, cfg) = reload.peers.last().unwrap();
+                    cfg.apply_settings_to_app_server(self, ptr)?;
+                    if resolve {
+                        self.resolve_endpoint_in_background(ptr)?;
+                    }
+                }
+            }
+        }
+
+        if new.cookie_secret_epoch != reload.config.cookie_secret_epoch {
+            self.set_cookie_secret_epoch(new.cookie_secret_epoch.unwrap_or(COOKIE_SECRET_EPOCH))?;
+            reload.config.cookie_secret_epoch = new.cookie_secret_epoch;
+        }
+        self.verbosity = new.verbosity;
+        reload.config.verbosity = new.verbosity;
+        // Without `log_level`, the filter configured before (possibly through the command line)
+        // is kept; changes through SIGUSR1 or the API are undone either way
+        match new.log_filter() {
+            Some(filter) => {
+                logging::set_log_filter(filter.clone());
+                reload.config.log_level = Some(filter);
+            }
+            None => {
+                logging::reset_log_filter();
+            }
+        }
+        // Without `log_format`, the format set on startup (possibly through the command line)
+        // is kept
+        if let Some(format) = new.log_format {
+            logging::set_log_format(format);
+            reload.config.log_format = Some(format);
+        }
+
+        info!(
+            "Configuration reloaded; {} peers configured",
+            reload.peers.len()
+        );
+        Ok(())
+    }
+
+    /// Apply the settings from a new configuration to a peer that is kept, retaining its session
+    ///
+    /// Failures to supply the current key to changed outputs are logged; the new settings are
+    /// applied either way.
+    fn update_peer(
+        &mut self,
+        (ptr, cfg): &mut (AppPeerPtr, RosenpassPeer),
+        new: PreparedPeer,
+    ) -> anyhow::Result<()> {
+        let ptr = *ptr;
+        new.cfg.apply_settings_to_app_server(self, ptr)?;
+
+        let osk = self
+            .crypto_server()
+            .ok()
+            .and_then(|srv| srv.osk(ptr.lower()).ok());
+        let wg_changed = new.cfg.wg != cfg.wg;
+        let outputs_changed = wg_changed || new.cfg.key_out != cfg.key_out;
+
+        // Do not leave the current key with a WireGuard peer we no longer supply keys to
+        if wg_changed && osk.is_some() && ptr.get_app(self).broker_peer.is_some() {
+            if let Err(e) = ptr.set_psk(self, &SymKey::random()) {
+                warn!(
+                    "Could not erase the key of peer {} from WireGuard: {e:?}",
+                    ptr.0
+                );
+            }
+        }
+
+        let endpoint_changed = new.cfg.endpoint != cfg.endpoint;
+        let resolve = endpoint_changed && new.endpoint.is_none() && new.cfg.endpoint.is_some();
+        let ap = ptr.get_app_mut(self);
+        ap.outfile = new.cfg.key_out.clone();
+        if wg_changed {
+            ap.broker_peer = new.broker_peer;
+        }
+        if endpoint_changed {
+            ap.initial_endpoint = new.endpoint;
+            ap.hostname = new.cfg.endpoint.clone();
+            ap.current_endpoint = None;
+        }
+        *cfg = new.cfg;
+
+        if resolve {
+            self.resolve_endpoint_in_background(ptr)?;
+        }
+
+        // Supply the current key to the new outputs
+        if let (true, Some(osk)) = (outputs_changed, osk) {
+            if let Err(e) = self.output_key(ptr, KeyOutputReason::Exchanged, &osk) {
+                warn!(
+                    "Could not supply the key of peer {} to its new outputs: {e:?}",
+                    ptr.0
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The public key of a peer, both before and after the [crate::protocol::CryptoServer]
+    /// is initialized
+    fn peer_public_key(&self, peer: AppPeerPtr) -> Option<&SPk> {
+        if !peer.exists(self) {
+            return None;
+        }
+        match &self.crypto_site {
+            ConstructionSite::Void => None,
+            ConstructionSite::Builder(builder) => builder.peers.get(peer.0).map(|p| &p.pk),
+            ConstructionSite::Product(srv) => Some(&peer.lower().get(srv).spkt),
+        }
+    }
+
+    /// Check whether a peer uses the given pre-shared key; `None` is equivalent to an all-zero
+    /// key
+    fn peer_psk_matches(&self, peer: AppPeerPtr, psk: Option<&SymKey>) -> bool {
+        let zero = SymKey::zero();
+        let current = match &self.crypto_site {
+            ConstructionSite::Void => return false,
+            ConstructionSite::Builder(builder) => {
+                match builder.peers.get(peer.0).and_then(|p| p.psk.as_ref()) {
+                    Some(psk) => psk,
+                    None => &zero,
+                }
+            }
+            ConstructionSite::Product(srv) => &peer.lower().get(srv).psk,
+        };
+        constant_time::memcmp(current.secret(), psk.unwrap_or(&zero).secret())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::ops::DerefMut;
+    use std::path::Path;
+
+    use rosenpass_cipher_traits::Kem;
+    use rosenpass_ciphers::kem::StaticKem;
+    use rosenpass_secret_memory::file::StoreSecret;
+    use rosenpass_util::file::{LoadValue, StoreValue};
+    use rosenpass_wireguard_broker::brokers::native_unix::NativeUnixBroker;
+
+    use crate::app_server::{AppPeerPtr, AppServer};
+    use crate::config::{Keypair, Rosenpass, RosenpassPeer, Verbosity};
+    use crate::protocol::{SPk, SSk};
+
+    fn gen_keypair(dir: &Path, name: &str) -> anyhow::Result<Keypair> {
+        let (mut sk, mut pk) = (SSk::zero(), SPk::zero());
+        StaticKem::keygen(sk.secret_mut(), pk.deref_mut())?;
+        let keypair = Keypair::new(
+            dir.join(format!("{name}.pk")),
+            dir.join(format!("{name}.sk")),
+        );
+        sk.store_secret(&keypair.secret_key)?;
+        pk.store(&keypair.public_key)?;
+        Ok(keypair)
+    }
+
+    fn peer(keypair: &Keypair, key_out: &str) -> RosenpassPeer {
+        RosenpassPeer {
+            public_key: keypair.public_key.clone(),
+            key_out: Some(key_out.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Start a server from a configuration file at `path` with the given peers
+    fn start(
+        path: &Path,
+        server: &Keypair,
+        peers: Vec<RosenpassPeer>,
+    ) -> anyhow::Result<(AppServer, Vec<AppPeerPtr>)> {
+        let mut config = Rosenpass::new(Some(server.clone()));
+        config.listen = vec![SocketAddr::from((Ipv4Addr::LOCALHOST, 0))];
+        config.peers = peers;
+        config.store(path)?;
+        let config = Rosenpass::load(path)?;
+
+        let keypair = (
+            SSk::load(&server.secret_key)?,
+            SPk::load(&server.public_key)?,
+        );
+        let mut srv = AppServer::new(Some(keypair), config.listen.clone(), Verbosity::Quiet, None)?;
+        let broker = srv.register_broker(Box::new(NativeUnixBroker::new()))?;
+        let peers = config
+            .peers
+            .iter()
+            .map(|p| p.apply_to_app_server(&mut srv, &broker))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        srv.enable_config_reload(config, peers.clone(), broker)?;
+        Ok((srv, peers))
+    }
+
+    #[test]
+    fn reload_adds_updates_and_removes_peers() -> anyhow::Result<()> {
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config.toml");
+
+        let server = gen_keypair(dir.path(), "server")?;
+        let (a, b, c) = (
+            gen_keypair(dir.path(), "a")?,
+            gen_keypair(dir.path(), "b")?,
+            gen_keypair(dir.path(), "c")?,
+        );
+
+        let (mut srv, peers) = start(&path, &server, vec![peer(&a, "a.osk"), peer(&b, "b.osk")])?;
+        let (ptr_a, ptr_b) = (peers[0], peers[1]);
+
+        // Keep a with a new key output file, remove b, add c
+        let mut config = Rosenpass::new(Some(server.clone()));
+        config.listen = vec![SocketAddr::from((Ipv4Addr::LOCALHOST, 0))];
+        config.verbosity = Verbosity::Verbose;
+        config.peers = vec![peer(&a, "a2.osk"), peer(&c, "c.osk")];
+        config.store(&path)?;
+        srv.reload_config()?;
+
+        assert!(ptr_a.exists(&srv));
+        assert_eq!(ptr_a.get_app(&srv).outfile, Some("a2.osk".into()));
+        assert!(!ptr_b.exists(&srv));
+        // New peers are appended
+        let ptr_c = AppPeerPtr(2);
+        assert_eq!(ptr_c.get_app(&srv).outfile, Some("c.osk".into()));
+        assert!(srv.verbose());
+
+        // An invalid configuration leaves the previous one in effect
+        fs::write(&path, "this is not a valid configuration")?;
+        assert!(srv.reload_config().is_err());
+        assert!(ptr_a.exists(&srv) && ptr_c.exists(&srv));
+
+        // Peers configured twice are rejected
+        config.peers = vec![peer(&c, "c.osk"), peer(&c, "c2.osk")];
+        config.store(&path)?;
+        assert!(srv.reload_config().is_err());
+        assert!(ptr_a.exists(&srv));
+        assert_eq!(ptr_c.get_app(&srv).outfile, Some("c.osk".into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_reload_changes_nothing() -> anyhow::Result<()> {
+        rosenpass_secret_memory::secret_policy_try_use_memfd_secrets();
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config.toml");
+
+        let server = gen_keypair(dir.path(), "server")?;
+        let (a, b, c) = (
+            gen_keypair(dir.path(), "a")?,
+            gen_keypair(dir.path(), "b")?,
+            gen_keypair(dir.path(), "c")?,
+        );
+
+        let (mut srv, peers) = start(&path, &server, vec![peer(&a, "a.osk"), peer(&b, "b.osk")])?;
+        let (ptr_a, ptr_b) = (peers[0], peers[1]);
+        let reload = |srv: &mut AppServer, config: &Rosenpass| -> anyhow::Result<()> {
+            config.store(&path)?;
+            srv.reload_config()
+        };
+
+        // Remove b and add c, which is already registered through other means
+        let ptr_c = srv.add_peer(None, SPk::load(&c.public_key)?, None, None, None)?;
+        let mut config = Rosenpass::new(Some(server.clone()));
+        config.listen = vec![SocketAddr::from((Ipv4Addr::LOCALHOST, 0))];
+        config.peers = vec![peer(&a, "a2.osk"), peer(&c, "c.osk")];
+        assert!(reload(&mut srv, &config).is_err());
+        assert!(ptr_b.exists(&srv));
+        assert_eq!(ptr_a.get_app(&srv).outfile, Some("a.osk".into()));
+        assert_eq!(ptr_c.get_app(&srv).outfile, None);
+        assert_eq!(srv.peer_ptrs().count(), 3);
+
+        // Remove b, with a listen address that can not be bound
+        srv.remove_peer(ptr_c)?;
+        config.listen = vec![SocketAddr::from((Ipv4Addr::new(192, 0, 2, 1), 0))];
+        assert!(reload(&mut srv, &config).is_err());
+        assert!(ptr_b.exists(&srv));
+        assert_eq!(ptr_a.get_app(&srv).outfile, Some("a.osk".into()));
+        assert_eq!(srv.peer_ptrs().count(), 2);
+
+        // Remove b, with listen sockets that can not be configured
+        config.listen = vec![SocketAddr::from((Ipv4Addr::LOCALHOST, 0))];
+        config.bind_device = Some("rp-no-such-dev".into());
+        assert!(reload(&mut srv, &config).is_err());
+        assert!(ptr_b.exists(&srv));
+        assert_eq!(ptr_a.get_app(&srv).outfile, Some("a.osk".into()));
+        assert_eq!(srv.peer_ptrs().count(), 2);
+
+        // The previous configuration is still the one in effect
+        config.bind_device = None;
+        reload(&mut srv, &config)?;
+        assert!(!ptr_b.exists(&srv));
+        assert_eq!(ptr_a.get_app(&srv).outfile, Some("a2.osk".into()));
+        assert_eq!(srv.peer_ptrs().count(), 2);
+
+        Ok(())
+    }
+}
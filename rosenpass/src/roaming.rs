@@ -0,0 +1,345 @@
+//! Controlling which addresses a peer may roam to
+//!
+//! Whenever a handshake with a peer completes, [AppServer] learns the address the peer sent its
+//! messages from. By default, this address replaces [AppPeer::current_endpoint], so peers can
+//! move between networks ("roam"). With [RoamingPolicy], roaming can be restricted per peer:
+//!
+//! - [RoamingPolicy::Roam] – accept any address (the default)
+//! - [RoamingPolicy::Pinned] – keep using the configured endpoint; if no endpoint is configured,
+//!   the first address a handshake completed from is kept
+//! - [RoamingPolicy::RoamWithin] – accept addresses from the listed networks and the configured
+//!   endpoint
+//!
+//! Each accepted change of the address raises an [AppEvent::EndpointChanged], each refused one
+//! an [AppEvent::EndpointRejected]; see [crate::events]. A refused address still completes the
+//! handshake, since the peer authenticated itself; the address is just not used to send further
+//! messages to.
+//!
+//! [AppPeer::current_endpoint]: crate::app_server::AppPeer::current_endpoint
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::app_server::{AppPeerPtr, AppServer, Endpoint};
+use crate::events::AppEvent;
+
+/// Which addresses a peer may roam to; see the [module documentation](self)
+///
+/// ```toml
+/// [[peers]]
+/// public_key = "/path/to/rp-peer-public-key"
+/// endpoint = "192.0.2.1:9999"
+/// key_out = "/path/to/rp-key-out.txt"
+/// roaming = "pinned"
+///
+/// [[peers]]
+/// public_key = "/path/to/rp-other-peer-public-key"
+/// key_out = "/path/to/rp-other-key-out.txt"
+/// roaming = { roam_within = ["10.0.0.0/8", "2001:db8::/32"] }
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum RoamingPolicy {
+    /// Never switch away from the configured endpoint
+    Pinned,
+    /// Switch to any address the peer completes a handshake from
+    #[default]
+    Roam,
+    /// Switch to addresses within the given networks
+    RoamWithin(Vec<IpNet>),
+}
+
+impl RoamingPolicy {
+    /// Check that the policy is sound
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::roaming::RoamingPolicy;
+    ///
+    /// assert!(RoamingPolicy::Pinned.validate().is_ok());
+    /// assert!(RoamingPolicy::RoamWithin(vec!["10.0.0.0/8".parse()?]).validate().is_ok());
+    ///
+    /// // Would never accept any address; use `pinned` instead
+    /// assert!(RoamingPolicy::RoamWithin(vec![]).validate().is_err());
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Self::RoamWithin(nets) = self {
+            ensure!(
+                !nets.is_empty(),
+                "roam_within requires at least one network; use `pinned` to disable roaming"
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the peer may switch to `to`
+    ///
+    /// `configured` holds the addresses of the configured endpoint, `from` the address the last
+    /// handshake completed from.
+    pub fn permits(
+        &self,
+        configured: &[SocketAddr],
+        from: Option<SocketAddr>,
+        to: SocketAddr,
+    ) -> bool {
+        let to = canonical(to);
+        if configured.iter().any(|&a| canonical(a) == to) {
+            return true;
+        }
+        match self {
+            Self::Roam => true,
+            Self::Pinned => match from {
+                Some(from) => configured.is_empty() && canonical(from) == to,
+                None => configured.is_empty(),
+            },
+            Self::RoamWithin(nets) => nets.iter().any(|net| net.contains(to.ip())),
+        }
+    }
+}
+
+/// Treat IPv4-mapped IPv6 addresses (as seen on dual-stack sockets) as IPv4 addresses
+fn canonical(addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(addr.ip().to_canonical(), addr.port())
+}
+
+/// A network given in CIDR notation, e.g. `10.0.0.0/8` or `2001:db8::/32`
+///
+/// A plain address is a network containing just that address.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNet {
+    /// Create a network from an address and a prefix length
+    ///
+    /// Bits of `addr` beyond the prefix are ignored.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> anyhow::Result<Self> {
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        ensure!(
+            prefix_len <= max,
+            "Prefix length {prefix_len} is too long for {addr}; the maximum is {max}"
+        );
+        Ok(Self { addr, prefix_len })
+    }
+
+    /// Whether `ip` lies within this network
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass::roaming::IpNet;
+    ///
+    /// let net: IpNet = "10.0.0.0/8".parse()?;
+    /// assert!(net.contains("10.1.2.3".parse()?));
+    /// assert!(net.contains("::ffff:10.1.2.3".parse()?));
+    /// assert!(!net.contains("11.0.0.1".parse()?));
+    ///
+    /// let net: IpNet = "2001:db8::/32".parse()?;
+    /// assert!(net.contains("2001:db8::1".parse()?));
+    /// assert!(!net.contains("2001:db9::1".parse()?));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, len)) => (addr, Some(len)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .with_context(|| format!("Invalid network {s:?}"))?;
+        let prefix_len = match (prefix_len, addr) {
+            (Some(len), _) => len
+                .parse()
+                .with_context(|| format!("Invalid prefix length in network {s:?}"))?,
+            (None, IpAddr::V4(_)) => 32,
+            (None, IpAddr::V6(_)) => 128,
+        };
+        Self::new(addr, prefix_len)
+    }
+}
+
+impl TryFrom<String> for IpNet {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> anyhow::Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<IpNet> for String {
+    fn from(net: IpNet) -> Self {
+        net.to_string()
+    }
+}
+
+/// Per-peer roaming state; see [crate::app_server::AppPeer::roaming]
+#[derive(Debug, Default)]
+pub struct Roaming {
+    /// Which addresses the peer may roam to
+    policy: RoamingPolicy,
+    /// The address the last accepted handshake completed from
+    confirmed: Option<SocketAddr>,
+}
+
+impl Roaming {
+    /// Which addresses the peer may roam to
+    pub fn policy(&self) -> &RoamingPolicy {
+        &self.policy
+    }
+
+    /// The address the last accepted handshake completed from
+    pub fn confirmed(&self) -> Option<SocketAddr> {
+        self.confirmed
+    }
+}
+
+impl AppServer {
+    /// Set the [RoamingPolicy] of a peer registered with [Self::add_peer]
+    ///
+    /// The policy applies to future handshakes; the current endpoint is kept.
+    pub fn set_peer_roaming(
+        &mut self,
+        peer: AppPeerPtr,
+        policy: RoamingPolicy,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            peer.exists(self),
+            "Cannot set roaming policy for peer {}; no such peer",
+            peer.0
+        );
+        policy.validate()?;
+        peer.get_app_mut(self).roaming.policy = policy;
+        Ok(())
+    }
+
+    /// Use the address a handshake with `peer` just completed from as its
+    /// [crate::app_server::AppPeer::current_endpoint], if the peer's [RoamingPolicy] permits it
+    pub(crate) fn peer_endpoint_confirmed(
+        &mut self,
+        peer: AppPeerPtr,
+        endpoint: Endpoint,
+    ) -> anyhow::Result<()> {
+        let to = match &endpoint {
+            Endpoint::SocketBoundAddress(_) => endpoint.addresses()[0],
+            Endpoint::Discovery(_) => bail!("Handshake completed through a discovery endpoint"),
+        };
+        let peer_id = peer.lower().get(self.crypto_server()?).pidt()?;
+
+        let ap = peer.get_app_mut(self);
+        let configured = ap
+            .initial_endpoint
+            .as_ref()
+            .map_or(&[][..], |e| e.addresses());
+        let from = ap.roaming.confirmed;
+        if !ap.roaming.policy.permits(configured, from, to) {
+            self.emit_event(AppEvent::EndpointRejected {
+                peer: peer_id,
+                current: from,
+                rejected: to,
+            });
+            return Ok(());
+        }
+
+        ap.current_endpoint = Some(endpoint);
+        ap.roaming.confirmed = Some(to);
+        if from != Some(to) {
+            self.emit_event(AppEvent::EndpointChanged {
+                peer: peer_id,
+                from,
+                to,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn policies_are_enforced() -> anyhow::Result<()> {
+        let configured = [addr("192.0.2.1:9999")];
+        let roamed = addr("198.51.100.7:4000");
+
+        assert!(RoamingPolicy::Roam.permits(&configured, None, roamed));
+
+        let pinned = RoamingPolicy::Pinned;
+        assert!(pinned.permits(&configured, None, configured[0]));
+        assert!(pinned.permits(&configured, None, addr("[::ffff:192.0.2.1]:9999")));
+        assert!(!pinned.permits(&configured, Some(configured[0]), roamed));
+        // Without a configured endpoint, the first address is kept
+        assert!(pinned.permits(&[], None, roamed));
+        assert!(pinned.permits(&[], Some(roamed), roamed));
+        assert!(!pinned.permits(&[], Some(roamed), configured[0]));
+
+        let within = RoamingPolicy::RoamWithin(vec!["198.51.100.0/24".parse()?]);
+        assert!(within.permits(&configured, None, configured[0]));
+        assert!(within.permits(&configured, Some(configured[0]), roamed));
+        assert!(!within.permits(&configured, Some(roamed), addr("203.0.113.1:4000")));
+        Ok(())
+    }
+
+    #[test]
+    fn policies_are_parsed_from_toml() -> anyhow::Result<()> {
+        #[derive(Deserialize)]
+        struct Peer {
+            roaming: RoamingPolicy,
+        }
+        let parse = |s: &str| toml::from_str::<Peer>(s).map(|p| p.roaming);
+
+        assert_eq!(parse(r#"roaming = "pinned""#)?, RoamingPolicy::Pinned);
+        assert_eq!(parse(r#"roaming = "roam""#)?, RoamingPolicy::Roam);
+        assert_eq!(
+            parse(r#"roaming = { roam_within = ["10.0.0.0/8", "2001:db8::1"] }"#)?,
+            RoamingPolicy::RoamWithin(vec!["10.0.0.0/8".parse()?, "2001:db8::1/128".parse()?])
+        );
+        assert!(parse(r#"roaming = { roam_within = ["10.0.0.0/33"] }"#).is_err());
+        assert!(parse(r#"roaming = "anywhere""#).is_err());
+        Ok(())
+    }
+}
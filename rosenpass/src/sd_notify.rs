@@ -0,0 +1,290 @@
+//! Readiness notification and watchdog support for systemd
+//!
+//! When Rosenpass is started by a systemd unit with `Type=notify`, the service manager passes
+//! the path of a unix datagram socket in the `NOTIFY_SOCKET` environment variable. Rosenpass
+//! sends `READY=1` to this socket once its listen sockets are bound and its brokers are
+//! connected, so units ordered after it do not race the startup. See sd_notify(3).
+//!
+//! While running, a human readable `STATUS=` line is sent whenever it changes (checked about
+//! once per [STATUS_INTERVAL]); if the unit sets
+//! `WatchdogSec=`, `WATCHDOG=1` is sent from the event loop at half the watchdog interval.
+//!
+//! The notify socket protocol is implemented directly; no libsystemd is needed.
+
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::os::unix::net::{SocketAddr as UnixAddr, UnixDatagram};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+
+/// Environment variable holding the path of the notify socket
+pub const NOTIFY_SOCKET_ENV: &str = "NOTIFY_SOCKET";
+/// Environment variable holding the watchdog interval in microseconds
+pub const WATCHDOG_USEC_ENV: &str = "WATCHDOG_USEC";
+/// Environment variable holding the process id the watchdog is meant for
+pub const WATCHDOG_PID_ENV: &str = "WATCHDOG_PID";
+
+/// How often the event loop checks whether the status reported to systemd changed
+pub const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Connection to the systemd notify socket; see the [module documentation](self)
+#[derive(Debug)]
+pub struct SdNotify {
+    /// Unbound socket used to send datagrams to the service manager
+    socket: UnixDatagram,
+    /// Address of the notify socket
+    addr: UnixAddr,
+    /// Interval at which `WATCHDOG=1` is sent; half of `WATCHDOG_USEC`
+    watchdog_interval: Option<Duration>,
+    /// When `WATCHDOG=1` was last sent
+    last_watchdog: Option<Instant>,
+    /// The last `STATUS=` line sent, so it is only sent again when it changes
+    last_status: Option<String>,
+    /// When the status was last checked for changes
+    last_status_check: Option<Instant>,
+    /// Whether `READY=1` was already sent
+    ready: bool,
+}
+
+impl SdNotify {
+    /// Connect to the notify socket given by the environment, if any
+    ///
+    /// Returns `None` if `NOTIFY_SOCKET` is not set, i.e. if Rosenpass was not started by
+    /// systemd or the unit does not use `Type=notify`.
+    ///
+    /// The notify related variables are removed from the environment, so child processes
+    /// (such as the PSK broker) do not pick them up. This should be called before starting
+    /// any threads.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        let notify_socket = std::env::var_os(NOTIFY_SOCKET_ENV);
+        let watchdog_usec = std::env::var_os(WATCHDOG_USEC_ENV);
+        let watchdog_pid = std::env::var_os(WATCHDOG_PID_ENV);
+        for var in [NOTIFY_SOCKET_ENV, WATCHDOG_USEC_ENV, WATCHDOG_PID_ENV] {
+            std::env::remove_var(var);
+        }
+
+        let Some(notify_socket) = notify_socket else {
+            return Ok(None);
+        };
+        let watchdog = watchdog_timeout(watchdog_usec, watchdog_pid, std::process::id())?;
+        Self::new(&notify_socket, watchdog).map(Some)
+    }
+
+    /// Connect to the notify socket at `path`
+    ///
+    /// Paths starting with `@` refer to the abstract socket namespace. If `watchdog` is
+    /// given, `WATCHDOG=1` is sent at half that interval; see [Self::tick].
+    pub fn new(path: &OsStr, watchdog: Option<Duration>) -> anyhow::Result<Self> {
+        let addr = notify_socket_addr(path)
+            .with_context(|| format!("Invalid notify socket address {path:?}"))?;
+        let socket = UnixDatagram::unbound()?;
+        Ok(Self {
+            socket,
+            addr,
+            watchdog_interval: watchdog.map(|w| w / 2),
+            last_watchdog: None,
+            last_status: None,
+            last_status_check: None,
+            ready: false,
+        })
+    }
+
+    /// Send a raw notification, e.g. `"READY=1"`; multiple assignments are separated by
+    /// newlines
+    pub fn notify(&self, msg: &str) -> io::Result<()> {
+        self.socket.send_to_addr(msg.as_bytes(), &self.addr)?;
+        Ok(())
+    }
+
+    /// Send `READY=1` along with the initial status
+    ///
+    /// Does nothing if readiness was already signalled.
+    pub fn ready(&mut self, status: &str) -> io::Result<()> {
+        if self.ready {
+            return Ok(());
+        }
+        self.notify(&format!("READY=1\nSTATUS={status}"))?;
+        self.ready = true;
+        self.last_status = Some(status.to_owned());
+        self.last_status_check = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Whether `READY=1` was sent
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Interval at which `WATCHDOG=1` is sent; `None` if the watchdog is disabled
+    pub fn watchdog_interval(&self) -> Option<Duration> {
+        self.watchdog_interval
+    }
+
+    /// How long the event loop may block before [Self::tick] must be called again
+    ///
+    /// Returns `None` if the watchdog is disabled.
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        let interval = self.watchdog_interval?;
+        let elapsed = self.last_watchdog.map_or(interval, |t| t.elapsed());
+        Some(interval.saturating_sub(elapsed))
+    }
+
+    /// Periodic update from the event loop
+    ///
+    /// Sends `STATUS=` if the status changed since it was last sent and `WATCHDOG=1` if the
+    /// watchdog is enabled and due. Nothing is sent before [Self::ready].
+    ///
+    /// `status` is only evaluated every [STATUS_INTERVAL], since this is called for every
+    /// iteration of the event loop.
+    pub fn tick<F: FnOnce() -> String>(&mut self, status: F) -> io::Result<()> {
+        if !self.ready {
+            return Ok(());
+        }
+
+        let watchdog_due = self.watchdog_timeout().is_some_and(|t| t.is_zero());
+        let status_due = match self.last_status_check {
+            Some(t) => t.elapsed() >= STATUS_INTERVAL,
+            None => true,
+        };
+        if !watchdog_due && !status_due {
+            return Ok(());
+        }
+
+        let mut msg = Vec::new();
+        if status_due {
+            self.last_status_check = Some(Instant::now());
+            let status = status();
+            if self.last_status.as_ref() != Some(&status) {
+                msg.push(format!("STATUS={status}"));
+                self.last_status = Some(status);
+            }
+        }
+        if watchdog_due {
+            msg.push("WATCHDOG=1".to_owned());
+        }
+        if msg.is_empty() {
+            return Ok(());
+        }
+
+        self.notify(&msg.join("\n"))?;
+        if watchdog_due {
+            self.last_watchdog = Some(Instant::now());
+        }
+        Ok(())
+    }
+}
+
+/// Parse `WATCHDOG_USEC`; the watchdog is only enabled if `WATCHDOG_PID` is unset or matches
+/// `pid`
+fn watchdog_timeout(
+    usec: Option<OsString>,
+    watchdog_pid: Option<OsString>,
+    pid: u32,
+) -> anyhow::Result<Option<Duration>> {
+    let Some(usec) = usec else {
+        return Ok(None);
+    };
+    if let Some(watchdog_pid) = watchdog_pid {
+        let watchdog_pid: u32 = watchdog_pid
+            .to_str()
+            .and_then(|p| p.parse().ok())
+            .with_context(|| format!("Invalid {WATCHDOG_PID_ENV} {watchdog_pid:?}"))?;
+        if watchdog_pid != pid {
+            return Ok(None);
+        }
+    }
+    let usec: u64 = usec
+        .to_str()
+        .and_then(|u| u.parse().ok())
+        .with_context(|| format!("Invalid {WATCHDOG_USEC_ENV} {usec:?}"))?;
+    match usec {
+        0 => Ok(None),
+        usec => Ok(Some(Duration::from_micros(usec))),
+    }
+}
+
+/// Parse the value of `NOTIFY_SOCKET`
+fn notify_socket_addr(path: &OsStr) -> anyhow::Result<UnixAddr> {
+    use std::os::unix::ffi::OsStrExt;
+
+    match path.as_bytes() {
+        [] => bail!("Empty path"),
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        [b'@', name @ ..] => {
+            #[cfg(target_os = "android")]
+            use std::os::android::net::SocketAddrExt;
+            #[cfg(target_os = "linux")]
+            use std::os::linux::net::SocketAddrExt;
+            Ok(UnixAddr::from_abstract_name(name)?)
+        }
+        [b'/', ..] => Ok(UnixAddr::from_pathname(Path::new(path))?),
+        _ => bail!("Expected an absolute path or an abstract socket name"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn recv(socket: &UnixDatagram) -> anyhow::Result<String> {
+        let mut buf = [0u8; 1024];
+        let len = socket.recv(&mut buf)?;
+        Ok(String::from_utf8(buf[..len].to_vec())?)
+    }
+
+    #[test]
+    fn notifications_are_sent_to_the_socket() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("notify");
+        let manager = UnixDatagram::bind(&path)?;
+        manager.set_nonblocking(true)?;
+
+        let mut notify = SdNotify::new(path.as_os_str(), Some(Duration::from_secs(3600)))?;
+        assert_eq!(notify.watchdog_interval(), Some(Duration::from_secs(1800)));
+
+        // Nothing is sent before readiness was signalled
+        notify.tick(|| "starting".to_owned())?;
+        assert!(manager.recv(&mut [0u8; 16]).is_err());
+
+        notify.ready("0/1 peers with a live key")?;
+        assert_eq!(recv(&manager)?, "READY=1\nSTATUS=0/1 peers with a live key");
+        notify.ready("0/1 peers with a live key")?;
+        assert!(manager.recv(&mut [0u8; 16]).is_err());
+
+        // The watchdog is due right after startup, then not again for a while
+        notify.tick(|| "0/1 peers with a live key".to_owned())?;
+        assert_eq!(recv(&manager)?, "WATCHDOG=1");
+        notify.tick(|| "0/1 peers with a live key".to_owned())?;
+        assert!(manager.recv(&mut [0u8; 16]).is_err());
+        assert!(notify.watchdog_timeout().is_some_and(|t| !t.is_zero()));
+
+        // Status changes are picked up at the next status check
+        notify.tick(|| "1/1 peers with a live key".to_owned())?;
+        assert!(manager.recv(&mut [0u8; 16]).is_err());
+        notify.last_status_check = None;
+        notify.tick(|| "1/1 peers with a live key".to_owned())?;
+        assert_eq!(recv(&manager)?, "STATUS=1/1 peers with a live key");
+        Ok(())
+    }
+
+    #[test]
+    fn watchdog_follows_the_environment() -> anyhow::Result<()> {
+        let os = |s: &str| Some(OsString::from(s));
+        assert_eq!(watchdog_timeout(None, None, 42)?, None);
+        assert_eq!(
+            watchdog_timeout(os("2000000"), None, 42)?,
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            watchdog_timeout(os("2000000"), os("42"), 42)?,
+            Some(Duration::from_secs(2))
+        );
+        // The watchdog is meant for another process
+        assert_eq!(watchdog_timeout(os("2000000"), os("43"), 42)?, None);
+        assert_eq!(watchdog_timeout(os("0"), None, 42)?, None);
+        assert!(watchdog_timeout(os("soon"), None, 42).is_err());
+        Ok(())
+    }
+}
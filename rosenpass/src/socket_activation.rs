@@ -0,0 +1,257 @@
+//! Socket activation through systemd
+//!
+//! Instead of binding its UDP listen sockets itself (see [crate::config::Rosenpass::listen]),
+//! Rosenpass can use sockets opened by the service manager. systemd passes these as file
+//! descriptors starting at [SD_LISTEN_FDS_START]; their number is given in `LISTEN_FDS` and
+//! their names (`FileDescriptorName=` in the socket unit) in `LISTEN_FDNAMES`. See
+//! sd_listen_fds(3).
+//!
+//! This way, the daemon can run without `CAP_NET_BIND_SERVICE` and be restarted without a
+//! window in which the port is closed.
+//!
+//! Only datagram sockets are used as listen sockets. Other passed file descriptors, such as a
+//! unix socket for the API, are left open; they can be used through `--api-listen-fd`.
+
+use std::ffi::OsString;
+use std::os::fd::{BorrowedFd, OwnedFd, RawFd};
+
+use anyhow::{bail, ensure, Context};
+use rosenpass_util::fd::claim_fd;
+use rustix::net::{sockopt::get_socket_type, SocketType};
+
+/// The first file descriptor passed by the service manager
+pub const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// Environment variable holding the process id the file descriptors are meant for
+pub const LISTEN_PID_ENV: &str = "LISTEN_PID";
+/// Environment variable holding the number of file descriptors passed
+pub const LISTEN_FDS_ENV: &str = "LISTEN_FDS";
+/// Environment variable holding the colon separated names of the file descriptors passed
+pub const LISTEN_FDNAMES_ENV: &str = "LISTEN_FDNAMES";
+
+/// A file descriptor passed by the service manager
+#[derive(Debug)]
+pub struct ListenFd {
+    /// The name from `LISTEN_FDNAMES`, if given
+    pub name: Option<String>,
+    /// The claimed file descriptor (see [claim_fd])
+    pub fd: OwnedFd,
+}
+
+impl ListenFd {
+    /// Human readable description for log and error messages
+    fn describe(&self) -> String {
+        match self.name.as_deref() {
+            Some(name) => format!("passed file descriptor {name:?}"),
+            None => "passed file descriptor".to_owned(),
+        }
+    }
+
+    /// Turn this into a non-blocking UDP socket
+    ///
+    /// Fails if the file descriptor is not a datagram socket.
+    pub fn into_udp_socket(self) -> anyhow::Result<mio::net::UdpSocket> {
+        let desc = self.describe();
+        let ty = get_socket_type(&self.fd).with_context(|| format!("{desc} is not a socket"))?;
+        ensure!(
+            ty == SocketType::DGRAM,
+            "{desc} is not a datagram socket; Rosenpass needs UDP sockets \
+            (use ListenDatagram= in the socket unit)"
+        );
+        let sock = std::net::UdpSocket::from(self.fd);
+        sock.set_nonblocking(true)?;
+        Ok(mio::net::UdpSocket::from_std(sock))
+    }
+}
+
+/// Claim the file descriptors passed by the service manager for which `select` returns true
+///
+/// Returns `None` if no file descriptors were passed to this process. The other file descriptors
+/// are left open, unclaimed. The socket activation related variables are removed from the
+/// environment, so child processes (such as the PSK broker) do not pick them up.
+pub fn listen_fds(select: fn(BorrowedFd<'_>) -> bool) -> anyhow::Result<Option<Vec<ListenFd>>> {
+    let listen_pid = std::env::var_os(LISTEN_PID_ENV);
+    let listen_fds = std::env::var_os(LISTEN_FDS_ENV);
+    let listen_fdnames = std::env::var_os(LISTEN_FDNAMES_ENV);
+    for var in [LISTEN_PID_ENV, LISTEN_FDS_ENV, LISTEN_FDNAMES_ENV] {
+        std::env::remove_var(var);
+    }
+
+    let pid = std::process::id();
+    let Some(names) = parse_listen_fds(listen_pid, listen_fds, listen_fdnames, pid)? else {
+        return Ok(None);
+    };
+
+    let fds = claim_selected((SD_LISTEN_FDS_START..).zip(names), select)?;
+    Ok(Some(fds))
+}
+
+/// Claim the UDP listen sockets passed by the service manager, if any; see [listen_fds]
+///
+/// Returns `None` if no datagram sockets were passed to this process.
+pub fn udp_listen_sockets() -> anyhow::Result<Option<Vec<mio::net::UdpSocket>>> {
+    let Some(fds) = listen_fds(is_datagram_socket)? else {
+        return Ok(None);
+    };
+    if fds.is_empty() {
+        return Ok(None);
+    }
+    let sockets = fds
+        .into_iter()
+        .map(ListenFd::into_udp_socket)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Some(sockets))
+}
+
+/// Whether `fd` is a datagram socket; used by [udp_listen_sockets]
+fn is_datagram_socket(fd: BorrowedFd<'_>) -> bool {
+    get_socket_type(fd).is_ok_and(|ty| ty == SocketType::DGRAM)
+}
+
+/// Claim the passed file descriptors for which `select` returns true; see [listen_fds]
+fn claim_selected(
+    fds: impl IntoIterator<Item = (RawFd, Option<String>)>,
+    select: fn(BorrowedFd<'_>) -> bool,
+) -> anyhow::Result<Vec<ListenFd>> {
+    let mut claimed = Vec::new();
+    for (fd, name) in fds {
+        // SAFETY: The service manager passed this file descriptor; it stays open until claimed
+        if !select(unsafe { BorrowedFd::borrow_raw(fd) }) {
+            log::debug!("Leaving passed file descriptor {fd} ({name:?}) for other uses");
+            continue;
+        }
+        let fd =
+            claim_fd(fd).with_context(|| format!("Could not claim passed file descriptor {fd}"))?;
+        claimed.push(ListenFd { name, fd });
+    }
+    Ok(claimed)
+}
+
+/// Parse the socket activation environment variables
+///
+/// Returns the names of the passed file descriptors (`None` for unnamed ones), or `None`
+/// if no file descriptors were passed to the process with id `pid`.
+fn parse_listen_fds(
+    listen_pid: Option<OsString>,
+    listen_fds: Option<OsString>,
+    listen_fdnames: Option<OsString>,
+    pid: u32,
+) -> anyhow::Result<Option<Vec<Option<String>>>> {
+    let (Some(listen_pid), Some(listen_fds)) = (listen_pid, listen_fds) else {
+        return Ok(None);
+    };
+
+    let listen_pid: u32 = listen_pid
+        .to_str()
+        .and_then(|p| p.parse().ok())
+        .with_context(|| format!("Invalid {LISTEN_PID_ENV} {listen_pid:?}"))?;
+    if listen_pid != pid {
+        return Ok(None);
+    }
+
+    let count: usize = listen_fds
+        .to_str()
+        .and_then(|n| n.parse().ok())
+        .with_context(|| format!("Invalid {LISTEN_FDS_ENV} {listen_fds:?}"))?;
+    if count == 0 {
+        return Ok(None);
+    }
+
+    let names = match listen_fdnames {
+        None => vec![None; count],
+        Some(names) => {
+            let Some(names) = names.to_str() else {
+                bail!("Invalid {LISTEN_FDNAMES_ENV} {names:?}");
+            };
+            let names = names
+                .split(':')
+                .map(|name| Some(name.to_owned()).filter(|name| !name.is_empty()))
+                .collect::<Vec<_>>();
+            ensure!(
+                names.len() == count,
+                "{LISTEN_FDNAMES_ENV} lists {} names, but {LISTEN_FDS_ENV} is {count}",
+                names.len()
+            );
+            names
+        }
+    };
+    Ok(Some(names))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::net::{Ipv4Addr, TcpListener, UdpSocket};
+    use std::os::fd::{FromRawFd, IntoRawFd};
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn passed_udp_sockets_are_accepted() -> anyhow::Result<()> {
+        let udp = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let addr = udp.local_addr()?;
+        let fd = ListenFd {
+            name: Some("rosenpass".to_owned()),
+            fd: udp.into(),
+        };
+        assert_eq!(fd.into_udp_socket()?.local_addr()?, addr);
+
+        let tcp = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let fd = ListenFd {
+            name: None,
+            fd: tcp.into(),
+        };
+        assert!(fd.into_udp_socket().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn only_datagram_sockets_are_claimed() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let udp_a = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let udp_b = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let addrs = [udp_a.local_addr()?, udp_b.local_addr()?];
+        let api = UnixListener::bind(dir.path().join("api.sock"))?;
+
+        let api_fd = api.into_raw_fd();
+        let passed = [
+            (udp_a.into_raw_fd(), Some("v4".to_owned())),
+            (api_fd, Some("api".to_owned())),
+            (udp_b.into_raw_fd(), None),
+        ];
+        let claimed = claim_selected(passed, is_datagram_socket)?;
+
+        let names = claimed.iter().map(|fd| fd.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names, [Some("v4".to_owned()), None]);
+        for (fd, addr) in claimed.into_iter().zip(addrs) {
+            assert_eq!(fd.into_udp_socket()?.local_addr()?, addr);
+        }
+
+        // The API socket is still usable
+        let api = unsafe { UnixListener::from_raw_fd(api_fd) };
+        assert_eq!(
+            api.local_addr()?.as_pathname(),
+            Some(dir.path().join("api.sock").as_path())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn environment_is_parsed() -> anyhow::Result<()> {
+        let os = |s: &str| Some(OsString::from(s));
+        assert_eq!(parse_listen_fds(None, None, None, 42)?, None);
+        assert_eq!(
+            parse_listen_fds(os("42"), os("2"), None, 42)?,
+            Some(vec![None, None])
+        );
+        assert_eq!(
+            parse_listen_fds(os("42"), os("2"), os("v4:"), 42)?,
+            Some(vec![Some("v4".to_owned()), None])
+        );
+        // The file descriptors are meant for another process
+        assert_eq!(parse_listen_fds(os("43"), os("2"), None, 42)?, None);
+        assert_eq!(parse_listen_fds(os("42"), os("0"), None, 42)?, None);
+        assert!(parse_listen_fds(os("42"), os("2"), os("v4"), 42).is_err());
+        assert!(parse_listen_fds(os("42"), os("many"), None, 42).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+//! Options applied to the UDP listen sockets, for policy routing
+//!
+//! Similar to WireGuard's `FwMark`, Rosenpass can mark its UDP traffic with a firewall mark
+//! (`SO_MARK`, see socket(7)) and bind its sockets to a network device (`SO_BINDTODEVICE`), so
+//! routing rules can send the key exchange through a specific interface:
+//!
+//! ```toml
+//! bind_device = "eth0"
+//! fwmark = 0x1234
+//! ```
+//!
+//! The [SocketOptions] of an [AppServer] are applied to every socket registered through
+//! [AppServer::register_listen_socket]; that includes sockets passed by the service manager
+//! (see [crate::socket_activation]) and sockets bound when the listen addresses change.
+//!
+//! Both options are only available on Linux. Setting the firewall mark requires
+//! `CAP_NET_ADMIN`; binding to a device requires `CAP_NET_RAW` on kernels older than 5.7.
+
+#[cfg(target_os = "linux")]
+use std::io;
+
+#[cfg(target_os = "linux")]
+use anyhow::Context;
+use log::warn;
+#[cfg(target_os = "linux")]
+use rustix::io::Errno;
+
+use crate::app_server::AppServer;
+
+/// Options applied to the UDP listen sockets; see the [module documentation](self)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Name of the network device the sockets are bound to, e.g. `eth0`
+    pub bind_device: Option<String>,
+    /// Firewall mark set on all packets sent through the sockets
+    pub fwmark: Option<u32>,
+}
+
+impl SocketOptions {
+    /// Whether no option is set, i.e. applying these options does nothing
+    pub fn is_empty(&self) -> bool {
+        self.bind_device.is_none() && self.fwmark.is_none()
+    }
+
+    /// Apply the options to a socket
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self, sock: &mio::net::UdpSocket) -> anyhow::Result<()> {
+        let sock = socket2::SockRef::from(sock);
+        if let Some(ref dev) = self.bind_device {
+            sock.bind_device(Some(dev.as_bytes()))
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::PermissionDenied => anyhow::Error::new(e)
+                        .context("Binding to a network device requires CAP_NET_RAW on this kernel"),
+                    _ if e.raw_os_error() == Some(Errno::NODEV.raw_os_error()) => {
+                        anyhow::Error::new(e).context("No such network device")
+                    }
+                    _ => e.into(),
+                })
+                .with_context(|| format!("Could not bind listen socket to device {dev:?}"))?;
+        }
+        if let Some(mark) = self.fwmark {
+            sock.set_mark(mark)
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::PermissionDenied => anyhow::Error::new(e)
+                        .context("Setting the firewall mark requires CAP_NET_ADMIN"),
+                    _ => e.into(),
+                })
+                .with_context(|| {
+                    format!("Could not set firewall mark {mark:#x} on listen socket")
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Undo the options set in `self` on a socket
+    #[cfg(target_os = "linux")]
+    pub fn clear(&self, sock: &mio::net::UdpSocket) -> anyhow::Result<()> {
+        let sock = socket2::SockRef::from(sock);
+        if self.bind_device.is_some() {
+            sock.bind_device(None)
+                .context("Could not unbind listen socket from its network device")?;
+        }
+        if self.fwmark.is_some() {
+            sock.set_mark(0)
+                .context("Could not clear the firewall mark of listen socket")?;
+        }
+        Ok(())
+    }
+
+    /// Apply the options to a socket
+    ///
+    /// Fails unless [Self::is_empty], as the options are only supported on Linux.
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self, _sock: &mio::net::UdpSocket) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.is_empty(),
+            "bind_device and fwmark are only supported on Linux"
+        );
+        Ok(())
+    }
+
+    /// Undo the options set in `self` on a socket
+    ///
+    /// Does nothing, as the options can not be set on other platforms than Linux.
+    #[cfg(not(target_os = "linux"))]
+    pub fn clear(&self, _sock: &mio::net::UdpSocket) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Apply the options to a socket currently using the options `previous`, clearing the
+    /// options no longer set
+    fn replace(&self, previous: &SocketOptions, sock: &mio::net::UdpSocket) -> anyhow::Result<()> {
+        let unset = SocketOptions {
+            bind_device: match self.bind_device {
+                Some(_) => None,
+                None => previous.bind_device.clone(),
+            },
+            fwmark: previous.fwmark.filter(|_| self.fwmark.is_none()),
+        };
+        unset.clear(sock)?;
+        self.apply(sock)
+    }
+}
+
+/// Parse a firewall mark given in decimal or, prefixed with `0x`, in hexadecimal notation
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass::socket_options::parse_fwmark;
+///
+/// assert_eq!(parse_fwmark("51820")?, 51820);
+/// assert_eq!(parse_fwmark("0xca6c")?, 0xca6c);
+/// assert!(parse_fwmark("0x100000000").is_err());
+/// assert!(parse_fwmark("mark").is_err());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn parse_fwmark(s: &str) -> anyhow::Result<u32> {
+    let res = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    res.map_err(|e| anyhow::anyhow!("Invalid firewall mark {s:?}: {e}"))
+}
+
+impl AppServer {
+    /// Set the [SocketOptions] used for all listen sockets
+    ///
+    /// The options are applied to the sockets already registered right away, and to all
+    /// sockets registered later on through [Self::register_listen_socket]. Options no longer
+    /// set are cleared on the registered sockets.
+    ///
+    /// If this fails for any socket, the previous options are restored on the sockets changed
+    /// so far and kept for future sockets.
+    pub fn set_socket_options(&mut self, options: SocketOptions) -> anyhow::Result<()> {
+        let previous = &self.socket_options;
+        for (idx, sock) in self.sockets.iter().enumerate() {
+            let Err(err) = options.replace(previous, sock) else {
+                continue;
+            };
+            // Including the socket that failed, which may have been changed partially
+            for sock in self.sockets[..=idx].iter() {
+                if let Err(e) = previous.replace(&options, sock) {
+                    warn!("Could not restore the previous options of a listen socket: {e:?}");
+                }
+            }
+            return Err(err);
+        }
+        self.socket_options = options;
+        Ok(())
+    }
+}
@@ -12,11 +12,12 @@ use command_fds::{CommandFdExt, FdMapping};
 use hex_literal::hex;
 use rosenpass::api::{
     self, add_listen_socket_response_status, add_psk_broker_response_status,
-    supply_keypair_response_status,
+    fetch_events_response_status, set_log_level_response_status, set_peer_timing_response_status,
+    shutdown_response_status, supply_keypair_response_status,
 };
 use rosenpass_util::{
     b64::B64Display,
-    file::LoadValueB64,
+    file::{LoadValue, LoadValueB64},
     io::IoErrorKind,
     length_prefix_encoding::{decoder::LengthPrefixDecoder, encoder::LengthPrefixEncoder},
     mem::{DiscardResultExt, MoveExt},
@@ -27,7 +28,8 @@ use std::os::fd::{AsFd, AsRawFd};
 use tempfile::TempDir;
 use zerocopy::AsBytes;
 
-use rosenpass::protocol::SymKey;
+use rosenpass::logging::LogFilter;
+use rosenpass::protocol::{PeerParams, SPk, SymKey, TimingProfile};
 
 struct KillChild(std::process::Child);
 
@@ -67,6 +69,16 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
     let peer_a_keypair = config::Keypair::new(tempfile!("a.pk"), tempfile!("a.sk"));
 
     let peer_b_osk = tempfile!("b.osk");
+    // Records the key output reasons; the delay makes sure shutdown waits for the command
+    let peer_b_reasons = tempfile!("b.reasons");
+    let peer_b_exchange_command = vec![
+        "sh".to_owned(),
+        "-c".to_owned(),
+        format!(
+            "sleep 0.5; echo $ROSENPASS_KEY_OUTPUT_REASON >> {:?}",
+            peer_b_reasons
+        ),
+    ];
     let peer_b_wg_device = "mock_device";
     let peer_b_wg_peer_id = hex!(
         "
@@ -80,7 +92,11 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
         config_file_path: tempfile!("a.config"),
         keypair: None,
         listen: vec![], // TODO: This could collide by accident
+        bind_device: None,
+        fwmark: None,
         verbosity: config::Verbosity::Verbose,
+        log_level: None,
+        log_format: None,
         api: api::config::ApiConfig {
             listen_path: vec![tempfile!("a.sock")],
             listen_fd: vec![],
@@ -96,7 +112,16 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
                 peer: format!("{}", peer_b_wg_peer_id.fmt_b64::<8129>()),
                 extra_params: vec![],
             }),
+            timing: None,
+            suite: None,
+            exchange_command: Some(peer_b_exchange_command),
+            endpoint_refresh: None,
+            roaming: None,
         }],
+        state_file: None,
+        cookie_secret_epoch: None,
+        trace_file: None,
+        metrics: None,
     };
 
     let peer_b_keypair = config::Keypair::new(tempfile!("b.pk"), tempfile!("b.sk"));
@@ -104,7 +129,11 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
         config_file_path: tempfile!("b.config"),
         keypair: Some(peer_b_keypair.clone()),
         listen: vec![],
+        bind_device: None,
+        fwmark: None,
         verbosity: config::Verbosity::Verbose,
+        log_level: None,
+        log_format: None,
         api: api::config::ApiConfig {
             listen_path: vec![tempfile!("b.sock")],
             listen_fd: vec![],
@@ -116,7 +145,16 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
             endpoint: Some(peer_a_endpoint.to_owned()),
             pre_shared_key: None,
             wg: None,
+            timing: None,
+            suite: None,
+            exchange_command: None,
+            endpoint_refresh: None,
+            roaming: None,
         }],
+        state_file: None,
+        cookie_secret_epoch: None,
+        trace_file: None,
+        metrics: None,
     };
 
     // Generate the keys
@@ -138,7 +176,7 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
     let deliberate_fail_child_fd = 3;
 
     // Start peer a
-    let _proc_a = KillChild(
+    let mut proc_a = KillChild(
         std::process::Command::new(env!("CARGO_BIN_EXE_rosenpass"))
             .args(["--api-stream-fd", &deliberate_fail_child_fd.to_string()])
             .fd_mappings(vec![FdMapping {
@@ -270,6 +308,47 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
         );
     }
 
+    // Exercise SetPeerTiming: unknown peer, out-of-bounds profile, valid profile
+    {
+        let peer_b_id = PeerParams::new(None, SPk::load(&peer_b_keypair.public_key)?).pidt()?;
+        let timing = TimingProfile {
+            rekey_after_time_responder: 100.0,
+            rekey_after_time_initiator: 110.0,
+            reject_after_time: 180.0,
+            ..TimingProfile::default()
+        };
+        let too_eager = TimingProfile {
+            rekey_after_time_responder: 0.5,
+            ..timing
+        };
+
+        let cases = [
+            (
+                [0xaau8; 32],
+                timing,
+                set_peer_timing_response_status::NO_SUCH_PEER,
+            ),
+            (
+                peer_b_id.value,
+                too_eager,
+                set_peer_timing_response_status::INVALID_REQUEST,
+            ),
+            (peer_b_id.value, timing, set_peer_timing_response_status::OK),
+        ];
+
+        for (peer_id, timing, status) in cases {
+            LengthPrefixEncoder::from_message(
+                api::SetPeerTimingRequest::new(peer_id, &timing).as_bytes(),
+            )
+            .write_all_to_stdio(&api)?;
+
+            let mut decoder = LengthPrefixDecoder::new([0u8; api::MAX_RESPONSE_LEN]);
+            let res = decoder.read_all_from_stdio(&api)?;
+            let res = res.zk_parse::<api::SetPeerTimingResponse>()?;
+            assert_eq!(*res, api::SetPeerTimingResponse::new(status));
+        }
+    }
+
     // Wait for the keys to successfully exchange a key
     let mut attempt = 0;
     loop {
@@ -336,5 +415,94 @@ fn api_integration_api_setup() -> anyhow::Result<()> {
         attempt += 1;
     }
 
+    // Peer A has no endpoint configured for peer B, so it learned it from the handshake
+    {
+        let peer_b_id = PeerParams::new(None, SPk::load(&peer_b_keypair.public_key)?).pidt()?;
+        LengthPrefixEncoder::from_message(api::FetchEventsRequest::new(0).as_bytes())
+            .write_all_to_stdio(&api)?;
+
+        let mut decoder = LengthPrefixDecoder::new([0u8; api::MAX_RESPONSE_LEN]);
+        let res = decoder.read_all_from_stdio(&api)?;
+        let res = res.zk_parse::<api::FetchEventsResponse>()?;
+        assert_eq!({ res.payload.status }, fetch_events_response_status::OK);
+        assert_eq!({ res.payload.missed }, 0);
+
+        let events = res.payload.events();
+        let idx = events
+            .iter()
+            .position(|e| { e.kind } == api::api_event_kind::ENDPOINT_CHANGED)
+            .context("No endpoint change event")?;
+        let event = events[idx];
+        assert_eq!(event.peer_id, peer_b_id.value);
+        assert_eq!(event.from.socket_addr(), None);
+        assert!(event
+            .to
+            .socket_addr()
+            .context("No address")?
+            .ip()
+            .is_loopback());
+
+        // The key exchanged in the same handshake is reported right after
+        let event = events.get(idx + 1).context("No key exchange event")?;
+        assert_eq!({ event.kind }, api::api_event_kind::KEY_EXCHANGED);
+        assert_eq!(event.peer_id, peer_b_id.value);
+    }
+
+    // Change the log filter at runtime
+    {
+        let filter: LogFilter = "warn,api=debug".parse()?;
+        let req = api::SetLogLevelRequest::new(Some(&filter))?;
+        LengthPrefixEncoder::from_message(req.as_bytes()).write_all_to_stdio(&api)?;
+
+        let mut decoder = LengthPrefixDecoder::new([0u8; api::MAX_RESPONSE_LEN]);
+        let res = decoder.read_all_from_stdio(&api)?;
+        let res = res.zk_parse::<api::SetLogLevelResponse>()?;
+        assert_eq!({ res.payload.status }, set_log_level_response_status::OK);
+        assert_eq!(res.payload.filter()?, filter);
+
+        // Invalid filters are rejected
+        let mut req = api::SetLogLevelRequest::new(None)?;
+        req.payload.filter[..12].copy_from_slice(b"api=verbose!");
+        LengthPrefixEncoder::from_message(req.as_bytes()).write_all_to_stdio(&api)?;
+
+        let mut decoder = LengthPrefixDecoder::new([0u8; api::MAX_RESPONSE_LEN]);
+        let res = decoder.read_all_from_stdio(&api)?;
+        let res = res.zk_parse::<api::SetLogLevelResponse>()?;
+        assert_eq!(
+            { res.payload.status },
+            set_log_level_response_status::INVALID_REQUEST
+        );
+        assert_eq!(res.payload.filter()?, filter);
+    }
+
+    // Shut down peer a; the key exchanged is replaced by a stale key before exiting
+    {
+        LengthPrefixEncoder::from_message(api::ShutdownRequest::new().as_bytes())
+            .write_all_to_stdio(&api)?;
+
+        let mut decoder = LengthPrefixDecoder::new([0u8; api::MAX_RESPONSE_LEN]);
+        let res = decoder.read_all_from_stdio(&api)?;
+        let res = res.zk_parse::<api::ShutdownResponse>()?;
+        assert_eq!(
+            *res,
+            api::ShutdownResponse::new(shutdown_response_status::OK)
+        );
+
+        use rosenpass_wireguard_broker::api::msgs as M;
+        type SetPskReqPkg = M::Envelope<M::SetPskRequest>;
+        let mut decoder = LengthPrefixDecoder::new([0u8; M::REQUEST_MSG_BUFFER_SIZE]);
+        let req = decoder.read_all_from_stdio(&psk_broker_sock)?;
+        let req = req.zk_parse::<SetPskReqPkg>()?;
+        assert_eq!(req.payload.peer_id, peer_b_wg_peer_id);
+        let osk_b = SymKey::load_b64::<64, _>(peer_b_osk.clone())?;
+        assert_ne!(&req.payload.psk[..], &osk_b.secret()[..]);
+
+        assert!(proc_a.0.wait()?.success());
+
+        // The exchange command ran for the stale key before peer a exited
+        let reasons = std::fs::read_to_string(&peer_b_reasons)?;
+        assert_eq!(reasons.lines().last(), Some("stale"));
+    }
+
     Ok(())
 }
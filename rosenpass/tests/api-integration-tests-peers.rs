@@ -0,0 +1,402 @@
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader},
+    net::ToSocketAddrs,
+    os::unix::net::UnixStream,
+    process::Stdio,
+    thread::sleep,
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use rosenpass::api::{
+    self, add_peer_response_status, force_rekey_response_status, get_peer_status_response_status,
+    list_peers_response_status, remove_peer_response_status, rotate_keypair_response_status,
+    subscribe_response_status,
+};
+use rosenpass::protocol::{PeerParams, SPk, SymKey};
+use rosenpass_util::{
+    b64::B64Display,
+    file::{LoadValue, LoadValueB64},
+    length_prefix_encoding::{decoder::LengthPrefixDecoder, encoder::LengthPrefixEncoder},
+    mem::DiscardResultExt,
+    mio::WriteWithFileDescriptors,
+    zerocopy::ZerocopySliceExt,
+};
+use tempfile::TempDir;
+use zerocopy::AsBytes;
+
+struct KillChild(std::process::Child);
+
+impl Drop for KillChild {
+    fn drop(&mut self) {
+        use rustix::process::{kill_process, Pid, Signal::Term};
+        let pid = Pid::from_child(&self.0);
+        // We seriously need to start handling signals with signalfd, our current signal handling
+        // system is a bit broken; there is probably a few functions that just restart on EINTR
+        // so the signal is absorbed
+        loop {
+            kill_process(pid, Term).discard_result();
+            if self.0.try_wait().unwrap().is_some() {
+                break;
+            }
+        }
+    }
+}
+
+/// Send a request along with the given file descriptors and read the response
+fn request<Res: zerocopy::FromBytes + Copy>(
+    api: &UnixStream,
+    req: &[u8],
+    fds: &[&std::os::fd::OwnedFd],
+) -> anyhow::Result<Res> {
+    {
+        use std::os::fd::AsFd;
+        let mut fds: VecDeque<_> = fds.iter().map(|fd| fd.as_fd()).collect();
+        let mut api = WriteWithFileDescriptors::<UnixStream, _, _, _>::new(api, &mut fds);
+        LengthPrefixEncoder::from_message(req).write_all_to_stdio(&mut api)?;
+        assert!(fds.is_empty(), "Failed to write all file descriptors");
+    }
+
+    let mut decoder = LengthPrefixDecoder::new([0u8; api::MAX_RESPONSE_LEN]);
+    let res = decoder.read_all_from_stdio(api)?;
+    Ok(*res.zk_parse::<Res>()?)
+}
+
+#[test]
+fn api_integration_add_and_remove_peers() -> anyhow::Result<()> {
+    rosenpass_secret_memory::policy::secret_policy_use_only_malloc_secrets();
+
+    let dir = TempDir::with_prefix("rosenpass-api-integration-test")?;
+
+    macro_rules! tempfile {
+        ($($lst:expr),+) => {{
+            let mut buf =  dir.path().to_path_buf();
+            $(buf.push($lst);)*
+            buf
+        }}
+    }
+
+    let peer_a_endpoint = "[::1]:61425";
+    let peer_a_osk = tempfile!("a.osk");
+    let peer_b_osk = tempfile!("b.osk");
+
+    use rosenpass::config;
+
+    // Peer a starts without any peers; peer b is added through the API
+    let peer_a_keypair = config::Keypair::new(tempfile!("a.pk"), tempfile!("a.sk"));
+    let peer_a = config::Rosenpass {
+        config_file_path: tempfile!("a.config"),
+        keypair: Some(peer_a_keypair.clone()),
+        listen: peer_a_endpoint.to_socket_addrs()?.collect(), // TODO: This could collide by accident
+        bind_device: None,
+        fwmark: None,
+        verbosity: config::Verbosity::Verbose,
+        log_level: None,
+        log_format: None,
+        api: api::config::ApiConfig {
+            listen_path: vec![tempfile!("a.sock")],
+            listen_fd: vec![],
+            stream_fd: vec![],
+        },
+        peers: vec![],
+        state_file: None,
+        cookie_secret_epoch: None,
+        trace_file: None,
+        metrics: None,
+    };
+
+    let peer_b_keypair = config::Keypair::new(tempfile!("b.pk"), tempfile!("b.sk"));
+    let peer_b = config::Rosenpass {
+        config_file_path: tempfile!("b.config"),
+        keypair: Some(peer_b_keypair.clone()),
+        listen: vec![],
+        bind_device: None,
+        fwmark: None,
+        verbosity: config::Verbosity::Verbose,
+        log_level: None,
+        log_format: None,
+        api: api::config::ApiConfig::default(),
+        peers: vec![config::RosenpassPeer {
+            public_key: tempfile!("a.pk"),
+            key_out: Some(peer_b_osk.clone()),
+            endpoint: Some(peer_a_endpoint.to_owned()),
+            pre_shared_key: None,
+            wg: None,
+            timing: None,
+            suite: None,
+            exchange_command: None,
+            endpoint_refresh: None,
+            roaming: None,
+        }],
+        state_file: None,
+        cookie_secret_epoch: None,
+        trace_file: None,
+        metrics: None,
+    };
+
+    // Generate the keys
+    rosenpass::cli::testing::generate_and_save_keypair(
+        peer_a_keypair.secret_key.clone(),
+        peer_a_keypair.public_key.clone(),
+    )?;
+    rosenpass::cli::testing::generate_and_save_keypair(
+        peer_b_keypair.secret_key.clone(),
+        peer_b_keypair.public_key.clone(),
+    )?;
+    let peer_b_id = PeerParams::new(None, SPk::load(&peer_b_keypair.public_key)?).pidt()?;
+
+    // Write the configuration files
+    peer_a.commit()?;
+    peer_b.commit()?;
+
+    // Start peer a
+    let mut proc_a = KillChild(
+        std::process::Command::new(env!("CARGO_BIN_EXE_rosenpass"))
+            .args([
+                "exchange-config",
+                peer_a.config_file_path.to_str().context("")?,
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?,
+    );
+
+    // Start peer b
+    let _proc_b = KillChild(
+        std::process::Command::new(env!("CARGO_BIN_EXE_rosenpass"))
+            .args([
+                "exchange-config",
+                peer_b.config_file_path.to_str().context("")?,
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()?,
+    );
+
+    // Acquire stdout
+    let mut out_a = BufReader::new(proc_a.0.stdout.take().context("")?).lines();
+
+    // Wait for the socket to be created
+    let api_path = peer_a.api.listen_path[0].as_path();
+    let mut attempt = 0;
+    while !api_path.exists() {
+        sleep(Duration::from_millis(200));
+        attempt += 1;
+        assert!(
+            attempt < 250,
+            "Api failed to be created even after 50 seconds"
+        );
+    }
+    let api = UnixStream::connect(api_path)?;
+
+    let open_pk = || {
+        use rustix::fs::{open, Mode, OFlags};
+        open(&peer_b_keypair.public_key, OFlags::RDONLY, Mode::empty())
+    };
+
+    // The public key is required
+    let req = api::AddPeerRequest::new(None, Some(&peer_a_osk), None)?;
+    let res: api::AddPeerResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!(
+        { res.payload.status },
+        add_peer_response_status::INVALID_REQUEST
+    );
+
+    // Add peer b
+    let res: api::AddPeerResponse = request(&api, req.as_bytes(), &[&open_pk()?])?;
+    assert_eq!(
+        res,
+        api::AddPeerResponse::new(add_peer_response_status::OK, peer_b_id.value)
+    );
+
+    // Adding the same peer twice fails
+    let res: api::AddPeerResponse = request(&api, req.as_bytes(), &[&open_pk()?])?;
+    assert_eq!(
+        res,
+        api::AddPeerResponse::new(
+            add_peer_response_status::PEER_ALREADY_EXISTS,
+            peer_b_id.value
+        )
+    );
+
+    // Wait for peer a to exchange a key with the new peer
+    let peer_b_id_b64 = peer_b_id.fmt_b64::<64>().to_string();
+    let mut attempt = 0;
+    loop {
+        let line = out_a.next().context("")??;
+        assert_eq!(
+            line,
+            format!(
+                "output-key peer {peer_b_id_b64} key-file \"{}\" exchanged",
+                peer_a_osk.to_str().context("")?
+            )
+        );
+
+        let osk_a = SymKey::load_b64::<64, _>(peer_a_osk.clone())?;
+        let osk_b = SymKey::load_b64::<64, _>(peer_b_osk.clone())?;
+        match osk_a.secret() == osk_b.secret() {
+            true => break,
+            false if attempt > 10 => bail!("Peers did not produce a matching key even after ten attempts. Something is wrong with the key exchange!"),
+            false => {},
+        };
+
+        attempt += 1;
+    }
+
+    // Peer b holds a live key now
+    let req = api::GetPeerStatusRequest::new(peer_b_id.value);
+    let res: api::GetPeerStatusResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!({ res.payload.status }, get_peer_status_response_status::OK);
+    let status = res.payload.peer;
+    assert_eq!(status.peer_id, peer_b_id.value);
+    assert_eq!(status.session, api::api_session_state::LIVE);
+    assert_ne!(status.session_role, api::api_handshake_role::NONE);
+    assert!(status
+        .endpoint
+        .socket_addr()
+        .is_some_and(|addr| addr.ip().is_loopback()));
+
+    // It is the only peer
+    let req = api::ListPeersRequest::new(0);
+    let res: api::ListPeersResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!({ res.payload.status }, list_peers_response_status::OK);
+    assert_eq!({ res.payload.total }, 1);
+    let peers = res.payload.peers();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].peer_id, peer_b_id.value);
+
+    let req = api::ListPeersRequest::new(1);
+    let res: api::ListPeersResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!({ res.payload.total }, 1);
+    assert!(res.payload.peers().is_empty());
+
+    // Subscribe to server events on a second connection
+    let events = UnixStream::connect(api_path)?;
+    events.set_read_timeout(Some(Duration::from_secs(60)))?;
+    let req = api::SubscribeRequest::new(u64::MAX);
+    let res: api::SubscribeResponse = request(&events, req.as_bytes(), &[])?;
+    assert_eq!({ res.payload.status }, subscribe_response_status::OK);
+    assert_eq!({ res.payload.event.kind }, api::api_event_kind::NONE);
+
+    // Force a new key exchange, erasing the current key right away
+    let req = api::ForceRekeyRequest::new([0u8; 32], true);
+    let res: api::ForceRekeyResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!(
+        res,
+        api::ForceRekeyResponse::new(force_rekey_response_status::NO_SUCH_PEER)
+    );
+
+    let req = api::ForceRekeyRequest::new(peer_b_id.value, true);
+    let res: api::ForceRekeyResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!(
+        res,
+        api::ForceRekeyResponse::new(force_rekey_response_status::OK)
+    );
+    for outcome in ["stale", "exchanged"] {
+        let line = out_a.next().context("")??;
+        assert_eq!(
+            line,
+            format!(
+                "output-key peer {peer_b_id_b64} key-file \"{}\" {outcome}",
+                peer_a_osk.to_str().context("")?
+            )
+        );
+    }
+
+    // Both outcomes are streamed to the subscriber
+    let mut decoder = LengthPrefixDecoder::new([0u8; api::MAX_RESPONSE_LEN]);
+    for kind in [
+        api::api_event_kind::KEY_STALE,
+        api::api_event_kind::KEY_EXCHANGED,
+    ] {
+        let event = loop {
+            let res = decoder.read_all_from_stdio(&events)?;
+            let res = *res.zk_parse::<api::SubscribeResponse>()?;
+            decoder.clear();
+            assert_eq!({ res.payload.status }, subscribe_response_status::OK);
+            assert_eq!({ res.payload.missed }, 0);
+            if { res.payload.event.kind } == kind {
+                break res.payload.event;
+            }
+        };
+        assert_eq!(event.peer_id, peer_b_id.value);
+    }
+
+    // Rotate the keypair of peer a; peer b keeps using the previous public key
+    let open_key = |path: &std::path::Path| {
+        use rustix::fs::{open, Mode, OFlags};
+        open(path, OFlags::RDONLY, Mode::empty())
+    };
+    let (new_sk, new_pk) = (tempfile!("a2.sk"), tempfile!("a2.pk"));
+    rosenpass::cli::testing::generate_and_save_keypair(new_sk.clone(), new_pk.clone())?;
+
+    let req = api::RotateKeypairRequest::new(60_000);
+    let res: api::RotateKeypairResponse = request(&api, req.as_bytes(), &[&open_key(&new_sk)?])?;
+    assert_eq!(
+        res,
+        api::RotateKeypairResponse::new(rotate_keypair_response_status::INVALID_REQUEST)
+    );
+
+    let res: api::RotateKeypairResponse = request(
+        &api,
+        req.as_bytes(),
+        &[&open_key(&new_sk)?, &open_key(&new_pk)?],
+    )?;
+    assert_eq!(
+        res,
+        api::RotateKeypairResponse::new(rotate_keypair_response_status::OK)
+    );
+
+    // Keys are still exchanged during the grace period
+    let req = api::ForceRekeyRequest::new(peer_b_id.value, false);
+    let res: api::ForceRekeyResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!(
+        res,
+        api::ForceRekeyResponse::new(force_rekey_response_status::OK)
+    );
+    let line = out_a.next().context("")??;
+    assert_eq!(
+        line,
+        format!(
+            "output-key peer {peer_b_id_b64} key-file \"{}\" exchanged",
+            peer_a_osk.to_str().context("")?
+        )
+    );
+
+    // Remove peer b; the key exchanged is erased
+    let req = api::RemovePeerRequest::new(peer_b_id.value);
+    let res: api::RemovePeerResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!(
+        res,
+        api::RemovePeerResponse::new(remove_peer_response_status::OK)
+    );
+    let line = out_a.next().context("")??;
+    assert_eq!(
+        line,
+        format!(
+            "output-key peer {peer_b_id_b64} key-file \"{}\" stale",
+            peer_a_osk.to_str().context("")?
+        )
+    );
+
+    // The peer is gone
+    let res: api::RemovePeerResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!(
+        res,
+        api::RemovePeerResponse::new(remove_peer_response_status::NO_SUCH_PEER)
+    );
+
+    let req = api::GetPeerStatusRequest::new(peer_b_id.value);
+    let res: api::GetPeerStatusResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!(
+        { res.payload.status },
+        get_peer_status_response_status::NO_SUCH_PEER
+    );
+
+    let req = api::ListPeersRequest::new(0);
+    let res: api::ListPeersResponse = request(&api, req.as_bytes(), &[])?;
+    assert_eq!({ res.payload.total }, 0);
+
+    Ok(())
+}
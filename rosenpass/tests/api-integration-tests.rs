@@ -61,7 +61,11 @@ fn api_integration_test() -> anyhow::Result<()> {
         config_file_path: tempfile!("a.config"),
         keypair: Some(peer_a_keypair.clone()),
         listen: peer_a_endpoint.to_socket_addrs()?.collect(), // TODO: This could collide by accident
+        bind_device: None,
+        fwmark: None,
         verbosity: config::Verbosity::Verbose,
+        log_level: None,
+        log_format: None,
         api: api::config::ApiConfig {
             listen_path: vec![tempfile!("a.sock")],
             listen_fd: vec![],
@@ -73,7 +77,16 @@ fn api_integration_test() -> anyhow::Result<()> {
             endpoint: None,
             pre_shared_key: None,
             wg: None,
+            timing: None,
+            suite: None,
+            exchange_command: None,
+            endpoint_refresh: None,
+            roaming: None,
         }],
+        state_file: None,
+        cookie_secret_epoch: None,
+        trace_file: None,
+        metrics: None,
     };
 
     let peer_b_keypair = config::Keypair::new(tempfile!("b.pk"), tempfile!("b.sk"));
@@ -81,7 +94,11 @@ fn api_integration_test() -> anyhow::Result<()> {
         config_file_path: tempfile!("b.config"),
         keypair: Some(peer_b_keypair.clone()),
         listen: vec![],
+        bind_device: None,
+        fwmark: None,
         verbosity: config::Verbosity::Verbose,
+        log_level: None,
+        log_format: None,
         api: api::config::ApiConfig {
             listen_path: vec![tempfile!("b.sock")],
             listen_fd: vec![],
@@ -93,7 +110,16 @@ fn api_integration_test() -> anyhow::Result<()> {
             endpoint: Some(peer_a_endpoint.to_owned()),
             pre_shared_key: None,
             wg: None,
+            timing: None,
+            suite: None,
+            exchange_command: None,
+            endpoint_refresh: None,
+            roaming: None,
         }],
+        state_file: None,
+        cookie_secret_epoch: None,
+        trace_file: None,
+        metrics: None,
     };
 
     // Generate the keys
@@ -8,3 +8,23 @@ fn main_fn_prints_errors() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn main_fn_prints_errors_as_json() -> anyhow::Result<()> {
+    let out = test_bin::get_test_bin("rosenpass")
+        .args(["--log-format", "json", "exchange-config", "/"])
+        .output()?;
+    assert!(!out.status.success());
+
+    let stderr = String::from_utf8(out.stderr)?;
+    let line = stderr.lines().last().unwrap_or_default();
+    let record: serde_json::Value = serde_json::from_str(line)?;
+    assert_eq!(record["level"], "error");
+    assert_eq!(record["event"], "error");
+    assert!(record["error"]
+        .as_str()
+        .unwrap_or_default()
+        .contains("Is a directory (os error 21)"));
+
+    Ok(())
+}
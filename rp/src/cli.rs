@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 use std::{iter::Peekable, net::SocketAddr};
 
+use rosenpass::socket_options::parse_fwmark;
+
 use crate::exchange::{ExchangeOptions, ExchangePeer};
 
 /// The different commands supported by the `rp` binary.
@@ -57,7 +59,7 @@ fn fatal<T>(note: &str, command: Option<CommandType>) -> Result<T, String> {
         Some(command) => match command {
             CommandType::GenKey => Err(format!("{}\nUsage: rp genkey PRIVATE_KEYS_DIR", note)),
             CommandType::PubKey => Err(format!("{}\nUsage: rp pubkey PRIVATE_KEYS_DIR PUBLIC_KEYS_DIR", note)),
-            CommandType::Exchange => Err(format!("{}\nUsage: rp exchange PRIVATE_KEYS_DIR [dev <device>] [ip <ip1>/<cidr1>] [listen <ip>:<port>] [peer PUBLIC_KEYS_DIR [endpoint <ip>:<port>] [persistent-keepalive <interval>] [allowed-ips <ip1>/<cidr1>[,<ip2>/<cidr2>]...]]...", note)),
+            CommandType::Exchange => Err(format!("{}\nUsage: rp exchange PRIVATE_KEYS_DIR [dev <device>] [ip <ip1>/<cidr1>] [listen <ip>:<port>] [bind-device <device>] [fwmark <mark>] [peer PUBLIC_KEYS_DIR [endpoint <ip>:<port>] [persistent-keepalive <interval>] [allowed-ips <ip1>/<cidr1>[,<ip2>/<cidr2>]...]]...", note)),
             CommandType::ExchangeConfig => Err(format!("{}\nUsage: rp exchange-config <CONFIG_FILE>", note)),
         },
         None => Err(format!("{}\nUsage: rp [verbose] genkey|pubkey|exchange|exchange-config [ARGS]...", note)),
@@ -200,6 +202,33 @@ impl ExchangeOptions {
                         );
                     }
                 }
+                "bind-device" => {
+                    if let Some(device) = args.next() {
+                        options.bind_device = Some(device);
+                    } else {
+                        return fatal(
+                            "bind-device option requires parameter",
+                            Some(CommandType::Exchange),
+                        );
+                    }
+                }
+                "fwmark" => {
+                    if let Some(mark) = args.next() {
+                        if let Ok(mark) = parse_fwmark(&mark) {
+                            options.fwmark = Some(mark);
+                        } else {
+                            return fatal(
+                                "invalid parameter for fwmark option",
+                                Some(CommandType::Exchange),
+                            );
+                        }
+                    } else {
+                        return fatal(
+                            "fwmark option requires parameter",
+                            Some(CommandType::Exchange),
+                        );
+                    }
+                }
                 "peer" => {
                     let peer = ExchangePeer::parse(&mut args)?;
                     options.peers.push(peer);
@@ -413,6 +442,15 @@ mod tests {
             "listen",
             "notarealip"
         ]));
+        assert!(parse_err(&["rp", "exchange", "./fakedir", "bind-device"]));
+        assert!(parse_err(&["rp", "exchange", "./fakedir", "fwmark"]));
+        assert!(parse_err(&[
+            "rp",
+            "exchange",
+            "./fakedir",
+            "fwmark",
+            "notamark"
+        ]));
     }
 
     #[test]
@@ -430,11 +468,34 @@ mod tests {
                 assert_eq!(options.private_keys_dir.to_str().unwrap(), "./fakedir");
                 assert!(options.dev.is_none());
                 assert!(options.listen.is_none());
+                assert!(options.bind_device.is_none());
+                assert!(options.fwmark.is_none());
                 assert_eq!(options.peers.len(), 0);
             }
             _ => unreachable!(),
         }
 
+        let cli = parse(&[
+            "rp",
+            "exchange",
+            "./fakedir",
+            "bind-device",
+            "eth0",
+            "fwmark",
+            "0xca6c",
+        ]);
+
+        assert!(cli.is_ok());
+        let cli = cli.unwrap();
+
+        match cli.command {
+            Some(Command::Exchange(options)) => {
+                assert_eq!(options.bind_device, Some("eth0".to_string()));
+                assert_eq!(options.fwmark, Some(0xca6c));
+            }
+            _ => unreachable!(),
+        }
+
         let cli = parse(&[
             "rp",
             "exchange",
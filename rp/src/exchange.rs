@@ -41,6 +41,11 @@ pub struct ExchangeOptions {
     /// The IP-address and port that the rosenpass [AppServer](rosenpass::app_server::AppServer)
     /// should use.
     pub listen: Option<SocketAddr>,
+    /// The network device the sockets of the rosenpass
+    /// [AppServer](rosenpass::app_server::AppServer) should be bound to.
+    pub bind_device: Option<String>,
+    /// The firewall mark set on the packets sent by rosenpass and by the WireGuard device.
+    pub fwmark: Option<u32>,
     /// Other peers a connection should be initialized to
     pub peers: Vec<ExchangePeer>,
 }
@@ -204,6 +209,7 @@ pub async fn exchange(options: ExchangeOptions) -> Result<()> {
         app_server::{AppServer, BrokerPeer},
         config::Verbosity,
         protocol::{SPk, SSk, SymKey},
+        socket_options::SocketOptions,
     };
     use rosenpass_secret_memory::Secret;
     use rosenpass_util::file::{LoadValue as _, LoadValueB64};
@@ -269,7 +275,7 @@ pub async fn exchange(options: ExchangeOptions) -> Result<()> {
 
     let wgsk = Secret::<WG_KEY_LEN>::load_b64::<WG_B64_LEN, _>(wgsk_path)?;
 
-    let mut attr: Vec<WgDeviceAttrs> = Vec::with_capacity(2);
+    let mut attr: Vec<WgDeviceAttrs> = Vec::with_capacity(3);
     attr.push(WgDeviceAttrs::PrivateKey(*wgsk.secret()));
 
     if let Some(listen) = options.listen {
@@ -280,6 +286,10 @@ pub async fn exchange(options: ExchangeOptions) -> Result<()> {
         attr.push(WgDeviceAttrs::ListenPort(listen.port() + 1));
     }
 
+    if let Some(fwmark) = options.fwmark {
+        attr.push(WgDeviceAttrs::Fwmark(fwmark));
+    }
+
     netlink::wg_set(&mut genetlink, link_index, attr).await?;
 
     // set up the rosenpass AppServer
@@ -304,6 +314,11 @@ pub async fn exchange(options: ExchangeOptions) -> Result<()> {
         None,
     )?);
 
+    srv.set_socket_options(SocketOptions {
+        bind_device: options.bind_device,
+        fwmark: options.fwmark,
+    })?;
+
     let broker_store_ptr = srv.register_broker(Box::new(NativeUnixBroker::new()))?;
 
     fn cfg_err_map(e: NativeUnixBrokerConfigBaseBuilderError) -> anyhow::Error {
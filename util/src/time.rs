@@ -1,10 +1,189 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
+use crate::mem::DiscardResultExt;
+
+/// A source of time.
+///
+/// Clocks return the seconds elapsed since some arbitrary, fixed point in time.
+/// The value must never decrease.
+///
+/// Usually you want to use a [Timebase] instead of a clock directly.
+///
+/// Implementations provided here are:
+///
+/// - [MonotonicClock]: The real, monotonic system clock; this is the default
+/// - [ManualClock]: Time only advances when explicitly told to; useful for tests
+/// - [SimulatedClock]: Real time running at an adjustable speed, with the ability to skip ahead
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the seconds elapsed since the clock's epoch
+    fn now(&self) -> f64;
+}
+
+/// The real, monotonic system clock.
+///
+/// The epoch is the time of creation of this clock.
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass_util::time::{Clock, MonotonicClock};
+///
+/// let clock = MonotonicClock::default();
+/// let t0 = clock.now();
+/// let t1 = clock.now();
+/// assert!(t1 >= t0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MonotonicClock(pub Instant);
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> f64 {
+        self.0.elapsed().as_secs_f64()
+    }
+}
+
+/// A clock that only advances when explicitly told to.
+///
+/// All clones of a manual clock share the same time, so a test can keep one
+/// handle while handing another one to the code under test.
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass_util::time::{Clock, ManualClock, Timebase};
+///
+/// let clock = ManualClock::default();
+/// let timebase = Timebase::new(clock.clone());
+/// assert_eq!(timebase.now(), 0.0);
+///
+/// clock.advance(120.0);
+/// assert_eq!(timebase.now(), 120.0);
+///
+/// clock.set(300.0);
+/// assert_eq!(clock.now(), 300.0);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock(Arc<AtomicU64>);
+
+impl ManualClock {
+    /// Creates a new manual clock starting at the given time
+    pub fn starting_at(secs: f64) -> Self {
+        let r = Self::default();
+        r.set(secs);
+        r
+    }
+
+    /// Set the current time
+    ///
+    /// # Panic & Safety
+    ///
+    /// Panics if this would move the clock backwards.
+    pub fn set(&self, secs: f64) {
+        let moved_forward = self
+            .0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| {
+                (f64::from_bits(now) <= secs).then_some(secs.to_bits())
+            })
+            .is_ok();
+        assert!(moved_forward, "ManualClock must not move backwards");
+    }
+
+    /// Move the clock forward by the given number of seconds
+    ///
+    /// # Panic & Safety
+    ///
+    /// Panics if `secs` is negative.
+    pub fn advance(&self, secs: f64) {
+        assert!(secs >= 0.0, "ManualClock must not move backwards");
+        // Concurrent calls must not lose an update, so this cannot be done through [Self::set]
+        self.0
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| {
+                Some((f64::from_bits(now) + secs).to_bits())
+            })
+            .discard_result();
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::SeqCst))
+    }
+}
+
+/// A simulated clock: Real time running at an adjustable speed.
+///
+/// This is useful to test time dependent behavior that spans minutes within a few
+/// seconds of real time while still letting time pass on its own, e.g. when the code under
+/// test is running in another thread. The clock can also skip ahead by a given amount.
+///
+/// All clones of a simulated clock share the same time.
+///
+/// # Examples
+///
+/// ```
+/// use rosenpass_util::time::{Clock, SimulatedClock};
+///
+/// // One real second corresponds to one simulated minute
+/// let clock = SimulatedClock::with_speed(60.0);
+/// clock.skip(3600.0);
+/// assert!(clock.now() >= 3600.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct SimulatedClock {
+    /// The underlying real time
+    real: MonotonicClock,
+    /// How many simulated seconds pass per real second
+    speed: f64,
+    /// Time skipped through [Self::skip]
+    skipped: ManualClock,
+}
+
+impl SimulatedClock {
+    /// Creates a simulated clock running `speed` times as fast as real time
+    ///
+    /// # Panic & Safety
+    ///
+    /// Panics if `speed` is negative or not finite.
+    pub fn with_speed(speed: f64) -> Self {
+        assert!(
+            speed.is_finite() && speed >= 0.0,
+            "SimulatedClock speed must be a non-negative number"
+        );
+        Self {
+            real: MonotonicClock::default(),
+            speed,
+            skipped: ManualClock::default(),
+        }
+    }
+
+    /// Skip ahead by the given number of seconds
+    pub fn skip(&self, secs: f64) {
+        self.skipped.advance(secs);
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> f64 {
+        self.real.now() * self.speed + self.skipped.now()
+    }
+}
+
 /// A timebase.
 ///
-/// This is a simple wrapper around `std::time::Instant` that provides a
-/// convenient way to get the seconds elapsed since the creation of the
-/// `Timebase` instance.
+/// This is a simple wrapper around a [Clock] (by default a [MonotonicClock],
+/// wrapping `std::time::Instant`) that provides a convenient way to get the seconds
+/// elapsed since the creation of the `Timebase` instance.
+///
+/// Clones of a timebase share the same clock.
 ///
 /// # Examples
 ///
@@ -17,19 +196,56 @@ use std::time::Instant;
 /// ```
 
 #[derive(Clone, Debug)]
-pub struct Timebase(pub Instant);
+pub struct Timebase {
+    /// The source of time
+    clock: Arc<dyn Clock>,
+    /// Added to the time returned by the clock; see [Self::travel_forward]
+    offset: f64,
+}
 
 impl Default for Timebase {
-    // TODO: Implement new()?
     fn default() -> Self {
-        Self(Instant::now())
+        Self::new(MonotonicClock::default())
     }
 }
 
 impl Timebase {
+    /// Creates a timebase using the given clock
+    ///
+    /// # Examples
+    ///
+    /// See [ManualClock].
+    pub fn new<C: Clock + 'static>(clock: C) -> Self {
+        Self {
+            clock: Arc::new(clock),
+            offset: 0.0,
+        }
+    }
+
     /// Returns the seconds elapsed since the creation of the `Timebase`
     pub fn now(&self) -> f64 {
-        self.0.elapsed().as_secs_f64()
+        self.clock.now() + self.offset
+    }
+
+    /// Moves this timebase (but not the underlying clock or other clones of this timebase)
+    /// forward in time by the given number of seconds
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rosenpass_util::time::{ManualClock, Timebase};
+    ///
+    /// let clock = ManualClock::default();
+    /// let mut timebase = Timebase::new(clock.clone());
+    /// let other = timebase.clone();
+    ///
+    /// timebase.travel_forward(10.0);
+    /// assert_eq!(timebase.now(), 10.0);
+    /// assert_eq!(other.now(), 0.0);
+    /// ```
+    pub fn travel_forward(&mut self, secs: f64) {
+        assert!(secs >= 0.0, "Timebase must not move backwards");
+        self.offset += secs;
     }
 }
 
@@ -48,9 +264,14 @@ mod tests {
 
     #[test]
     fn test_timebase_clone() {
-        let timebase = Timebase::default();
+        let clock = ManualClock::starting_at(5.0);
+        let timebase = Timebase::new(clock.clone());
         let timebase_clone = timebase.clone();
-        assert_eq!(timebase.0, timebase_clone.0);
+        assert_eq!(timebase.now(), timebase_clone.now());
+
+        clock.advance(1.0);
+        assert_eq!(timebase.now(), 6.0);
+        assert_eq!(timebase_clone.now(), 6.0);
     }
 
     #[test]
@@ -60,4 +281,41 @@ mod tests {
         let now = timebase.now();
         assert!(now > 1.0);
     }
+
+    #[test]
+    #[should_panic]
+    fn test_manual_clock_backwards() {
+        let clock = ManualClock::starting_at(5.0);
+        clock.set(4.0);
+    }
+
+    #[test]
+    fn test_manual_clock_concurrent_advance() {
+        let clock = ManualClock::default();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let clock = clock.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        clock.advance(1.0);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(clock.now(), 4000.0);
+    }
+
+    #[test]
+    fn test_simulated_clock() {
+        let clock = SimulatedClock::with_speed(1000.0);
+        sleep(Duration::from_millis(10));
+        assert!(clock.now() >= 10.0);
+
+        let clock = SimulatedClock::with_speed(0.0);
+        clock.skip(42.0);
+        assert_eq!(clock.now(), 42.0);
+    }
 }
@@ -215,6 +215,10 @@ impl WireguardBrokerMio for MioBrokerClient {
         Ok(())
     }
 
+    fn has_pending_writes(&self) -> bool {
+        !self.inner.io().write_buffer.exhausted()
+    }
+
     fn unregister(&mut self, registry: &mio::Registry) -> Result<(), Self::MioError> {
         self.mio_token = None;
         registry.deregister(&mut self.inner.io_mut().socket)?;
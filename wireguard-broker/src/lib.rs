@@ -100,6 +100,13 @@ pub trait WireguardBrokerMio: WireGuardBroker {
     /// Process events after a mio poll operation
     fn process_poll(&mut self) -> Result<(), Self::MioError>;
 
+    /// Whether messages are still waiting to be written; call [Self::process_poll] to write them
+    ///
+    /// Brokers that do not buffer their messages never have pending writes.
+    fn has_pending_writes(&self) -> bool {
+        false
+    }
+
     /// Unregister the broker from a mio Registry
     fn unregister(&mut self, registry: &mio::Registry) -> Result<(), Self::MioError>;
 }